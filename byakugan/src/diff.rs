@@ -5,7 +5,7 @@
 use crate::git;
 use crate::review;
 use anyhow::Result;
-use nakama_ai::AiProvider;
+use nakama_ai::{AiProvider, ContextBudget};
 use nakama_ui::NakamaUI;
 
 /// Review changes to a single file.
@@ -16,6 +16,7 @@ pub async fn review_file(
     ui: &NakamaUI,
     provider: &dyn AiProvider,
     model: &str,
+    budget: &ContextBudget,
     file_path: &str,
 ) -> Result<()> {
     // Validate the file exists.
@@ -32,7 +33,7 @@ pub async fn review_file(
     ));
 
     let context_label = format!("file: {}", file_path);
-    let results = review::run_review(ui, provider, model, &diff, &context_label).await?;
+    let results = review::run_review(ui, provider, model, budget, &diff, &context_label).await?;
 
     let stats = review::ReviewStats::from_results(&results);
     ui.panel(
@@ -32,13 +32,13 @@ mod watch;
 
 use anyhow::{Context, Result};
 use clap::{Parser, Subcommand};
-use nakama_ai::{create_provider, AiProvider};
+use nakama_ai::client::parse_provider_choice;
+use nakama_ai::{AiArgs, AiClient, AiProvider, ContextBudget};
 use nakama_audit::{AuditEntry, AuditLog, Category, Outcome};
 use nakama_core::config::Config;
-use nakama_core::types::{ModelTier, Provider};
+use nakama_core::types::ModelTier;
 use nakama_log::init_logging;
 use nakama_ui::NakamaUI;
-use nakama_vault::{CredentialStore, Vault};
 use output::OutputFormat;
 use std::time::Instant;
 
@@ -48,9 +48,12 @@ const TOOL_NAME: &str = "byakugan";
 #[derive(Parser, Debug)]
 #[command(name = TOOL_NAME, version, about, long_about = None)]
 struct Cli {
-    /// AI provider to use (anthropic, openai, google, ollama)
-    #[arg(long, default_value = "anthropic")]
-    provider: String,
+    #[command(flatten)]
+    ai: AiArgs,
+
+    /// Deprecated alias for --ai-provider
+    #[arg(long, hide = true)]
+    provider: Option<String>,
 
     /// Model tier: fast, balanced, powerful
     #[arg(long, default_value = "balanced")]
@@ -152,10 +155,11 @@ enum RulesAction {
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let config = Config::load(TOOL_NAME).unwrap_or_default();
+    let (mut config, diagnostics) = Config::load_or_default(TOOL_NAME);
     let _log_guard = init_logging(TOOL_NAME, &config.logging)?;
 
     let ui = NakamaUI::from_config(&config);
+    ui.config_diagnostics(&diagnostics);
 
     let update_rx = nakama_update::spawn_check(&config.updates, env!("CARGO_PKG_VERSION"));
 
@@ -164,7 +168,15 @@ async fn main() -> Result<()> {
         &format!("AI-Powered Code Reviewer v{}", env!("CARGO_PKG_VERSION")),
     );
 
-    let cli = Cli::parse();
+    let mut cli = Cli::parse();
+    if let Some(name) = cli.provider.take() {
+        ui.warn("--provider is deprecated; use --ai-provider instead");
+        if cli.ai.ai_provider.is_none() {
+            cli.ai.ai_provider = Some(parse_provider_choice(&name).map_err(anyhow::Error::msg)?);
+        }
+    }
+    cli.ai.apply(&mut config);
+    nakama_vault::startup(&config, &ui);
     let format = OutputFormat::from_str(&cli.format);
 
     // Check if this command needs an AI provider.
@@ -179,18 +191,17 @@ async fn main() -> Result<()> {
     );
 
     // Create AI provider only when needed.
-    let ai_provider: Option<Box<dyn AiProvider>> = if needs_ai {
-        let provider_enum = parse_provider(&cli.provider)?;
-        let model_tier = parse_tier(&cli.tier)?;
-        let _model = config.resolve_model(provider_enum, model_tier);
-        Some(create_ai_provider(&config, provider_enum, &_model)?)
+    let model_tier = parse_tier(&cli.tier)?;
+    let ai_client = if needs_ai {
+        Some(AiClient::new(&config, TOOL_NAME, model_tier)?)
     } else {
         None
     };
-
-    let provider_enum = parse_provider(&cli.provider)?;
-    let model_tier = parse_tier(&cli.tier)?;
-    let model = config.resolve_model(provider_enum, model_tier);
+    let budget = ai_client
+        .as_ref()
+        .map(|ai| ai.context_budget(review::PASS_MAX_TOKENS));
+    let ai_provider: Option<Box<dyn AiProvider>> = ai_client.map(|ai| ai.into_parts().0);
+    let model = nakama_ai::client::default_model(&config, model_tier);
 
     // Open the audit log.
     let audit_log = AuditLog::new(&config.audit).ok();
@@ -203,6 +214,7 @@ async fn main() -> Result<()> {
                 &ui,
                 ai_provider.as_ref().unwrap().as_ref(),
                 &model,
+                budget.as_ref().unwrap(),
                 &config,
             )
             .await;
@@ -213,6 +225,7 @@ async fn main() -> Result<()> {
                 &ui,
                 ai_provider.as_ref().unwrap().as_ref(),
                 &model,
+                budget.as_ref().unwrap(),
                 pr_ref,
                 &config,
                 cli.platform.as_deref(),
@@ -224,13 +237,24 @@ async fn main() -> Result<()> {
             ("pr", result)
         }
         Commands::Diff { ref file } => {
-            let result =
-                cmd_diff(&ui, ai_provider.as_ref().unwrap().as_ref(), &model, file).await;
+            let result = cmd_diff(
+                &ui,
+                ai_provider.as_ref().unwrap().as_ref(),
+                &model,
+                budget.as_ref().unwrap(),
+                file,
+            )
+            .await;
             ("diff", result)
         }
         Commands::Suggest => {
-            let result =
-                cmd_suggest(&ui, ai_provider.as_ref().unwrap().as_ref(), &model).await;
+            let result = cmd_suggest(
+                &ui,
+                ai_provider.as_ref().unwrap().as_ref(),
+                &model,
+                budget.as_ref().unwrap(),
+            )
+            .await;
             ("suggest", result)
         }
         Commands::Scan => {
@@ -242,6 +266,7 @@ async fn main() -> Result<()> {
                 &ui,
                 ai_provider.as_ref().unwrap().as_ref(),
                 &model,
+                budget.as_ref().unwrap(),
                 &config,
                 format,
             )
@@ -270,6 +295,7 @@ async fn main() -> Result<()> {
                 &ui,
                 ai_provider.as_ref().unwrap().as_ref(),
                 &model,
+                budget.as_ref().unwrap(),
                 &config,
                 once,
             )
@@ -286,7 +312,7 @@ async fn main() -> Result<()> {
             Ok(()) => (
                 Outcome::Success,
                 serde_json::json!({
-                    "provider": cli.provider,
+                    "provider": config.ai.default_provider.to_string(),
                     "model": model,
                     "duration_secs": duration.as_secs_f64(),
                 }),
@@ -294,7 +320,7 @@ async fn main() -> Result<()> {
             Err(e) => (
                 Outcome::Failure,
                 serde_json::json!({
-                    "provider": cli.provider,
+                    "provider": config.ai.default_provider.to_string(),
                     "model": model,
                     "error": format!("{:#}", e),
                     "duration_secs": duration.as_secs_f64(),
@@ -348,6 +374,7 @@ async fn cmd_review(
     ui: &NakamaUI,
     provider: &dyn AiProvider,
     model: &str,
+    budget: &ContextBudget,
     config: &Config,
 ) -> Result<()> {
     let spinner = ui.step_start("Collecting branch diff...");
@@ -373,6 +400,7 @@ async fn cmd_review(
         ui,
         provider,
         model,
+        budget,
         &branch_diff.diff_text,
         &context_label,
         &config.byakugan.passes,
@@ -407,6 +435,7 @@ async fn cmd_pr(
     ui: &NakamaUI,
     provider: &dyn AiProvider,
     model: &str,
+    budget: &ContextBudget,
     pr_ref: &str,
     config: &Config,
     platform_name: Option<&str>,
@@ -469,6 +498,7 @@ async fn cmd_pr(
         ui,
         provider,
         model,
+        budget,
         &pr_data.diff,
         &context_label,
         &config.byakugan.passes,
@@ -625,14 +655,20 @@ async fn cmd_diff(
     ui: &NakamaUI,
     provider: &dyn AiProvider,
     model: &str,
+    budget: &ContextBudget,
     file: &str,
 ) -> Result<()> {
-    diff::review_file(ui, provider, model, file).await
+    diff::review_file(ui, provider, model, budget, file).await
 }
 
 /// `byakugan suggest` — Suggest improvements for current changes.
-async fn cmd_suggest(ui: &NakamaUI, provider: &dyn AiProvider, model: &str) -> Result<()> {
-    suggest::suggest_improvements(ui, provider, model).await
+async fn cmd_suggest(
+    ui: &NakamaUI,
+    provider: &dyn AiProvider,
+    model: &str,
+    budget: &ContextBudget,
+) -> Result<()> {
+    suggest::suggest_improvements(ui, provider, model, budget).await
 }
 
 /// `byakugan scan` — Run custom rules against local diff.
@@ -645,10 +681,11 @@ async fn cmd_report(
     ui: &NakamaUI,
     provider: &dyn AiProvider,
     model: &str,
+    budget: &ContextBudget,
     config: &Config,
     format: OutputFormat,
 ) -> Result<()> {
-    report::run_report(ui, provider, model, &config.byakugan, format).await
+    report::run_report(ui, provider, model, budget, &config.byakugan, format).await
 }
 
 /// `byakugan comment` — Post a comment to a PR/MR.
@@ -696,6 +733,7 @@ async fn cmd_watch(
     ui: &NakamaUI,
     provider: &dyn AiProvider,
     model: &str,
+    budget: &ContextBudget,
     config: &Config,
     once: bool,
 ) -> Result<()> {
@@ -703,6 +741,7 @@ async fn cmd_watch(
         ui,
         provider,
         model,
+        budget,
         &config.byakugan,
         &config.platforms,
         once,
@@ -714,20 +753,6 @@ async fn cmd_watch(
 // Helpers
 // ---------------------------------------------------------------------------
 
-/// Parse the provider string into a `Provider` enum.
-fn parse_provider(s: &str) -> Result<Provider> {
-    match s.to_lowercase().as_str() {
-        "anthropic" | "claude" => Ok(Provider::Anthropic),
-        "openai" | "gpt" => Ok(Provider::OpenAI),
-        "google" | "gemini" => Ok(Provider::Google),
-        "ollama" | "local" => Ok(Provider::Ollama),
-        other => anyhow::bail!(
-            "Unknown provider '{}'. Use: anthropic, openai, google, or ollama",
-            other
-        ),
-    }
-}
-
 /// Parse the tier string into a `ModelTier` enum.
 fn parse_tier(s: &str) -> Result<ModelTier> {
     match s.to_lowercase().as_str() {
@@ -740,50 +765,3 @@ fn parse_tier(s: &str) -> Result<ModelTier> {
         ),
     }
 }
-
-/// Create a boxed AI provider, fetching the API key from the vault.
-fn create_ai_provider(
-    config: &Config,
-    provider: Provider,
-    model: &str,
-) -> Result<Box<dyn AiProvider>> {
-    let api_key = get_api_key(provider)?;
-
-    let base_url = match provider {
-        Provider::Anthropic => config.ai.anthropic.base_url.as_deref(),
-        Provider::OpenAI => config.ai.openai.base_url.as_deref(),
-        Provider::Google => config.ai.google.base_url.as_deref(),
-        Provider::Ollama => Some(config.ai.ollama.base_url.as_str()),
-    };
-
-    create_provider(provider, &api_key, model, base_url)
-        .context("Failed to create AI provider")
-        .map_err(|e| anyhow::anyhow!("{}", e))
-}
-
-/// Retrieve the API key for the given provider from the vault,
-/// falling back to environment variables.
-fn get_api_key(provider: Provider) -> Result<String> {
-    if provider == Provider::Ollama {
-        return Ok(String::new());
-    }
-
-    let (service, env_var) = match provider {
-        Provider::Anthropic => ("anthropic", "NAKAMA_ANTHROPIC_API_KEY"),
-        Provider::OpenAI => ("openai", "NAKAMA_OPENAI_API_KEY"),
-        Provider::Google => ("google", "NAKAMA_GOOGLE_API_KEY"),
-        Provider::Ollama => unreachable!(),
-    };
-
-    if let Ok(vault) = Vault::new() {
-        if let Ok(secret) = vault.retrieve("nakama", &format!("{}_api_key", service)) {
-            return Ok(secret.expose_secret().to_string());
-        }
-    }
-
-    std::env::var(env_var).context(format!(
-        "API key for {} not found. Set the {} environment variable or store it with:\n  \
-         nakama vault store nakama {}_api_key <your-key>",
-        provider, env_var, service
-    ))
-}
@@ -4,7 +4,7 @@
 //! Used by review, scan, and report commands for consistent output.
 
 use crate::dedup::DedupFinding;
-use crate::passes::{PassResult, Severity};
+use crate::passes::{Finding, PassResult, Severity};
 use crate::rules::RuleFinding;
 use serde::Serialize;
 use std::fmt;
@@ -56,6 +56,8 @@ pub struct JsonPassResult {
     pub finding_count: usize,
     pub severity: String,
     pub content: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub findings: Vec<Finding>,
     pub input_tokens: u32,
     pub output_tokens: u32,
 }
@@ -104,6 +106,7 @@ pub fn format_json(
                 finding_count: r.finding_count,
                 severity: r.severity.label().to_string(),
                 content: r.content.clone(),
+                findings: r.findings.clone(),
                 input_tokens: r.input_tokens,
                 output_tokens: r.output_tokens,
             })
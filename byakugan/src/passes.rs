@@ -4,7 +4,10 @@
 //! specialized system prompt that instructs the AI model to focus on that
 //! dimension alone.
 
+use nakama_ai::load_prompt;
+use nakama_ai::types::ResponseFormat;
 use nakama_core::config::ByakuganPromptsConfig;
+use serde::{Deserialize, Serialize};
 use std::fmt;
 
 /// The different review passes that Byakugan runs against a diff.
@@ -29,133 +32,22 @@ impl ReviewPass {
         ]
     }
 
-    /// The system prompt sent to the AI model for this pass.
-    pub fn system_prompt(&self) -> &'static str {
+    /// Id of this pass's prompt in the prompt registry (`byakugan/<id>`).
+    pub fn prompt_id(&self) -> &'static str {
         match self {
-            ReviewPass::Security => {
-                "You are a senior security engineer performing a code review. \
-                 Analyze the following diff for security vulnerabilities. Look specifically for:\n\
-                 - SQL injection, command injection, or other injection attacks\n\
-                 - Cross-site scripting (XSS) vulnerabilities\n\
-                 - Authentication or authorization bypasses\n\
-                 - Hardcoded secrets, API keys, tokens, or passwords in code\n\
-                 - Insecure cryptographic practices\n\
-                 - Path traversal vulnerabilities\n\
-                 - Insecure deserialization\n\
-                 - Missing input validation or sanitization\n\
-                 - Information leakage through error messages or logs\n\n\
-                 IMPORTANT: Your findings will be posted as inline comments on the PR diff. \
-                 For each finding, you MUST include the exact file path and line number.\n\n\
-                 Format each finding as:\n\
-                 1. **Short title**\n\
-                 **Severity:** CRITICAL | HIGH | MEDIUM | LOW\n\
-                 **File:** `exact/path/to/file.ext` **Line:** N\n\
-                 **Issue:** Brief explanation of the risk.\n\
-                 **Fix:** Suggested fix or code snippet.\n\n\
-                 Use the EXACT file paths from the diff headers (e.g., `src/main/java/com/example/Foo.java`), \
-                 not shortened names. The line number must be from the NEW side of the diff (+ lines).\n\n\
-                 If no issues are found, respond with: \"No security issues found.\"\n\
-                 Be concise and precise. Do not invent problems that don't exist."
-            }
-            ReviewPass::Performance => {
-                "You are a performance engineering specialist reviewing code changes. \
-                 Analyze the following diff for performance concerns. Look specifically for:\n\
-                 - N+1 query patterns or inefficient database access\n\
-                 - Unnecessary memory allocations, copies, or clones\n\
-                 - Blocking calls in async contexts\n\
-                 - Missing caching opportunities\n\
-                 - Algorithmic complexity issues (O(n^2) where O(n) is possible)\n\
-                 - Unbounded collection growth\n\
-                 - Unnecessary network round-trips\n\
-                 - Hot-path allocations that could be avoided\n\
-                 - Large structs passed by value instead of by reference\n\n\
-                 IMPORTANT: Your findings will be posted as inline comments on the PR diff. \
-                 For each finding, you MUST include the exact file path and line number.\n\n\
-                 Format each finding as:\n\
-                 1. **Short title**\n\
-                 **Severity:** CRITICAL | HIGH | MEDIUM | LOW\n\
-                 **File:** `exact/path/to/file.ext` **Line:** N\n\
-                 **Issue:** Brief explanation of the impact.\n\
-                 **Fix:** Suggested optimization.\n\n\
-                 Use the EXACT file paths from the diff headers (e.g., `src/main/java/com/example/Foo.java`), \
-                 not shortened names. The line number must be from the NEW side of the diff (+ lines).\n\n\
-                 If no issues are found, respond with: \"No performance concerns found.\"\n\
-                 Be concise and precise. Do not invent problems that don't exist."
-            }
-            ReviewPass::Style => {
-                "You are a code style and maintainability reviewer. \
-                 Analyze the following diff for style and maintainability issues. Look for:\n\
-                 - Inconsistent naming conventions\n\
-                 - Poor code organization or structure\n\
-                 - DRY (Don't Repeat Yourself) violations\n\
-                 - Overly complex functions that should be decomposed\n\
-                 - Missing or misleading documentation/comments\n\
-                 - Magic numbers or strings that should be constants\n\
-                 - Dead code or unreachable branches\n\
-                 - Inconsistent error message formatting\n\
-                 - Overly broad imports or unused dependencies\n\n\
-                 IMPORTANT: Your findings will be posted as inline comments on the PR diff. \
-                 For each finding, you MUST include the exact file path and line number.\n\n\
-                 Format each finding as:\n\
-                 1. **Short title**\n\
-                 **Severity:** HIGH | MEDIUM | LOW\n\
-                 **File:** `exact/path/to/file.ext` **Line:** N\n\
-                 **Issue:** Brief explanation.\n\
-                 **Fix:** Suggested improvement.\n\n\
-                 Use the EXACT file paths from the diff headers (e.g., `src/main/java/com/example/Foo.java`), \
-                 not shortened names. The line number must be from the NEW side of the diff (+ lines).\n\n\
-                 If no issues are found, respond with: \"No style issues found.\"\n\
-                 Be concise. Focus on actionable improvements, not nitpicks."
-            }
-            ReviewPass::Logic => {
-                "You are a senior software engineer reviewing code for logical correctness. \
-                 Analyze the following diff for logic issues. Look specifically for:\n\
-                 - Unhandled edge cases (empty inputs, null values, boundary conditions)\n\
-                 - Missing or incorrect error handling\n\
-                 - Race conditions or concurrency bugs\n\
-                 - Off-by-one errors\n\
-                 - Incorrect boolean logic or control flow\n\
-                 - Resource leaks (unclosed handles, missing cleanup)\n\
-                 - Incorrect type conversions or truncation\n\
-                 - Assumptions that may not hold in production\n\
-                 - Missing validation of preconditions or invariants\n\n\
-                 IMPORTANT: Your findings will be posted as inline comments on the PR diff. \
-                 For each finding, you MUST include the exact file path and line number.\n\n\
-                 Format each finding as:\n\
-                 1. **Short title**\n\
-                 **Severity:** CRITICAL | HIGH | MEDIUM | LOW\n\
-                 **File:** `exact/path/to/file.ext` **Line:** N\n\
-                 **Issue:** Brief explanation of the bug or risk.\n\
-                 **Fix:** Suggested fix.\n\n\
-                 Use the EXACT file paths from the diff headers (e.g., `src/main/java/com/example/Foo.java`), \
-                 not shortened names. The line number must be from the NEW side of the diff (+ lines).\n\n\
-                 If no issues are found, respond with: \"No logic issues found.\"\n\
-                 Be concise and precise. Do not invent problems that don't exist."
-            }
-            ReviewPass::Summary => {
-                "You are a tech lead providing an overall code review summary. \
-                 Based on the following diff, provide:\n\n\
-                 1. **Overall Assessment**: A 2-3 sentence summary of the changes.\n\
-                 2. **Quality Score**: Rate the code from 1-10 where:\n\
-                    - 1-3: Serious problems, should not merge\n\
-                    - 4-5: Significant concerns that need addressing\n\
-                    - 6-7: Acceptable with minor improvements needed\n\
-                    - 8-9: Good quality, minor suggestions only\n\
-                    - 10: Excellent, no concerns\n\
-                 3. **Key Recommendations**: Top 3-5 actionable items ranked by priority.\n\
-                 4. **Merge Readiness**: READY, NEEDS_CHANGES, or BLOCK\n\n\
-                 Format the score as: Score: X/10\n\
-                 Format merge readiness as: Merge: READY|NEEDS_CHANGES|BLOCK\n\n\
-                 Be constructive and balanced. Acknowledge what was done well."
-            }
+            ReviewPass::Security => "security",
+            ReviewPass::Performance => "performance",
+            ReviewPass::Style => "style",
+            ReviewPass::Logic => "logic",
+            ReviewPass::Summary => "summary",
         }
     }
 
     /// Resolve the system prompt, using config overrides if present.
     ///
     /// If the config has a custom prompt for this pass, that is used; otherwise
-    /// the hardcoded default is used. In either case, `preamble` (if set) is
-    /// prepended.
+    /// the prompt comes from the registry (a prompt file override or the
+    /// built-in). In either case, `preamble` (if set) is prepended.
     pub fn system_prompt_with_config(&self, prompts: &ByakuganPromptsConfig) -> String {
         let override_prompt = match self {
             ReviewPass::Security => prompts.security.as_deref(),
@@ -165,7 +57,10 @@ impl ReviewPass {
             ReviewPass::Summary => prompts.summary.as_deref(),
         };
 
-        let base = override_prompt.unwrap_or_else(|| self.system_prompt());
+        let base = match override_prompt {
+            Some(prompt) => prompt.to_string(),
+            None => load_prompt(crate::TOOL_NAME, self.prompt_id(), &[]),
+        };
 
         match &prompts.preamble {
             Some(preamble) => format!("{}\n\n{}", preamble, base),
@@ -205,6 +100,23 @@ impl ReviewPass {
         }
     }
 
+    /// Whether this pass reports individual findings (as opposed to the
+    /// free-form summary).
+    pub fn has_findings(&self) -> bool {
+        *self != ReviewPass::Summary
+    }
+
+    /// What to show when this pass found nothing.
+    pub fn clean_message(&self) -> &'static str {
+        match self {
+            ReviewPass::Security => "No security issues found.",
+            ReviewPass::Performance => "No performance concerns found.",
+            ReviewPass::Style => "No style issues found.",
+            ReviewPass::Logic => "No logic issues found.",
+            ReviewPass::Summary => "No issues found.",
+        }
+    }
+
     /// A human-readable label for display in tables and panels.
     pub fn label(&self) -> &'static str {
         match self {
@@ -228,8 +140,10 @@ impl fmt::Display for ReviewPass {
 pub struct PassResult {
     /// Which pass produced this result.
     pub pass: ReviewPass,
-    /// The raw AI response text.
+    /// The response text (findings rendered as markdown for finding passes).
     pub content: String,
+    /// The findings reported by this pass (empty for the summary pass).
+    pub findings: Vec<Finding>,
     /// Number of findings extracted (0 = clean).
     pub finding_count: usize,
     /// The highest severity found in this pass.
@@ -240,7 +154,8 @@ pub struct PassResult {
 }
 
 /// Severity levels for review findings.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
 pub enum Severity {
     Ok,
     Low,
@@ -268,7 +183,87 @@ impl fmt::Display for Severity {
     }
 }
 
+/// A single issue reported by a review pass.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Finding {
+    pub title: String,
+    pub severity: Severity,
+    /// Path from the diff header, if the model gave one.
+    #[serde(default)]
+    pub file: Option<String>,
+    /// Line number on the new side of the diff.
+    #[serde(default)]
+    pub line: Option<u32>,
+    pub issue: String,
+    #[serde(default)]
+    pub fix: Option<String>,
+}
+
+/// The JSON document a finding pass responds with.
+#[derive(Debug, Deserialize)]
+pub struct FindingsReport {
+    pub findings: Vec<Finding>,
+}
+
+/// The response schema for finding passes.
+pub fn findings_format() -> ResponseFormat {
+    ResponseFormat::new(
+        "review_findings",
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "findings": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "title": { "type": "string" },
+                            "severity": { "type": "string", "enum": ["CRITICAL", "HIGH", "MEDIUM", "LOW"] },
+                            "file": { "type": "string" },
+                            "line": { "type": "integer" },
+                            "issue": { "type": "string" },
+                            "fix": { "type": "string" }
+                        },
+                        "required": ["title", "severity", "file", "line", "issue"]
+                    }
+                }
+            },
+            "required": ["findings"]
+        }),
+    )
+}
+
+/// Render findings as the markdown shown in panels and PR comments.
+pub fn render_findings(pass: ReviewPass, findings: &[Finding]) -> String {
+    if findings.is_empty() {
+        return pass.clean_message().to_string();
+    }
+
+    let mut out = String::new();
+    for (i, f) in findings.iter().enumerate() {
+        out.push_str(&format!("{}. **{}**\n", i + 1, f.title));
+        out.push_str(&format!("**Severity:** {}\n", f.severity));
+        if let Some(file) = &f.file {
+            out.push_str(&format!("**File:** `{}`", file));
+            if let Some(line) = f.line {
+                out.push_str(&format!(" **Line:** {}", line));
+            }
+            out.push('\n');
+        }
+        out.push_str(&format!("**Issue:** {}\n", f.issue));
+        if let Some(fix) = &f.fix {
+            out.push_str(&format!("**Fix:** {}\n", fix));
+        }
+        out.push('\n');
+    }
+    out.truncate(out.trim_end().len());
+    out
+}
+
 /// Parse the AI response to estimate finding count and max severity.
+///
+/// Only used for the free-form summary pass; finding passes return
+/// structured [`Finding`]s.
 pub fn parse_findings(content: &str) -> (usize, Severity) {
     let lower = content.to_lowercase();
 
@@ -345,3 +340,27 @@ pub fn parse_findings(content: &str) -> (usize, Severity) {
         (count.max(if severity > Severity::Ok { 1 } else { 0 }), severity)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_findings_report_round_trip() {
+        let raw = r#"{"findings": [{
+            "title": "SQL built from user input",
+            "severity": "HIGH",
+            "file": "src/db.rs",
+            "line": 42,
+            "issue": "The query concatenates the request parameter.",
+            "fix": "Use a bound parameter."
+        }]}"#;
+        let report: FindingsReport = nakama_ai::structured::parse_json(raw).unwrap();
+        assert_eq!(report.findings[0].severity, Severity::High);
+
+        let rendered = render_findings(ReviewPass::Security, &report.findings);
+        assert!(rendered.starts_with("1. **SQL built from user input**"));
+        assert!(rendered.contains("**File:** `src/db.rs` **Line:** 42"));
+        assert_eq!(render_findings(ReviewPass::Style, &[]), "No style issues found.");
+    }
+}
@@ -6,7 +6,7 @@ use crate::output::{self, OutputFormat};
 use crate::review;
 use crate::rules;
 use anyhow::Result;
-use nakama_ai::AiProvider;
+use nakama_ai::{AiProvider, ContextBudget};
 use nakama_core::config::ByakuganConfig;
 use nakama_ui::NakamaUI;
 
@@ -15,6 +15,7 @@ pub async fn run_report(
     ui: &NakamaUI,
     provider: &dyn AiProvider,
     model: &str,
+    budget: &ContextBudget,
     byakugan_config: &ByakuganConfig,
     format: OutputFormat,
 ) -> Result<()> {
@@ -33,7 +34,7 @@ pub async fn run_report(
     spinner.finish_with_success(&format!("Diff size: {} chars", diff_text.len()));
 
     // Run AI review.
-    let results = review::run_review(ui, provider, model, &diff_text, &context_label).await?;
+    let results = review::run_review(ui, provider, model, budget, &diff_text, &context_label).await?;
 
     // Run rule scan.
     let rule_findings = if !byakugan_config.rules.is_empty() {
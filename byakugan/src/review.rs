@@ -5,66 +5,90 @@
 //! `Box<dyn AiProvider>` and uses it for all passes.
 
 use crate::dedup;
-use crate::passes::{parse_findings, PassResult, ReviewPass, Severity};
+use crate::passes::{
+    findings_format, parse_findings, render_findings, FindingsReport, PassResult, ReviewPass,
+    Severity,
+};
 use crate::platform;
 use anyhow::{Context, Result};
 use nakama_ai::types::{CompletionRequest, Message};
-use nakama_ai::AiProvider;
+use nakama_ai::{collect_stream, complete_json, AiProvider, ContextBudget};
 use nakama_core::config::ByakuganPromptsConfig;
+use nakama_ui::spinner::Spinner;
 use nakama_ui::NakamaUI;
 use std::time::Instant;
 
-/// Maximum characters of diff to send per pass.
-const MAX_DIFF_CHARS: usize = 60_000;
+/// Maximum tokens of diff to send per pass, even to models with room for
+/// more.
+const MAX_DIFF_TOKENS: usize = 15_000;
+
+/// Response token limit for each pass.
+pub const PASS_MAX_TOKENS: u32 = 2048;
 
 /// Run all review passes against the given diff text and display results.
 pub async fn run_review(
     ui: &NakamaUI,
     provider: &dyn AiProvider,
     model: &str,
+    budget: &ContextBudget,
     diff: &str,
     context_label: &str,
 ) -> Result<Vec<PassResult>> {
     let default_prompts = ByakuganPromptsConfig::default();
-    run_review_with_passes(ui, provider, model, diff, context_label, &[], &default_prompts).await
+    run_review_with_passes(ui, provider, model, budget, diff, context_label, &[], &default_prompts)
+        .await
 }
 
 /// Run review with configurable passes and prompt overrides.
+#[allow(clippy::too_many_arguments)]
 pub async fn run_review_with_passes(
     ui: &NakamaUI,
     provider: &dyn AiProvider,
     model: &str,
+    budget: &ContextBudget,
     diff: &str,
     context_label: &str,
     pass_names: &[String],
     prompts: &ByakuganPromptsConfig,
 ) -> Result<Vec<PassResult>> {
-    let truncated_diff = nakama_core::diff::compress_diff(diff, MAX_DIFF_CHARS);
+    let passes = ReviewPass::from_names(pass_names);
+
+    // Every pass gets the same diff, sized for the longest system prompt.
+    let mut budget = budget.clone();
+    if let Some(longest) = passes
+        .iter()
+        .map(|pass| pass.system_prompt_with_config(prompts))
+        .max_by_key(|prompt| budget.count(prompt))
+    {
+        budget.reserve(&longest);
+    }
+    let budget = budget.at_most(MAX_DIFF_TOKENS);
+    let diff_tokens = budget.count(diff);
+    let truncated_diff = budget.fit_diff(diff);
 
     ui.panel(
         "Byakugan Review",
         &format!(
-            "Context: {}\nProvider: {}\nModel: {}\nDiff size: {} chars{}",
+            "Context: {}\nProvider: {}\nModel: {}\nDiff size: {} tokens{}",
             context_label,
             provider.provider_name(),
             model,
-            diff.len(),
-            if diff.len() > MAX_DIFF_CHARS {
-                format!(" (truncated to {})", MAX_DIFF_CHARS)
+            diff_tokens,
+            if diff_tokens > budget.available() {
+                format!(" (truncated to {})", budget.available())
             } else {
                 String::new()
             },
         ),
     );
 
-    let passes = ReviewPass::from_names(pass_names);
     let mut results: Vec<PassResult> = Vec::with_capacity(passes.len());
 
     for &pass in &passes {
         let spinner = ui.step_start(&format!("Running {} pass...", pass.label()));
 
         let start = Instant::now();
-        let result = run_single_pass(provider, model, &truncated_diff, pass, prompts).await;
+        let result = run_single_pass(provider, model, &truncated_diff, pass, prompts, &spinner).await;
         let elapsed = start.elapsed();
 
         match result {
@@ -93,6 +117,7 @@ pub async fn run_review_with_passes(
                 results.push(PassResult {
                     pass,
                     content: format!("Error: {}", e),
+                    findings: Vec::new(),
                     finding_count: 0,
                     severity: Severity::Ok,
                     input_tokens: 0,
@@ -112,12 +137,17 @@ pub async fn run_review_with_passes(
 }
 
 /// Run a single review pass against the diff.
+///
+/// The response is streamed so the spinner can report progress while the
+/// model is still writing.  Finding passes ask for JSON matching
+/// [`findings_format`]; the summary pass stays free text.
 async fn run_single_pass(
     provider: &dyn AiProvider,
     model: &str,
     diff: &str,
     pass: ReviewPass,
     prompts: &ByakuganPromptsConfig,
+    spinner: &Spinner,
 ) -> Result<PassResult> {
     let user_message = format!(
         "Please review the following code diff:\n\n```diff\n{}\n```",
@@ -128,12 +158,45 @@ async fn run_single_pass(
         system_prompt: pass.system_prompt_with_config(prompts),
         messages: vec![Message::user(user_message)],
         model: model.to_string(),
-        max_tokens: 2048,
+        max_tokens: PASS_MAX_TOKENS,
         temperature: 0.2,
+        ..Default::default()
     };
 
-    let response = provider
-        .complete(request)
+    let mut received = 0;
+    let on_delta = |delta: &str| {
+        received += delta.len();
+        spinner.update_message(&format!(
+            "Running {} pass... ({} chars received)",
+            pass.label(),
+            received
+        ));
+    };
+
+    if pass.has_findings() {
+        let structured = complete_json::<FindingsReport, _>(provider, request, findings_format(), on_delta)
+            .await
+            .context(format!("AI completion failed for {} pass", pass.label()))?;
+        let findings = structured.value.findings;
+        let usage = structured.response.usage;
+
+        return Ok(PassResult {
+            pass,
+            content: render_findings(pass, &findings),
+            finding_count: findings.len(),
+            severity: findings.iter().map(|f| f.severity).max().unwrap_or(Severity::Ok),
+            findings,
+            input_tokens: usage.input_tokens,
+            output_tokens: usage.output_tokens,
+        });
+    }
+
+    let stream = provider
+        .complete_stream(request)
+        .await
+        .context(format!("AI completion failed for {} pass", pass.label()))?;
+
+    let response = collect_stream(stream, on_delta)
         .await
         .context(format!("AI completion failed for {} pass", pass.label()))?;
 
@@ -142,6 +205,7 @@ async fn run_single_pass(
     Ok(PassResult {
         pass,
         content: response.content,
+        findings: Vec::new(),
         finding_count,
         severity,
         input_tokens: response.usage.input_tokens,
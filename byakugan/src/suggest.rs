@@ -6,31 +6,18 @@
 use crate::git;
 use anyhow::{Context, Result};
 use nakama_ai::types::{CompletionRequest, Message};
-use nakama_ai::AiProvider;
+use nakama_ai::{collect_stream, load_prompt, AiProvider, ContextBudget};
 use nakama_ui::NakamaUI;
 
-const SUGGEST_SYSTEM_PROMPT: &str = "\
-You are a senior software engineer reviewing uncommitted changes. \
-Your goal is to suggest concrete, actionable improvements. \
-For each suggestion, provide:\n\
-1. A one-line title\n\
-2. Where in the code it applies (file/function/line context)\n\
-3. The current code pattern\n\
-4. Your suggested improvement\n\
-5. Why it is better (one sentence)\n\n\
-Focus on the most impactful improvements. Aim for 3-7 suggestions. \
-Order them by impact (highest first). \
-If the code is already well-written, say so and suggest only minor polish.\n\
-Do not repeat the entire diff back. Be concise.";
-
-/// Maximum diff chars for the suggest command.
-const MAX_DIFF_CHARS: usize = 50_000;
+/// Maximum diff tokens for the suggest command.
+const MAX_DIFF_TOKENS: usize = 12_500;
 
 /// Suggest improvements for current uncommitted changes.
 pub async fn suggest_improvements(
     ui: &NakamaUI,
     provider: &dyn AiProvider,
     model: &str,
+    budget: &ContextBudget,
 ) -> Result<()> {
     let spinner = ui.step_start("Collecting working-tree changes...");
 
@@ -55,7 +42,10 @@ pub async fn suggest_improvements(
         }
     };
 
-    let truncated = nakama_core::diff::compress_diff(&diff, MAX_DIFF_CHARS);
+    let mut budget = budget.clone();
+    let system_prompt = load_prompt(crate::TOOL_NAME, "suggest", &[]);
+    budget.reserve(&system_prompt);
+    let truncated = budget.at_most(MAX_DIFF_TOKENS).fit_diff(&diff);
 
     let ai_spinner = ui.step_start("Generating improvement suggestions...");
 
@@ -65,24 +55,29 @@ pub async fn suggest_improvements(
     );
 
     let request = CompletionRequest {
-        system_prompt: SUGGEST_SYSTEM_PROMPT.to_string(),
+        system_prompt,
         messages: vec![Message::user(user_message)],
         model: model.to_string(),
         max_tokens: 2048,
         temperature: 0.3,
+        ..Default::default()
     };
 
-    let response = provider
-        .complete(request)
+    let stream = provider
+        .complete_stream(request)
         .await
         .context("AI suggestion generation failed")?;
+    ai_spinner.finish_and_clear();
+
+    let mut panel = ui.stream_panel("Improvement Suggestions");
+    let response = collect_stream(stream, |delta| panel.push(delta)).await;
+    panel.finish();
+    let response = response.context("AI suggestion generation failed")?;
 
-    ai_spinner.finish_with_success(&format!(
+    ui.step_done(&format!(
         "Suggestions generated ({}/{} tokens)",
         response.usage.input_tokens, response.usage.output_tokens
     ));
 
-    ui.panel("Improvement Suggestions", &response.content);
-
     Ok(())
 }
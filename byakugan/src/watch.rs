@@ -3,7 +3,7 @@
 use crate::platform::{self, Platform};
 use crate::review;
 use anyhow::{Context, Result};
-use nakama_ai::AiProvider;
+use nakama_ai::{AiProvider, ContextBudget};
 use nakama_core::config::{ByakuganConfig, PlatformsConfig};
 use nakama_ui::NakamaUI;
 use std::collections::HashMap;
@@ -21,6 +21,7 @@ pub async fn run_watch(
     ui: &NakamaUI,
     provider: &dyn AiProvider,
     model: &str,
+    budget: &ContextBudget,
     byakugan_config: &ByakuganConfig,
     platforms_config: &PlatformsConfig,
     once: bool,
@@ -63,6 +64,7 @@ pub async fn run_watch(
                 ui,
                 provider,
                 model,
+                budget,
                 byakugan_config,
                 platforms_config,
                 spec,
@@ -96,10 +98,12 @@ pub async fn run_watch(
 }
 
 /// Poll a single repository for new PRs.
+#[allow(clippy::too_many_arguments)]
 async fn poll_repo(
     ui: &NakamaUI,
     provider: &dyn AiProvider,
     model: &str,
+    budget: &ContextBudget,
     byakugan_config: &ByakuganConfig,
     platforms_config: &PlatformsConfig,
     spec: &RepoSpec,
@@ -127,7 +131,7 @@ async fn poll_repo(
                 .await
             {
                 let context = format!("PR #{}: {}", pr.number, pr.title);
-                let _ = review::run_review(ui, provider, model, &diff, &context).await;
+                let _ = review::run_review(ui, provider, model, budget, &diff, &context).await;
             }
         }
     } else if byakugan_config.watch.notify && !new_prs.is_empty() {
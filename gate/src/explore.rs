@@ -1,14 +1,24 @@
-use crate::ai_helper::{ask_ai, make_provider};
+use nakama_ai::{load_prompt, AiClient, AttachArgs, Message};
 use nakama_audit::{AuditEntry, AuditLog, Category, Outcome};
 use nakama_core::config::Config;
 use nakama_core::error::{NakamaError, NakamaResult};
+use nakama_core::tokens::floor_char_boundary;
 use nakama_core::trace::TraceContext;
 use nakama_core::types::ModelTier;
 use nakama_ui::NakamaUI;
 use std::time::Instant;
 
+/// Upper bound on the response body sent to the model, in tokens.
+const MAX_BODY_TOKENS: usize = 500;
+
 /// Explore an API by sending a GET to the base URL and analyzing the response with AI.
-pub async fn run(config: &Config, ui: &NakamaUI, base_url: &str) -> NakamaResult<()> {
+pub async fn run(
+    config: &Config,
+    ui: &NakamaUI,
+    base_url: &str,
+    attach: &AttachArgs,
+) -> NakamaResult<()> {
+    let attachments = attach.load()?;
     let trace = TraceContext::new("gate", "explore");
     let spinner = ui.step_start(&format!("Exploring {}...", base_url));
     let start = Instant::now();
@@ -51,7 +61,7 @@ pub async fn run(config: &Config, ui: &NakamaUI, base_url: &str) -> NakamaResult
 
     // Try to pretty-print if JSON
     let body_preview = if body.len() > 3000 {
-        format!("{}...\n\n[Truncated at 3000 chars, total: {} bytes]", &body[..3000], body.len())
+        format!("{}...\n\n[Truncated at 3000 chars, total: {} bytes]", &body[..floor_char_boundary(&body, 3000)], body.len())
     } else {
         body.clone()
     };
@@ -65,27 +75,25 @@ pub async fn run(config: &Config, ui: &NakamaUI, base_url: &str) -> NakamaResult
 
     // AI analysis of the API
     let ai_spinner = ui.step_start("Analyzing API with AI...");
-    match make_provider(config, ModelTier::Fast) {
-        Ok((provider, model)) => {
-            let system_prompt = r#"You are Gate, an API exploration assistant. Analyze this API response and provide:
-
-1. What kind of API this appears to be (REST, GraphQL, etc.)
-2. Any discoverable endpoints or links found in the response
-3. Authentication requirements if visible (API keys, OAuth, etc.)
-4. Content type and data format
-5. Suggestions for further exploration
+    match AiClient::new(config, crate::TOOL_NAME, ModelTier::Fast) {
+        Ok(ai) => {
+            let system_prompt = load_prompt(crate::TOOL_NAME, "explore", &[]);
 
-Be concise and actionable."#;
+            let mut budget = ai.context_budget(1024);
+            budget.reserve(&system_prompt);
+            budget.reserve(&header_lines.join(", "));
+            let body_excerpt = budget.at_most(MAX_BODY_TOKENS).fit_head(&body);
 
             let user_msg = format!(
-                "Explore this API:\nBase URL: {}\nStatus: {}\nHeaders: {}\nBody (first 2000 chars): {}",
+                "Explore this API:\nBase URL: {}\nStatus: {}\nHeaders: {}\nBody: {}",
                 base_url,
                 status,
                 header_lines.join(", "),
-                &body[..body.len().min(2000)]
+                body_excerpt
             );
 
-            match ask_ai(provider.as_ref(), system_prompt, &user_msg, &model, 1024, 0.3).await {
+            let message = Message::user(&user_msg).with_attachments(attachments);
+            match ai.send(&system_prompt, message, 1024, 0.3).await {
                 Ok(analysis) => {
                     ai_spinner.finish_with_success("API analysis complete");
                     ui.panel("AI API Analysis", &analysis);
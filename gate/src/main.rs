@@ -1,4 +1,3 @@
-mod ai_helper;
 mod explore;
 mod flow;
 mod mock;
@@ -6,6 +5,7 @@ mod test_endpoint;
 
 use anyhow::Result;
 use clap::{Parser, Subcommand};
+use nakama_ai::{AiArgs, AttachArgs};
 use nakama_core::Config;
 use nakama_log::init_logging;
 use nakama_ui::NakamaUI;
@@ -16,6 +16,9 @@ const TOOL_NAME: &str = "gate";
 #[derive(Parser, Debug)]
 #[command(name = TOOL_NAME, version, about, long_about = None)]
 struct Cli {
+    #[command(flatten)]
+    ai: AiArgs,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -34,6 +37,9 @@ enum Commands {
         /// The base URL of the API to explore
         #[arg()]
         base_url: String,
+
+        #[command(flatten)]
+        attach: AttachArgs,
     },
 
     /// Read a JSON/YAML spec file and list the endpoints found
@@ -56,17 +62,20 @@ enum Commands {
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let config = Config::load(TOOL_NAME).unwrap_or_default();
+    let (mut config, diagnostics) = Config::load_or_default(TOOL_NAME);
     let _log_guard = init_logging(TOOL_NAME, &config.logging)?;
     let ui = NakamaUI::from_config(&config);
+    ui.config_diagnostics(&diagnostics);
 
     let update_rx = nakama_update::spawn_check(&config.updates, env!("CARGO_PKG_VERSION"));
 
     let cli = Cli::parse();
+    cli.ai.apply(&mut config);
+    nakama_vault::startup(&config, &ui);
 
     let result = match cli.command {
         Commands::Test { url } => test_endpoint::run(&config, &ui, &url).await,
-        Commands::Explore { base_url } => explore::run(&config, &ui, &base_url).await,
+        Commands::Explore { base_url, attach } => explore::run(&config, &ui, &base_url, &attach).await,
         Commands::Mock { spec } => mock::run(&config, &ui, &spec).await,
         Commands::Flow { config_file } => flow::run(&config, &ui, &config_file).await,
         Commands::History => {
@@ -1,12 +1,16 @@
-use crate::ai_helper::{ask_ai, make_provider};
+use nakama_ai::{load_prompt, AiClient};
 use nakama_audit::{AuditEntry, AuditLog, Category, Outcome};
 use nakama_core::config::Config;
 use nakama_core::error::{NakamaError, NakamaResult};
+use nakama_core::tokens::floor_char_boundary;
 use nakama_core::trace::TraceContext;
 use nakama_core::types::ModelTier;
 use nakama_ui::NakamaUI;
 use std::time::Instant;
 
+/// Upper bound on the response body sent to the model, in tokens.
+const MAX_BODY_TOKENS: usize = 500;
+
 /// Test an API endpoint by sending an HTTP GET request and displaying the response.
 pub async fn run(config: &Config, ui: &NakamaUI, url: &str) -> NakamaResult<()> {
     let trace = TraceContext::new("gate", "test");
@@ -60,7 +64,7 @@ pub async fn run(config: &Config, ui: &NakamaUI, url: &str) -> NakamaResult<()>
 
     // Display body (truncate if very long)
     let display_body = if body.len() > 5000 {
-        format!("{}...\n\n[Body truncated at 5000 characters, total: {} bytes]", &body[..5000], body.len())
+        format!("{}...\n\n[Body truncated at 5000 characters, total: {} bytes]", &body[..floor_char_boundary(&body, 5000)], body.len())
     } else {
         body.clone()
     };
@@ -75,26 +79,24 @@ pub async fn run(config: &Config, ui: &NakamaUI, url: &str) -> NakamaResult<()>
 
     // Use AI to explain the response
     let ai_spinner = ui.step_start("Analyzing response with AI...");
-    match make_provider(config, ModelTier::Fast) {
-        Ok((provider, model)) => {
-            let system_prompt = r#"You are Gate, an API analysis assistant. Explain the API response concisely.
+    match AiClient::new(config, crate::TOOL_NAME, ModelTier::Fast) {
+        Ok(ai) => {
+            let system_prompt = load_prompt(crate::TOOL_NAME, "test", &[]);
 
-Rules:
-1. Summarize what the response contains.
-2. Note the status code and what it means.
-3. Highlight any interesting headers (auth, rate limiting, caching).
-4. If the body is JSON, describe the data structure briefly.
-5. Keep it to 3-5 sentences max."#;
+            let mut budget = ai.context_budget(512);
+            budget.reserve(&system_prompt);
+            budget.reserve(&header_lines.join(", "));
+            let body_excerpt = budget.at_most(MAX_BODY_TOKENS).fit_head(&body);
 
             let user_msg = format!(
-                "Analyze this API response:\nURL: {}\nStatus: {}\nHeaders: {}\nBody (first 2000 chars): {}",
+                "Analyze this API response:\nURL: {}\nStatus: {}\nHeaders: {}\nBody: {}",
                 url,
                 status,
                 header_lines.join(", "),
-                &body[..body.len().min(2000)]
+                body_excerpt
             );
 
-            match ask_ai(provider.as_ref(), system_prompt, &user_msg, &model, 512, 0.2).await {
+            match ai.ask( &system_prompt, &user_msg, 512, 0.2).await {
                 Ok(analysis) => {
                     ai_spinner.finish_with_success("Analysis complete");
                     ui.panel("AI Analysis", &analysis);
@@ -23,6 +23,8 @@ REPO="tchandrakar/nakama-cli-suite"
 INSTALL_DIR="${HOME}/.cargo/bin"
 
 TOOLS=(
+    nakama
+    nakama-vault
     zangetsu
     shinigami
     jogan
@@ -168,10 +170,14 @@ verbosity = "normal"
 [audit]
 enabled = true
 retention_days = 90
+auto_cleanup = true
 
 [updates]
 enabled = true
 check_interval_hours = 24
+
+[vault]
+rotation_warn_days = 90
 TOML
     chmod 600 "${HOME}/.nakama/config.toml"
     echo ""
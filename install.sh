@@ -22,6 +22,8 @@ PURPLE='\033[0;35m'
 NC='\033[0m'
 
 TOOLS=(
+    nakama
+    nakama-vault
     zangetsu
     shinigami
     jogan
@@ -214,6 +216,7 @@ verbosity = "normal"
 [audit]
 enabled = true
 retention_days = 90
+# auto_cleanup = true
 # chain_verification = true
 
 # [ipc]
@@ -223,6 +226,9 @@ retention_days = 90
 [updates]
 enabled = true
 check_interval_hours = 24
+
+[vault]
+rotation_warn_days = 90
 TOML
     chmod 600 "${HOME}/.nakama/config.toml"
     echo ""
@@ -1,4 +1,4 @@
-use crate::ai_helper::{ask_ai, make_provider};
+use nakama_ai::{load_prompt, AiClient, AttachArgs, Message};
 use crate::atlassian::AtlassianClient;
 use nakama_core::config::Config;
 use nakama_core::error::NakamaResult;
@@ -6,7 +6,13 @@ use nakama_core::types::ModelTier;
 use nakama_ui::NakamaUI;
 
 /// Ask a question across both Jira and Confluence.
-pub async fn run(config: &Config, ui: &NakamaUI, question: &str) -> NakamaResult<()> {
+pub async fn run(
+    config: &Config,
+    ui: &NakamaUI,
+    question: &str,
+    attach: &AttachArgs,
+) -> NakamaResult<()> {
+    let attachments = attach.load()?;
     let spinner = ui.step_start("Searching across Jira and Confluence...");
 
     let client = AtlassianClient::new()?;
@@ -43,18 +49,17 @@ pub async fn run(config: &Config, ui: &NakamaUI, question: &str) -> NakamaResult
     spinner.finish_with_success("Search complete");
 
     let spinner = ui.step_start("Analyzing results...");
-    let (provider, model) = make_provider(config, ModelTier::Balanced)?;
+    let ai = AiClient::new(config, crate::TOOL_NAME, ModelTier::Balanced)?;
 
-    let system_prompt = r#"You are Itachi, an Atlassian intelligence assistant. Answer the user's question using the provided Jira issues and Confluence pages as context.
-
-Be specific — reference issue keys and page titles. If the context doesn't fully answer the question, say what's missing."#;
+    let system_prompt = load_prompt(crate::TOOL_NAME, "ask", &[]);
 
     let user_msg = format!(
         "Question: {}\n\nJira Issues:\n{}\n\nConfluence Pages:\n{}",
         question, jira_context, wiki_context,
     );
 
-    let result = ask_ai(provider.as_ref(), system_prompt, &user_msg, &model, 2048, 0.3).await;
+    let message = Message::user(&user_msg).with_attachments(attachments);
+    let result = ai.send(&system_prompt, message, 2048, 0.3).await;
 
     match &result {
         Ok(content) => {
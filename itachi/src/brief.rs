@@ -1,4 +1,4 @@
-use crate::ai_helper::{ask_ai, make_provider};
+use nakama_ai::{load_prompt, AiClient};
 use crate::atlassian::AtlassianClient;
 use nakama_core::config::Config;
 use nakama_core::error::NakamaResult;
@@ -23,32 +23,14 @@ pub async fn run(config: &Config, ui: &NakamaUI, team: Option<&str>) -> NakamaRe
     spinner.finish_with_success(&format!("Found {} recent issues", result.total));
 
     let spinner = ui.step_start("Generating briefing...");
-    let (provider, model) = make_provider(config, ModelTier::Balanced)?;
+    let ai = AiClient::new(config, crate::TOOL_NAME, ModelTier::Balanced)?;
 
-    let system_prompt = r#"You are Itachi, a team briefing generator. Create a concise team briefing from recent Jira activity.
-
-Format:
-## Team Briefing
-
-### In Progress
-Issues actively being worked on.
-
-### Recently Completed
-Issues closed/done recently.
-
-### Blockers & Risks
-Issues that look stuck or high-priority unresolved items.
-
-### Key Metrics
-- Total active issues
-- Completion rate this week
-
-Keep it actionable and brief."#;
+    let system_prompt = load_prompt(crate::TOOL_NAME, "brief", &[]);
 
     let team_label = team.unwrap_or("All Teams");
     let user_msg = format!("Generate briefing for {}.\n\nRecent issues:\n{}", team_label, issues_summary);
 
-    let result = ask_ai(provider.as_ref(), system_prompt, &user_msg, &model, 2048, 0.3).await;
+    let result = ai.ask( &system_prompt, &user_msg, 2048, 0.3).await;
 
     match &result {
         Ok(content) => {
@@ -1,4 +1,4 @@
-use crate::ai_helper::{ask_ai, make_provider};
+use nakama_ai::{load_prompt, AiClient};
 use crate::atlassian::AtlassianClient;
 use nakama_audit::{AuditEntry, AuditLog, Category, Outcome};
 use nakama_core::config::Config;
@@ -15,17 +15,11 @@ pub async fn run(config: &Config, ui: &NakamaUI, query: &str) -> NakamaResult<()
 
     // First, translate natural language to JQL using AI
     let spinner = ui.step_start("Translating query to JQL...");
-    let (provider, model) = make_provider(config, ModelTier::Fast)?;
+    let ai = AiClient::new(config, crate::TOOL_NAME, ModelTier::Fast)?;
 
-    let system_prompt = r#"You are a Jira JQL expert. Convert the natural language query to a JQL search string.
-Return ONLY the JQL query, nothing else. No explanation.
+    let system_prompt = load_prompt(crate::TOOL_NAME, "jira", &[]);
 
-Examples:
-- "my open bugs" → assignee = currentUser() AND type = Bug AND status != Done
-- "high priority tasks this sprint" → priority in (High, Highest) AND sprint in openSprints()
-- "recently updated in PROJECT" → project = PROJECT AND updated >= -7d ORDER BY updated DESC"#;
-
-    let jql = ask_ai(provider.as_ref(), system_prompt, query, &model, 256, 0.1).await?;
+    let jql = ai.ask( &system_prompt, query, 256, 0.1).await?;
     let jql = jql.trim().trim_matches('`').trim();
     spinner.finish_with_success(&format!("JQL: {}", jql));
 
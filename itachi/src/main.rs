@@ -1,4 +1,3 @@
-mod ai_helper;
 mod ask;
 mod atlassian;
 mod brief;
@@ -10,6 +9,7 @@ mod wiki;
 
 use anyhow::Result;
 use clap::{Parser, Subcommand};
+use nakama_ai::{AiArgs, AttachArgs};
 use nakama_core::Config;
 use nakama_log::init_logging;
 use nakama_ui::NakamaUI;
@@ -20,6 +20,9 @@ const TOOL_NAME: &str = "itachi";
 #[derive(Parser, Debug)]
 #[command(name = TOOL_NAME, version, about, long_about = None)]
 struct Cli {
+    #[command(flatten)]
+    ai: AiArgs,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -42,6 +45,9 @@ enum Commands {
     Ask {
         #[arg()]
         question: String,
+
+        #[command(flatten)]
+        attach: AttachArgs,
     },
 
     /// Generate a team briefing from recent activity
@@ -73,18 +79,21 @@ enum Commands {
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let config = Config::load(TOOL_NAME).unwrap_or_default();
+    let (mut config, diagnostics) = Config::load_or_default(TOOL_NAME);
     let _log_guard = init_logging(TOOL_NAME, &config.logging)?;
     let ui = NakamaUI::from_config(&config);
+    ui.config_diagnostics(&diagnostics);
 
     let update_rx = nakama_update::spawn_check(&config.updates, env!("CARGO_PKG_VERSION"));
 
     let cli = Cli::parse();
+    cli.ai.apply(&mut config);
+    nakama_vault::startup(&config, &ui);
 
     let result = match cli.command {
         Commands::Jira { query } => jira::run(&config, &ui, &query).await,
         Commands::Wiki { query } => wiki::run(&config, &ui, &query).await,
-        Commands::Ask { question } => ask::run(&config, &ui, &question).await,
+        Commands::Ask { question, attach } => ask::run(&config, &ui, &question, &attach).await,
         Commands::Brief { team } => brief::run(&config, &ui, team.as_deref()).await,
         Commands::Standup => standup::run(&config, &ui).await,
         Commands::Create { issue_type, summary } => create::run(&config, &ui, &issue_type, &summary).await,
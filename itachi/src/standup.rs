@@ -1,4 +1,4 @@
-use crate::ai_helper::{ask_ai, make_provider};
+use nakama_ai::{load_prompt, AiClient};
 use crate::atlassian::AtlassianClient;
 use nakama_core::config::Config;
 use nakama_core::error::NakamaResult;
@@ -17,23 +17,11 @@ pub async fn run(config: &Config, ui: &NakamaUI) -> NakamaResult<()> {
     spinner.finish_with_success(&format!("Found {} recent issues", result.total));
 
     let spinner = ui.step_start("Generating standup...");
-    let (provider, model) = make_provider(config, ModelTier::Fast)?;
+    let ai = AiClient::new(config, crate::TOOL_NAME, ModelTier::Fast)?;
 
-    let system_prompt = r#"You are Itachi. Generate a standup summary from the user's Jira issues.
+    let system_prompt = load_prompt(crate::TOOL_NAME, "standup", &[]);
 
-Format:
-## Yesterday
-- What was worked on (issues with recent updates)
-
-## Today
-- What will be worked on (in-progress issues)
-
-## Blockers
-- Any blocked or stalled issues
-
-Reference actual issue keys. Be concise."#;
-
-    let result = ask_ai(provider.as_ref(), system_prompt, &format!("My Jira issues:\n{}", issues), &model, 1024, 0.3).await;
+    let result = ai.ask( &system_prompt, &format!("My Jira issues:\n{}", issues), 1024, 0.3).await;
 
     match &result {
         Ok(content) => {
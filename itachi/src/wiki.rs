@@ -1,4 +1,4 @@
-use crate::ai_helper::{ask_ai, make_provider};
+use nakama_ai::{load_prompt, AiClient};
 use crate::atlassian::AtlassianClient;
 use nakama_audit::{AuditEntry, AuditLog, Category, Outcome};
 use nakama_core::config::Config;
@@ -14,17 +14,11 @@ pub async fn run(config: &Config, ui: &NakamaUI, query: &str) -> NakamaResult<()
     let start = Instant::now();
 
     let spinner = ui.step_start("Translating query to CQL...");
-    let (provider, model) = make_provider(config, ModelTier::Fast)?;
+    let ai = AiClient::new(config, crate::TOOL_NAME, ModelTier::Fast)?;
 
-    let system_prompt = r#"You are a Confluence CQL expert. Convert the natural language query to a CQL search string.
-Return ONLY the CQL query, nothing else.
+    let system_prompt = load_prompt(crate::TOOL_NAME, "wiki", &[]);
 
-Examples:
-- "deployment docs" → type = page AND text ~ "deployment"
-- "onboarding guide for new engineers" → type = page AND text ~ "onboarding" AND text ~ "engineer"
-- "API documentation updated recently" → type = page AND text ~ "API" AND lastModified > now("-30d")"#;
-
-    let cql = ask_ai(provider.as_ref(), system_prompt, query, &model, 256, 0.1).await?;
+    let cql = ai.ask( &system_prompt, query, 256, 0.1).await?;
     let cql = cql.trim().trim_matches('`').trim();
     spinner.finish_with_success(&format!("CQL: {}", cql));
 
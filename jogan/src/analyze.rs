@@ -1,6 +1,6 @@
 //! Read a log file, extract error patterns, and send to AI for root cause analysis.
 
-use crate::ai_helper::{ask_ai, make_provider};
+use nakama_ai::{load_prompt, AiClient};
 use nakama_audit::{AuditEntry, AuditLog, Category, Outcome};
 use nakama_core::config::Config;
 use nakama_core::error::NakamaResult;
@@ -92,19 +92,11 @@ pub async fn run(config: &Config, ui: &NakamaUI, log_path: &str) -> NakamaResult
 
     // Send to AI
     let spinner = ui.step_start("Analyzing log patterns with AI...");
-    let (provider, model) = make_provider(config, ModelTier::Balanced)?;
+    let ai = AiClient::new(config, crate::TOOL_NAME, ModelTier::Balanced)?;
 
-    let system_prompt = r#"You are Jogan, an infrastructure debugging assistant. Analyze the log file summary and error patterns provided. Give:
+    let system_prompt = load_prompt(crate::TOOL_NAME, "analyze", &[]);
 
-1. **Root Cause**: The most likely root cause of the errors.
-2. **Error Classification**: Categorize the errors (e.g., connectivity, resource exhaustion, configuration, application bug).
-3. **Timeline**: If timestamps are available, describe when issues started and any patterns.
-4. **Recommendations**: Concrete steps to fix the issues.
-5. **Priority**: Which errors to address first and why.
-
-Be concise and actionable."#;
-
-    let result = ask_ai(provider.as_ref(), system_prompt, &error_summary, &model, 2048, 0.3).await;
+    let result = ai.ask( &system_prompt, &error_summary, 2048, 0.3).await;
     let elapsed = start.elapsed().as_millis() as u64;
 
     match &result {
@@ -123,7 +115,7 @@ Be concise and actionable."#;
                         "log_path": log_path,
                         "total_lines": total_lines,
                         "error_count": error_lines.len(),
-                        "model": model,
+                        "model": ai.model(),
                     }),
                     Outcome::Success,
                     elapsed,
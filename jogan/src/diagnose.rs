@@ -1,6 +1,6 @@
 //! Run system diagnostics and send results to AI for analysis.
 
-use crate::ai_helper::{ask_ai, make_provider};
+use nakama_ai::{load_prompt, AiClient, AttachArgs, Message};
 use crate::system::SystemInfo;
 use nakama_audit::{AuditEntry, AuditLog, Category, Outcome};
 use nakama_core::config::Config;
@@ -11,7 +11,13 @@ use nakama_ui::NakamaUI;
 use std::time::Instant;
 
 /// Run system diagnostics, display results in a table, and send to AI for analysis.
-pub async fn run(config: &Config, ui: &NakamaUI, symptom: &str) -> NakamaResult<()> {
+pub async fn run(
+    config: &Config,
+    ui: &NakamaUI,
+    symptom: &str,
+    attach: &AttachArgs,
+) -> NakamaResult<()> {
+    let attachments = attach.load()?;
     let trace = TraceContext::new("jogan", "diagnose");
     let start = Instant::now();
 
@@ -27,16 +33,9 @@ pub async fn run(config: &Config, ui: &NakamaUI, symptom: &str) -> NakamaResult<
 
     // Send to AI for analysis
     let spinner = ui.step_start("Analyzing diagnostics with AI...");
-    let (provider, model) = make_provider(config, ModelTier::Balanced)?;
+    let ai = AiClient::new(config, crate::TOOL_NAME, ModelTier::Balanced)?;
 
-    let system_prompt = r#"You are Jogan, an infrastructure debugging assistant. The user reports a symptom and provides system diagnostics. Analyze the diagnostics data and:
-
-1. **Diagnosis**: Identify the most likely root cause of the symptom.
-2. **Evidence**: Point to specific metrics that support your diagnosis.
-3. **Recommendations**: Suggest concrete steps to resolve the issue.
-4. **Severity**: Rate the severity as LOW, MEDIUM, HIGH, or CRITICAL.
-
-Be concise and actionable."#;
+    let system_prompt = load_prompt(crate::TOOL_NAME, "diagnose", &[]);
 
     let report = info.format_report();
     let user_message = format!(
@@ -44,7 +43,8 @@ Be concise and actionable."#;
         symptom, report
     );
 
-    let result = ask_ai(provider.as_ref(), system_prompt, &user_message, &model, 2048, 0.3).await;
+    let message = Message::user(&user_message).with_attachments(attachments);
+    let result = ai.send(&system_prompt, message, 2048, 0.3).await;
     let elapsed = start.elapsed().as_millis() as u64;
 
     match &result {
@@ -61,8 +61,8 @@ Be concise and actionable."#;
                     &format!("Diagnosed symptom: {}", truncate(symptom, 100)),
                     serde_json::json!({
                         "symptom": symptom,
-                        "model": model,
-                        "provider": provider.provider_name(),
+                        "model": ai.model(),
+                        "provider": ai.provider().provider_name(),
                     }),
                     Outcome::Success,
                     elapsed,
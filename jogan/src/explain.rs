@@ -1,6 +1,6 @@
 //! Take an error message and send to AI for explanation.
 
-use crate::ai_helper::{ask_ai, make_provider};
+use nakama_ai::{load_prompt, AiClient, AttachArgs, Message};
 use nakama_audit::{AuditEntry, AuditLog, Category, Outcome};
 use nakama_core::config::Config;
 use nakama_core::error::NakamaResult;
@@ -10,25 +10,23 @@ use nakama_ui::NakamaUI;
 use std::time::Instant;
 
 /// Take an error message or infrastructure concept and explain it via AI.
-pub async fn run(config: &Config, ui: &NakamaUI, resource: &str) -> NakamaResult<()> {
+pub async fn run(
+    config: &Config,
+    ui: &NakamaUI,
+    resource: &str,
+    attach: &AttachArgs,
+) -> NakamaResult<()> {
+    let attachments = attach.load()?;
     let trace = TraceContext::new("jogan", "explain");
     let start = Instant::now();
 
     let spinner = ui.step_start(&format!("Explaining: {}", truncate(resource, 60)));
-    let (provider, model) = make_provider(config, ModelTier::Balanced)?;
+    let ai = AiClient::new(config, crate::TOOL_NAME, ModelTier::Balanced)?;
 
-    let system_prompt = r#"You are Jogan, an infrastructure debugging assistant. The user will provide an error message, infrastructure resource, or concept. Explain it clearly:
+    let system_prompt = load_prompt(crate::TOOL_NAME, "explain", &[]);
 
-1. **What It Means**: A clear, plain-language explanation.
-2. **Common Causes**: Why this error or situation typically occurs.
-3. **How to Fix**: Step-by-step resolution instructions.
-4. **Prevention**: How to prevent this from happening again.
-
-If it is a concept rather than an error (e.g., "k8s pod", "nginx config"), explain what it is, how it works, and common best practices.
-
-Be concise and practical."#;
-
-    let result = ask_ai(provider.as_ref(), system_prompt, resource, &model, 2048, 0.3).await;
+    let message = Message::user(resource).with_attachments(attachments);
+    let result = ai.send(&system_prompt, message, 2048, 0.3).await;
     let elapsed = start.elapsed().as_millis() as u64;
 
     match &result {
@@ -45,8 +43,8 @@ Be concise and practical."#;
                     &format!("Explained: {}", truncate(resource, 100)),
                     serde_json::json!({
                         "resource": resource,
-                        "model": model,
-                        "provider": provider.provider_name(),
+                        "model": ai.model(),
+                        "provider": ai.provider().provider_name(),
                     }),
                     Outcome::Success,
                     elapsed,
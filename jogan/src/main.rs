@@ -1,4 +1,3 @@
-mod ai_helper;
 mod analyze;
 mod diagnose;
 mod explain;
@@ -8,6 +7,7 @@ mod system;
 
 use anyhow::Result;
 use clap::{Parser, Subcommand};
+use nakama_ai::{AiArgs, AttachArgs};
 use nakama_core::Config;
 use nakama_log::init_logging;
 use nakama_ui::NakamaUI;
@@ -18,6 +18,9 @@ const TOOL_NAME: &str = "jogan";
 #[derive(Parser, Debug)]
 #[command(name = TOOL_NAME, version, about, long_about = None)]
 struct Cli {
+    #[command(flatten)]
+    ai: AiArgs,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -29,6 +32,9 @@ enum Commands {
         /// The symptom to investigate (e.g., "high latency", "connection refused")
         #[arg()]
         symptom: String,
+
+        #[command(flatten)]
+        attach: AttachArgs,
     },
 
     /// Analyze a log file for error patterns and root causes
@@ -46,6 +52,9 @@ enum Commands {
         /// The error message or concept to explain (e.g., "ECONNREFUSED", "k8s pod")
         #[arg()]
         resource: String,
+
+        #[command(flatten)]
+        attach: AttachArgs,
     },
 
     /// Scan the local environment for common infrastructure issues
@@ -64,19 +73,22 @@ enum Commands {
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let config = Config::load(TOOL_NAME).unwrap_or_default();
+    let (mut config, diagnostics) = Config::load_or_default(TOOL_NAME);
     let _log_guard = init_logging(TOOL_NAME, &config.logging)?;
     let ui = NakamaUI::from_config(&config);
+    ui.config_diagnostics(&diagnostics);
 
     let update_rx = nakama_update::spawn_check(&config.updates, env!("CARGO_PKG_VERSION"));
 
     let cli = Cli::parse();
+    cli.ai.apply(&mut config);
+    nakama_vault::startup(&config, &ui);
 
     let result = match cli.command {
-        Commands::Diagnose { symptom } => diagnose::run(&config, &ui, &symptom).await,
+        Commands::Diagnose { symptom, attach } => diagnose::run(&config, &ui, &symptom, &attach).await,
         Commands::Analyze { logfile } => analyze::run(&config, &ui, &logfile).await,
         Commands::Health => health::run(&config, &ui).await,
-        Commands::Explain { resource } => explain::run(&config, &ui, &resource).await,
+        Commands::Explain { resource, attach } => explain::run(&config, &ui, &resource, &attach).await,
         Commands::Scan => {
             println!("[scan] Coming soon: infrastructure issue scanner");
             Ok(())
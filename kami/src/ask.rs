@@ -1,4 +1,4 @@
-use crate::ai_helper::{ask_ai, make_provider};
+use nakama_ai::{collect_stream, load_prompt, AiClient, AttachArgs, Message, SessionArgs};
 use nakama_audit::{AuditEntry, AuditLog, Category, Outcome};
 use nakama_core::config::Config;
 use nakama_core::error::NakamaResult;
@@ -8,31 +8,48 @@ use nakama_ui::NakamaUI;
 use std::time::Instant;
 
 /// Ask a question and get a grounded, sourced answer.
-pub async fn run(config: &Config, ui: &NakamaUI, question: &str) -> NakamaResult<()> {
+pub async fn run(
+    config: &Config,
+    ui: &NakamaUI,
+    question: &str,
+    attach: &AttachArgs,
+    session: &SessionArgs,
+) -> NakamaResult<()> {
+    let attachments = attach.load()?;
+    let mut conversation = session.open(crate::TOOL_NAME)?;
     let trace = TraceContext::new("kami", "ask");
     let spinner = ui.step_start("Thinking...");
     let start = Instant::now();
 
-    let (provider, model) = make_provider(config, ModelTier::Balanced)?;
+    let ai = AiClient::new(config, crate::TOOL_NAME, ModelTier::Balanced)?;
 
-    let system_prompt = r#"You are Kami, a knowledgeable Q&A assistant. Answer questions accurately and concisely.
+    let system_prompt = load_prompt(crate::TOOL_NAME, "ask", &[]);
 
-Rules:
-1. Be direct — answer the question first, then provide context.
-2. If you're not sure, say "I'm not certain, but..." and give your best understanding.
-3. For technical questions, include code examples when helpful.
-4. For factual questions, be precise and cite timeframes when relevant.
-5. Structure longer answers with headers and bullet points.
-6. Keep answers focused — don't over-explain simple questions."#;
+    let message = Message::user(question).with_attachments(attachments);
+    let stream = match conversation.prepare(&ai, &system_prompt, message, 2048, 0.3).await {
+        Ok(request) => ai.provider().complete_stream(request).await,
+        Err(e) => Err(e),
+    };
+    spinner.finish_and_clear();
 
-    let result = ask_ai(provider.as_ref(), system_prompt, question, &model, 2048, 0.3).await;
+    let result = match stream {
+        Ok(stream) => {
+            let mut panel = ui.stream_panel("Answer");
+            let response = collect_stream(stream, |delta| panel.push(delta)).await;
+            panel.finish();
+            response
+        }
+        Err(e) => Err(e),
+    };
 
     let elapsed = start.elapsed().as_millis() as u64;
 
     match &result {
-        Ok(content) => {
-            spinner.finish_with_success("Answer ready");
-            ui.panel("Answer", content);
+        Ok(response) => {
+            ui.step_done("Answer ready");
+            if let Err(e) = conversation.record(&response.content) {
+                ui.warn(&format!("Failed to save session: {}", e));
+            }
 
             if let Ok(audit) = AuditLog::new(&config.audit) {
                 let entry = AuditEntry::new(
@@ -43,8 +60,9 @@ Rules:
                     &format!("Q&A: {}", &question[..question.len().min(100)]),
                     serde_json::json!({
                         "question": question,
-                        "model": model,
-                        "provider": provider.provider_name(),
+                        "session": conversation.is_stored().then(|| &conversation.session().name),
+                        "model": ai.model(),
+                        "provider": ai.provider().provider_name(),
                     }),
                     Outcome::Success,
                     elapsed,
@@ -53,7 +71,7 @@ Rules:
             }
         }
         Err(e) => {
-            spinner.finish_with_error(&format!("Failed: {}", e));
+            ui.step_fail(&format!("Failed: {}", e));
         }
     }
 
@@ -1,4 +1,4 @@
-use crate::ai_helper::{ask_ai, make_provider};
+use nakama_ai::{load_prompt, AiClient};
 use nakama_audit::{AuditEntry, AuditLog, Category, Outcome};
 use nakama_core::config::Config;
 use nakama_core::error::NakamaResult;
@@ -14,31 +14,12 @@ pub async fn run(config: &Config, ui: &NakamaUI, items: &[String]) -> NakamaResu
     let spinner = ui.step_start(&format!("Comparing: {}...", items_str));
     let start = Instant::now();
 
-    let (provider, model) = make_provider(config, ModelTier::Balanced)?;
+    let ai = AiClient::new(config, crate::TOOL_NAME, ModelTier::Balanced)?;
 
-    let system_prompt = r#"You are Kami, a comparison analyst. The user will provide items to compare.
-
-Provide a structured comparison:
-
-## Overview
-Brief 1-2 sentence overview of what's being compared.
-
-## Strengths
-For each item, list its unique strengths (2-3 bullets each).
-
-## Weaknesses
-For each item, list its weaknesses or limitations (2-3 bullets each).
-
-## Use Cases
-When to prefer each item — provide specific scenarios.
-
-## Recommendation
-Your suggestion based on common use cases. Be opinionated but acknowledge trade-offs.
-
-Be objective, specific, and practical. Use concrete examples over vague statements."#;
+    let system_prompt = load_prompt(crate::TOOL_NAME, "compare", &[]);
 
     let user_msg = format!("Please compare: {}", items_str);
-    let result = ask_ai(provider.as_ref(), system_prompt, &user_msg, &model, 3072, 0.4).await;
+    let result = ai.ask( &system_prompt, &user_msg, 3072, 0.4).await;
 
     let elapsed = start.elapsed().as_millis() as u64;
 
@@ -56,8 +37,8 @@ Be objective, specific, and practical. Use concrete examples over vague statemen
                     &format!("Compared: {}", items_str),
                     serde_json::json!({
                         "items": items,
-                        "model": model,
-                        "provider": provider.provider_name(),
+                        "model": ai.model(),
+                        "provider": ai.provider().provider_name(),
                     }),
                     Outcome::Success,
                     elapsed,
@@ -1,4 +1,4 @@
-use crate::ai_helper::{ask_ai, make_provider};
+use nakama_ai::{collect_stream, load_prompt, AiClient};
 use nakama_audit::{AuditEntry, AuditLog, Category, Outcome};
 use nakama_core::config::Config;
 use nakama_core::error::NakamaResult;
@@ -13,49 +13,28 @@ pub async fn run(config: &Config, ui: &NakamaUI, query: &str) -> NakamaResult<()
     let spinner = ui.step_start("Deep researching (this may take a moment)...");
     let start = Instant::now();
 
-    let (provider, model) = make_provider(config, ModelTier::Powerful)?;
+    let ai = AiClient::new(config, crate::TOOL_NAME, ModelTier::Powerful)?;
 
-    let system_prompt = r#"You are Kami, performing a deep research analysis. Provide a thorough, well-structured research report on the given topic.
+    let system_prompt = load_prompt(crate::TOOL_NAME, "deep", &[]);
 
-Structure your response as follows:
+    let stream = ai.ask_stream( &system_prompt, query, 4096, 0.5).await;
+    spinner.finish_and_clear();
 
-## Executive Summary
-A brief 2-3 sentence overview.
-
-## Background
-Historical context and foundational concepts.
-
-## Current State
-What is the current state of this topic? Recent developments, trends.
-
-## Key Findings
-Detailed analysis with the most important discoveries and insights:
-1. Finding 1: ...
-2. Finding 2: ...
-3. Finding 3: ...
-
-## Technical Details
-If applicable, provide technical depth with code examples, specifications, or data.
-
-## Challenges & Considerations
-Known issues, trade-offs, or areas of debate.
-
-## Future Outlook
-Where is this heading? Predictions and emerging trends.
-
-## Conclusion
-Final summary and recommendations.
-
-Be thorough and analytical. Cite concepts and frameworks where relevant. Aim for depth over breadth."#;
-
-    let result = ask_ai(provider.as_ref(), system_prompt, query, &model, 4096, 0.5).await;
+    let result = match stream {
+        Ok(stream) => {
+            let mut panel = ui.stream_panel(&format!("Deep Research: {}", query));
+            let response = collect_stream(stream, |delta| panel.push(delta)).await;
+            panel.finish();
+            response
+        }
+        Err(e) => Err(e),
+    };
 
     let elapsed = start.elapsed().as_millis() as u64;
 
     match &result {
-        Ok(content) => {
-            spinner.finish_with_success(&format!("Deep research complete ({:.1}s)", elapsed as f64 / 1000.0));
-            ui.panel(&format!("Deep Research: {}", query), content);
+        Ok(_) => {
+            ui.step_done(&format!("Deep research complete ({:.1}s)", elapsed as f64 / 1000.0));
 
             if let Ok(audit) = AuditLog::new(&config.audit) {
                 let entry = AuditEntry::new(
@@ -66,8 +45,8 @@ Be thorough and analytical. Cite concepts and frameworks where relevant. Aim for
                     &format!("Deep research on: {}", &query[..query.len().min(100)]),
                     serde_json::json!({
                         "query": query,
-                        "model": model,
-                        "provider": provider.provider_name(),
+                        "model": ai.model(),
+                        "provider": ai.provider().provider_name(),
                         "duration_ms": elapsed,
                     }),
                     Outcome::Success,
@@ -77,7 +56,7 @@ Be thorough and analytical. Cite concepts and frameworks where relevant. Aim for
             }
         }
         Err(e) => {
-            spinner.finish_with_error(&format!("Deep research failed: {}", e));
+            ui.step_fail(&format!("Deep research failed: {}", e));
         }
     }
 
@@ -1,4 +1,4 @@
-use crate::ai_helper::{ask_ai, make_provider};
+use nakama_ai::{load_prompt, AiClient};
 use nakama_audit::{AuditEntry, AuditLog, Category, Outcome};
 use nakama_core::config::Config;
 use nakama_core::error::NakamaResult;
@@ -13,32 +13,12 @@ pub async fn run(config: &Config, ui: &NakamaUI, claim: &str) -> NakamaResult<()
     let spinner = ui.step_start("Fact-checking...");
     let start = Instant::now();
 
-    let (provider, model) = make_provider(config, ModelTier::Powerful)?;
+    let ai = AiClient::new(config, crate::TOOL_NAME, ModelTier::Powerful)?;
 
-    let system_prompt = r#"You are Kami, a fact-checking assistant. The user will provide a claim to verify.
-
-Analyze the claim and respond with this structure:
-
-## Verdict
-One of: TRUE, MOSTLY TRUE, MIXED, MOSTLY FALSE, FALSE, UNVERIFIABLE
-
-## Analysis
-Explain why this claim is or isn't accurate. Break down the claim into verifiable components.
-
-## Evidence For
-- List evidence or reasoning that supports the claim.
-
-## Evidence Against
-- List evidence or reasoning that contradicts the claim.
-
-## Nuance
-Important context, caveats, or conditions that affect the claim's accuracy.
-
-## Confidence
-Rate your confidence: HIGH, MEDIUM, or LOW — and explain why."#;
+    let system_prompt = load_prompt(crate::TOOL_NAME, "grounded", &[]);
 
     let user_msg = format!("Please fact-check this claim: \"{}\"", claim);
-    let result = ask_ai(provider.as_ref(), system_prompt, &user_msg, &model, 2048, 0.2).await;
+    let result = ai.ask( &system_prompt, &user_msg, 2048, 0.2).await;
 
     let elapsed = start.elapsed().as_millis() as u64;
 
@@ -56,8 +36,8 @@ Rate your confidence: HIGH, MEDIUM, or LOW — and explain why."#;
                     &format!("Fact-checked: {}", &claim[..claim.len().min(100)]),
                     serde_json::json!({
                         "claim": claim,
-                        "model": model,
-                        "provider": provider.provider_name(),
+                        "model": ai.model(),
+                        "provider": ai.provider().provider_name(),
                     }),
                     Outcome::Success,
                     elapsed,
@@ -1,4 +1,3 @@
-mod ai_helper;
 mod ask;
 mod compare;
 mod deep;
@@ -8,6 +7,7 @@ mod summarize;
 
 use anyhow::Result;
 use clap::{Parser, Subcommand};
+use nakama_ai::{AiArgs, AttachArgs, SessionArgs};
 use nakama_core::Config;
 use nakama_log::init_logging;
 use nakama_ui::NakamaUI;
@@ -18,6 +18,9 @@ const TOOL_NAME: &str = "kami";
 #[derive(Parser, Debug)]
 #[command(name = TOOL_NAME, version, about, long_about = None)]
 struct Cli {
+    #[command(flatten)]
+    ai: AiArgs,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -50,6 +53,12 @@ enum Commands {
         /// The question to answer
         #[arg()]
         question: String,
+
+        #[command(flatten)]
+        attach: AttachArgs,
+
+        #[command(flatten)]
+        session: SessionArgs,
     },
 
     /// Fact-check a claim
@@ -69,19 +78,26 @@ enum Commands {
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let config = Config::load(TOOL_NAME).unwrap_or_default();
+    let (mut config, diagnostics) = Config::load_or_default(TOOL_NAME);
     let _log_guard = init_logging(TOOL_NAME, &config.logging)?;
     let ui = NakamaUI::from_config(&config);
+    ui.config_diagnostics(&diagnostics);
 
     let update_rx = nakama_update::spawn_check(&config.updates, env!("CARGO_PKG_VERSION"));
 
     let cli = Cli::parse();
+    cli.ai.apply(&mut config);
+    nakama_vault::startup(&config, &ui);
 
     let result = match cli.command {
         Commands::Search { query } => search::run(&config, &ui, &query).await,
         Commands::Deep { query } => deep::run(&config, &ui, &query).await,
         Commands::Summarize { url } => summarize::run(&config, &ui, &url).await,
-        Commands::Ask { question } => ask::run(&config, &ui, &question).await,
+        Commands::Ask {
+            question,
+            attach,
+            session,
+        } => ask::run(&config, &ui, &question, &attach, &session).await,
         Commands::Grounded { claim } => grounded::run(&config, &ui, &claim).await,
         Commands::Compare { items } => compare::run(&config, &ui, &items).await,
     };
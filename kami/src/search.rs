@@ -1,7 +1,8 @@
-use crate::ai_helper::{ask_ai, make_provider};
+use nakama_ai::{load_prompt, AiClient};
 use nakama_audit::{AuditEntry, AuditLog, Category, Outcome};
 use nakama_core::config::Config;
 use nakama_core::error::NakamaResult;
+use nakama_core::tokens::floor_char_boundary;
 use nakama_core::trace::TraceContext;
 use nakama_core::types::ModelTier;
 use nakama_ui::NakamaUI;
@@ -13,26 +14,11 @@ pub async fn run(config: &Config, ui: &NakamaUI, query: &str) -> NakamaResult<()
     let spinner = ui.step_start("Searching...");
     let start = Instant::now();
 
-    let (provider, model) = make_provider(config, ModelTier::Balanced)?;
+    let ai = AiClient::new(config, crate::TOOL_NAME, ModelTier::Balanced)?;
 
-    let system_prompt = r#"You are Kami, an AI-powered search assistant. The user will provide a search query.
-Provide a comprehensive, well-structured answer with the following sections:
+    let system_prompt = load_prompt(crate::TOOL_NAME, "search", &[]);
 
-## Answer
-A clear, concise answer to the query.
-
-## Key Points
-- Bullet points of the most important information.
-
-## Details
-More detailed explanation if needed.
-
-## Related Topics
-- Suggest related topics the user might want to explore.
-
-Be factual, concise, and helpful. If you're not certain about something, say so."#;
-
-    let result = ask_ai(provider.as_ref(), system_prompt, query, &model, 2048, 0.4).await;
+    let result = ai.ask( &system_prompt, query, 2048, 0.4).await;
 
     let elapsed = start.elapsed().as_millis() as u64;
 
@@ -51,8 +37,8 @@ Be factual, concise, and helpful. If you're not certain about something, say so.
                     &format!("AI search for: {}", truncate(query, 100)),
                     serde_json::json!({
                         "query": query,
-                        "model": model,
-                        "provider": provider.provider_name(),
+                        "model": ai.model(),
+                        "provider": ai.provider().provider_name(),
                     }),
                     Outcome::Success,
                     elapsed,
@@ -82,10 +68,22 @@ Be factual, concise, and helpful. If you're not certain about something, say so.
     result.map(|_| ())
 }
 
+/// The longest prefix of `s` of at most `max` bytes that ends on a char
+/// boundary.
 fn truncate(s: &str, max: usize) -> &str {
-    if s.len() <= max {
-        s
-    } else {
-        &s[..max]
+    &s[..floor_char_boundary(s, max)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_truncate_multibyte_query() {
+        let query = "検索".repeat(40);
+        let truncated = truncate(&query, 100);
+        assert_eq!(truncated.len(), 99);
+        assert!(query.starts_with(truncated));
+        assert_eq!(truncate("short", 100), "short");
     }
 }
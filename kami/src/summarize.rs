@@ -1,4 +1,4 @@
-use crate::ai_helper::{ask_ai, make_provider};
+use nakama_ai::{load_prompt, AiClient};
 use nakama_audit::{AuditEntry, AuditLog, Category, Outcome};
 use nakama_core::config::Config;
 use nakama_core::error::{NakamaError, NakamaResult};
@@ -7,6 +7,9 @@ use nakama_core::types::ModelTier;
 use nakama_ui::NakamaUI;
 use std::time::Instant;
 
+/// Upper bound on the page text sent to the model, in tokens.
+const MAX_CONTENT_TOKENS: usize = 3_000;
+
 /// Fetch a URL's content and summarize it using AI.
 pub async fn run(config: &Config, ui: &NakamaUI, url: &str) -> NakamaResult<()> {
     let trace = TraceContext::new("kami", "summarize");
@@ -45,33 +48,24 @@ pub async fn run(config: &Config, ui: &NakamaUI, url: &str) -> NakamaResult<()>
     // Strip HTML tags
     let text = strip_html(&body);
 
-    // Truncate to reasonable size for AI context
-    let truncated = if text.len() > 12000 {
-        format!("{}...\n\n[Content truncated at 12000 characters]", &text[..12000])
-    } else {
-        text
-    };
-
     let spinner = ui.step_start("Summarizing...");
 
-    let (provider, model) = make_provider(config, ModelTier::Balanced)?;
-
-    let system_prompt = r#"You are Kami, a content summarization assistant. The user will provide text content from a URL.
-Provide a structured summary:
+    let ai = AiClient::new(config, crate::TOOL_NAME, ModelTier::Balanced)?;
 
-## Summary
-A concise 2-3 paragraph summary of the content.
+    let system_prompt = load_prompt(crate::TOOL_NAME, "summarize", &[]);
 
-## Key Takeaways
-- The most important points as bullet items.
-
-## Topics Covered
-- List of main topics/sections covered.
-
-Be concise and accurate. Focus on the most important information."#;
+    // Trim to what fits in the model's context
+    let mut budget = ai.context_budget(2048);
+    budget.reserve(&system_prompt);
+    let fitted = budget.at_most(MAX_CONTENT_TOKENS).fit_head(&text);
+    let truncated = if fitted.len() < text.len() {
+        format!("{}...\n\n[Content truncated at {} tokens]", fitted, MAX_CONTENT_TOKENS)
+    } else {
+        text.clone()
+    };
 
     let user_msg = format!("Please summarize this content from {}:\n\n{}", url, truncated);
-    let result = ask_ai(provider.as_ref(), system_prompt, &user_msg, &model, 2048, 0.3).await;
+    let result = ai.ask( &system_prompt, &user_msg, 2048, 0.3).await;
 
     let elapsed = start.elapsed().as_millis() as u64;
 
@@ -90,7 +84,7 @@ Be concise and accurate. Focus on the most important information."#;
                     serde_json::json!({
                         "url": url,
                         "content_length": body.len(),
-                        "model": model,
+                        "model": ai.model(),
                     }),
                     Outcome::Success,
                     elapsed,
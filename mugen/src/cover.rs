@@ -1,4 +1,4 @@
-use crate::ai_helper::{ask_ai, make_provider};
+use nakama_ai::{load_prompt, AiClient};
 use nakama_audit::{AuditEntry, AuditLog, Category, Outcome};
 use nakama_core::config::Config;
 use nakama_core::error::NakamaResult;
@@ -66,18 +66,11 @@ pub async fn run(config: &Config, ui: &NakamaUI) -> NakamaResult<()> {
 
     // Send untested files to AI for analysis
     let spinner = ui.step_start("Analyzing gaps with AI...");
-    let (provider, model) = make_provider(config, ModelTier::Fast)?;
+    let ai = AiClient::new(config, crate::TOOL_NAME, ModelTier::Fast)?;
 
     let file_list = untested.iter().take(30).map(|f| f.as_str()).collect::<Vec<_>>().join("\n");
 
-    let system_prompt = r#"You are Mugen, a test coverage analyst. Given a list of source files without tests, prioritize which files need tests most urgently.
-
-For each file, assess:
-1. Risk level (HIGH/MEDIUM/LOW) based on the filename/path
-2. Why tests are important for this file
-3. What types of tests to write
-
-Output a prioritized list with the most critical files first."#;
+    let system_prompt = load_prompt(crate::TOOL_NAME, "cover", &[]);
 
     let user_msg = format!(
         "These {} source files have no corresponding test files:\n\n{}",
@@ -85,7 +78,7 @@ Output a prioritized list with the most critical files first."#;
         file_list,
     );
 
-    let result = ask_ai(provider.as_ref(), system_prompt, &user_msg, &model, 2048, 0.3).await;
+    let result = ai.ask( &system_prompt, &user_msg, 2048, 0.3).await;
     let elapsed = start.elapsed().as_millis() as u64;
 
     match &result {
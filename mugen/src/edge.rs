@@ -1,4 +1,4 @@
-use crate::ai_helper::{ask_ai, make_provider};
+use nakama_ai::{load_prompt, AiClient};
 use nakama_audit::{AuditEntry, AuditLog, Category, Outcome};
 use nakama_core::config::Config;
 use nakama_core::error::NakamaResult;
@@ -8,6 +8,9 @@ use nakama_ui::NakamaUI;
 use std::path::Path;
 use std::time::Instant;
 
+/// Upper bound on the source code sent to the model, in tokens.
+const MAX_SOURCE_TOKENS: usize = 2_000;
+
 /// Generate edge-case tests for a function in a file.
 pub async fn run(config: &Config, ui: &NakamaUI, function: &str) -> NakamaResult<()> {
     let trace = TraceContext::new("mugen", "edge");
@@ -28,31 +31,20 @@ pub async fn run(config: &Config, ui: &NakamaUI, function: &str) -> NakamaResult
 
     let spinner = ui.step_start(&format!("Generating edge-case tests for {}...", func_name));
 
-    let (provider, model) = make_provider(config, ModelTier::Balanced)?;
-
-    let system_prompt = r#"You are Mugen, an expert at finding edge cases. Generate edge-case tests for the given function.
-
-Focus on:
-1. Boundary values (0, -1, MAX, MIN, empty)
-2. Null/None/nil inputs
-3. Type boundaries (overflow, underflow)
-4. Empty collections, single-element collections
-5. Unicode and special characters for string inputs
-6. Concurrent access patterns if applicable
-7. Error conditions and exception paths
-8. Large inputs (performance boundaries)
+    let ai = AiClient::new(config, crate::TOOL_NAME, ModelTier::Balanced)?;
 
-For each test, explain WHY this edge case is important.
-Output ready-to-use test code."#;
+    let system_prompt = load_prompt(crate::TOOL_NAME, "edge", &[]);
 
     let user_msg = if file_content.is_empty() {
         format!("Generate edge-case tests for this function: {}", func_name)
     } else {
-        let truncated = if file_content.len() > 8000 { &file_content[..8000] } else { &file_content };
+        let mut budget = ai.context_budget(3072);
+        budget.reserve(&system_prompt);
+        let truncated = budget.at_most(MAX_SOURCE_TOKENS).fit_head(&file_content);
         format!("Generate edge-case tests for the function `{}` in this code:\n\n```\n{}\n```", func_name, truncated)
     };
 
-    let result = ask_ai(provider.as_ref(), system_prompt, &user_msg, &model, 3072, 0.4).await;
+    let result = ai.ask( &system_prompt, &user_msg, 3072, 0.4).await;
     let elapsed = start.elapsed().as_millis() as u64;
 
     match &result {
@@ -64,7 +56,7 @@ Output ready-to-use test code."#;
                 let entry = AuditEntry::new(
                     &trace.trace_id, "mugen", "edge", Category::AiInteraction,
                     &format!("Edge-case tests for {}", func_name),
-                    serde_json::json!({ "function": func_name, "model": model }),
+                    serde_json::json!({ "function": func_name, "model": ai.model() }),
                     Outcome::Success, elapsed,
                 );
                 let _ = audit.log(entry);
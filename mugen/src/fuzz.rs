@@ -1,4 +1,4 @@
-use crate::ai_helper::{ask_ai, make_provider};
+use nakama_ai::{load_prompt, AiClient};
 use nakama_audit::{AuditEntry, AuditLog, Category, Outcome};
 use nakama_core::config::Config;
 use nakama_core::error::NakamaResult;
@@ -7,6 +7,9 @@ use nakama_core::types::ModelTier;
 use nakama_ui::NakamaUI;
 use std::time::Instant;
 
+/// Upper bound on the source code sent to the model, in tokens.
+const MAX_SOURCE_TOKENS: usize = 2_000;
+
 /// Generate fuzz test harnesses for a function.
 pub async fn run(config: &Config, ui: &NakamaUI, function: &str) -> NakamaResult<()> {
     let trace = TraceContext::new("mugen", "fuzz");
@@ -23,36 +26,23 @@ pub async fn run(config: &Config, ui: &NakamaUI, function: &str) -> NakamaResult
 
     let spinner = ui.step_start(&format!("Generating fuzz tests for {}...", func_name));
 
-    let (provider, model) = make_provider(config, ModelTier::Balanced)?;
-
-    let system_prompt = r#"You are Mugen, a fuzz testing expert. Generate fuzz test harnesses for the given function.
-
-Depending on the language, use the appropriate fuzz framework:
-- Rust: cargo-fuzz / libfuzzer (use `#![no_main]` and `libfuzzer_sys::fuzz_target!`)
-- Python: hypothesis or atheris
-- Go: go-fuzz / testing.F
-- JavaScript: jsfuzz
-- Other: property-based testing approach
-
-Include:
-1. The fuzz harness code
-2. Seed corpus suggestions (interesting inputs to start with)
-3. Instructions to run the fuzzer
-4. What kinds of bugs the fuzzer might find
+    let ai = AiClient::new(config, crate::TOOL_NAME, ModelTier::Balanced)?;
 
-Make the fuzz target robust — handle panics, don't crash on invalid UTF-8, etc."#;
+    let system_prompt = load_prompt(crate::TOOL_NAME, "fuzz", &[]);
 
     let user_msg = if file_content.is_empty() {
         format!("Generate a fuzz test harness for: {}", func_name)
     } else {
-        let truncated = if file_content.len() > 8000 { &file_content[..8000] } else { &file_content };
+        let mut budget = ai.context_budget(3072);
+        budget.reserve(&system_prompt);
+        let truncated = budget.at_most(MAX_SOURCE_TOKENS).fit_head(&file_content);
         format!(
             "Generate a fuzz test harness for `{}` in this code:\n\n```\n{}\n```",
             func_name, truncated
         )
     };
 
-    let result = ask_ai(provider.as_ref(), system_prompt, &user_msg, &model, 3072, 0.3).await;
+    let result = ai.ask( &system_prompt, &user_msg, 3072, 0.3).await;
     let elapsed = start.elapsed().as_millis() as u64;
 
     match &result {
@@ -64,7 +54,7 @@ Make the fuzz target robust — handle panics, don't crash on invalid UTF-8, etc
                 let entry = AuditEntry::new(
                     &trace.trace_id, "mugen", "fuzz", Category::AiInteraction,
                     &format!("Fuzz test for {}", func_name),
-                    serde_json::json!({ "function": func_name, "model": model }),
+                    serde_json::json!({ "function": func_name, "model": ai.model() }),
                     Outcome::Success, elapsed,
                 );
                 let _ = audit.log(entry);
@@ -1,4 +1,4 @@
-use crate::ai_helper::{ask_ai, make_provider};
+use nakama_ai::{load_prompt, AiClient};
 use nakama_audit::{AuditEntry, AuditLog, Category, Outcome};
 use nakama_core::config::Config;
 use nakama_core::error::{NakamaError, NakamaResult};
@@ -8,6 +8,9 @@ use nakama_ui::NakamaUI;
 use std::path::Path;
 use std::time::Instant;
 
+/// Upper bound on the source code sent to the model, in tokens.
+const MAX_SOURCE_TOKENS: usize = 2_500;
+
 /// Generate tests for a target file.
 pub async fn run(config: &Config, ui: &NakamaUI, target: &str) -> NakamaResult<()> {
     let trace = TraceContext::new("mugen", "gen");
@@ -28,33 +31,25 @@ pub async fn run(config: &Config, ui: &NakamaUI, target: &str) -> NakamaResult<(
 
     let spinner = ui.step_start(&format!("Generating {} tests for {}...", lang, target));
 
-    let (provider, model) = make_provider(config, ModelTier::Balanced)?;
-
-    let system_prompt = format!(
-        r#"You are Mugen, an expert test generator. Generate comprehensive tests for the given {} source code.
-
-Rules:
-1. Generate unit tests that cover all public functions/methods.
-2. Include edge cases: empty inputs, boundary values, error conditions.
-3. Use the standard testing framework for the language ({}).
-4. Include descriptive test names that explain what's being tested.
-5. Add comments explaining the test strategy for each test.
-6. Group related tests together.
-7. Generate the test code ONLY — no explanations outside of code comments.
+    let ai = AiClient::new(config, crate::TOOL_NAME, ModelTier::Balanced)?;
 
-Output format: Return the complete test file/module ready to be saved."#,
-        lang,
-        test_framework(&lang),
+    let system_prompt = load_prompt(
+        crate::TOOL_NAME,
+        "gen",
+        &[("language", &lang), ("test_framework", test_framework(&lang))],
     );
 
-    let truncated = if content.len() > 10000 {
-        format!("{}...\n[Truncated]", &content[..10000])
+    let mut budget = ai.context_budget(4096);
+    budget.reserve(&system_prompt);
+    let fitted = budget.at_most(MAX_SOURCE_TOKENS).fit_head(&content);
+    let truncated = if fitted.len() < content.len() {
+        format!("{}...\n[Truncated]", fitted)
     } else {
         content.clone()
     };
 
     let user_msg = format!("Generate tests for this {} file ({}):\n\n```{}\n{}\n```", lang, target, lang.to_lowercase(), truncated);
-    let result = ask_ai(provider.as_ref(), &system_prompt, &user_msg, &model, 4096, 0.3).await;
+    let result = ai.ask( &system_prompt, &user_msg, 4096, 0.3).await;
 
     let elapsed = start.elapsed().as_millis() as u64;
 
@@ -74,7 +69,7 @@ Output format: Return the complete test file/module ready to be saved."#,
                     "gen",
                     Category::AiInteraction,
                     &format!("Generated tests for {}", target),
-                    serde_json::json!({ "target": target, "language": lang, "model": model }),
+                    serde_json::json!({ "target": target, "language": lang, "model": ai.model() }),
                     Outcome::Success,
                     elapsed,
                 );
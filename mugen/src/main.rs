@@ -1,4 +1,3 @@
-mod ai_helper;
 mod cover;
 mod edge;
 mod fuzz;
@@ -8,6 +7,7 @@ mod review;
 
 use anyhow::Result;
 use clap::{Parser, Subcommand};
+use nakama_ai::AiArgs;
 use nakama_core::Config;
 use nakama_log::init_logging;
 use nakama_ui::NakamaUI;
@@ -18,6 +18,9 @@ const TOOL_NAME: &str = "mugen";
 #[derive(Parser, Debug)]
 #[command(name = TOOL_NAME, version, about, long_about = None)]
 struct Cli {
+    #[command(flatten)]
+    ai: AiArgs,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -65,13 +68,16 @@ enum Commands {
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let config = Config::load(TOOL_NAME).unwrap_or_default();
+    let (mut config, diagnostics) = Config::load_or_default(TOOL_NAME);
     let _log_guard = init_logging(TOOL_NAME, &config.logging)?;
     let ui = NakamaUI::from_config(&config);
+    ui.config_diagnostics(&diagnostics);
 
     let update_rx = nakama_update::spawn_check(&config.updates, env!("CARGO_PKG_VERSION"));
 
     let cli = Cli::parse();
+    cli.ai.apply(&mut config);
+    nakama_vault::startup(&config, &ui);
 
     let result = match cli.command {
         Commands::Gen { target } => gen::run(&config, &ui, &target).await,
@@ -1,4 +1,4 @@
-use crate::ai_helper::{ask_ai, make_provider};
+use nakama_ai::{load_prompt, AiClient};
 use nakama_audit::{AuditEntry, AuditLog, Category, Outcome};
 use nakama_core::config::Config;
 use nakama_core::error::{NakamaError, NakamaResult};
@@ -8,6 +8,9 @@ use nakama_ui::NakamaUI;
 use std::path::Path;
 use std::time::Instant;
 
+/// Upper bound on the source code sent to the model, in tokens.
+const MAX_SOURCE_TOKENS: usize = 2_500;
+
 /// Analyze a source file and suggest mutations to validate test quality.
 pub async fn run(config: &Config, ui: &NakamaUI, file: &str) -> NakamaResult<()> {
     let trace = TraceContext::new("mugen", "mutate");
@@ -24,31 +27,15 @@ pub async fn run(config: &Config, ui: &NakamaUI, file: &str) -> NakamaResult<()>
     let content = std::fs::read_to_string(path)?;
     let spinner = ui.step_start(&format!("Analyzing {} for mutation testing...", file));
 
-    let (provider, model) = make_provider(config, ModelTier::Balanced)?;
-
-    let system_prompt = r#"You are Mugen, a mutation testing expert. Analyze the given source code and suggest mutations that would test whether the existing test suite catches bugs.
-
-For each mutation:
-1. **Location**: Line number or code section
-2. **Original**: The original code
-3. **Mutation**: The mutated version
-4. **Type**: Category (boundary, operator, logic, return value, null check, etc.)
-5. **Expected**: Should tests catch this? If not, what test is missing?
-
-Common mutation types:
-- Replace `>` with `>=`, `<` with `<=`
-- Replace `&&` with `||`
-- Replace `+` with `-`
-- Remove null checks
-- Return early with default values
-- Off-by-one errors
-- Swap true/false returns
+    let ai = AiClient::new(config, crate::TOOL_NAME, ModelTier::Balanced)?;
 
-Suggest 8-12 mutations, prioritized by likelihood of catching real bugs."#;
+    let system_prompt = load_prompt(crate::TOOL_NAME, "mutate", &[]);
 
-    let truncated = if content.len() > 10000 { &content[..10000] } else { &content };
+    let mut budget = ai.context_budget(3072);
+    budget.reserve(&system_prompt);
+    let truncated = budget.at_most(MAX_SOURCE_TOKENS).fit_head(&content);
     let user_msg = format!("Suggest mutations for this file ({}):\n\n```\n{}\n```", file, truncated);
-    let result = ask_ai(provider.as_ref(), system_prompt, &user_msg, &model, 3072, 0.4).await;
+    let result = ai.ask( &system_prompt, &user_msg, 3072, 0.4).await;
     let elapsed = start.elapsed().as_millis() as u64;
 
     match &result {
@@ -60,7 +47,7 @@ Suggest 8-12 mutations, prioritized by likelihood of catching real bugs."#;
                 let entry = AuditEntry::new(
                     &trace.trace_id, "mugen", "mutate", Category::AiInteraction,
                     &format!("Mutation analysis for {}", file),
-                    serde_json::json!({ "file": file, "model": model }),
+                    serde_json::json!({ "file": file, "model": ai.model() }),
                     Outcome::Success, elapsed,
                 );
                 let _ = audit.log(entry);
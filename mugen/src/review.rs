@@ -1,4 +1,4 @@
-use crate::ai_helper::{ask_ai, make_provider};
+use nakama_ai::{load_prompt, AiClient};
 use nakama_audit::{AuditEntry, AuditLog, Category, Outcome};
 use nakama_core::config::Config;
 use nakama_core::error::{NakamaError, NakamaResult};
@@ -8,6 +8,9 @@ use nakama_ui::NakamaUI;
 use std::path::Path;
 use std::time::Instant;
 
+/// Upper bound on the source code sent to the model, in tokens.
+const MAX_SOURCE_TOKENS: usize = 2_500;
+
 /// Review an existing test file for quality and completeness.
 pub async fn run(config: &Config, ui: &NakamaUI, test_file: &str) -> NakamaResult<()> {
     let trace = TraceContext::new("mugen", "review");
@@ -24,38 +27,21 @@ pub async fn run(config: &Config, ui: &NakamaUI, test_file: &str) -> NakamaResul
     let content = std::fs::read_to_string(path)?;
     let spinner = ui.step_start(&format!("Reviewing test file: {}...", test_file));
 
-    let (provider, model) = make_provider(config, ModelTier::Balanced)?;
-
-    let system_prompt = r#"You are Mugen, a test quality expert. Review the given test file and provide:
-
-## Quality Score
-Rate 1-10 with justification.
-
-## Strengths
-What the tests do well.
-
-## Missing Coverage
-Functions or branches not covered by tests.
-
-## Improvements
-Specific suggestions to improve test quality:
-- Missing edge cases
-- Brittle assertions
-- Test isolation issues
-- Missing error path tests
-- Naming/organization improvements
+    let ai = AiClient::new(config, crate::TOOL_NAME, ModelTier::Balanced)?;
 
-## Suggested New Tests
-List specific new tests that should be added."#;
+    let system_prompt = load_prompt(crate::TOOL_NAME, "review", &[]);
 
-    let truncated = if content.len() > 10000 {
-        format!("{}...\n[Truncated]", &content[..10000])
+    let mut budget = ai.context_budget(3072);
+    budget.reserve(&system_prompt);
+    let fitted = budget.at_most(MAX_SOURCE_TOKENS).fit_head(&content);
+    let truncated = if fitted.len() < content.len() {
+        format!("{}...\n[Truncated]", fitted)
     } else {
         content.clone()
     };
 
     let user_msg = format!("Review this test file ({}):\n\n```\n{}\n```", test_file, truncated);
-    let result = ask_ai(provider.as_ref(), system_prompt, &user_msg, &model, 3072, 0.3).await;
+    let result = ai.ask( &system_prompt, &user_msg, 3072, 0.3).await;
     let elapsed = start.elapsed().as_millis() as u64;
 
     match &result {
@@ -67,7 +53,7 @@ List specific new tests that should be added."#;
                 let entry = AuditEntry::new(
                     &trace.trace_id, "mugen", "review", Category::AiInteraction,
                     &format!("Reviewed test file {}", test_file),
-                    serde_json::json!({ "file": test_file, "model": model }),
+                    serde_json::json!({ "file": test_file, "model": ai.model() }),
                     Outcome::Success, elapsed,
                 );
                 let _ = audit.log(entry);
@@ -0,0 +1,709 @@
+//! `nakama audit` — browse, verify and export the audit log.
+//!
+//! Every Nakama tool records its actions in `~/.nakama/audit/audit.db`.
+//! `list` (the default) shows matching entries, `show` prints one entry in
+//! full, `verify` walks the SHA-256 hash chain, `export` writes entries as
+//! JSONL or CSV for compliance reviews, `prune` archives entries past the
+//! retention period, and `anchor` signs the chain head with the vault-held
+//! anchor key so `verify` can detect a rewritten chain.  Time filters take
+//! dates, RFC 3339 timestamps or relative expressions such as `2d`, `12h`
+//! or `1 hour ago`.
+
+use crate::usage::{self, csv_field, OutputFormat, Period};
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Duration, NaiveDate, NaiveTime, Utc};
+use clap::{Args, Subcommand, ValueEnum};
+use nakama_audit::anchor::VerifyingKey;
+use nakama_audit::{AnchorLog, AnchorReport, AuditEntry, AuditFilter, AuditLog, Category, Outcome};
+use nakama_core::config::Config;
+use nakama_ipc::{pipe, NmpMessage};
+use nakama_ui::NakamaUI;
+use nakama_vault::{audit_anchor, Vault};
+use std::io::Write;
+use std::path::PathBuf;
+
+/// Widest action text shown in the entry table.
+const ACTION_WIDTH: usize = 50;
+
+/// Length of the entry ids shown in the table (`aud_` plus 12 hex digits);
+/// `show` accepts them as prefixes.
+const SHORT_ID_LEN: usize = 16;
+
+#[derive(Subcommand, Debug)]
+pub enum AuditCommand {
+    /// List audit entries, most recent first (the default)
+    List(ListArgs),
+
+    /// Print every field of one entry
+    Show {
+        /// Entry id, or a unique prefix of it
+        id: String,
+
+        /// Output format
+        #[arg(long, value_enum, default_value_t = ViewFormat::Table)]
+        format: ViewFormat,
+    },
+
+    /// Verify the integrity of the hash chain and its signed anchors
+    Verify {
+        /// Output format
+        #[arg(long, value_enum, default_value_t = ViewFormat::Table)]
+        format: ViewFormat,
+    },
+
+    /// Export entries, oldest first, for archiving or compliance review
+    Export {
+        #[command(flatten)]
+        filter: FilterArgs,
+
+        /// Export format
+        #[arg(long, value_enum, default_value_t = ExportFormat::Jsonl)]
+        format: ExportFormat,
+
+        /// Write to this file instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Archive entries older than the retention period and replace them
+    /// with a signed checkpoint
+    Prune {
+        /// Retention period in days (defaults to [audit] retention_days)
+        #[arg(long)]
+        retention_days: Option<u32>,
+
+        /// Only report how many entries would be pruned
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Sign the current chain head with the anchor key held in the vault
+    Anchor,
+
+    /// Show AI token usage and estimated cost (same as `nakama usage`)
+    Usage {
+        /// Reporting period
+        #[arg(long, value_enum, default_value_t = Period::Week)]
+        period: Period,
+
+        /// Only include usage by this tool
+        #[arg(long)]
+        tool: Option<String>,
+
+        /// Output format
+        #[arg(long, value_enum, default_value_t = OutputFormat::Table)]
+        format: OutputFormat,
+    },
+}
+
+#[derive(Args, Debug)]
+pub struct ListArgs {
+    #[command(flatten)]
+    filter: FilterArgs,
+
+    /// Maximum number of entries to show
+    #[arg(long, default_value_t = 50)]
+    limit: u32,
+
+    /// Output format
+    #[arg(long, value_enum, default_value_t = ViewFormat::Table)]
+    format: ViewFormat,
+}
+
+#[derive(Args, Debug)]
+pub struct FilterArgs {
+    /// Only entries from this tool
+    #[arg(long)]
+    tool: Option<String>,
+
+    /// Only entries in this category (e.g. ai_interaction, credential_access)
+    #[arg(long, value_parser = parse_category)]
+    category: Option<Category>,
+
+    /// Only entries at or after this time (2026-02-27, 2d, 12h, "1 hour ago")
+    #[arg(long)]
+    since: Option<String>,
+
+    /// Only entries at or before this time (a date includes the whole day)
+    #[arg(long)]
+    until: Option<String>,
+
+    /// Only entries of this trace (a cross-tool pipeline run)
+    #[arg(long)]
+    trace: Option<String>,
+
+    /// Only entries with this outcome (success, failure, denied, skipped)
+    #[arg(long, value_parser = parse_outcome)]
+    outcome: Option<Outcome>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ViewFormat {
+    /// Human-readable table
+    Table,
+    /// NMP message, for piping into other tools
+    Json,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ExportFormat {
+    /// One JSON entry per line
+    Jsonl,
+    Csv,
+}
+
+pub fn run(
+    config: &Config,
+    ui: &NakamaUI,
+    command: Option<AuditCommand>,
+    list_args: ListArgs,
+) -> Result<()> {
+    match command.unwrap_or(AuditCommand::List(list_args)) {
+        AuditCommand::List(args) => list(ui, &open(config, ui)?, args),
+        AuditCommand::Show { id, format } => show(ui, &open(config, ui)?, &id, format),
+        AuditCommand::Verify { format } => verify(ui, &open(config, ui)?, format),
+        AuditCommand::Export {
+            filter,
+            format,
+            output,
+        } => export(ui, &open(config, ui)?, &filter, format, output),
+        AuditCommand::Prune {
+            retention_days,
+            dry_run,
+        } => prune(
+            ui,
+            &open(config, ui)?,
+            retention_days.unwrap_or(config.audit.retention_days),
+            dry_run,
+        ),
+        AuditCommand::Anchor => anchor(ui, &open(config, ui)?),
+        AuditCommand::Usage {
+            period,
+            tool,
+            format,
+        } => usage::run(config, ui, period, tool.as_deref(), format),
+    }
+}
+
+fn open(config: &Config, ui: &NakamaUI) -> Result<AuditLog> {
+    if !config.audit.enabled {
+        ui.warn("Audit logging is disabled ([audit] enabled = false); showing previously recorded entries");
+    }
+    AuditLog::new(&config.audit).context("Failed to open audit log")
+}
+
+fn list(ui: &NakamaUI, audit: &AuditLog, args: ListArgs) -> Result<()> {
+    let filter = args.filter.to_filter(Utc::now())?.with_limit(args.limit);
+    let entries = audit.query(&filter).context("Failed to query audit log")?;
+
+    if args.format == ViewFormat::Json {
+        let data = serde_json::json!({ "count": entries.len(), "entries": entries });
+        pipe::write_stdout(&NmpMessage::new(
+            "nakama",
+            "audit list",
+            "audit.entries.v1",
+            data,
+        ))?;
+        return Ok(());
+    }
+
+    if entries.is_empty() {
+        ui.warn("No audit entries match.");
+        return Ok(());
+    }
+
+    let rows = entries
+        .iter()
+        .map(|entry| {
+            vec![
+                entry.id.chars().take(SHORT_ID_LEN).collect(),
+                entry.timestamp.format("%Y-%m-%d %H:%M:%S").to_string(),
+                entry.tool.clone(),
+                entry.category.to_string(),
+                truncate(&entry.action, ACTION_WIDTH),
+                entry.outcome.to_string(),
+                format_duration(entry.duration_ms),
+            ]
+        })
+        .collect();
+    ui.table(
+        &[
+            "ID",
+            "Time (UTC)",
+            "Tool",
+            "Category",
+            "Action",
+            "Outcome",
+            "Duration",
+        ],
+        rows,
+    );
+
+    let mut footer = format!("  Total: {} entries", entries.len());
+    if entries.len() as u32 == args.limit {
+        footer.push_str(" (limit reached; use --limit or narrow the filters)");
+    }
+    println!("{footer}");
+    Ok(())
+}
+
+fn show(ui: &NakamaUI, audit: &AuditLog, id: &str, format: ViewFormat) -> Result<()> {
+    let Some(entry) = audit.get(id)? else {
+        bail!("No audit entry with id '{id}'");
+    };
+
+    if format == ViewFormat::Json {
+        let data = serde_json::to_value(&entry)?;
+        pipe::write_stdout(&NmpMessage::with_trace_id(
+            &entry.trace_id,
+            "nakama",
+            "audit show",
+            "audit.entry.v1",
+            data,
+        ))?;
+        return Ok(());
+    }
+
+    let detail = serde_json::to_string_pretty(&entry.detail)?;
+    let mut content = format!(
+        "ID:       {}\nTime:     {}\nTrace:    {}\nTool:     {} {}\nCategory: {}\nAction:   {}\nOutcome:  {}\nDuration: {}",
+        entry.id,
+        entry.timestamp.to_rfc3339(),
+        entry.trace_id,
+        entry.tool,
+        entry.command,
+        entry.category,
+        entry.action,
+        entry.outcome,
+        format_duration(entry.duration_ms),
+    );
+    if let (Some(input), Some(output)) = (entry.input_tokens, entry.output_tokens) {
+        content.push_str(&format!("\nTokens:   {input} in / {output} out"));
+    }
+    if let Some(cost) = entry.cost_usd {
+        content.push_str(&format!("\nCost:     ${cost:.4}"));
+    }
+    if let Some(repo) = &entry.repo {
+        content.push_str(&format!("\nRepo:     {repo}"));
+    }
+    content.push_str(&format!("\n\nDetail:\n{detail}"));
+    ui.panel("Audit Entry", &content);
+    Ok(())
+}
+
+fn verify(ui: &NakamaUI, audit: &AuditLog, format: ViewFormat) -> Result<()> {
+    let report = audit
+        .verify_chain_report()
+        .context("Failed to verify audit chain")?;
+    let anchors = check_anchors(audit)?;
+    let checkpoint_trusted = report.checkpoint_key.map(check_checkpoint_key);
+
+    if format == ViewFormat::Json {
+        let anchors_json = match &anchors {
+            Ok(anchors) => serde_json::json!({
+                "valid": anchors.is_valid(),
+                "last_trusted": anchors.last_trusted,
+                "unanchored_entries": anchors.unanchored,
+                "untrusted": anchors.untrusted(),
+                "diverged": anchors.diverged().map(|c| &c.anchor.head_id).collect::<Vec<_>>(),
+            }),
+            Err(reason) => serde_json::json!({ "skipped": reason }),
+        };
+        let data = serde_json::json!({
+            "valid": report.is_valid()
+                && anchors.as_ref().map_or(true, AnchorReport::is_valid)
+                && !matches!(checkpoint_trusted, Some(Ok(false))),
+            "verified_entries": report.entries,
+            "head": report.head,
+            "broken_at": report.broken_at,
+            "checkpoint": report.checkpoint,
+            "checkpoint_trusted": checkpoint_trusted.as_ref().and_then(|t| t.as_ref().ok()),
+            "anchors": anchors_json,
+        });
+        pipe::write_stdout(&NmpMessage::new(
+            "nakama",
+            "audit verify",
+            "audit.verify.v1",
+            data,
+        ))?;
+    } else if report.is_valid() {
+        ui.success(&format!(
+            "Audit chain intact: {} entries verified",
+            report.entries
+        ));
+        if let Some(checkpoint) = &report.checkpoint {
+            println!(
+                "  Chain starts at retention checkpoint {checkpoint}; older entries are archived"
+            );
+        }
+        if let Some(Err(reason)) = &checkpoint_trusted {
+            println!("  Checkpoint signer not checked: {reason}");
+        }
+        match &anchors {
+            Ok(anchors) => {
+                match &anchors.last_trusted {
+                    Some(anchor) => println!(
+                        "  Last trusted anchor: {} at entry {} ({} entries since)",
+                        anchor.created_at.format("%Y-%m-%d %H:%M:%S UTC"),
+                        anchor.head_id,
+                        anchors.unanchored
+                    ),
+                    None => {
+                        println!("  No trusted anchor yet; run `nakama audit anchor` to create one")
+                    }
+                }
+                if anchors.untrusted() > 0 {
+                    ui.warn(&format!(
+                        "{} anchors are not signed by the vault's anchor key (rotated or forged) and were not checked",
+                        anchors.untrusted()
+                    ));
+                }
+            }
+            Err(reason) => println!("  Anchors not checked: {reason}"),
+        }
+    }
+
+    if let Some(id) = &report.broken_at {
+        bail!(
+            "Audit chain broken at entry {id} after {} valid entries; it or an earlier entry was modified or deleted",
+            report.entries
+        );
+    }
+    if let (Some(Ok(false)), Some(id)) = (&checkpoint_trusted, &report.checkpoint) {
+        bail!(
+            "Retention checkpoint {id} is not signed by the vault's anchor key; the pruned history cannot be trusted"
+        );
+    }
+    if let Some(check) = anchors.as_ref().ok().and_then(|a| a.diverged().next()) {
+        bail!(
+            "Audit anchor signed {} no longer matches entry {}; the chain up to it was rewritten",
+            check.anchor.created_at.to_rfc3339(),
+            check.anchor.head_id
+        );
+    }
+    Ok(())
+}
+
+/// Whether a retention checkpoint signed by `signer` was signed with the
+/// vault's anchor key, or why that could not be checked.
+fn check_checkpoint_key(signer: VerifyingKey) -> Result<bool, String> {
+    match Vault::new().and_then(|vault| audit_anchor::load(&vault)) {
+        Ok(Some(key)) => Ok(key.verifying_key() == signer),
+        Ok(None) => Err("no anchor key in the vault".to_string()),
+        Err(e) => Err(format!("cannot read the anchor key ({e})")),
+    }
+}
+
+/// Check the anchor file against the database, or say why it was skipped.
+fn check_anchors(audit: &AuditLog) -> Result<Result<AnchorReport, String>> {
+    let anchors = AnchorLog::open()?
+        .read()
+        .context("Failed to read audit anchors")?;
+    if anchors.is_empty() {
+        return Ok(Err("no anchors have been created".to_string()));
+    }
+    let key = match Vault::new().and_then(|vault| audit_anchor::load(&vault)) {
+        Ok(Some(key)) => key,
+        Ok(None) => return Ok(Err("no anchor key in the vault".to_string())),
+        Err(e) => return Ok(Err(format!("cannot read the anchor key ({e})"))),
+    };
+    let report = audit
+        .verify_anchors(&anchors, &key.verifying_key())
+        .context("Failed to verify audit anchors")?;
+    Ok(Ok(report))
+}
+
+fn export(
+    ui: &NakamaUI,
+    audit: &AuditLog,
+    filter: &FilterArgs,
+    format: ExportFormat,
+    output: Option<PathBuf>,
+) -> Result<()> {
+    let mut entries = audit
+        .query(&filter.to_filter(Utc::now())?)
+        .context("Failed to query audit log")?;
+    entries.reverse();
+
+    let rendered = match format {
+        ExportFormat::Jsonl => render_jsonl(&entries)?,
+        ExportFormat::Csv => render_csv(&entries),
+    };
+
+    match output {
+        Some(path) => {
+            std::fs::write(&path, rendered)
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+            ui.success(&format!(
+                "Exported {} entries to {}",
+                entries.len(),
+                path.display()
+            ));
+        }
+        None => std::io::stdout().lock().write_all(rendered.as_bytes())?,
+    }
+    Ok(())
+}
+
+fn prune(ui: &NakamaUI, audit: &AuditLog, retention_days: u32, dry_run: bool) -> Result<()> {
+    if retention_days == 0 {
+        ui.warn("Retention is disabled (retention_days = 0); nothing to prune");
+        return Ok(());
+    }
+    let cutoff = Utc::now() - Duration::days(i64::from(retention_days));
+
+    if dry_run {
+        let count = audit
+            .count_prunable(cutoff)
+            .context("Failed to count expired audit entries")?;
+        println!("  {count} entries are older than {retention_days} days and would be archived");
+        return Ok(());
+    }
+
+    let vault = Vault::new().context("Failed to open the vault")?;
+    let key =
+        audit_anchor::load_or_create(&vault).context("Failed to load the audit anchor key")?;
+    let report = audit
+        .prune(cutoff, &key)
+        .context("Failed to prune audit log")?;
+    match (&report.archive, &report.checkpoint) {
+        (Some(archive), Some(checkpoint)) => ui.success(&format!(
+            "Archived {} entries to {} (checkpoint {checkpoint})",
+            report.pruned,
+            archive.display()
+        )),
+        _ => ui.success(&format!(
+            "No entries older than {retention_days} days; nothing to prune"
+        )),
+    }
+    Ok(())
+}
+
+fn anchor(ui: &NakamaUI, audit: &AuditLog) -> Result<()> {
+    let vault = Vault::new().context("Failed to open the vault")?;
+    let key =
+        audit_anchor::load_or_create(&vault).context("Failed to load the audit anchor key")?;
+    let anchors = AnchorLog::open()?;
+    match audit
+        .anchor(&key, &anchors, Utc::now())
+        .context("Failed to anchor audit chain")?
+    {
+        Some(anchor) => ui.success(&format!(
+            "Anchored {} entries at entry {}",
+            anchor.entries, anchor.head_id
+        )),
+        None => ui.success("Audit chain head is already anchored (or the log is empty)"),
+    }
+    Ok(())
+}
+
+impl FilterArgs {
+    fn to_filter(&self, now: DateTime<Utc>) -> Result<AuditFilter> {
+        let mut filter = AuditFilter::new();
+        if let Some(tool) = &self.tool {
+            filter = filter.with_tool(tool);
+        }
+        if let Some(category) = self.category {
+            filter = filter.with_category(category);
+        }
+        if let Some(since) = &self.since {
+            filter = filter.with_since(parse_time(since, now, false).context("Invalid --since")?);
+        }
+        if let Some(until) = &self.until {
+            filter = filter.with_until(parse_time(until, now, true).context("Invalid --until")?);
+        }
+        if let Some(trace) = &self.trace {
+            filter = filter.with_trace_id(trace);
+        }
+        if let Some(outcome) = self.outcome {
+            filter = filter.with_outcome(outcome);
+        }
+        Ok(filter)
+    }
+}
+
+/// Parse a time expression relative to `now`.
+///
+/// Accepts RFC 3339 timestamps, `YYYY-MM-DD` dates (the start of the day, or
+/// its end when `end_of_day` is set), `today`, `yesterday`, `now`, and
+/// durations into the past such as `30m`, `12h`, `2d`, `1w` or `3 days ago`.
+fn parse_time(expr: &str, now: DateTime<Utc>, end_of_day: bool) -> Result<DateTime<Utc>> {
+    let expr = expr.trim();
+    if let Ok(time) = DateTime::parse_from_rfc3339(expr) {
+        return Ok(time.with_timezone(&Utc));
+    }
+
+    let day = match expr {
+        "now" => return Ok(now),
+        "today" => Some(now.date_naive()),
+        "yesterday" => Some(now.date_naive() - Duration::days(1)),
+        _ => NaiveDate::parse_from_str(expr, "%Y-%m-%d").ok(),
+    };
+    if let Some(day) = day {
+        let time = if end_of_day {
+            NaiveTime::from_hms_milli_opt(23, 59, 59, 999)
+        } else {
+            NaiveTime::from_hms_opt(0, 0, 0)
+        };
+        return Ok(day.and_time(time.expect("valid time")).and_utc());
+    }
+
+    let relative = expr.strip_suffix("ago").unwrap_or(expr).trim();
+    let split = relative
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(relative.len());
+    let (amount, unit) = relative.split_at(split);
+    let amount: i64 = amount
+        .parse()
+        .with_context(|| format!("Unrecognized time '{expr}' (try 2026-02-27, 2d or 12h)"))?;
+    let duration = match unit.trim() {
+        "s" | "sec" | "secs" | "second" | "seconds" => Duration::try_seconds(amount),
+        "m" | "min" | "mins" | "minute" | "minutes" => Duration::try_minutes(amount),
+        "h" | "hr" | "hrs" | "hour" | "hours" => Duration::try_hours(amount),
+        "d" | "day" | "days" => Duration::try_days(amount),
+        "w" | "week" | "weeks" => Duration::try_weeks(amount),
+        other => bail!("Unknown time unit '{other}' in '{expr}' (use s, m, h, d or w)"),
+    };
+    duration
+        .and_then(|duration| now.checked_sub_signed(duration))
+        .with_context(|| format!("Time '{expr}' is too far in the past"))
+}
+
+fn parse_category(value: &str) -> Result<Category, String> {
+    value.parse()
+}
+
+fn parse_outcome(value: &str) -> Result<Outcome, String> {
+    value.parse()
+}
+
+fn render_jsonl(entries: &[AuditEntry]) -> Result<String> {
+    let mut out = String::new();
+    for entry in entries {
+        out.push_str(&serde_json::to_string(entry)?);
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+fn render_csv(entries: &[AuditEntry]) -> String {
+    let mut out = String::from(
+        "id,timestamp,trace_id,tool,command,category,action,outcome,duration_ms,input_tokens,output_tokens,cost_usd,repo,detail\n",
+    );
+    let optional = |value: Option<String>| value.unwrap_or_default();
+    for entry in entries {
+        out.push_str(&format!(
+            "{},{},{},{},{},{},{},{},{},{},{},{},{},{}\n",
+            entry.id,
+            entry.timestamp.to_rfc3339(),
+            csv_field(&entry.trace_id),
+            csv_field(&entry.tool),
+            csv_field(&entry.command),
+            entry.category,
+            csv_field(&entry.action),
+            entry.outcome,
+            entry.duration_ms,
+            optional(entry.input_tokens.map(|t| t.to_string())),
+            optional(entry.output_tokens.map(|t| t.to_string())),
+            optional(entry.cost_usd.map(|c| c.to_string())),
+            optional(entry.repo.as_deref().map(csv_field)),
+            csv_field(&entry.detail.to_string()),
+        ));
+    }
+    out
+}
+
+fn format_duration(ms: u64) -> String {
+    match ms {
+        0 => "-".to_string(),
+        ms if ms < 1000 => format!("{ms}ms"),
+        ms => format!("{:.1}s", ms as f64 / 1000.0),
+    }
+}
+
+/// Truncate to `max_chars` characters, adding an ellipsis if needed.
+fn truncate(s: &str, max_chars: usize) -> String {
+    if s.chars().count() <= max_chars {
+        s.to_string()
+    } else {
+        let kept: String = s.chars().take(max_chars.saturating_sub(1)).collect();
+        format!("{kept}…")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn now() -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339("2026-03-10T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    #[test]
+    fn test_parse_relative_times() {
+        assert_eq!(
+            parse_time("2d", now(), false).unwrap(),
+            now() - Duration::days(2)
+        );
+        assert_eq!(
+            parse_time("12h", now(), false).unwrap(),
+            now() - Duration::hours(12)
+        );
+        assert_eq!(
+            parse_time("1 hour ago", now(), false).unwrap(),
+            now() - Duration::hours(1)
+        );
+        assert_eq!(
+            parse_time("3 weeks", now(), false).unwrap(),
+            now() - Duration::weeks(3)
+        );
+        assert!(parse_time("2 fortnights", now(), false).is_err());
+        assert!(parse_time("soon", now(), false).is_err());
+        assert!(parse_time("99999999999d", now(), false).is_err());
+        assert!(parse_time("9999999999999w", now(), false).is_err());
+    }
+
+    #[test]
+    fn test_parse_dates() {
+        assert_eq!(
+            parse_time("2026-02-27", now(), false).unwrap().to_rfc3339(),
+            "2026-02-27T00:00:00+00:00"
+        );
+        assert_eq!(
+            parse_time("2026-02-28", now(), true).unwrap().to_rfc3339(),
+            "2026-02-28T23:59:59.999+00:00"
+        );
+        assert_eq!(
+            parse_time("yesterday", now(), false).unwrap().to_rfc3339(),
+            "2026-03-09T00:00:00+00:00"
+        );
+        assert_eq!(
+            parse_time("2026-03-01T08:30:00+02:00", now(), false)
+                .unwrap()
+                .to_rfc3339(),
+            "2026-03-01T06:30:00+00:00"
+        );
+    }
+
+    #[test]
+    fn test_csv_escapes_detail() {
+        let entry = AuditEntry::new(
+            "tr_1",
+            "zangetsu",
+            "ask",
+            Category::AiInteraction,
+            "Asked, then answered",
+            serde_json::json!({"model": "m"}),
+            Outcome::Success,
+            10,
+        )
+        .with_usage(120, 30, 0.25);
+        let csv = render_csv(&[entry]);
+        let row = csv.lines().nth(1).unwrap();
+        assert!(row.contains("\"Asked, then answered\""));
+        assert!(row.contains(",10,120,30,0.25,"));
+        assert!(row.ends_with("\"{\"\"model\"\":\"\"m\"\"}\""));
+    }
+}
@@ -0,0 +1,416 @@
+//! `nakama config` — inspect, validate and scaffold the configuration.
+//!
+//! Settings are merged from the built-in defaults, `~/.nakama/config.toml`,
+//! the project's `.nakama.toml`, the tool's `~/.<tool>/config.toml` and
+//! `NAKAMA_*` environment variables.  `show` prints the result with the
+//! layer each value came from, `validate` checks it for mistakes the tools
+//! would otherwise only hit at request time, and `init` writes a commented
+//! config with every default.
+
+use anyhow::{bail, Context, Result};
+use clap::Subcommand;
+use nakama_ai::PriceTable;
+use nakama_core::config::{LoadedConfig, DEFAULT_CONFIG_TEMPLATE};
+use nakama_core::types::Provider;
+use nakama_core::{paths, Config};
+use nakama_ui::NakamaUI;
+
+#[derive(Subcommand, Debug)]
+pub enum ConfigCommand {
+    /// Print the effective configuration and where each value comes from
+    Show {
+        /// Include the config of this tool (~/.<tool>/config.toml)
+        #[arg(long)]
+        tool: Option<String>,
+
+        /// Print the merged configuration as TOML
+        #[arg(long)]
+        toml: bool,
+    },
+
+    /// Check the configuration for errors, unknown keys, bad model names
+    /// and invalid byakugan rules
+    Validate {
+        /// Include the config of this tool (~/.<tool>/config.toml)
+        #[arg(long)]
+        tool: Option<String>,
+    },
+
+    /// Write a commented config with every default to ~/.nakama/config.toml
+    Init {
+        /// Overwrite an existing config file
+        #[arg(long)]
+        force: bool,
+
+        /// Print the config instead of writing it
+        #[arg(long)]
+        stdout: bool,
+    },
+}
+
+/// Keys whose values are never printed.
+const SECRET_KEYS: &[&str] = &["token", "app_password", "password", "api_key"];
+
+/// Review passes byakugan knows.
+const BYAKUGAN_PASSES: &[&str] = &["security", "performance", "style", "logic", "summary"];
+
+/// Severities of byakugan findings and rules.
+const SEVERITIES: &[&str] = &["critical", "high", "medium", "low"];
+
+pub fn run(ui: &NakamaUI, command: ConfigCommand) -> Result<()> {
+    match command {
+        ConfigCommand::Show { tool, toml } => {
+            let loaded = load(tool.as_deref())?;
+            show(ui, &loaded, toml)
+        }
+        ConfigCommand::Validate { tool } => {
+            let loaded = load(tool.as_deref())?;
+            validate(ui, &loaded)
+        }
+        ConfigCommand::Init { force, stdout } => init(ui, force, stdout),
+    }
+}
+
+fn load(tool: Option<&str>) -> Result<LoadedConfig> {
+    Config::load_layered(tool.unwrap_or(crate::TOOL_NAME)).context("Failed to load configuration")
+}
+
+fn show(ui: &NakamaUI, loaded: &LoadedConfig, as_toml: bool) -> Result<()> {
+    let mut effective =
+        toml::Value::try_from(&loaded.config).context("Failed to serialize configuration")?;
+    mask_secrets(&mut effective);
+
+    if as_toml {
+        print!("{}", toml::to_string_pretty(&effective)?);
+        return Ok(());
+    }
+
+    let layers: Vec<String> = loaded.layers.iter().map(|layer| layer.describe()).collect();
+    ui.panel("Config layers (later override earlier)", &layers.join("\n"));
+
+    let mut rows = Vec::new();
+    flatten(loaded, &effective, "", &[], &mut rows);
+    ui.table(&["Key", "Value", "Source"], rows);
+
+    for error in &loaded.errors {
+        ui.error(&format!("{} (ignored)", error));
+    }
+    for warning in &loaded.warnings {
+        ui.warn(warning);
+    }
+    Ok(())
+}
+
+/// One row per setting.  Plain arrays are shown whole; arrays of tables
+/// (byakugan rules) get a row per field, attributed to the array's layer.
+fn flatten(
+    loaded: &LoadedConfig,
+    value: &toml::Value,
+    key: &str,
+    source_path: &[String],
+    rows: &mut Vec<Vec<String>>,
+) {
+    match value {
+        toml::Value::Table(table) => {
+            for (name, value) in table {
+                let key = if key.is_empty() {
+                    name.clone()
+                } else {
+                    format!("{}.{}", key, name)
+                };
+                let mut path = source_path.to_vec();
+                path.push(name.clone());
+                flatten(loaded, value, &key, &path, rows);
+            }
+        }
+        toml::Value::Array(items) if items.iter().any(toml::Value::is_table) => {
+            for (index, item) in items.iter().enumerate() {
+                flatten(
+                    loaded,
+                    item,
+                    &format!("{}[{}]", key, index),
+                    source_path,
+                    rows,
+                );
+            }
+        }
+        _ => {
+            let source = loaded
+                .source_of(source_path)
+                .map(|layer| layer.source.to_string())
+                .unwrap_or_else(|| "default".to_string());
+            let shown = match value {
+                toml::Value::String(s) => s.clone(),
+                other => other.to_string(),
+            };
+            rows.push(vec![key.to_string(), shown, source]);
+        }
+    }
+}
+
+fn mask_secrets(value: &mut toml::Value) {
+    if let Some(table) = value.as_table_mut() {
+        for (key, value) in table.iter_mut() {
+            if SECRET_KEYS.contains(&key.as_str()) && value.is_str() {
+                *value = toml::Value::String("********".to_string());
+            } else {
+                mask_secrets(value);
+            }
+        }
+    }
+}
+
+fn validate(ui: &NakamaUI, loaded: &LoadedConfig) -> Result<()> {
+    let problems = check(&loaded.config);
+
+    let errors: Vec<&String> = loaded.errors.iter().chain(&problems.errors).collect();
+    let warnings: Vec<&String> = loaded.warnings.iter().chain(&problems.warnings).collect();
+    for error in &errors {
+        ui.error(error);
+    }
+    for warning in &warnings {
+        ui.warn(warning);
+    }
+
+    if !errors.is_empty() {
+        bail!(
+            "{} error(s), {} warning(s) in configuration",
+            errors.len(),
+            warnings.len()
+        );
+    }
+    if warnings.is_empty() {
+        ui.success(&format!(
+            "Configuration is valid ({} layers).",
+            loaded.layers.len()
+        ));
+    } else {
+        ui.info(&format!(
+            "Configuration is usable, with {} warning(s).",
+            warnings.len()
+        ));
+    }
+    Ok(())
+}
+
+#[derive(Debug, Default)]
+struct Problems {
+    errors: Vec<String>,
+    warnings: Vec<String>,
+}
+
+/// Check settings that parse fine but would fail, or silently misbehave,
+/// at run time.
+fn check(config: &Config) -> Problems {
+    let mut problems = Problems::default();
+    let ai = &config.ai;
+    let prices = PriceTable::from_config(ai);
+
+    let hosted = [
+        (Provider::Anthropic, &ai.anthropic),
+        (Provider::OpenAI, &ai.openai),
+        (Provider::Google, &ai.google),
+    ];
+    for (provider, models) in hosted {
+        for (tier, model) in [
+            ("model_fast", &models.model_fast),
+            ("model_balanced", &models.model_balanced),
+            ("model_powerful", &models.model_powerful),
+        ] {
+            check_model(
+                &mut problems,
+                provider,
+                &format!("ai.{}.{}", provider, tier),
+                model,
+                &prices,
+            );
+        }
+    }
+    if let Some(model) = &ai.model {
+        check_model(
+            &mut problems,
+            ai.default_provider,
+            "ai.model",
+            model,
+            &prices,
+        );
+    }
+    for (key, model) in [
+        ("ai.ollama.model_fast", &ai.ollama.model_fast),
+        ("ai.ollama.model_balanced", &ai.ollama.model_balanced),
+    ] {
+        if model.trim().is_empty() {
+            problems.errors.push(format!("{} is empty", key));
+        }
+    }
+
+    let uses_compatible = ai.default_provider == Provider::OpenAICompatible
+        || ai.fallback_providers.contains(&Provider::OpenAICompatible);
+    if uses_compatible {
+        if let Err(e) = config.compatible_endpoint() {
+            problems.errors.push(e.to_string());
+        }
+    }
+    let mut endpoints: Vec<_> = ai.openai_compatible.iter().collect();
+    endpoints.sort_by_key(|(name, _)| name.as_str());
+    for (name, endpoint) in endpoints {
+        if !endpoint.base_url.starts_with("http://") && !endpoint.base_url.starts_with("https://") {
+            problems.errors.push(format!(
+                "ai.openai_compatible.{}.base_url must be an http(s) URL, got '{}'",
+                name, endpoint.base_url
+            ));
+        }
+        if endpoint.model_fast.is_empty()
+            && endpoint.model_balanced.is_empty()
+            && endpoint.model_powerful.is_empty()
+        {
+            problems.warnings.push(format!(
+                "ai.openai_compatible.{} has no models configured",
+                name
+            ));
+        }
+    }
+
+    let audit = &config.audit;
+    if audit.batch_write && audit.max_batch_size == 0 {
+        problems
+            .warnings
+            .push("audit.max_batch_size is 0; entries are written one at a time".to_string());
+    }
+
+    let byakugan = &config.byakugan;
+    for pass in &byakugan.passes {
+        if !BYAKUGAN_PASSES.contains(&pass.as_str()) {
+            problems.errors.push(format!(
+                "byakugan.passes: unknown pass '{}' (expected one of {})",
+                pass,
+                BYAKUGAN_PASSES.join(", ")
+            ));
+        }
+    }
+    if !SEVERITIES.contains(&byakugan.severity_threshold.as_str()) {
+        problems.errors.push(format!(
+            "byakugan.severity_threshold: unknown severity '{}' (expected one of {})",
+            byakugan.severity_threshold,
+            SEVERITIES.join(", ")
+        ));
+    }
+    for (index, rule) in byakugan.rules.iter().enumerate() {
+        let key = format!("byakugan.rules[{}] ({})", index, rule.name);
+        if let Err(e) = regex::Regex::new(&rule.pattern) {
+            problems
+                .errors
+                .push(format!("{}: invalid pattern: {}", key, e));
+        }
+        for exclude in &rule.exclude {
+            if let Err(e) = glob::Pattern::new(exclude) {
+                problems.errors.push(format!(
+                    "{}: invalid exclude glob '{}': {}",
+                    key, exclude, e
+                ));
+            }
+        }
+        if !SEVERITIES.contains(&rule.severity.to_lowercase().as_str()) {
+            problems.warnings.push(format!(
+                "{}: unknown severity '{}', treated as medium",
+                key, rule.severity
+            ));
+        }
+        if byakugan.rules[..index]
+            .iter()
+            .any(|other| other.name == rule.name)
+        {
+            problems
+                .warnings
+                .push(format!("{}: duplicate rule name", key));
+        }
+    }
+
+    problems
+}
+
+/// A hosted provider's model must belong to that provider.  One without a
+/// known price is most likely a typo, and its cost isn't tracked either.
+fn check_model(
+    problems: &mut Problems,
+    provider: Provider,
+    key: &str,
+    model: &str,
+    prices: &PriceTable,
+) {
+    let families: &[&str] = match provider {
+        Provider::Anthropic => &["claude-"],
+        Provider::OpenAI => &["gpt-", "chatgpt-", "o1", "o3", "o4"],
+        Provider::Google => &["gemini-"],
+        Provider::Ollama | Provider::OpenAICompatible => &[],
+    };
+
+    if model.trim().is_empty() {
+        problems.errors.push(format!("{} is empty", key));
+    } else if !families.is_empty() && !families.iter().any(|family| model.starts_with(family)) {
+        problems.errors.push(format!(
+            "{}: '{}' is not a model of {}",
+            key, model, provider
+        ));
+    } else if !families.is_empty() && prices.lookup(model).is_none() {
+        problems.warnings.push(format!(
+            "{}: unknown model '{}' (check for typos, or add its price under [ai.pricing])",
+            key, model
+        ));
+    }
+}
+
+fn init(ui: &NakamaUI, force: bool, stdout: bool) -> Result<()> {
+    if stdout {
+        print!("{}", DEFAULT_CONFIG_TEMPLATE);
+        return Ok(());
+    }
+
+    let path = paths::global_config_path()?;
+    if path.exists() && !force {
+        bail!(
+            "{} already exists; pass --force to overwrite it",
+            path.display()
+        );
+    }
+    paths::ensure_nakama_dirs()?;
+    std::fs::write(&path, DEFAULT_CONFIG_TEMPLATE)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    nakama_core::permissions::set_file_permissions(&path)?;
+    ui.success(&format!("Wrote {}", path.display()));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check() {
+        assert!(check(&Config::default()).errors.is_empty());
+        assert!(check(&Config::default()).warnings.is_empty());
+
+        let config: Config = toml::from_str(
+            r#"
+            [ai.anthropic]
+            model_fast = "gpt-4.1"
+            model_balanced = "claude-sonet-4-6"
+            model_powerful = "claude-opus-4-6"
+            [byakugan]
+            passes = ["security", "secruity"]
+            [[byakugan.rules]]
+            name = "todo"
+            pattern = "TODO("
+            exclude = ["[docs"]
+            severity = "urgent"
+        "#,
+        )
+        .unwrap();
+        let problems = check(&config);
+        assert_eq!(problems.errors.len(), 4, "{:?}", problems.errors);
+        assert!(problems.errors[0]
+            .starts_with("ai.anthropic.model_fast: 'gpt-4.1' is not a model of anthropic"));
+        assert_eq!(problems.warnings.len(), 2, "{:?}", problems.warnings);
+        assert!(problems.warnings[0].contains("claude-sonet-4-6"));
+    }
+}
@@ -0,0 +1,110 @@
+mod audit;
+mod config;
+mod prompts;
+mod sessions;
+mod usage;
+
+use anyhow::Result;
+use audit::{AuditCommand, ListArgs};
+use clap::{Parser, Subcommand};
+use config::ConfigCommand;
+use nakama_core::config::ConfigDiagnostics;
+use nakama_core::Config;
+use nakama_log::init_logging;
+use nakama_ui::NakamaUI;
+use prompts::PromptCommand;
+use sessions::SessionCommand;
+use usage::{OutputFormat, Period};
+
+const TOOL_NAME: &str = "nakama";
+
+/// Nakama - suite-wide utilities for the Nakama CLI Suite
+#[derive(Parser, Debug)]
+#[command(name = TOOL_NAME, version, about, long_about = None)]
+struct Cli {
+    #[command(subcommand)]
+    command: Commands,
+}
+
+#[derive(Subcommand, Debug)]
+enum Commands {
+    /// Show AI token usage and estimated cost across all tools
+    Usage {
+        /// Reporting period
+        #[arg(long, value_enum, default_value_t = Period::Week)]
+        period: Period,
+
+        /// Only include usage by this tool
+        #[arg(long)]
+        tool: Option<String>,
+
+        /// Output format
+        #[arg(long, value_enum, default_value_t = OutputFormat::Table)]
+        format: OutputFormat,
+    },
+
+    /// Browse, verify and export the audit log
+    #[command(args_conflicts_with_subcommands = true)]
+    Audit {
+        #[command(subcommand)]
+        command: Option<AuditCommand>,
+
+        #[command(flatten)]
+        list: ListArgs,
+    },
+
+    /// Inspect, validate or create the configuration
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommand,
+    },
+
+    /// Inspect the system prompts used by each tool
+    Prompts {
+        #[command(subcommand)]
+        command: PromptCommand,
+    },
+
+    /// Manage saved conversation sessions of the `ask` commands
+    Sessions {
+        #[command(subcommand)]
+        command: SessionCommand,
+    },
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+
+    // `nakama config` reports config problems itself
+    let (config, diagnostics) = match cli.command {
+        Commands::Config { .. } => (
+            Config::load_layered(TOOL_NAME)
+                .map(|loaded| loaded.config)
+                .unwrap_or_default(),
+            ConfigDiagnostics::default(),
+        ),
+        _ => Config::load_or_default(TOOL_NAME),
+    };
+    let _log_guard = init_logging(TOOL_NAME, &config.logging)?;
+    let ui = NakamaUI::from_config(&config);
+    ui.config_diagnostics(&diagnostics);
+
+    let result = match cli.command {
+        Commands::Usage {
+            period,
+            tool,
+            format,
+        } => usage::run(&config, &ui, period, tool.as_deref(), format),
+        Commands::Audit { command, list } => audit::run(&config, &ui, command, list),
+        Commands::Config { command } => config::run(&ui, command),
+        Commands::Prompts { command } => prompts::run(&ui, command),
+        Commands::Sessions { command } => sessions::run(&ui, command),
+    };
+
+    if let Err(e) = result {
+        ui.error(&format!("{:#}", e));
+        std::process::exit(1);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,180 @@
+//! `nakama prompts` — inspect the effective system prompts.
+//!
+//! Every prompt has a built-in template that can be overridden by
+//! `<repo>/.nakama/prompts/<tool>/<id>.md` or
+//! `~/.nakama/prompts/<tool>/<id>.md`.  These commands show which template
+//! each tool will use and how an override differs from the built-in.  A
+//! repository override is listed as untrusted until a tool has asked about
+//! it and the user agreed.
+
+use anyhow::{Context, Result};
+use clap::Subcommand;
+use nakama_ai::prompts::{Prompt, PromptVars};
+use nakama_ai::PromptRegistry;
+use nakama_ui::NakamaUI;
+
+#[derive(Subcommand, Debug)]
+pub enum PromptCommand {
+    /// List every prompt and where its effective template comes from
+    List {
+        /// Only list prompts of this tool
+        #[arg(long)]
+        tool: Option<String>,
+    },
+
+    /// Print the effective template of a prompt
+    Show {
+        /// Tool the prompt belongs to
+        tool: String,
+
+        /// Prompt id
+        id: String,
+
+        /// Substitute the detected repository variables
+        #[arg(long)]
+        render: bool,
+
+        /// Show the built-in template even if it is overridden
+        #[arg(long)]
+        builtin: bool,
+    },
+
+    /// Compare an override with the built-in template
+    Diff {
+        /// Tool the prompt belongs to
+        tool: String,
+
+        /// Prompt id
+        id: String,
+    },
+}
+
+pub fn run(ui: &NakamaUI, command: PromptCommand) -> Result<()> {
+    let registry = PromptRegistry::new();
+
+    match command {
+        PromptCommand::List { tool } => list(ui, &registry, tool.as_deref()),
+        PromptCommand::Show {
+            tool,
+            id,
+            render,
+            builtin,
+        } => {
+            let prompt = registry.resolve(&tool, &id)?;
+            let (template, source) = if builtin {
+                (prompt.builtin.template.trim_end().to_string(), "built-in".to_string())
+            } else {
+                (prompt.template.clone(), describe_source(&prompt))
+            };
+            let text = if render {
+                nakama_ai::prompts::render(&template, &PromptVars::detect())
+            } else {
+                template
+            };
+            ui.panel(&format!("{} — {}", prompt.name(), source), &text);
+            Ok(())
+        }
+        PromptCommand::Diff { tool, id } => {
+            let prompt = registry.resolve(&tool, &id)?;
+            if !prompt.is_overridden() {
+                ui.info(&format!("{} uses the built-in template.", prompt.name()));
+                return Ok(());
+            }
+            println!("--- built-in {}", prompt.name());
+            println!("+++ {}", describe_source(&prompt));
+            for line in line_diff(prompt.builtin.template.trim_end(), &prompt.template) {
+                println!("{line}");
+            }
+            Ok(())
+        }
+    }
+}
+
+fn list(ui: &NakamaUI, registry: &PromptRegistry, tool: Option<&str>) -> Result<()> {
+    let prompts = registry.list(tool).context("Failed to load prompts")?;
+    if prompts.is_empty() {
+        ui.warn(&format!("No prompts for tool '{}'.", tool.unwrap_or_default()));
+        return Ok(());
+    }
+
+    let rows = prompts
+        .iter()
+        .map(|p| vec![p.name(), describe_source(p), p.builtin.description.to_string()])
+        .collect();
+    ui.table(&["Prompt", "Source", "Description"], rows);
+
+    let overridden = prompts.iter().filter(|p| p.is_overridden()).count();
+    println!();
+    ui.info(&format!(
+        "{} of {} prompts overridden. Override a prompt by creating <tool>/<id>.md in {}{}",
+        overridden,
+        prompts.len(),
+        registry
+            .repo_dir()
+            .map(|dir| format!("{} or ", dir.display()))
+            .unwrap_or_default(),
+        registry
+            .user_dir()
+            .map(|dir| dir.display().to_string())
+            .unwrap_or_else(|| "~/.nakama/prompts".to_string()),
+    ));
+    Ok(())
+}
+
+fn describe_source(prompt: &Prompt) -> String {
+    let source = match prompt.source.path() {
+        Some(path) => format!("{} ({})", prompt.source, path.display()),
+        None => prompt.source.to_string(),
+    };
+    match &prompt.untrusted {
+        Some(path) => format!("{source}; untrusted {} ignored", path.display()),
+        None => source,
+    }
+}
+
+/// A line diff of `old` and `new`, each line prefixed with `-`, `+` or a
+/// space.  Prompts are short, so a plain LCS table is fine.
+fn line_diff(old: &str, new: &str) -> Vec<String> {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+
+    // lcs[i][j] = length of the LCS of old[i..] and new[j..]
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut out = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            out.push(format!(" {}", old[i]));
+            i += 1;
+            j += 1;
+        } else if i < old.len() && (j == new.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            out.push(format!("-{}", old[i]));
+            i += 1;
+        } else {
+            out.push(format!("+{}", new[j]));
+            j += 1;
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_diff() {
+        let diff = line_diff("a\nb\nc", "a\nB\nc\nd");
+        assert_eq!(diff, vec![" a", "-b", "+B", " c", "+d"]);
+    }
+}
@@ -0,0 +1,135 @@
+//! `nakama sessions` — list, show and delete saved conversation sessions.
+//!
+//! Sessions are created by the `ask` commands of kami, senku and zangetsu
+//! with `--session NAME` and live in `~/.nakama/sessions/sessions.db`.
+//! Names are unique per tool, so `--tool` is only needed when two tools
+//! have a session with the same name.
+
+use anyhow::{bail, Context, Result};
+use clap::Subcommand;
+use nakama_ai::session::SessionInfo;
+use nakama_ai::{Role, SessionStore};
+use nakama_ui::NakamaUI;
+
+#[derive(Subcommand, Debug)]
+pub enum SessionCommand {
+    /// List saved sessions, most recently used first
+    List {
+        /// Only list sessions of this tool
+        #[arg(long)]
+        tool: Option<String>,
+    },
+
+    /// Print a session's summary and messages
+    Show {
+        /// Session name
+        name: String,
+
+        /// Tool the session belongs to
+        #[arg(long)]
+        tool: Option<String>,
+    },
+
+    /// Delete a session
+    Delete {
+        /// Session name
+        name: String,
+
+        /// Tool the session belongs to
+        #[arg(long)]
+        tool: Option<String>,
+    },
+}
+
+pub fn run(ui: &NakamaUI, command: SessionCommand) -> Result<()> {
+    let store = SessionStore::new().context("Failed to open session store")?;
+
+    match command {
+        SessionCommand::List { tool } => list(ui, &store, tool.as_deref()),
+        SessionCommand::Show { name, tool } => show(ui, &store, &name, tool.as_deref()),
+        SessionCommand::Delete { name, tool } => {
+            let tool = resolve_tool(&store, &name, tool.as_deref())?;
+            store.delete(&tool, &name)?;
+            ui.success(&format!("Deleted session '{}' ({})", name, tool));
+            Ok(())
+        }
+    }
+}
+
+fn list(ui: &NakamaUI, store: &SessionStore, tool: Option<&str>) -> Result<()> {
+    let sessions = store.list(tool)?;
+    if sessions.is_empty() {
+        ui.warn("No saved sessions.");
+        return Ok(());
+    }
+
+    let rows = sessions
+        .iter()
+        .map(|s| {
+            vec![
+                s.name.clone(),
+                s.tool.clone(),
+                s.messages.to_string(),
+                if s.summarized { "yes" } else { "no" }.to_string(),
+                s.updated_at.format("%Y-%m-%d %H:%M").to_string(),
+            ]
+        })
+        .collect();
+    ui.table(&["Session", "Tool", "Messages", "Summarized", "Last Used"], rows);
+    Ok(())
+}
+
+fn show(ui: &NakamaUI, store: &SessionStore, name: &str, tool: Option<&str>) -> Result<()> {
+    let tool = resolve_tool(store, name, tool)?;
+    let session = store
+        .load(&tool, name)?
+        .with_context(|| format!("Session '{}' not found for {}", name, tool))?;
+
+    ui.panel(
+        &format!("Session '{}' — {}", session.name, session.tool),
+        &format!(
+            "Created:   {}\nLast used: {}\nMessages:  {}",
+            session.created_at.format("%Y-%m-%d %H:%M"),
+            session.updated_at.format("%Y-%m-%d %H:%M"),
+            session.messages.len(),
+        ),
+    );
+    if let Some(summary) = &session.summary {
+        ui.panel("Summary of earlier turns", summary);
+    }
+    for message in &session.messages {
+        let title = match message.role {
+            Role::User => "You",
+            Role::Assistant => "Assistant",
+            Role::System | Role::Tool => continue,
+        };
+        ui.panel(title, &message.content);
+    }
+    Ok(())
+}
+
+/// The tool that owns session `name`: `tool` if given, otherwise the only
+/// tool with a session of that name.
+fn resolve_tool(store: &SessionStore, name: &str, tool: Option<&str>) -> Result<String> {
+    let matches: Vec<SessionInfo> = store
+        .list(tool)?
+        .into_iter()
+        .filter(|s| s.name == name)
+        .collect();
+
+    match matches.as_slice() {
+        [] => match tool {
+            Some(tool) => bail!("Session '{}' not found for {}", name, tool),
+            None => bail!("Session '{}' not found", name),
+        },
+        [session] => Ok(session.tool.clone()),
+        _ => {
+            let tools: Vec<&str> = matches.iter().map(|s| s.tool.as_str()).collect();
+            bail!(
+                "Session '{}' exists for several tools ({}); pass --tool to choose one",
+                name,
+                tools.join(", ")
+            )
+        }
+    }
+}
@@ -0,0 +1,258 @@
+//! `nakama usage` — AI token usage and cost report.
+//!
+//! Every AI completion made by a Nakama tool is audited as an
+//! `ai_interaction` entry carrying provider, model, token counts and
+//! estimated cost.  This command queries those entries and renders them as
+//! a dashboard, JSON, or CSV.
+
+use anyhow::{Context, Result};
+use chrono::{Duration, Utc};
+use clap::ValueEnum;
+use nakama_audit::{AuditFilter, AuditLog, Category, UsageLedger, UsageReport, UsageTotals};
+use nakama_core::config::Config;
+use nakama_ui::NakamaUI;
+
+/// Width of the longest bar in the daily trend.
+const TREND_WIDTH: usize = 30;
+
+/// Reporting window, ending now and aligned to the start of a UTC day.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Period {
+    Day,
+    Week,
+    Month,
+}
+
+impl Period {
+    fn days(self) -> i64 {
+        match self {
+            Period::Day => 1,
+            Period::Week => 7,
+            Period::Month => 30,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    Table,
+    Json,
+    Csv,
+}
+
+/// Build and print the usage report.
+pub fn run(
+    config: &Config,
+    ui: &NakamaUI,
+    period: Period,
+    tool: Option<&str>,
+    format: OutputFormat,
+) -> Result<()> {
+    let audit = AuditLog::new(&config.audit).context("Failed to open audit log")?;
+
+    let until = Utc::now();
+    let first_day = until.date_naive() - Duration::days(period.days() - 1);
+    let since = first_day
+        .and_hms_opt(0, 0, 0)
+        .expect("midnight is a valid time")
+        .and_utc();
+
+    let mut filter = AuditFilter::new()
+        .with_category(Category::AiInteraction)
+        .with_since(since)
+        .with_until(until);
+    if let Some(tool) = tool {
+        filter = filter.with_tool(tool);
+    }
+
+    let entries = audit.query(&filter).context("Failed to query audit log")?;
+    let report = UsageReport::from_entries(&entries, since, until);
+
+    match format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&report)?),
+        OutputFormat::Csv => print!("{}", render_csv(&report)),
+        OutputFormat::Table => render_table(config, ui, tool, &report),
+    }
+
+    Ok(())
+}
+
+fn render_table(config: &Config, ui: &NakamaUI, tool: Option<&str>, report: &UsageReport) {
+    let range = format!(
+        "{} to {}",
+        report.since.format("%b %d"),
+        report.until.format("%b %d, %Y")
+    );
+    let title = match tool {
+        Some(tool) => format!("AI Usage Report — {tool}, {range}"),
+        None => format!("AI Usage Report — {range}"),
+    };
+
+    let mut summary = format!(
+        "Requests: {}\nTokens:   {} ({} in / {} out)\nCost:     {}",
+        report.total.requests,
+        format_count(report.total.total_tokens()),
+        format_count(report.total.input_tokens),
+        format_count(report.total.output_tokens),
+        format_cost(report.total.cost_usd),
+    );
+    if let Some(line) = budget_line(config) {
+        summary.push_str(&format!("\n\n{line}"));
+    }
+    ui.panel(&title, &summary);
+
+    if report.total.requests == 0 {
+        ui.warn("No AI usage recorded for this period.");
+        return;
+    }
+
+    println!("\n  By Tool:");
+    ui.table(
+        &["Tool", "Requests", "Tokens", "Cost"],
+        totals_rows(&report.by_tool, &report.total),
+    );
+
+    println!("\n  By Model:");
+    ui.table(
+        &["Model", "Requests", "Tokens", "Cost"],
+        totals_rows(&report.by_model, &report.total),
+    );
+
+    println!("\n  Daily Trend:");
+    let max_cost = report
+        .daily
+        .iter()
+        .map(|d| d.totals.cost_usd)
+        .fold(0.0_f64, f64::max);
+    for day in &report.daily {
+        println!(
+            "  {}: {:>8} ▏{}",
+            day.date.format("%a %m-%d"),
+            format_cost(day.totals.cost_usd),
+            trend_bar(day.totals.cost_usd, max_cost),
+        );
+    }
+}
+
+/// Table rows for a grouped breakdown, followed by a total row.
+fn totals_rows(groups: &[(String, UsageTotals)], total: &UsageTotals) -> Vec<Vec<String>> {
+    groups
+        .iter()
+        .map(|(name, totals)| (name.as_str(), totals))
+        .chain(std::iter::once(("Total", total)))
+        .map(|(name, totals)| {
+            vec![
+                name.to_string(),
+                totals.requests.to_string(),
+                format_count(totals.total_tokens()),
+                format_cost(totals.cost_usd),
+            ]
+        })
+        .collect()
+}
+
+/// "Budget: $10.00/week — 14.4% used", using the same rolling window as
+/// budget enforcement.
+fn budget_line(config: &Config) -> Option<String> {
+    let budget = config.ai.budget.as_ref()?;
+    let ledger = UsageLedger::new(&config.audit).ok()?;
+    let spent = ledger
+        .total_cost_since(Utc::now() - Duration::days(7))
+        .ok()?;
+    let percent = if budget.weekly_limit_usd > 0.0 {
+        spent / budget.weekly_limit_usd * 100.0
+    } else {
+        0.0
+    };
+    Some(format!(
+        "Budget: {}/week — {:.1}% used{}",
+        format_cost(budget.weekly_limit_usd),
+        percent,
+        if budget.hard_limit { " (hard limit)" } else { "" },
+    ))
+}
+
+fn render_csv(report: &UsageReport) -> String {
+    let mut out = String::from("tool,provider,model,requests,input_tokens,output_tokens,cost_usd\n");
+    for row in &report.rows {
+        out.push_str(&format!(
+            "{},{},{},{},{},{},{:.6}\n",
+            csv_field(&row.tool),
+            csv_field(&row.provider),
+            csv_field(&row.model),
+            row.totals.requests,
+            row.totals.input_tokens,
+            row.totals.output_tokens,
+            row.totals.cost_usd,
+        ));
+    }
+    out
+}
+
+/// Quote a CSV field if it contains a delimiter, quote, or newline.
+pub(crate) fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Format an integer with thousands separators (e.g. `125,400`).
+fn format_count(n: u64) -> String {
+    let digits: Vec<char> = n.to_string().chars().collect();
+    digits
+        .rchunks(3)
+        .rev()
+        .map(|group| group.iter().collect::<String>())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Format a USD amount, keeping precision for sub-cent costs.
+fn format_cost(usd: f64) -> String {
+    if usd > 0.0 && usd < 0.01 {
+        format!("${usd:.4}")
+    } else {
+        format!("${usd:.2}")
+    }
+}
+
+fn trend_bar(value: f64, max: f64) -> String {
+    if max <= 0.0 || value <= 0.0 {
+        return String::new();
+    }
+    let width = ((value / max) * TREND_WIDTH as f64).round().max(1.0) as usize;
+    "█".repeat(width)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_count() {
+        assert_eq!(format_count(0), "0");
+        assert_eq!(format_count(999), "999");
+        assert_eq!(format_count(1000), "1,000");
+        assert_eq!(format_count(125_400), "125,400");
+        assert_eq!(format_count(1_234_567), "1,234,567");
+    }
+
+    #[test]
+    fn test_format_cost_and_bars() {
+        assert_eq!(format_cost(1.4449), "$1.44");
+        assert_eq!(format_cost(0.0023), "$0.0023");
+        assert_eq!(format_cost(0.0), "$0.00");
+        assert_eq!(trend_bar(0.0, 1.0), "");
+        assert_eq!(trend_bar(1.0, 1.0).chars().count(), TREND_WIDTH);
+        assert_eq!(trend_bar(0.001, 1.0).chars().count(), 1);
+    }
+
+    #[test]
+    fn test_csv_field_quoting() {
+        assert_eq!(csv_field("claude-sonnet-4-6"), "claude-sonnet-4-6");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+    }
+}
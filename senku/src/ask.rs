@@ -1,4 +1,4 @@
-use crate::ai_helper::{ask_ai, make_provider};
+use nakama_ai::{collect_stream, load_prompt, AiClient, AttachArgs, Message, SessionArgs};
 use nakama_audit::{AuditEntry, AuditLog, Category, Outcome};
 use nakama_core::config::Config;
 use nakama_core::error::NakamaResult;
@@ -11,7 +11,15 @@ use std::path::Path;
 use std::time::Instant;
 
 /// Collect a codebase summary plus the user question, then send to AI for analysis.
-pub async fn run(config: &Config, ui: &NakamaUI, question: &str) -> NakamaResult<()> {
+pub async fn run(
+    config: &Config,
+    ui: &NakamaUI,
+    question: &str,
+    attach: &AttachArgs,
+    session: &SessionArgs,
+) -> NakamaResult<()> {
+    let attachments = attach.load()?;
+    let mut conversation = session.open(crate::TOOL_NAME)?;
     let trace = TraceContext::new("senku", "ask");
     let start = Instant::now();
 
@@ -27,28 +35,33 @@ pub async fn run(config: &Config, ui: &NakamaUI, question: &str) -> NakamaResult
 
     // Ask AI
     let ai_spinner = ui.step_start("Asking AI about the codebase...");
-    match make_provider(config, ModelTier::Balanced) {
-        Ok((provider, model)) => {
-            let system_prompt = r#"You are Senku, a codebase knowledge assistant. You help developers understand their codebase.
-
-Given a summary of the codebase structure and a question, provide a helpful, accurate answer.
-
-Rules:
-1. Base your answer on the codebase summary provided.
-2. If you cannot determine the answer from the summary alone, say so clearly.
-3. Be concise but thorough.
-4. Reference specific files and directories when relevant.
-5. Use code formatting for file paths and technical terms."#;
-
-            let user_msg = format!(
-                "Codebase Summary:\n{}\n\nQuestion: {}",
-                summary, question
-            );
-
-            match ask_ai(provider.as_ref(), system_prompt, &user_msg, &model, 2048, 0.3).await {
-                Ok(answer) => {
-                    ai_spinner.finish_with_success("Answer received");
-                    ui.panel("Answer", &answer);
+    match AiClient::new(config, crate::TOOL_NAME, ModelTier::Balanced) {
+        Ok(ai) => {
+            let system_prompt = load_prompt(crate::TOOL_NAME, "ask", &[]);
+
+            // The summary goes in the system prompt so follow-up questions
+            // in a session don't repeat it.
+            let system_prompt = format!("{}\n\nCodebase Summary:\n{}", system_prompt, summary);
+
+            let message = Message::user(question).with_attachments(attachments);
+            let stream = match conversation.prepare(&ai, &system_prompt, message, 2048, 0.3).await {
+                Ok(request) => ai.provider().complete_stream(request).await,
+                Err(e) => Err(e),
+            };
+            match stream {
+                Ok(stream) => {
+                    ai_spinner.finish_and_clear();
+                    let mut panel = ui.stream_panel("Answer");
+                    let response = collect_stream(stream, |delta| panel.push(delta)).await;
+                    panel.finish();
+                    match response {
+                        Ok(response) => {
+                            if let Err(e) = conversation.record(&response.content) {
+                                ui.warn(&format!("Failed to save session: {}", e));
+                            }
+                        }
+                        Err(e) => ui.step_fail(&format!("AI query failed: {}", e)),
+                    }
                 }
                 Err(e) => {
                     ai_spinner.finish_with_error(&format!("AI query failed: {}", e));
@@ -72,6 +85,7 @@ Rules:
             "Asked question about codebase",
             serde_json::json!({
                 "question": question,
+                "session": conversation.is_stored().then(|| &conversation.session().name),
                 "directory": cwd.display().to_string(),
             }),
             Outcome::Success,
@@ -1,4 +1,3 @@
-mod ai_helper;
 mod ask;
 mod deps;
 mod index_cmd;
@@ -6,6 +5,7 @@ mod map;
 
 use anyhow::Result;
 use clap::{Parser, Subcommand};
+use nakama_ai::{AiArgs, AttachArgs, SessionArgs};
 use nakama_core::Config;
 use nakama_log::init_logging;
 use nakama_ui::NakamaUI;
@@ -16,6 +16,9 @@ const TOOL_NAME: &str = "senku";
 #[derive(Parser, Debug)]
 #[command(name = TOOL_NAME, version, about, long_about = None)]
 struct Cli {
+    #[command(flatten)]
+    ai: AiArgs,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -30,6 +33,12 @@ enum Commands {
         /// The question to ask about the codebase
         #[arg()]
         question: String,
+
+        #[command(flatten)]
+        attach: AttachArgs,
+
+        #[command(flatten)]
+        session: SessionArgs,
     },
 
     /// Generate a directory tree visualization of the codebase
@@ -48,17 +57,24 @@ enum Commands {
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let config = Config::load(TOOL_NAME).unwrap_or_default();
+    let (mut config, diagnostics) = Config::load_or_default(TOOL_NAME);
     let _log_guard = init_logging(TOOL_NAME, &config.logging)?;
     let ui = NakamaUI::from_config(&config);
+    ui.config_diagnostics(&diagnostics);
 
     let update_rx = nakama_update::spawn_check(&config.updates, env!("CARGO_PKG_VERSION"));
 
     let cli = Cli::parse();
+    cli.ai.apply(&mut config);
+    nakama_vault::startup(&config, &ui);
 
     let result = match cli.command {
         Commands::Index => index_cmd::run(&config, &ui).await,
-        Commands::Ask { question } => ask::run(&config, &ui, &question).await,
+        Commands::Ask {
+            question,
+            attach,
+            session,
+        } => ask::run(&config, &ui, &question, &attach, &session).await,
         Commands::Map => map::run(&config, &ui).await,
         Commands::Deps => deps::run(&config, &ui).await,
         Commands::Search { query } => {
@@ -1,7 +1,12 @@
 //! Anthropic (Claude) provider implementation.
 
-use crate::provider::AiProvider;
-use crate::types::{CompletionRequest, CompletionResponse, Role, TokenUsage};
+use crate::attachment::split_content;
+use crate::provider::{response_stream, AiProvider};
+use crate::retry::{api_error, parse_retry_after};
+use crate::stream::{decode_response, CompletionStream, SseParser, StreamDecoder};
+use crate::types::{
+    CompletionRequest, CompletionResponse, Role, StreamEvent, TokenUsage, ToolCall, ToolDefinition,
+};
 use async_trait::async_trait;
 use nakama_core::error::{NakamaError, NakamaResult};
 use reqwest::Client;
@@ -28,6 +33,146 @@ impl AnthropicProvider {
             base_url: base_url.unwrap_or(DEFAULT_BASE_URL).trim_end_matches('/').to_string(),
         }
     }
+
+    /// Build the Messages API request body.
+    fn build_body(&self, request: &CompletionRequest, stream: bool) -> NakamaResult<AnthropicRequest> {
+        let model = if request.model.is_empty() {
+            &self.default_model
+        } else {
+            &request.model
+        };
+
+        // Build the messages list, filtering out system messages (sent separately).
+        let mut messages: Vec<AnthropicMessage> = Vec::new();
+        for m in request.messages.iter().filter(|m| m.role != Role::System) {
+            match m.role {
+                Role::Tool => {
+                    let block = AnthropicBlock::ToolResult {
+                        tool_use_id: m.tool_call_id.clone().unwrap_or_default(),
+                        content: m.content.clone(),
+                    };
+                    // Results of parallel tool calls must share one user turn.
+                    match messages.last_mut() {
+                        Some(AnthropicMessage {
+                            role,
+                            content: AnthropicMessageContent::Blocks(blocks),
+                        }) if role == "user" => blocks.push(block),
+                        _ => messages.push(AnthropicMessage {
+                            role: "user".to_string(),
+                            content: AnthropicMessageContent::Blocks(vec![block]),
+                        }),
+                    }
+                }
+                Role::Assistant if !m.tool_calls.is_empty() => {
+                    let text = (!m.content.is_empty()).then(|| AnthropicBlock::Text {
+                        text: m.content.clone(),
+                    });
+                    let calls = m.tool_calls.iter().map(|call| AnthropicBlock::ToolUse {
+                        id: call.id.clone(),
+                        name: call.name.clone(),
+                        input: call.arguments.clone(),
+                    });
+                    messages.push(AnthropicMessage {
+                        role: "assistant".to_string(),
+                        content: AnthropicMessageContent::Blocks(text.into_iter().chain(calls).collect()),
+                    });
+                }
+                _ if !m.attachments.is_empty() => {
+                    let (text, attachments) = split_content(&m.content, &m.attachments);
+                    let mut blocks = attachments
+                        .into_iter()
+                        .map(|a| {
+                            let source = AnthropicSource {
+                                kind: "base64",
+                                media_type: a.media_type.clone(),
+                                data: a.base64(),
+                            };
+                            if a.is_image() {
+                                Ok(AnthropicBlock::Image { source })
+                            } else if a.is_pdf() {
+                                Ok(AnthropicBlock::Document { source })
+                            } else {
+                                Err(a.unsupported("Anthropic"))
+                            }
+                        })
+                        .collect::<NakamaResult<Vec<_>>>()?;
+                    blocks.push(AnthropicBlock::Text { text });
+                    messages.push(AnthropicMessage {
+                        role: "user".to_string(),
+                        content: AnthropicMessageContent::Blocks(blocks),
+                    });
+                }
+                _ => messages.push(AnthropicMessage {
+                    role: match m.role {
+                        Role::Assistant => "assistant".to_string(),
+                        _ => "user".to_string(),
+                    },
+                    content: AnthropicMessageContent::Text(m.content.clone()),
+                }),
+            }
+        }
+
+        // The Messages API has no JSON mode, so the schema goes in the prompt.
+        let system_prompt = match &request.response_format {
+            Some(format) => Some(format.with_instructions(&request.system_prompt)),
+            None if request.system_prompt.is_empty() => None,
+            None => Some(request.system_prompt.clone()),
+        };
+
+        Ok(AnthropicRequest {
+            model: model.to_string(),
+            max_tokens: request.max_tokens,
+            system: system_prompt,
+            messages,
+            temperature: Some(request.temperature),
+            tools: request.tools.clone(),
+            stream,
+        })
+    }
+
+    /// Send the request and return the raw response, mapping HTTP errors.
+    async fn send(&self, body: &AnthropicRequest) -> NakamaResult<reqwest::Response> {
+        let url = format!("{}/v1/messages", self.base_url);
+
+        tracing::debug!(
+            provider = "anthropic",
+            model = %body.model,
+            url = %url,
+            stream = body.stream,
+            "Sending completion request"
+        );
+
+        let response = self
+            .client
+            .post(&url)
+            .header("x-api-key", &self.api_key)
+            .header("anthropic-version", ANTHROPIC_VERSION)
+            .header("content-type", "application/json")
+            .json(body)
+            .send()
+            .await
+            .map_err(|e| NakamaError::Network {
+                message: format!("Anthropic API request failed: {e}"),
+                source: Some(Box::new(e)),
+            })?;
+
+        let status = response.status();
+        if status.is_success() {
+            return Ok(response);
+        }
+        let retry_after = parse_retry_after(response.headers());
+
+        let response_text = response.text().await.map_err(|e| NakamaError::Network {
+            message: format!("Failed to read Anthropic response body: {e}"),
+            source: Some(Box::new(e)),
+        })?;
+
+        let error_msg = serde_json::from_str::<AnthropicError>(&response_text)
+            .map(|e| e.error.message)
+            .unwrap_or_else(|_| response_text.clone());
+
+        Err(api_error("Anthropic", status, retry_after, &error_msg))
+    }
 }
 
 // --- Anthropic API request/response types ---
@@ -41,12 +186,58 @@ struct AnthropicRequest {
     messages: Vec<AnthropicMessage>,
     #[serde(skip_serializing_if = "Option::is_none")]
     temperature: Option<f32>,
+    /// `ToolDefinition` already has the `name`/`description`/`input_schema`
+    /// shape the Messages API expects.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tools: Vec<ToolDefinition>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    stream: bool,
 }
 
 #[derive(Debug, Serialize)]
 struct AnthropicMessage {
     role: String,
-    content: String,
+    content: AnthropicMessageContent,
+}
+
+/// Plain text, or content blocks when the turn involves tool use or
+/// attachments.
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+enum AnthropicMessageContent {
+    Text(String),
+    Blocks(Vec<AnthropicBlock>),
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum AnthropicBlock {
+    Text {
+        text: String,
+    },
+    ToolUse {
+        id: String,
+        name: String,
+        input: serde_json::Value,
+    },
+    ToolResult {
+        tool_use_id: String,
+        content: String,
+    },
+    Image {
+        source: AnthropicSource,
+    },
+    Document {
+        source: AnthropicSource,
+    },
+}
+
+#[derive(Debug, Serialize)]
+struct AnthropicSource {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    media_type: String,
+    data: String,
 }
 
 #[derive(Debug, Deserialize)]
@@ -57,8 +248,18 @@ struct AnthropicResponse {
 }
 
 #[derive(Debug, Deserialize)]
-struct AnthropicContent {
-    text: String,
+#[serde(tag = "type", rename_all = "snake_case")]
+enum AnthropicContent {
+    Text {
+        text: String,
+    },
+    ToolUse {
+        id: String,
+        name: String,
+        input: serde_json::Value,
+    },
+    #[serde(other)]
+    Other,
 }
 
 #[derive(Debug, Deserialize)]
@@ -77,96 +278,152 @@ struct AnthropicErrorDetail {
     message: String,
 }
 
-#[async_trait]
-impl AiProvider for AnthropicProvider {
-    async fn complete(&self, request: CompletionRequest) -> NakamaResult<CompletionResponse> {
-        let model = if request.model.is_empty() {
-            &self.default_model
-        } else {
-            &request.model
-        };
+// --- Anthropic streaming event types ---
 
-        // Build the messages list, filtering out system messages (sent separately).
-        let messages: Vec<AnthropicMessage> = request
-            .messages
-            .iter()
-            .filter(|m| m.role != Role::System)
-            .map(|m| AnthropicMessage {
-                role: match m.role {
-                    Role::User => "user".to_string(),
-                    Role::Assistant => "assistant".to_string(),
-                    Role::System => "user".to_string(), // unreachable due to filter
-                },
-                content: m.content.clone(),
-            })
-            .collect();
-
-        let system_prompt = if request.system_prompt.is_empty() {
-            None
-        } else {
-            Some(request.system_prompt.clone())
-        };
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum AnthropicStreamEvent {
+    MessageStart {
+        message: AnthropicStreamMessage,
+    },
+    ContentBlockDelta {
+        delta: AnthropicStreamDelta,
+    },
+    MessageDelta {
+        #[serde(default)]
+        usage: Option<AnthropicStreamUsage>,
+    },
+    Error {
+        error: AnthropicErrorDetail,
+    },
+    #[serde(other)]
+    Other,
+}
 
-        let body = AnthropicRequest {
-            model: model.to_string(),
-            max_tokens: request.max_tokens,
-            system: system_prompt,
-            messages,
-            temperature: Some(request.temperature),
-        };
+#[derive(Debug, Deserialize)]
+struct AnthropicStreamMessage {
+    model: String,
+    #[serde(default)]
+    usage: Option<AnthropicStreamUsage>,
+}
 
-        let url = format!("{}/v1/messages", self.base_url);
+#[derive(Debug, Deserialize)]
+struct AnthropicStreamDelta {
+    #[serde(default)]
+    text: Option<String>,
+}
 
-        tracing::debug!(
-            provider = "anthropic",
-            model = %model,
-            url = %url,
-            "Sending completion request"
-        );
+#[derive(Debug, Default, Deserialize)]
+struct AnthropicStreamUsage {
+    #[serde(default)]
+    input_tokens: Option<u32>,
+    #[serde(default)]
+    output_tokens: Option<u32>,
+}
 
-        let response = self
-            .client
-            .post(&url)
-            .header("x-api-key", &self.api_key)
-            .header("anthropic-version", ANTHROPIC_VERSION)
-            .header("content-type", "application/json")
-            .json(&body)
-            .send()
-            .await
-            .map_err(|e| NakamaError::Network {
-                message: format!("Anthropic API request failed: {e}"),
+/// Decodes the Messages API SSE stream.
+#[derive(Default)]
+struct AnthropicDecoder {
+    sse: SseParser,
+    model: String,
+    usage: TokenUsage,
+}
+
+impl AnthropicDecoder {
+    fn handle_event(&mut self, data: &str) -> NakamaResult<Vec<StreamEvent>> {
+        let parsed: AnthropicStreamEvent =
+            serde_json::from_str(data).map_err(|e| NakamaError::AiProvider {
+                message: format!("Failed to parse Anthropic stream event: {e}"),
                 source: Some(Box::new(e)),
             })?;
 
-        let status = response.status();
+        match parsed {
+            AnthropicStreamEvent::MessageStart { message } => {
+                self.model = message.model;
+                self.apply_usage(message.usage);
+            }
+            AnthropicStreamEvent::ContentBlockDelta { delta } => {
+                if let Some(text) = delta.text.filter(|t| !t.is_empty()) {
+                    return Ok(vec![StreamEvent::Delta { text }]);
+                }
+            }
+            AnthropicStreamEvent::MessageDelta { usage } => self.apply_usage(usage),
+            AnthropicStreamEvent::Error { error } => {
+                return Err(NakamaError::AiProvider {
+                    message: format!("Anthropic stream error: {}", error.message),
+                    source: None,
+                });
+            }
+            AnthropicStreamEvent::Other => {}
+        }
+
+        Ok(Vec::new())
+    }
+
+    fn apply_usage(&mut self, usage: Option<AnthropicStreamUsage>) {
+        if let Some(usage) = usage {
+            if let Some(input) = usage.input_tokens {
+                self.usage.input_tokens = input;
+            }
+            if let Some(output) = usage.output_tokens {
+                self.usage.output_tokens = output;
+            }
+        }
+    }
+}
+
+impl StreamDecoder for AnthropicDecoder {
+    fn decode_line(&mut self, line: &str) -> NakamaResult<Vec<StreamEvent>> {
+        match self.sse.push_line(line) {
+            Some(event) => self.handle_event(&event.data),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    fn finish(&mut self) -> NakamaResult<Vec<StreamEvent>> {
+        let mut events = match self.sse.flush() {
+            Some(event) => self.handle_event(&event.data)?,
+            None => Vec::new(),
+        };
+        events.push(StreamEvent::Done {
+            model: std::mem::take(&mut self.model),
+            usage: std::mem::take(&mut self.usage),
+            cached: false,
+        });
+        Ok(events)
+    }
+}
+
+#[async_trait]
+impl AiProvider for AnthropicProvider {
+    async fn complete(&self, request: CompletionRequest) -> NakamaResult<CompletionResponse> {
+        let body = self.build_body(&request, false)?;
+        let response = self.send(&body).await?;
+
         let response_text = response.text().await.map_err(|e| NakamaError::Network {
             message: format!("Failed to read Anthropic response body: {e}"),
             source: Some(Box::new(e)),
         })?;
 
-        if !status.is_success() {
-            let error_msg = serde_json::from_str::<AnthropicError>(&response_text)
-                .map(|e| e.error.message)
-                .unwrap_or_else(|_| response_text.clone());
-
-            return Err(NakamaError::AiProvider {
-                message: format!("Anthropic API error (HTTP {status}): {error_msg}"),
-                source: None,
-            });
-        }
-
         let api_response: AnthropicResponse =
             serde_json::from_str(&response_text).map_err(|e| NakamaError::AiProvider {
                 message: format!("Failed to parse Anthropic response: {e}"),
                 source: Some(Box::new(e)),
             })?;
 
-        let content = api_response
-            .content
-            .into_iter()
-            .map(|c| c.text)
-            .collect::<Vec<_>>()
-            .join("");
+        let mut content = String::new();
+        let mut tool_calls = Vec::new();
+        for block in api_response.content {
+            match block {
+                AnthropicContent::Text { text } => content.push_str(&text),
+                AnthropicContent::ToolUse { id, name, input } => tool_calls.push(ToolCall {
+                    id,
+                    name,
+                    arguments: input,
+                }),
+                AnthropicContent::Other => {}
+            }
+        }
 
         Ok(CompletionResponse {
             content,
@@ -175,10 +432,150 @@ impl AiProvider for AnthropicProvider {
                 input_tokens: api_response.usage.input_tokens,
                 output_tokens: api_response.usage.output_tokens,
             },
+            tool_calls,
+            cached: false,
         })
     }
 
+    /// Tool-use blocks are not decoded from the stream, so requests that
+    /// offer tools are answered in one piece.
+    async fn complete_stream(&self, request: CompletionRequest) -> NakamaResult<CompletionStream> {
+        if !request.tools.is_empty() {
+            return Ok(response_stream(self.complete(request).await?));
+        }
+        let body = self.build_body(&request, true)?;
+        let response = self.send(&body).await?;
+        Ok(decode_response(
+            response,
+            AnthropicDecoder::default(),
+            "anthropic",
+        ))
+    }
+
     fn provider_name(&self) -> &str {
         "anthropic"
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stream::collect_stream;
+    use crate::attachment::Attachment;
+    use crate::test_support::{serve, MockResponse};
+    use crate::types::Message;
+
+    fn request() -> CompletionRequest {
+        CompletionRequest {
+            system_prompt: "Be brief.".to_string(),
+            messages: vec![Message::user("Hi")],
+            model: String::new(),
+            max_tokens: 64,
+            temperature: 0.0,
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_complete_stream_against_mock_server() {
+        let body = "event: message_start\n\
+data: {\"type\":\"message_start\",\"message\":{\"model\":\"claude-test\",\"usage\":{\"input_tokens\":12,\"output_tokens\":1}}}\n\n\
+event: content_block_delta\n\
+data: {\"type\":\"content_block_delta\",\"index\":0,\"delta\":{\"type\":\"text_delta\",\"text\":\"Hello\"}}\n\n\
+event: ping\n\
+data: {\"type\":\"ping\"}\n\n\
+event: content_block_delta\n\
+data: {\"type\":\"content_block_delta\",\"index\":0,\"delta\":{\"type\":\"text_delta\",\"text\":\", world\"}}\n\n\
+event: message_delta\n\
+data: {\"type\":\"message_delta\",\"delta\":{\"stop_reason\":\"end_turn\"},\"usage\":{\"output_tokens\":5}}\n\n\
+event: message_stop\n\
+data: {\"type\":\"message_stop\"}\n\n";
+        let (base_url, requests) = serve(vec![MockResponse::sse(body)]).await;
+
+        let provider = AnthropicProvider::new("test-key", "claude-test", Some(&base_url));
+        let stream = provider.complete_stream(request()).await.unwrap();
+
+        let mut deltas = Vec::new();
+        let response = collect_stream(stream, |d| deltas.push(d.to_string()))
+            .await
+            .unwrap();
+
+        assert_eq!(deltas, vec!["Hello", ", world"]);
+        assert_eq!(response.content, "Hello, world");
+        assert_eq!(response.model, "claude-test");
+        assert_eq!(response.usage.input_tokens, 12);
+        assert_eq!(response.usage.output_tokens, 5);
+
+        let raw = requests.await.unwrap();
+        assert!(raw[0].contains("\"stream\":true"));
+        assert!(raw[0].contains("x-api-key: test-key"));
+    }
+
+    #[tokio::test]
+    async fn test_tool_use_round_trip() {
+        let body = r#"{"model":"claude-test","content":[
+            {"type":"text","text":"Checking."},
+            {"type":"tool_use","id":"toolu_1","name":"read_file","input":{"path":"Cargo.toml"}}
+        ],"usage":{"input_tokens":20,"output_tokens":9}}"#;
+        let (base_url, requests) = serve(vec![MockResponse::json(body)]).await;
+
+        let provider = AnthropicProvider::new("k", "claude-test", Some(&base_url));
+        let mut req = request();
+        req.tools = vec![ToolDefinition {
+            name: "read_file".to_string(),
+            description: "Read a file".to_string(),
+            input_schema: serde_json::json!({ "type": "object" }),
+        }];
+        let response = provider.complete(req.clone()).await.unwrap();
+        assert_eq!(response.content, "Checking.");
+        assert_eq!(response.tool_calls[0].id, "toolu_1");
+        assert_eq!(response.tool_calls[0].arguments["path"], "Cargo.toml");
+
+        req.messages.push(Message::assistant_tool_calls(response.content, response.tool_calls));
+        req.messages.push(Message::tool_result("toolu_1", "[package]"));
+        let body = serde_json::to_value(provider.build_body(&req, false).unwrap()).unwrap();
+        assert_eq!(body["tools"][0]["input_schema"]["type"], "object");
+        assert_eq!(body["messages"][1]["content"][1]["type"], "tool_use");
+        assert_eq!(body["messages"][2]["role"], "user");
+        assert_eq!(body["messages"][2]["content"][0]["tool_use_id"], "toolu_1");
+
+        let raw = requests.await.unwrap();
+        assert!(raw[0].contains("\"tools\":["));
+    }
+
+    #[tokio::test]
+    async fn test_complete_stream_surfaces_error_event() {
+        let body = "event: error\n\
+data: {\"type\":\"error\",\"error\":{\"type\":\"overloaded_error\",\"message\":\"Overloaded\"}}\n\n";
+        let (base_url, _) = serve(vec![MockResponse::sse(body)]).await;
+
+        let provider = AnthropicProvider::new("k", "claude-test", Some(&base_url));
+        let stream = provider.complete_stream(request()).await.unwrap();
+        let err = collect_stream(stream, |_| {}).await.unwrap_err();
+        assert!(err.to_string().contains("Overloaded"));
+    }
+
+    #[test]
+    fn test_attachments_become_content_blocks() {
+        let provider = AnthropicProvider::new("k", "claude-test", None);
+        let mut req = request();
+        req.messages = vec![Message::user("What's wrong here?").with_attachments([
+            Attachment::new("ui.png", "image/png", vec![1, 2, 3]),
+            Attachment::new("spec.pdf", "application/pdf", b"%PDF".to_vec()),
+        ])];
+        let body = serde_json::to_value(provider.build_body(&req, false).unwrap()).unwrap();
+        let blocks = &body["messages"][0]["content"];
+        assert_eq!(blocks[0]["type"], "image");
+        assert_eq!(blocks[0]["source"]["media_type"], "image/png");
+        assert_eq!(blocks[0]["source"]["data"], "AQID");
+        assert_eq!(blocks[1]["type"], "document");
+        assert_eq!(blocks[2]["text"], "What's wrong here?");
+
+        req.messages = vec![Message::user("Hi").with_attachments([Attachment::new(
+            "clip.mp4",
+            "video/mp4",
+            vec![0],
+        )])];
+        assert!(provider.build_body(&req, false).is_err());
+    }
+}
@@ -0,0 +1,221 @@
+//! Files attached to messages: images, PDFs and text documents.
+//!
+//! Text attachments are folded into the message text for every provider;
+//! images and PDFs are sent in each provider's native multimodal format.
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use nakama_core::error::{NakamaError, NakamaResult};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Largest file accepted as an attachment.  Provider limits are of the same
+/// order, and anything bigger is almost certainly a mistake.
+pub const MAX_ATTACHMENT_BYTES: u64 = 20 * 1024 * 1024;
+
+/// A file attached to a message.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Attachment {
+    /// File name, passed to providers that display it.
+    pub name: String,
+
+    /// MIME type (e.g. `"image/png"`, `"application/pdf"`, `"text/plain"`).
+    pub media_type: String,
+
+    /// Raw file contents (base64 when serialized).
+    #[serde(with = "base64_bytes")]
+    pub data: Vec<u8>,
+}
+
+impl Attachment {
+    pub fn new(name: impl Into<String>, media_type: impl Into<String>, data: Vec<u8>) -> Self {
+        Self {
+            name: name.into(),
+            media_type: media_type.into(),
+            data,
+        }
+    }
+
+    /// Read a file, inferring its media type from the extension (or, for
+    /// unknown extensions, whether it is valid UTF-8).
+    pub fn from_path(path: &Path) -> NakamaResult<Self> {
+        let size = std::fs::metadata(path)?.len();
+        if size > MAX_ATTACHMENT_BYTES {
+            return Err(NakamaError::Tool {
+                tool: "attach".to_string(),
+                message: format!(
+                    "{} is {} MB; attachments are limited to {} MB",
+                    path.display(),
+                    size / (1024 * 1024),
+                    MAX_ATTACHMENT_BYTES / (1024 * 1024)
+                ),
+            });
+        }
+
+        let data = std::fs::read(path)?;
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or_default()
+            .to_lowercase();
+        let media_type = match media_type_for_extension(&extension) {
+            Some(media_type) => media_type,
+            None if std::str::from_utf8(&data).is_ok() => "text/plain",
+            None => {
+                return Err(NakamaError::Tool {
+                    tool: "attach".to_string(),
+                    message: format!(
+                        "Unsupported attachment type: {} (expected an image, PDF or text file)",
+                        path.display()
+                    ),
+                })
+            }
+        };
+
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| path.display().to_string());
+        Ok(Self::new(name, media_type, data))
+    }
+
+    pub fn is_image(&self) -> bool {
+        self.media_type.starts_with("image/")
+    }
+
+    pub fn is_pdf(&self) -> bool {
+        self.media_type == "application/pdf"
+    }
+
+    /// Whether the attachment is text that can be inlined into the prompt.
+    pub fn is_text(&self) -> bool {
+        self.media_type.starts_with("text/")
+            || matches!(
+                self.media_type.as_str(),
+                "application/json" | "application/yaml" | "application/toml" | "application/xml"
+            )
+    }
+
+    /// Base64 encoding of the contents.
+    pub fn base64(&self) -> String {
+        STANDARD.encode(&self.data)
+    }
+
+    /// A `data:` URL of the contents.
+    pub fn data_url(&self) -> String {
+        format!("data:{};base64,{}", self.media_type, self.base64())
+    }
+
+    /// The error providers return for attachments they can't accept.
+    pub(crate) fn unsupported(&self, provider: &str) -> NakamaError {
+        NakamaError::AiProvider {
+            message: format!(
+                "{provider} does not accept {} attachments ({})",
+                self.media_type, self.name
+            ),
+            source: None,
+        }
+    }
+}
+
+fn media_type_for_extension(extension: &str) -> Option<&'static str> {
+    Some(match extension {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "pdf" => "application/pdf",
+        "json" => "application/json",
+        "yaml" | "yml" => "application/yaml",
+        "toml" => "application/toml",
+        "xml" => "application/xml",
+        "md" | "markdown" => "text/markdown",
+        "csv" => "text/csv",
+        "html" | "htm" => "text/html",
+        "txt" | "log" => "text/plain",
+        _ => return None,
+    })
+}
+
+/// Split a message's content for serialization: the text (with any text
+/// attachments appended) and the binary attachments.
+pub(crate) fn split_content<'a>(
+    content: &str,
+    attachments: &'a [Attachment],
+) -> (String, Vec<&'a Attachment>) {
+    let mut text = content.to_string();
+    let mut binary = Vec::new();
+
+    for attachment in attachments {
+        if attachment.is_text() {
+            text.push_str(&format!(
+                "\n\n--- {} ---\n{}",
+                attachment.name,
+                String::from_utf8_lossy(&attachment.data)
+            ));
+        } else {
+            binary.push(attachment);
+        }
+    }
+
+    (text, binary)
+}
+
+/// Serde helper storing bytes as a base64 string.
+mod base64_bytes {
+    use super::STANDARD;
+    use base64::Engine;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(data: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&STANDARD.encode(data))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let encoded = String::deserialize(deserializer)?;
+        STANDARD.decode(encoded).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_path_detects_media_type() {
+        let dir = std::env::temp_dir().join(format!("nakama-attach-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let png = dir.join("shot.PNG");
+        std::fs::write(&png, [0x89, b'P', b'N', b'G']).unwrap();
+        let attachment = Attachment::from_path(&png).unwrap();
+        assert_eq!(attachment.media_type, "image/png");
+        assert_eq!(attachment.name, "shot.PNG");
+
+        let notes = dir.join("NOTES");
+        std::fs::write(&notes, "plain text").unwrap();
+        assert!(Attachment::from_path(&notes).unwrap().is_text());
+
+        let blob = dir.join("blob.bin");
+        std::fs::write(&blob, [0xff, 0xfe, 0x00]).unwrap();
+        assert!(Attachment::from_path(&blob).is_err());
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_text_attachments_are_inlined() {
+        let attachments = vec![
+            Attachment::new("spec.yaml", "application/yaml", b"openapi: 3.0".to_vec()),
+            Attachment::new("ui.png", "image/png", vec![1, 2, 3]),
+        ];
+        let (text, binary) = split_content("Why?", &attachments);
+        assert_eq!(text, "Why?\n\n--- spec.yaml ---\nopenapi: 3.0");
+        assert_eq!(binary.len(), 1);
+
+        let json = serde_json::to_value(&attachments[1]).unwrap();
+        assert_eq!(json["data"], "AQID");
+        let back: Attachment = serde_json::from_value(json).unwrap();
+        assert_eq!(back.data, vec![1, 2, 3]);
+    }
+}
@@ -0,0 +1,527 @@
+//! Weekly spend accounting and budget enforcement.
+//!
+//! [`BudgetedProvider`] prices every completion with a [`PriceTable`],
+//! records it in the [`UsageLedger`] and (when auditing is enabled) as an
+//! `ai_interaction` audit entry, and enforces `[ai.budget]`: an alert
+//! fires once spend over the last 7 days crosses `alert_threshold_percent`,
+//! and with `hard_limit = true` requests are refused once the weekly limit is
+//! reached (except those the response cache can answer, which are free).
+
+use crate::pricing::PriceTable;
+use crate::provider::{response_stream, AiProvider};
+use crate::stream::CompletionStream;
+use crate::types::{CompletionRequest, CompletionResponse, StreamEvent, TokenUsage};
+use async_trait::async_trait;
+use chrono::{Duration, Utc};
+use futures_util::StreamExt;
+use nakama_audit::{AuditEntry, AuditLog, Category, Outcome, UsageLedger, UsageRecord};
+use nakama_core::config::{BudgetConfig, Config};
+use nakama_core::error::{NakamaError, NakamaResult};
+use nakama_core::trace::TraceContext;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+
+/// Audit `command` used for entries describing a single AI completion.
+pub const AUDIT_COMMAND: &str = "ai_completion";
+
+/// Length of the rolling budget window.
+const BUDGET_WINDOW_DAYS: i64 = 7;
+
+/// Callback invoked with a human-readable message when spend crosses the
+/// alert threshold.
+pub type AlertHook = Box<dyn Fn(&str) + Send + Sync>;
+
+/// State shared between the provider and the streams it returns.
+struct BudgetState {
+    tool: String,
+    provider: String,
+    budget: Option<BudgetConfig>,
+    pricing: PriceTable,
+    ledger: UsageLedger,
+    audit: Option<AuditLog>,
+    on_alert: Option<AlertHook>,
+    alerted: AtomicBool,
+}
+
+impl BudgetState {
+    fn spent_this_week(&self) -> NakamaResult<f64> {
+        self.ledger
+            .total_cost_since(Utc::now() - Duration::days(BUDGET_WINDOW_DAYS))
+    }
+
+    /// Refuse the request if the hard limit has been reached.
+    fn check(&self) -> NakamaResult<()> {
+        let Some(budget) = self.budget.as_ref().filter(|b| b.hard_limit) else {
+            return Ok(());
+        };
+
+        let spent = self.spent_this_week()?;
+        if spent >= budget.weekly_limit_usd {
+            return Err(NakamaError::Budget {
+                message: format!(
+                    "weekly limit of ${:.2} reached (${:.2} spent in the last {} days). \
+                     Raise ai.budget.weekly_limit_usd or set ai.budget.hard_limit = false.",
+                    budget.weekly_limit_usd, spent, BUDGET_WINDOW_DAYS,
+                ),
+            });
+        }
+        Ok(())
+    }
+
+    /// Price and persist a completion, then fire the alert if needed.
+    ///
+    /// Responses served from the response cache cost nothing: they are
+    /// audited with `cache_hit: true` and zero usage, and skip the ledger.
+    fn record(&self, model: &str, usage: &TokenUsage, cached: bool, started: Instant) {
+        let usage = if cached { TokenUsage::default() } else { usage.clone() };
+        let record = UsageRecord {
+            timestamp: Utc::now(),
+            tool: self.tool.clone(),
+            provider: self.provider.clone(),
+            model: model.to_string(),
+            input_tokens: usage.input_tokens,
+            output_tokens: usage.output_tokens,
+            cost_usd: self.pricing.cost_usd(model, &usage),
+        };
+
+        let mut detail = record.to_audit_detail();
+        if cached {
+            detail["cache_hit"] = serde_json::Value::Bool(true);
+        }
+        self.audit(&format!("Completion from {model}"), detail, Some(&record), Outcome::Success, started);
+        if cached {
+            return;
+        }
+
+        if let Err(e) = self.ledger.record(&record) {
+            tracing::warn!(error = %e, "Failed to record AI usage");
+            return;
+        }
+
+        let Some(budget) = &self.budget else {
+            return;
+        };
+        let spent = match self.spent_this_week() {
+            Ok(spent) => spent,
+            Err(e) => {
+                tracing::warn!(error = %e, "Failed to compute weekly AI spend");
+                return;
+            }
+        };
+
+        if let Some(message) = alert_message(budget, spent) {
+            if !self.alerted.swap(true, Ordering::SeqCst) {
+                tracing::warn!(spent, limit = budget.weekly_limit_usd, "AI budget alert");
+                if let Some(hook) = &self.on_alert {
+                    hook(&message);
+                }
+            }
+        }
+    }
+
+    /// Audit a request that failed before producing a completion.
+    fn record_failure(&self, model: &str, error: &NakamaError, started: Instant) {
+        self.audit(
+            &format!("Completion from {model} failed"),
+            serde_json::json!({
+                "provider": self.provider,
+                "model": model,
+                "error": error.to_string(),
+            }),
+            None,
+            Outcome::Failure,
+            started,
+        );
+    }
+
+    fn audit(
+        &self,
+        action: &str,
+        detail: serde_json::Value,
+        usage: Option<&UsageRecord>,
+        outcome: Outcome,
+        started: Instant,
+    ) {
+        let Some(audit) = &self.audit else {
+            return;
+        };
+        let entry = AuditEntry::new(
+            &TraceContext::new(&self.tool, AUDIT_COMMAND).trace_id,
+            &self.tool,
+            AUDIT_COMMAND,
+            Category::AiInteraction,
+            action,
+            detail,
+            outcome,
+            started.elapsed().as_millis() as u64,
+        );
+        let entry = match usage {
+            Some(usage) => entry.with_usage(
+                u64::from(usage.input_tokens),
+                u64::from(usage.output_tokens),
+                usage.cost_usd,
+            ),
+            None => entry,
+        };
+        if let Err(e) = audit.log(entry) {
+            tracing::warn!(error = %e, "Failed to audit AI completion");
+        }
+    }
+}
+
+/// The alert to show for the given weekly spend, if any.
+fn alert_message(budget: &BudgetConfig, spent: f64) -> Option<String> {
+    if budget.weekly_limit_usd <= 0.0 {
+        return None;
+    }
+    let percent = spent / budget.weekly_limit_usd * 100.0;
+    if percent < budget.alert_threshold_percent as f64 {
+        return None;
+    }
+
+    let message = if spent >= budget.weekly_limit_usd {
+        if budget.hard_limit {
+            format!(
+                "AI budget exhausted: ${:.2} of ${:.2} weekly limit spent. Further requests will be refused.",
+                spent, budget.weekly_limit_usd
+            )
+        } else {
+            format!(
+                "AI budget exceeded: ${:.2} of ${:.2} weekly limit spent ({:.0}%).",
+                spent, budget.weekly_limit_usd, percent
+            )
+        }
+    } else {
+        format!(
+            "AI spend at {:.0}% of weekly budget (${:.2} of ${:.2}).",
+            percent, spent, budget.weekly_limit_usd
+        )
+    };
+    Some(message)
+}
+
+/// An [`AiProvider`] wrapper that records spend and enforces the weekly budget.
+pub struct BudgetedProvider {
+    inner: Box<dyn AiProvider>,
+    state: Arc<BudgetState>,
+}
+
+impl BudgetedProvider {
+    /// Wrap `inner`, attributing its usage to `tool` and recording it in `ledger`.
+    pub fn new(inner: Box<dyn AiProvider>, tool: &str, config: &Config, ledger: UsageLedger) -> Self {
+        let state = BudgetState {
+            tool: tool.to_string(),
+            provider: inner.provider_name().to_string(),
+            budget: config.ai.budget.clone(),
+            pricing: PriceTable::from_config(&config.ai),
+            ledger,
+            audit: None,
+            on_alert: None,
+            alerted: AtomicBool::new(false),
+        };
+        Self {
+            inner,
+            state: Arc::new(state),
+        }
+    }
+
+    /// Also record every completion in the audit log.
+    pub fn with_audit(mut self, audit: AuditLog) -> Self {
+        if let Some(state) = Arc::get_mut(&mut self.state) {
+            state.audit = Some(audit);
+        }
+        self
+    }
+
+    /// Set the callback used to surface budget alerts (e.g. `NakamaUI::warn`).
+    pub fn on_alert(mut self, hook: impl Fn(&str) + Send + Sync + 'static) -> Self {
+        if let Some(state) = Arc::get_mut(&mut self.state) {
+            state.on_alert = Some(Box::new(hook));
+        }
+        self
+    }
+}
+
+impl BudgetedProvider {
+    /// Record the completion a stream ends with, or its failure.
+    fn track(&self, stream: CompletionStream, model: String, started: Instant) -> CompletionStream {
+        let state = Arc::clone(&self.state);
+        Box::pin(stream.inspect(move |event| match event {
+            Ok(StreamEvent::Done {
+                model: served,
+                usage,
+                cached,
+            }) => state.record(served, usage, *cached, started),
+            Ok(StreamEvent::Delta { .. } | StreamEvent::ToolCall(_)) => {}
+            Err(e) => state.record_failure(&model, e, started),
+        }))
+    }
+}
+
+#[async_trait]
+impl AiProvider for BudgetedProvider {
+    async fn complete(&self, request: CompletionRequest) -> NakamaResult<CompletionResponse> {
+        let started = Instant::now();
+        if let Err(e) = self.state.check() {
+            // Cached responses cost nothing, so the hard limit does not
+            // apply to them.
+            let response = self.inner.cached(&request).ok_or(e)?;
+            self.state
+                .record(&response.model, &response.usage, true, started);
+            return Ok(response);
+        }
+        let model = request.model.clone();
+        match self.inner.complete(request).await {
+            Ok(response) => {
+                self.state
+                    .record(&response.model, &response.usage, response.cached, started);
+                Ok(response)
+            }
+            Err(e) => {
+                self.state.record_failure(&model, &e, started);
+                Err(e)
+            }
+        }
+    }
+
+    async fn complete_stream(&self, request: CompletionRequest) -> NakamaResult<CompletionStream> {
+        let started = Instant::now();
+        let model = request.model.clone();
+        if let Err(e) = self.state.check() {
+            let response = self.inner.cached(&request).ok_or(e)?;
+            return Ok(self.track(response_stream(response), model, started));
+        }
+        match self.inner.complete_stream(request).await {
+            Ok(stream) => Ok(self.track(stream, model, started)),
+            Err(e) => {
+                self.state.record_failure(&model, &e, started);
+                Err(e)
+            }
+        }
+    }
+
+    fn cached(&self, request: &CompletionRequest) -> Option<CompletionResponse> {
+        self.inner.cached(request)
+    }
+
+    fn provider_name(&self) -> &str {
+        self.inner.provider_name()
+    }
+}
+
+/// Wrap a provider with spend tracking, auditing and budget enforcement for
+/// `tool`.
+///
+/// If the usage ledger cannot be opened the provider is returned unwrapped
+/// (with a logged warning) rather than failing the tool.
+pub fn with_budget(
+    provider: Box<dyn AiProvider>,
+    tool: &str,
+    config: &Config,
+    on_alert: impl Fn(&str) + Send + Sync + 'static,
+) -> Box<dyn AiProvider> {
+    match UsageLedger::new(&config.audit) {
+        Ok(ledger) => {
+            let mut budgeted = BudgetedProvider::new(provider, tool, config, ledger).on_alert(on_alert);
+            if config.audit.enabled {
+                match AuditLog::new(&config.audit) {
+                    Ok(audit) => budgeted = budgeted.with_audit(audit),
+                    Err(e) => tracing::warn!(error = %e, "Audit log unavailable; AI calls not audited"),
+                }
+            }
+            Box::new(budgeted)
+        }
+        Err(e) => {
+            tracing::warn!(error = %e, "AI usage ledger unavailable; budget not enforced");
+            provider
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::{CachingProvider, ResponseCache};
+    use crate::stream::collect_stream;
+    use crate::types::Message;
+    use std::path::Path;
+    use std::sync::Mutex;
+
+    struct FixedProvider;
+
+    #[async_trait]
+    impl AiProvider for FixedProvider {
+        async fn complete(&self, _request: CompletionRequest) -> NakamaResult<CompletionResponse> {
+            Ok(CompletionResponse {
+                content: "ok".to_string(),
+                model: "claude-sonnet-4-6".to_string(),
+                // $3 + $15 per million -> $0.30 + $0.15 = $0.45
+                usage: TokenUsage {
+                    input_tokens: 100_000,
+                    output_tokens: 10_000,
+                },
+                tool_calls: Vec::new(),
+                cached: false,
+            })
+        }
+
+        fn provider_name(&self) -> &str {
+            "anthropic"
+        }
+    }
+
+    fn request() -> CompletionRequest {
+        CompletionRequest {
+            system_prompt: String::new(),
+            messages: vec![Message::user("hi")],
+            model: String::new(),
+            max_tokens: 16,
+            temperature: 0.0,
+            ..Default::default()
+        }
+    }
+
+    fn provider(budget: Option<BudgetConfig>) -> (BudgetedProvider, Arc<Mutex<Vec<String>>>) {
+        let mut config = Config::default();
+        config.ai.budget = budget;
+        let ledger = UsageLedger::open_at(Path::new(":memory:")).unwrap();
+
+        let alerts = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&alerts);
+        let provider = BudgetedProvider::new(Box::new(FixedProvider), "test", &config, ledger)
+            .on_alert(move |msg| sink.lock().unwrap().push(msg.to_string()));
+        (provider, alerts)
+    }
+
+    #[tokio::test]
+    async fn test_records_spend_without_budget() {
+        let (provider, alerts) = provider(None);
+        provider.complete(request()).await.unwrap();
+        provider.complete(request()).await.unwrap();
+
+        let spent = provider.state.spent_this_week().unwrap();
+        assert!((spent - 0.90).abs() < 1e-9);
+        assert!(alerts.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_audits_each_completion() {
+        let (provider, _) = provider(None);
+        let provider = provider.with_audit(AuditLog::open_at(Path::new(":memory:")).unwrap());
+        provider.complete(request()).await.unwrap();
+
+        let entries = provider
+            .state
+            .audit
+            .as_ref()
+            .unwrap()
+            .query(&nakama_audit::AuditFilter::new())
+            .unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].command, AUDIT_COMMAND);
+        let record = UsageRecord::from_audit_entry(&entries[0]).unwrap();
+        assert_eq!(record.model, "claude-sonnet-4-6");
+        assert_eq!(record.input_tokens, 100_000);
+        assert!((record.cost_usd - 0.45).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_cache_hits_are_free() {
+        let (provider, _) = provider(None);
+        let provider = provider.with_audit(AuditLog::open_at(Path::new(":memory:")).unwrap());
+        let usage = TokenUsage {
+            input_tokens: 100_000,
+            output_tokens: 10_000,
+        };
+        provider
+            .state
+            .record("claude-sonnet-4-6", &usage, true, Instant::now());
+
+        assert_eq!(provider.state.spent_this_week().unwrap(), 0.0);
+        let entries = provider
+            .state
+            .audit
+            .as_ref()
+            .unwrap()
+            .query(&nakama_audit::AuditFilter::new())
+            .unwrap();
+        assert_eq!(entries[0].detail["cache_hit"], true);
+        assert_eq!(entries[0].detail["cost_usd"], 0.0);
+    }
+
+    #[tokio::test]
+    async fn test_alerts_once_at_threshold() {
+        let (provider, alerts) = provider(Some(BudgetConfig {
+            weekly_limit_usd: 1.0,
+            alert_threshold_percent: 80,
+            hard_limit: false,
+        }));
+
+        provider.complete(request()).await.unwrap(); // $0.45
+        assert!(alerts.lock().unwrap().is_empty());
+        provider.complete(request()).await.unwrap(); // $0.90
+        provider.complete(request()).await.unwrap(); // $1.35, soft limit
+        provider.complete(request()).await.unwrap();
+
+        let alerts = alerts.lock().unwrap();
+        assert_eq!(alerts.len(), 1);
+        assert!(alerts[0].contains("90%"));
+    }
+
+    #[tokio::test]
+    async fn test_hard_limit_refuses_requests() {
+        let (provider, _) = provider(Some(BudgetConfig {
+            weekly_limit_usd: 0.5,
+            alert_threshold_percent: 80,
+            hard_limit: true,
+        }));
+
+        provider.complete(request()).await.unwrap(); // $0.45
+        let stream = provider.complete_stream(request()).await.unwrap(); // $0.90
+        collect_stream(stream, |_| {}).await.unwrap();
+
+        let err = provider.complete(request()).await.unwrap_err();
+        assert!(matches!(err, NakamaError::Budget { .. }));
+        assert!(err.to_string().contains("$0.50"));
+        assert!(provider.complete_stream(request()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_hard_limit_serves_cache_hits() {
+        let mut config = Config::default();
+        config.ai.budget = Some(BudgetConfig {
+            weekly_limit_usd: 0.5,
+            alert_threshold_percent: 80,
+            hard_limit: true,
+        });
+        let dir = std::env::temp_dir().join(format!("nakama-budget-cache-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let cache =
+            ResponseCache::open_at(&dir, std::time::Duration::from_secs(3600), u64::MAX).unwrap();
+        let provider = BudgetedProvider::new(
+            Box::new(CachingProvider::new(Box::new(FixedProvider), cache)),
+            "test",
+            &config,
+            UsageLedger::open_at(Path::new(":memory:")).unwrap(),
+        );
+        let prompt = |text: &str| CompletionRequest {
+            messages: vec![Message::user(text)],
+            ..request()
+        };
+
+        provider.complete(prompt("a")).await.unwrap(); // $0.45
+        provider.complete(prompt("b")).await.unwrap(); // $0.90, over the limit
+        assert!(provider.complete(prompt("c")).await.is_err());
+
+        let hit = provider.complete(prompt("a")).await.unwrap();
+        assert!(hit.cached);
+        let streamed = collect_stream(provider.complete_stream(prompt("b")).await.unwrap(), |_| {})
+            .await
+            .unwrap();
+        assert!(streamed.cached);
+        assert!((provider.state.spent_this_week().unwrap() - 0.90).abs() < 1e-9);
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
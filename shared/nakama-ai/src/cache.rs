@@ -0,0 +1,426 @@
+//! On-disk cache of AI responses.
+//!
+//! With `[ai.cache] enabled = true`, [`CachingProvider`] stores every
+//! successful completion under `~/.nakama/cache/ai/`, keyed by a SHA-256 of
+//! the provider, model, system prompt, messages, temperature, token limit and
+//! tool definitions.
+//! An identical request made within `ttl_hours` is answered from disk without
+//! contacting the provider.  When the directory grows past `max_size_mb` the
+//! oldest entries are evicted.
+//!
+//! Requests that leave `model` empty are keyed on the empty string, so a
+//! change to the provider's default model is only picked up once the cached
+//! entry expires.
+
+use crate::provider::{response_stream, AiProvider};
+use crate::stream::CompletionStream;
+use crate::types::{
+    CompletionRequest, CompletionResponse, Message, ResponseFormat, StreamEvent, ToolDefinition,
+};
+use async_trait::async_trait;
+use chrono::Utc;
+use futures_util::StreamExt;
+use nakama_core::config::CacheConfig;
+use nakama_core::error::NakamaResult;
+use nakama_core::paths;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Bumped whenever the key derivation or entry format changes, so stale
+/// entries are never misread.
+const CACHE_VERSION: u32 = 1;
+
+/// The request fields that determine a response.
+#[derive(Serialize)]
+struct CacheKey<'a> {
+    version: u32,
+    provider: &'a str,
+    model: &'a str,
+    system_prompt: &'a str,
+    messages: &'a [Message],
+    temperature: f32,
+    max_tokens: u32,
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    tools: &'a [ToolDefinition],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response_format: Option<&'a ResponseFormat>,
+}
+
+/// A cached response as stored on disk.
+#[derive(Serialize, Deserialize)]
+struct CacheEntry {
+    /// Unix timestamp (seconds) at which the response was stored.
+    created_at: i64,
+    response: CompletionResponse,
+}
+
+/// A directory of cached responses, one JSON file per key.
+#[derive(Debug, Clone)]
+pub struct ResponseCache {
+    dir: PathBuf,
+    ttl: Duration,
+    max_bytes: u64,
+}
+
+impl ResponseCache {
+    /// Open the cache at `~/.nakama/cache/ai/`, creating it if needed.
+    pub fn new(config: &CacheConfig) -> NakamaResult<Self> {
+        let dir = paths::cache_dir()?.join("ai");
+        Self::open_at(
+            &dir,
+            Duration::from_secs(config.ttl_hours * 3600),
+            config.max_size_mb * 1024 * 1024,
+        )
+    }
+
+    /// Open a cache in an explicit directory.
+    pub fn open_at(dir: &Path, ttl: Duration, max_bytes: u64) -> NakamaResult<Self> {
+        paths::ensure_dir(&dir.to_path_buf())?;
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            ttl,
+            max_bytes,
+        })
+    }
+
+    /// The cache key for `request` sent to `provider`.
+    pub fn key(provider: &str, request: &CompletionRequest) -> String {
+        let key = CacheKey {
+            version: CACHE_VERSION,
+            provider,
+            model: &request.model,
+            system_prompt: &request.system_prompt,
+            messages: &request.messages,
+            temperature: request.temperature,
+            max_tokens: request.max_tokens,
+            tools: &request.tools,
+            response_format: request.response_format.as_ref(),
+        };
+        let bytes = serde_json::to_vec(&key).expect("cache key is always serializable");
+        format!("{:x}", Sha256::digest(&bytes))
+    }
+
+    /// Look up a fresh entry.  Expired or unreadable entries are removed.
+    pub fn get(&self, key: &str) -> Option<CompletionResponse> {
+        let path = self.entry_path(key);
+        let data = std::fs::read(&path).ok()?;
+
+        let entry = match serde_json::from_slice::<CacheEntry>(&data) {
+            Ok(entry) if !self.is_expired(entry.created_at) => entry,
+            _ => {
+                let _ = std::fs::remove_file(&path);
+                return None;
+            }
+        };
+
+        tracing::debug!(key, "AI response cache hit");
+        Some(CompletionResponse {
+            cached: true,
+            ..entry.response
+        })
+    }
+
+    /// Store a response, then evict old entries if the cache is over its
+    /// size limit.
+    pub fn put(&self, key: &str, response: &CompletionResponse) -> NakamaResult<()> {
+        let entry = CacheEntry {
+            created_at: Utc::now().timestamp(),
+            response: CompletionResponse {
+                cached: false,
+                ..response.clone()
+            },
+        };
+        let data = serde_json::to_vec(&entry)?;
+
+        // Write to a temporary file and rename so concurrent readers never
+        // see a partial entry.
+        let path = self.entry_path(key);
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, data)?;
+        nakama_core::permissions::set_file_permissions(&tmp)?;
+        std::fs::rename(&tmp, &path)?;
+
+        self.evict()
+    }
+
+    /// Remove expired entries, then the oldest entries until the cache fits
+    /// within `max_bytes`.
+    fn evict(&self) -> NakamaResult<()> {
+        let mut entries = Vec::new();
+        let mut total = 0u64;
+
+        for dir_entry in std::fs::read_dir(&self.dir)? {
+            let path = dir_entry?.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("json") {
+                continue;
+            }
+            let Ok(metadata) = std::fs::metadata(&path) else {
+                continue;
+            };
+            let modified = metadata.modified().unwrap_or(std::time::UNIX_EPOCH);
+            if modified.elapsed().unwrap_or_default() > self.ttl {
+                let _ = std::fs::remove_file(&path);
+                continue;
+            }
+            total += metadata.len();
+            entries.push((modified, metadata.len(), path));
+        }
+
+        if total <= self.max_bytes {
+            return Ok(());
+        }
+
+        entries.sort_by_key(|(modified, _, _)| *modified);
+        for (_, len, path) in entries {
+            if total <= self.max_bytes {
+                break;
+            }
+            std::fs::remove_file(&path)?;
+            total -= len;
+        }
+        Ok(())
+    }
+
+    fn is_expired(&self, created_at: i64) -> bool {
+        let age = Utc::now().timestamp().saturating_sub(created_at);
+        age < 0 || age as u64 >= self.ttl.as_secs()
+    }
+
+    fn entry_path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{key}.json"))
+    }
+}
+
+/// An [`AiProvider`] wrapper that serves repeated requests from a
+/// [`ResponseCache`].
+///
+/// Cached responses are marked with `cached = true` (and a cached
+/// [`StreamEvent::Done`]) so spend tracking can record them at no cost.
+pub struct CachingProvider {
+    inner: Box<dyn AiProvider>,
+    cache: Arc<ResponseCache>,
+}
+
+impl CachingProvider {
+    pub fn new(inner: Box<dyn AiProvider>, cache: ResponseCache) -> Self {
+        Self {
+            inner,
+            cache: Arc::new(cache),
+        }
+    }
+
+    fn store(cache: &ResponseCache, key: &str, response: &CompletionResponse) {
+        if let Err(e) = cache.put(key, response) {
+            tracing::warn!(error = %e, "Failed to write AI response cache");
+        }
+    }
+}
+
+#[async_trait]
+impl AiProvider for CachingProvider {
+    async fn complete(&self, request: CompletionRequest) -> NakamaResult<CompletionResponse> {
+        let key = ResponseCache::key(self.inner.provider_name(), &request);
+        if let Some(response) = self.cache.get(&key) {
+            return Ok(response);
+        }
+
+        let response = self.inner.complete(request).await?;
+        Self::store(&self.cache, &key, &response);
+        Ok(response)
+    }
+
+    async fn complete_stream(&self, request: CompletionRequest) -> NakamaResult<CompletionStream> {
+        let key = ResponseCache::key(self.inner.provider_name(), &request);
+        if let Some(response) = self.cache.get(&key) {
+            return Ok(response_stream(response));
+        }
+
+        // Accumulate the streamed response and store it once the stream
+        // completes successfully.
+        let stream = self.inner.complete_stream(request).await?;
+        let cache = Arc::clone(&self.cache);
+        let partial = Arc::new(Mutex::new(CompletionResponse::default()));
+        Ok(Box::pin(stream.inspect(move |event| {
+            let Ok(mut partial) = partial.lock() else {
+                return;
+            };
+            match event {
+                Ok(StreamEvent::Delta { text }) => partial.content.push_str(text),
+                Ok(StreamEvent::ToolCall(call)) => partial.tool_calls.push(call.clone()),
+                Ok(StreamEvent::Done { model, usage, .. }) => {
+                    partial.model = model.clone();
+                    partial.usage = usage.clone();
+                    Self::store(&cache, &key, &partial);
+                }
+                Err(_) => {}
+            }
+        })))
+    }
+
+    fn cached(&self, request: &CompletionRequest) -> Option<CompletionResponse> {
+        self.cache
+            .get(&ResponseCache::key(self.inner.provider_name(), request))
+    }
+
+    fn provider_name(&self) -> &str {
+        self.inner.provider_name()
+    }
+}
+
+/// Wrap a provider with the response cache if `[ai.cache]` is enabled.
+///
+/// If the cache directory cannot be created the provider is returned
+/// unwrapped (with a logged warning) rather than failing the tool.
+pub fn with_cache(provider: Box<dyn AiProvider>, config: &CacheConfig) -> Box<dyn AiProvider> {
+    if !config.enabled {
+        return provider;
+    }
+    match ResponseCache::new(config) {
+        Ok(cache) => Box::new(CachingProvider::new(provider, cache)),
+        Err(e) => {
+            tracing::warn!(error = %e, "AI response cache unavailable; caching disabled");
+            provider
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stream::collect_stream;
+    use crate::types::TokenUsage;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    struct CountingProvider {
+        calls: Arc<AtomicU32>,
+    }
+
+    #[async_trait]
+    impl AiProvider for CountingProvider {
+        async fn complete(&self, request: CompletionRequest) -> NakamaResult<CompletionResponse> {
+            let n = self.calls.fetch_add(1, Ordering::SeqCst) + 1;
+            Ok(CompletionResponse {
+                content: format!("answer {n} to {}", request.messages[0].content),
+                model: "m".to_string(),
+                usage: TokenUsage {
+                    input_tokens: 10,
+                    output_tokens: 5,
+                },
+                tool_calls: Vec::new(),
+                cached: false,
+            })
+        }
+
+        fn provider_name(&self) -> &str {
+            "mock"
+        }
+    }
+
+    fn temp_cache(name: &str, max_bytes: u64) -> ResponseCache {
+        let dir = std::env::temp_dir().join(format!(
+            "nakama-ai-cache-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        ResponseCache::open_at(&dir, Duration::from_secs(3600), max_bytes).unwrap()
+    }
+
+    fn request(prompt: &str, temperature: f32) -> CompletionRequest {
+        CompletionRequest {
+            system_prompt: "sys".to_string(),
+            messages: vec![Message::user(prompt)],
+            model: "m".to_string(),
+            max_tokens: 100,
+            temperature,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_key_covers_request_fields() {
+        let base = ResponseCache::key("anthropic", &request("diff", 0.0));
+        assert_eq!(base, ResponseCache::key("anthropic", &request("diff", 0.0)));
+        assert_ne!(base, ResponseCache::key("openai", &request("diff", 0.0)));
+        assert_ne!(base, ResponseCache::key("anthropic", &request("diff2", 0.0)));
+        assert_ne!(base, ResponseCache::key("anthropic", &request("diff", 0.2)));
+    }
+
+    #[tokio::test]
+    async fn test_repeated_request_served_from_cache() {
+        let calls = Arc::new(AtomicU32::new(0));
+        let provider = CachingProvider::new(
+            Box::new(CountingProvider {
+                calls: Arc::clone(&calls),
+            }),
+            temp_cache("hit", u64::MAX),
+        );
+
+        let first = provider.complete(request("diff", 0.0)).await.unwrap();
+        assert!(!first.cached);
+        let second = provider.complete(request("diff", 0.0)).await.unwrap();
+        assert!(second.cached);
+        assert_eq!(second.content, first.content);
+
+        let streamed = collect_stream(
+            provider.complete_stream(request("diff", 0.0)).await.unwrap(),
+            |_| {},
+        )
+        .await
+        .unwrap();
+        assert!(streamed.cached);
+        assert_eq!(streamed.content, first.content);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        provider.complete(request("other", 0.0)).await.unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_sampled_review_request_cached() {
+        let calls = Arc::new(AtomicU32::new(0));
+        let provider = CachingProvider::new(
+            Box::new(CountingProvider {
+                calls: Arc::clone(&calls),
+            }),
+            temp_cache("sampled", u64::MAX),
+        );
+
+        // Review passes sample at 0.2; rerunning one on the same diff is
+        // answered from disk.
+        let first = provider.complete(request("diff", 0.2)).await.unwrap();
+        let second = provider.complete(request("diff", 0.2)).await.unwrap();
+        assert!(second.cached);
+        assert_eq!(second.content, first.content);
+        assert!(provider.cached(&request("diff", 0.2)).is_some());
+        assert!(provider.cached(&request("diff", 0.3)).is_none());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_expired_and_oversized_entries_removed() {
+        let cache = temp_cache("evict", 1);
+        let response = CompletionResponse {
+            content: "x".repeat(64),
+            model: "m".to_string(),
+            usage: TokenUsage::default(),
+            tool_calls: Vec::new(),
+            cached: false,
+        };
+
+        cache.put("a", &response).unwrap();
+        assert!(cache.get("a").is_none(), "entry larger than the limit is evicted");
+
+        let cache = ResponseCache {
+            max_bytes: u64::MAX,
+            ttl: Duration::ZERO,
+            ..cache
+        };
+        cache.put("b", &response).unwrap();
+        assert!(cache.get("b").is_none(), "entry past its TTL is not served");
+        assert!(!cache.entry_path("b").exists());
+    }
+}
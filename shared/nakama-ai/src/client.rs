@@ -0,0 +1,436 @@
+//! Building a ready-to-use provider for a tool.
+//!
+//! Every binary goes through [`AiClient::new`], which picks the provider and
+//! model from the [`Config`] (after any [`AiArgs`] overrides), fetches the API
+//! key from the vault, and wraps the provider with the shared retry, cache,
+//! budget/audit and fallback policies.  Tools that take free-form questions
+//! also flatten [`AttachArgs`] into those subcommands.
+
+use crate::attachment::Attachment;
+use crate::budget::with_budget;
+use crate::cache::with_cache;
+use crate::embedding::EmbeddingProvider;
+use crate::fallback::with_fallback;
+use crate::openai::OpenAIProvider;
+use crate::provider::{create_embedding_provider, create_provider, AiProvider};
+use crate::retry::with_retry;
+use crate::stream::CompletionStream;
+use crate::tokens::{ContextBudget, ContextWindows};
+use crate::types::{CompletionRequest, Message};
+use nakama_core::config::Config;
+use nakama_core::error::{NakamaError, NakamaResult};
+use nakama_core::types::{ModelTier, Provider};
+use nakama_ui::NakamaUI;
+use nakama_vault::{CredentialStore, Vault};
+use std::path::PathBuf;
+
+/// Command-line overrides for the AI provider and model, shared by every tool.
+///
+/// Flatten into a tool's `Cli` with `#[command(flatten)]` and call
+/// [`AiArgs::apply`] on the loaded config before creating a client.
+#[derive(Debug, Clone, Default, clap::Args)]
+pub struct AiArgs {
+    /// AI provider to use instead of the configured default
+    /// (anthropic, openai, google, ollama, openai_compatible or
+    /// openai_compatible:ENDPOINT)
+    #[arg(long = "ai-provider", global = true, value_parser = parse_provider_choice)]
+    pub ai_provider: Option<ProviderChoice>,
+
+    /// Exact model to use instead of the tier's configured model
+    #[arg(long = "ai-model", global = true)]
+    pub ai_model: Option<String>,
+
+    /// Bypass the AI response cache for this run
+    #[arg(long = "no-cache", global = true)]
+    pub no_cache: bool,
+}
+
+impl AiArgs {
+    /// Apply the overrides to `config`.
+    ///
+    /// Switching provider (or endpoint) drops a configured `ai.model`, since
+    /// it names a model of the previous provider.
+    pub fn apply(&self, config: &mut Config) {
+        if let Some(choice) = &self.ai_provider {
+            let switching_endpoint =
+                choice.endpoint.is_some() && choice.endpoint != config.ai.endpoint;
+            if choice.provider != config.ai.default_provider || switching_endpoint {
+                config.ai.model = None;
+            }
+            config.ai.default_provider = choice.provider;
+            if let Some(endpoint) = &choice.endpoint {
+                config.ai.endpoint = Some(endpoint.clone());
+            }
+        }
+        if let Some(model) = &self.ai_model {
+            config.ai.model = Some(model.clone());
+        }
+        if self.no_cache {
+            config.ai.cache.enabled = false;
+        }
+    }
+}
+
+/// A provider named on the command line, with the endpoint for
+/// `openai_compatible:<endpoint>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProviderChoice {
+    pub provider: Provider,
+    pub endpoint: Option<String>,
+}
+
+impl From<Provider> for ProviderChoice {
+    fn from(provider: Provider) -> Self {
+        Self {
+            provider,
+            endpoint: None,
+        }
+    }
+}
+
+/// Files to attach to a question (`--attach`, repeatable).
+#[derive(Debug, Clone, Default, clap::Args)]
+pub struct AttachArgs {
+    /// Attach a file to the question (image, PDF or text); may be repeated
+    #[arg(long = "attach", value_name = "FILE")]
+    pub attach: Vec<PathBuf>,
+}
+
+impl AttachArgs {
+    /// Read the attached files.
+    pub fn load(&self) -> NakamaResult<Vec<Attachment>> {
+        self.attach.iter().map(|path| Attachment::from_path(path)).collect()
+    }
+}
+
+/// Parse a provider name, accepting common aliases (`claude`, `gpt`, ...).
+pub fn parse_provider(name: &str) -> Result<Provider, String> {
+    match name.to_lowercase().as_str() {
+        "anthropic" | "claude" => Ok(Provider::Anthropic),
+        "openai" | "gpt" => Ok(Provider::OpenAI),
+        "google" | "gemini" => Ok(Provider::Google),
+        "ollama" | "local" => Ok(Provider::Ollama),
+        "openai_compatible" | "openai-compatible" | "compatible" => Ok(Provider::OpenAICompatible),
+        other => Err(format!(
+            "unknown provider '{other}' (expected anthropic, openai, google, ollama \
+             or openai_compatible)"
+        )),
+    }
+}
+
+/// Parse a provider name as [`parse_provider`] does, also accepting
+/// `openai_compatible:<endpoint>` to pick a named endpoint.
+pub fn parse_provider_choice(name: &str) -> Result<ProviderChoice, String> {
+    if let Some((provider, endpoint)) = name.split_once(':') {
+        return match parse_provider(provider)? {
+            Provider::OpenAICompatible if !endpoint.is_empty() => Ok(ProviderChoice {
+                provider: Provider::OpenAICompatible,
+                endpoint: Some(endpoint.to_string()),
+            }),
+            _ => Err(format!(
+                "'{name}': only openai_compatible takes an endpoint name"
+            )),
+        };
+    }
+    parse_provider(name).map(ProviderChoice::from)
+}
+
+/// The model a tool should use for `tier` with the default provider.
+pub fn default_model(config: &Config, tier: ModelTier) -> String {
+    config
+        .ai
+        .model
+        .clone()
+        .unwrap_or_else(|| config.resolve_model(config.ai.default_provider, tier))
+}
+
+/// Look up the API key for `provider`.
+///
+/// Checks the vault entry `<provider>/api_key` (which includes the
+/// `NAKAMA_<PROVIDER>_API_KEY` environment variable), then the older
+/// `nakama/<provider>_api_key` entry and `<PROVIDER>_API_KEY` variable.
+/// Ollama needs no key.
+pub fn api_key(provider: Provider) -> NakamaResult<String> {
+    if provider == Provider::Ollama {
+        return Ok(String::new());
+    }
+
+    let service = provider.to_string();
+    match Vault::new() {
+        Ok(vault) => {
+            let secret = vault
+                .retrieve(&service, "api_key")
+                .or_else(|_| vault.retrieve("nakama", &format!("{service}_api_key")));
+            if let Ok(secret) = secret {
+                return Ok(secret.expose_secret().to_string());
+            }
+        }
+        Err(e) => tracing::warn!(error = %e, "Credential vault unavailable"),
+    }
+
+    let legacy_var = format!("{}_API_KEY", service.to_uppercase());
+    std::env::var(&legacy_var).map_err(|_| NakamaError::Auth {
+        message: format!(
+            "No API key found for {service}. Store it with: \
+             nakama-vault store {service} api_key, \
+             or set NAKAMA_{}_API_KEY.",
+            service.to_uppercase()
+        ),
+    })
+}
+
+/// Look up the API key for the OpenAI-compatible endpoint `name`: the vault
+/// entry `<name>/api_key` (or `NAKAMA_<NAME>_API_KEY`).  Endpoints without a
+/// key get an empty one and are sent no auth header.
+pub fn endpoint_api_key(name: &str) -> String {
+    match Vault::new() {
+        Ok(vault) => match vault.retrieve(name, "api_key") {
+            Ok(secret) => return secret.expose_secret().to_string(),
+            Err(_) => tracing::debug!(endpoint = name, "No API key stored for endpoint"),
+        },
+        Err(e) => tracing::warn!(error = %e, "Credential vault unavailable"),
+    }
+    String::new()
+}
+
+/// Create a single provider of the given kind for `tool`, wrapped with the
+/// shared retry, cache and budget policies (but no fallback).
+pub fn create_for(
+    config: &Config,
+    tool: &str,
+    provider: Provider,
+    tier: ModelTier,
+) -> NakamaResult<Box<dyn AiProvider>> {
+    let model = if provider == config.ai.default_provider {
+        default_model(config, tier)
+    } else {
+        config.resolve_model(provider, tier)
+    };
+    let raw = if provider == Provider::OpenAICompatible {
+        let (name, endpoint) = config.compatible_endpoint()?;
+        let key = endpoint_api_key(name);
+        Box::new(OpenAIProvider::compatible(name, &key, &model, endpoint))
+    } else {
+        create_provider(provider, &api_key(provider)?, &model, base_url(config, provider))?
+    };
+
+    let ui = NakamaUI::from_config(config);
+    Ok(with_budget(
+        with_cache(with_retry(raw, &config.ai.retry), &config.ai.cache),
+        tool,
+        config,
+        move |msg| ui.warn(msg),
+    ))
+}
+
+/// Create an embedding provider of the given kind, with its API key and base
+/// URL from `config`.
+pub fn create_embedder(
+    config: &Config,
+    provider: Provider,
+) -> NakamaResult<Box<dyn EmbeddingProvider>> {
+    if provider == Provider::OpenAICompatible {
+        let (name, endpoint) = config.compatible_endpoint()?;
+        let key = endpoint_api_key(name);
+        return Ok(Box::new(OpenAIProvider::compatible(name, &key, "", endpoint)));
+    }
+    let key = api_key(provider)?;
+    create_embedding_provider(provider, &key, base_url(config, provider))
+}
+
+/// The configured base URL override for a built-in provider.
+fn base_url(config: &Config, provider: Provider) -> Option<&str> {
+    match provider {
+        Provider::Anthropic => config.ai.anthropic.base_url.as_deref(),
+        Provider::OpenAI => config.ai.openai.base_url.as_deref(),
+        Provider::Google => config.ai.google.base_url.as_deref(),
+        Provider::Ollama => Some(config.ai.ollama.base_url.as_str()),
+        Provider::OpenAICompatible => None,
+    }
+}
+
+/// A configured provider and the model to request from it.
+pub struct AiClient {
+    provider: Box<dyn AiProvider>,
+    kind: Provider,
+    model: String,
+    windows: ContextWindows,
+}
+
+impl AiClient {
+    /// Create the client `tool` uses for requests of the given tier, with the
+    /// providers in `ai.fallback_providers` behind the default one.
+    pub fn new(config: &Config, tool: &str, tier: ModelTier) -> NakamaResult<Self> {
+        let kind = config.ai.default_provider;
+        let primary = create_for(config, tool, kind, tier)?;
+        let ui = NakamaUI::from_config(config);
+        let provider = with_fallback(
+            kind,
+            primary,
+            config,
+            |fallback| create_for(config, tool, fallback, tier),
+            move |msg| ui.warn(msg),
+        );
+        let model = default_model(config, tier);
+
+        tracing::debug!(tool, provider = %kind, model = %model, "AI client ready");
+        Ok(Self {
+            provider,
+            kind,
+            model,
+            windows: ContextWindows::from_config(&config.ai),
+        })
+    }
+
+    /// A client for `model` on an already-built provider.
+    #[cfg(test)]
+    pub(crate) fn from_provider(
+        provider: Box<dyn AiProvider>,
+        kind: Provider,
+        model: &str,
+    ) -> Self {
+        Self {
+            provider,
+            kind,
+            model: model.to_string(),
+            windows: ContextWindows::default(),
+        }
+    }
+
+    /// The wrapped provider, for building requests directly.
+    pub fn provider(&self) -> &dyn AiProvider {
+        self.provider.as_ref()
+    }
+
+    /// The model requests should name.
+    pub fn model(&self) -> &str {
+        &self.model
+    }
+
+    /// The prompt budget for this client's model, keeping `max_output_tokens`
+    /// free for the response.
+    pub fn context_budget(&self, max_output_tokens: u32) -> ContextBudget {
+        ContextBudget::with_windows(&self.windows, self.kind, &self.model, max_output_tokens)
+    }
+
+    /// Split into the provider and model.
+    pub fn into_parts(self) -> (Box<dyn AiProvider>, String) {
+        (self.provider, self.model)
+    }
+
+    /// A single-turn request to this client's model.
+    pub fn request(
+        &self,
+        system_prompt: &str,
+        message: Message,
+        max_tokens: u32,
+        temperature: f32,
+    ) -> CompletionRequest {
+        CompletionRequest {
+            system_prompt: system_prompt.to_string(),
+            messages: vec![message],
+            model: self.model.clone(),
+            max_tokens,
+            temperature,
+            ..Default::default()
+        }
+    }
+
+    /// Send a single-turn completion request and return the response text.
+    pub async fn ask(
+        &self,
+        system_prompt: &str,
+        user_message: &str,
+        max_tokens: u32,
+        temperature: f32,
+    ) -> NakamaResult<String> {
+        self.send(system_prompt, Message::user(user_message), max_tokens, temperature)
+            .await
+    }
+
+    /// Send a single-turn completion request and stream the response.
+    pub async fn ask_stream(
+        &self,
+        system_prompt: &str,
+        user_message: &str,
+        max_tokens: u32,
+        temperature: f32,
+    ) -> NakamaResult<CompletionStream> {
+        self.send_stream(system_prompt, Message::user(user_message), max_tokens, temperature)
+            .await
+    }
+
+    /// Like [`AiClient::ask`], for a message built by the caller (e.g. one
+    /// with attachments).
+    pub async fn send(
+        &self,
+        system_prompt: &str,
+        message: Message,
+        max_tokens: u32,
+        temperature: f32,
+    ) -> NakamaResult<String> {
+        let request = self.request(system_prompt, message, max_tokens, temperature);
+        Ok(self.provider.complete(request).await?.content)
+    }
+
+    /// Like [`AiClient::ask_stream`], for a message built by the caller.
+    pub async fn send_stream(
+        &self,
+        system_prompt: &str,
+        message: Message,
+        max_tokens: u32,
+        temperature: f32,
+    ) -> NakamaResult<CompletionStream> {
+        let request = self.request(system_prompt, message, max_tokens, temperature);
+        self.provider.complete_stream(request).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    #[derive(Parser)]
+    struct Cli {
+        #[command(flatten)]
+        ai: AiArgs,
+    }
+
+    #[test]
+    fn test_cli_overrides_apply_to_config() {
+        let mut config = Config::default();
+        config.ai.model = Some("claude-custom".to_string());
+
+        let cli = Cli::parse_from(["tool", "--ai-provider", "gemini"]);
+        cli.ai.apply(&mut config);
+        assert_eq!(config.ai.default_provider, Provider::Google);
+        assert_eq!(default_model(&config, ModelTier::Powerful), "gemini-2.5-pro");
+
+        let cli = Cli::parse_from(["tool", "--ai-model", "gemini-exp"]);
+        cli.ai.apply(&mut config);
+        assert_eq!(default_model(&config, ModelTier::Fast), "gemini-exp");
+
+        config.ai.cache.enabled = true;
+        let cli = Cli::parse_from(["tool", "--no-cache"]);
+        cli.ai.apply(&mut config);
+        assert!(!config.ai.cache.enabled);
+
+        assert!(Cli::try_parse_from(["tool", "--ai-provider", "skynet"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_selects_compatible_endpoint() {
+        let mut config = Config::default();
+        config.ai.model = Some("claude-custom".to_string());
+
+        let cli = Cli::parse_from(["tool", "--ai-provider", "openai_compatible:gateway"]);
+        cli.ai.apply(&mut config);
+        assert_eq!(config.ai.default_provider, Provider::OpenAICompatible);
+        assert_eq!(config.ai.endpoint.as_deref(), Some("gateway"));
+        assert_eq!(config.ai.model, None);
+
+        assert!(Cli::try_parse_from(["tool", "--ai-provider", "openai:gateway"]).is_err());
+        assert!(Cli::try_parse_from(["tool", "--ai-provider", "compatible"]).is_ok());
+    }
+}
@@ -0,0 +1,255 @@
+//! Text embeddings and vector similarity.
+//!
+//! [`EmbeddingProvider`] is implemented by the OpenAI (and OpenAI-compatible),
+//! Google and Ollama providers; Anthropic has no embeddings API.  Each call
+//! to [`EmbeddingProvider::embed`] is a single API request, and
+//! [`embed_batched`] splits larger inputs into requests the provider accepts.
+//! [`cosine_similarity`] and [`top_k`] rank vectors locally, which is enough
+//! for the few thousand items a CLI tool works with.
+
+use crate::types::TokenUsage;
+use async_trait::async_trait;
+use nakama_core::error::{NakamaError, NakamaResult};
+use std::cmp::Ordering;
+
+/// A request to embed one or more texts.
+#[derive(Debug, Clone, Default)]
+pub struct EmbeddingRequest {
+    /// Texts to embed; the response has one vector per input, in order.
+    pub inputs: Vec<String>,
+
+    /// Embedding model; empty selects the provider's default.
+    pub model: String,
+
+    /// Shorter vectors to ask for, on models that support it (OpenAI
+    /// `text-embedding-3-*`, Gemini).  Ignored by Ollama.
+    pub dimensions: Option<usize>,
+}
+
+impl EmbeddingRequest {
+    /// Embed `inputs` with the provider's default model.
+    pub fn new<S: Into<String>>(inputs: impl IntoIterator<Item = S>) -> Self {
+        Self {
+            inputs: inputs.into_iter().map(Into::into).collect(),
+            ..Default::default()
+        }
+    }
+
+    pub fn with_model(mut self, model: impl Into<String>) -> Self {
+        self.model = model.into();
+        self
+    }
+
+    pub fn with_dimensions(mut self, dimensions: usize) -> Self {
+        self.dimensions = Some(dimensions);
+        self
+    }
+}
+
+/// Vectors returned for an [`EmbeddingRequest`].
+#[derive(Debug, Clone, Default)]
+pub struct EmbeddingResponse {
+    /// One vector per input, in input order.
+    pub embeddings: Vec<Vec<f32>>,
+
+    /// Model that produced the vectors.
+    pub model: String,
+
+    /// Length of each vector.
+    pub dimensions: usize,
+
+    /// Input tokens billed (`output_tokens` is always zero).  Zero when the
+    /// provider doesn't report usage.
+    pub usage: TokenUsage,
+}
+
+impl EmbeddingResponse {
+    /// Build a response, checking that there is one vector per input and
+    /// that they all have the same length.
+    pub(crate) fn new(
+        provider: &str,
+        inputs: usize,
+        embeddings: Vec<Vec<f32>>,
+        model: String,
+        input_tokens: u32,
+    ) -> NakamaResult<Self> {
+        let dimensions = embeddings.first().map_or(0, Vec::len);
+        if embeddings.len() != inputs || embeddings.iter().any(|v| v.len() != dimensions) {
+            return Err(NakamaError::AiProvider {
+                message: format!(
+                    "{provider} returned {} embeddings for {inputs} inputs",
+                    embeddings.len()
+                ),
+                source: None,
+            });
+        }
+        Ok(Self {
+            embeddings,
+            model,
+            dimensions,
+            usage: TokenUsage {
+                input_tokens,
+                output_tokens: 0,
+            },
+        })
+    }
+}
+
+/// Interface for providers that can embed text.
+#[async_trait]
+pub trait EmbeddingProvider: Send + Sync {
+    /// Embed every input in a single API request.
+    async fn embed(&self, request: EmbeddingRequest) -> NakamaResult<EmbeddingResponse>;
+
+    /// Most inputs the provider accepts in one request.
+    fn max_batch_size(&self) -> usize;
+
+    /// Model used when a request doesn't name one.
+    fn default_embedding_model(&self) -> &str;
+
+    /// Human-readable name of this provider (e.g. `"openai"`).
+    fn provider_name(&self) -> &str;
+}
+
+/// Embed any number of inputs, sending as many requests as the provider's
+/// batch size requires.
+pub async fn embed_batched(
+    provider: &dyn EmbeddingProvider,
+    request: EmbeddingRequest,
+) -> NakamaResult<EmbeddingResponse> {
+    let batch_size = provider.max_batch_size().max(1);
+    if request.inputs.len() <= batch_size {
+        return provider.embed(request).await;
+    }
+
+    let mut result = EmbeddingResponse::default();
+    for batch in request.inputs.chunks(batch_size) {
+        let response = provider
+            .embed(EmbeddingRequest {
+                inputs: batch.to_vec(),
+                model: request.model.clone(),
+                dimensions: request.dimensions,
+            })
+            .await?;
+        result.embeddings.extend(response.embeddings);
+        result.model = response.model;
+        result.dimensions = response.dimensions;
+        result.usage.input_tokens += response.usage.input_tokens;
+    }
+    Ok(result)
+}
+
+/// Native vector length of well-known embedding models, by model id prefix.
+const KNOWN_DIMENSIONS: &[(&str, usize)] = &[
+    ("text-embedding-3-small", 1536),
+    ("text-embedding-3-large", 3072),
+    ("text-embedding-ada-002", 1536),
+    ("gemini-embedding-001", 3072),
+    ("text-embedding-004", 768),
+    ("nomic-embed-text", 768),
+    ("mxbai-embed-large", 1024),
+    ("all-minilm", 384),
+    ("bge-m3", 1024),
+];
+
+/// The native vector length of `model`, if it is a well-known embedding
+/// model.  Useful for sizing storage before the first request.
+pub fn known_dimensions(model: &str) -> Option<usize> {
+    KNOWN_DIMENSIONS
+        .iter()
+        .filter(|(prefix, _)| model.starts_with(prefix))
+        .max_by_key(|(prefix, _)| prefix.len())
+        .map(|(_, dimensions)| *dimensions)
+}
+
+/// Cosine similarity of two vectors, in `[-1, 1]`.  Zero if either vector is
+/// all zeros or their lengths differ.
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() {
+        return 0.0;
+    }
+    let (mut dot, mut norm_a, mut norm_b) = (0.0f32, 0.0f32, 0.0f32);
+    for (x, y) in a.iter().zip(b) {
+        dot += x * y;
+        norm_a += x * x;
+        norm_b += y * y;
+    }
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return 0.0;
+    }
+    dot / (norm_a.sqrt() * norm_b.sqrt())
+}
+
+/// The `k` candidates most similar to `query`, as `(index, similarity)`
+/// pairs, most similar first.
+pub fn top_k<V: AsRef<[f32]>>(query: &[f32], candidates: &[V], k: usize) -> Vec<(usize, f32)> {
+    let mut scored: Vec<(usize, f32)> = candidates
+        .iter()
+        .enumerate()
+        .map(|(index, candidate)| (index, cosine_similarity(query, candidate.as_ref())))
+        .collect();
+    scored.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(Ordering::Equal));
+    scored.truncate(k);
+    scored
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[test]
+    fn test_similarity_ranking() {
+        assert!((cosine_similarity(&[1.0, 0.0], &[2.0, 0.0]) - 1.0).abs() < 1e-6);
+        assert_eq!(cosine_similarity(&[1.0, 0.0], &[0.0, 0.0]), 0.0);
+        assert_eq!(cosine_similarity(&[1.0], &[1.0, 0.0]), 0.0);
+
+        let candidates = vec![vec![0.0, 1.0], vec![1.0, 0.1], vec![-1.0, 0.0], vec![0.7, 0.7]];
+        let ranked = top_k(&[1.0, 0.0], &candidates, 2);
+        assert_eq!(ranked.iter().map(|(i, _)| *i).collect::<Vec<_>>(), vec![1, 3]);
+
+        assert_eq!(known_dimensions("text-embedding-3-large"), Some(3072));
+        assert_eq!(known_dimensions("nomic-embed-text:latest"), Some(768));
+        assert_eq!(known_dimensions("gpt-4.1"), None);
+    }
+
+    /// Embeds each input as `[len]`, recording batch sizes.
+    struct Lengths {
+        batches: Mutex<Vec<usize>>,
+    }
+
+    #[async_trait]
+    impl EmbeddingProvider for Lengths {
+        async fn embed(&self, request: EmbeddingRequest) -> NakamaResult<EmbeddingResponse> {
+            self.batches.lock().unwrap().push(request.inputs.len());
+            let embeddings = request.inputs.iter().map(|s| vec![s.len() as f32]).collect();
+            EmbeddingResponse::new("mock", request.inputs.len(), embeddings, "len".to_string(), 2)
+        }
+
+        fn max_batch_size(&self) -> usize {
+            2
+        }
+
+        fn default_embedding_model(&self) -> &str {
+            "len"
+        }
+
+        fn provider_name(&self) -> &str {
+            "mock"
+        }
+    }
+
+    #[tokio::test]
+    async fn test_embed_batched_preserves_order() {
+        let provider = Lengths {
+            batches: Mutex::new(Vec::new()),
+        };
+        let request = EmbeddingRequest::new(["a", "bb", "ccc", "dddd", "eeeee"]);
+        let response = embed_batched(&provider, request).await.unwrap();
+
+        assert_eq!(*provider.batches.lock().unwrap(), vec![2, 2, 1]);
+        assert_eq!(response.embeddings, vec![vec![1.0], vec![2.0], vec![3.0], vec![4.0], vec![5.0]]);
+        assert_eq!(response.dimensions, 1);
+        assert_eq!(response.usage.input_tokens, 6);
+    }
+}
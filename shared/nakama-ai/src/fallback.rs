@@ -0,0 +1,286 @@
+//! Falling back to other providers when the primary one is unavailable.
+//!
+//! [`FallbackProvider`] holds the primary provider followed by each provider
+//! in `ai.fallback_providers`.  When a request fails with a transient error
+//! (after the primary's own retries) or an authentication error, it is sent
+//! to the next provider in the chain, with the model mapped to the same
+//! [`ModelTier`] via [`Config::resolve_model`].
+
+use crate::provider::AiProvider;
+use crate::retry::{classify, ApiStatus, ErrorClass};
+use crate::stream::CompletionStream;
+use crate::types::{CompletionRequest, CompletionResponse};
+use async_trait::async_trait;
+use nakama_core::config::Config;
+use nakama_core::error::{NakamaError, NakamaResult};
+use nakama_core::types::{ModelTier, Provider};
+use std::future::Future;
+
+/// Callback invoked with a human-readable message when a request falls back
+/// to another provider.
+pub type FallbackHook = Box<dyn Fn(&str) + Send + Sync>;
+
+const TIERS: [ModelTier; 3] = [ModelTier::Fast, ModelTier::Balanced, ModelTier::Powerful];
+
+/// Whether an error from one provider should send the request to the next.
+///
+/// Transient failures (which have already exhausted their retries) and
+/// authentication failures fall back; bad requests, budget refusals and
+/// unparseable responses would fail the same way everywhere, so they don't.
+pub fn should_fall_back(error: &NakamaError) -> bool {
+    match error {
+        NakamaError::Auth { .. } => true,
+        NakamaError::AiProvider {
+            source: Some(source),
+            ..
+        } if source
+            .downcast_ref::<ApiStatus>()
+            .is_some_and(|api| api.status == 401 || api.status == 403) =>
+        {
+            true
+        }
+        _ => matches!(classify(error), ErrorClass::Transient { .. }),
+    }
+}
+
+/// An [`AiProvider`] that tries each provider of a chain in order.
+pub struct FallbackProvider {
+    chain: Vec<(Provider, Box<dyn AiProvider>)>,
+    config: Config,
+    on_fallback: Option<FallbackHook>,
+}
+
+impl FallbackProvider {
+    /// Build a chain starting with `primary`, the provider `kind` was created
+    /// from.
+    pub fn new(kind: Provider, primary: Box<dyn AiProvider>, config: &Config) -> Self {
+        Self {
+            chain: vec![(kind, primary)],
+            config: config.clone(),
+            on_fallback: None,
+        }
+    }
+
+    /// Append a provider to the end of the chain.
+    pub fn then(mut self, kind: Provider, provider: Box<dyn AiProvider>) -> Self {
+        self.chain.push((kind, provider));
+        self
+    }
+
+    /// Set the callback used to report fallbacks (e.g. `NakamaUI::warn`).
+    pub fn on_fallback(mut self, hook: impl Fn(&str) + Send + Sync + 'static) -> Self {
+        self.on_fallback = Some(Box::new(hook));
+        self
+    }
+
+    /// The model to request from `to` for a request made to `from` with
+    /// `model`.
+    ///
+    /// The tier is found by matching `model` against `from`'s configured
+    /// models.  An empty or unrecognized model maps to an empty string, which
+    /// selects the fallback provider's own default.
+    fn map_model(&self, from: Provider, to: Provider, model: &str) -> String {
+        if from == to || model.is_empty() {
+            return model.to_string();
+        }
+        TIERS
+            .iter()
+            .find(|&&tier| self.config.resolve_model(from, tier) == model)
+            .map(|&tier| self.config.resolve_model(to, tier))
+            .unwrap_or_default()
+    }
+
+    async fn run<'a, T, F, Fut>(&'a self, request: CompletionRequest, op: F) -> NakamaResult<T>
+    where
+        F: Fn(&'a dyn AiProvider, CompletionRequest) -> Fut,
+        Fut: Future<Output = NakamaResult<T>>,
+    {
+        let primary = self.chain[0].0;
+        let mut last_error = None;
+
+        for (index, (kind, provider)) in self.chain.iter().enumerate() {
+            let mut request = request.clone();
+            request.model = self.map_model(primary, *kind, &request.model);
+
+            if let Some(error) = &last_error {
+                let message = format!(
+                    "{} failed ({error}); falling back to {kind}",
+                    self.chain[index - 1].0
+                );
+                tracing::warn!(from = %self.chain[index - 1].0, to = %kind, error = %error, "AI provider fallback");
+                if let Some(hook) = &self.on_fallback {
+                    hook(&message);
+                }
+            }
+
+            match op(provider.as_ref(), request).await {
+                Ok(value) => {
+                    if index > 0 {
+                        tracing::info!(provider = %kind, "Request answered by fallback provider");
+                    }
+                    return Ok(value);
+                }
+                Err(e) if index + 1 < self.chain.len() && should_fall_back(&e) => {
+                    last_error = Some(e);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        Err(last_error.expect("fallback chain is never empty"))
+    }
+}
+
+#[async_trait]
+impl AiProvider for FallbackProvider {
+    async fn complete(&self, request: CompletionRequest) -> NakamaResult<CompletionResponse> {
+        self.run(request, |provider, request| provider.complete(request))
+            .await
+    }
+
+    /// Only opening the stream falls back; an error after the first event is
+    /// passed through, since part of the response has already been consumed.
+    async fn complete_stream(&self, request: CompletionRequest) -> NakamaResult<CompletionStream> {
+        self.run(request, |provider, request| provider.complete_stream(request))
+            .await
+    }
+
+    /// The primary provider's name.  Usage is attributed per provider by the
+    /// budget wrapper around each link, so this never changes.
+    fn provider_name(&self) -> &str {
+        self.chain[0].1.provider_name()
+    }
+}
+
+/// Add the providers in `config.ai.fallback_providers` behind `provider`.
+///
+/// `create` builds a fully wrapped provider for a given kind; providers that
+/// cannot be created (e.g. no API key stored) are skipped with a logged
+/// warning.  Returns `provider` unchanged when there is nothing to fall back
+/// to.
+pub fn with_fallback<E: std::fmt::Display>(
+    kind: Provider,
+    provider: Box<dyn AiProvider>,
+    config: &Config,
+    mut create: impl FnMut(Provider) -> Result<Box<dyn AiProvider>, E>,
+    on_fallback: impl Fn(&str) + Send + Sync + 'static,
+) -> Box<dyn AiProvider> {
+    let mut chain = FallbackProvider::new(kind, provider, config);
+    let mut seen = vec![kind];
+
+    for &fallback in &config.ai.fallback_providers {
+        if seen.contains(&fallback) {
+            continue;
+        }
+        seen.push(fallback);
+        match create(fallback) {
+            Ok(p) => chain = chain.then(fallback, p),
+            Err(e) => {
+                tracing::warn!(provider = %fallback, error = %e, "Skipping unavailable fallback provider")
+            }
+        }
+    }
+
+    if chain.chain.len() == 1 {
+        return chain.chain.pop().expect("primary provider").1;
+    }
+    Box::new(chain.on_fallback(on_fallback))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::retry::api_error;
+    use crate::types::{Message, TokenUsage};
+    use reqwest::StatusCode;
+    use std::sync::{Arc, Mutex};
+
+    /// Provider that fails with a fixed status or answers with its name, and
+    /// records the models it was asked for.
+    struct StubProvider {
+        name: &'static str,
+        fail_with: Option<u16>,
+        models: Arc<Mutex<Vec<String>>>,
+    }
+
+    #[async_trait]
+    impl AiProvider for StubProvider {
+        async fn complete(&self, request: CompletionRequest) -> NakamaResult<CompletionResponse> {
+            self.models.lock().unwrap().push(request.model.clone());
+            if let Some(status) = self.fail_with {
+                let status = StatusCode::from_u16(status).unwrap();
+                return Err(api_error(self.name, status, None, "stub failure"));
+            }
+            Ok(CompletionResponse {
+                content: format!("from {}", self.name),
+                model: request.model,
+                usage: TokenUsage::default(),
+                tool_calls: Vec::new(),
+                cached: false,
+            })
+        }
+
+        fn provider_name(&self) -> &str {
+            self.name
+        }
+    }
+
+    fn stub(name: &'static str, fail_with: Option<u16>) -> (Box<dyn AiProvider>, Arc<Mutex<Vec<String>>>) {
+        let models = Arc::new(Mutex::new(Vec::new()));
+        let provider = StubProvider {
+            name,
+            fail_with,
+            models: Arc::clone(&models),
+        };
+        (Box::new(provider), models)
+    }
+
+    fn request(config: &Config) -> CompletionRequest {
+        CompletionRequest {
+            system_prompt: String::new(),
+            messages: vec![Message::user("hi")],
+            model: config.resolve_model(Provider::Anthropic, ModelTier::Powerful),
+            max_tokens: 16,
+            temperature: 0.0,
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_falls_back_with_equivalent_tier() {
+        let config = Config::default();
+        let (primary, _) = stub("anthropic", Some(529));
+        let (openai, openai_models) = stub("openai", None);
+        let notices = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&notices);
+
+        let chain = FallbackProvider::new(Provider::Anthropic, primary, &config)
+            .then(Provider::OpenAI, openai)
+            .on_fallback(move |msg| sink.lock().unwrap().push(msg.to_string()));
+
+        let response = chain.complete(request(&config)).await.unwrap();
+        assert_eq!(response.content, "from openai");
+        assert_eq!(*openai_models.lock().unwrap(), vec!["gpt-4.1".to_string()]);
+        let notices = notices.lock().unwrap();
+        assert_eq!(notices.len(), 1);
+        assert!(notices[0].contains("falling back to openai"));
+    }
+
+    #[tokio::test]
+    async fn test_permanent_errors_do_not_fall_back() {
+        let config = Config::default();
+        let (primary, _) = stub("anthropic", Some(400));
+        let (openai, openai_models) = stub("openai", None);
+        let chain =
+            FallbackProvider::new(Provider::Anthropic, primary, &config).then(Provider::OpenAI, openai);
+
+        assert!(chain.complete(request(&config)).await.is_err());
+        assert!(openai_models.lock().unwrap().is_empty());
+
+        let (primary, _) = stub("anthropic", Some(401));
+        let (ollama, _) = stub("ollama", None);
+        let chain =
+            FallbackProvider::new(Provider::Anthropic, primary, &config).then(Provider::Ollama, ollama);
+        assert_eq!(chain.complete(request(&config)).await.unwrap().content, "from ollama");
+    }
+}
@@ -1,13 +1,19 @@
 //! Google Gemini provider implementation.
 
-use crate::provider::AiProvider;
-use crate::types::{CompletionRequest, CompletionResponse, Role, TokenUsage};
+use crate::attachment::split_content;
+use crate::embedding::{EmbeddingProvider, EmbeddingRequest, EmbeddingResponse};
+use crate::provider::{response_stream, AiProvider};
+use crate::retry::{api_error, parse_retry_after};
+use crate::stream::{decode_response, CompletionStream, SseParser, StreamDecoder};
+use crate::types::{CompletionRequest, CompletionResponse, Role, StreamEvent, TokenUsage, ToolCall};
 use async_trait::async_trait;
 use nakama_core::error::{NakamaError, NakamaResult};
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 const DEFAULT_BASE_URL: &str = "https://generativelanguage.googleapis.com";
+const DEFAULT_EMBEDDING_MODEL: &str = "gemini-embedding-001";
 
 /// Google Gemini provider.
 pub struct GoogleProvider {
@@ -27,6 +33,171 @@ impl GoogleProvider {
             base_url: base_url.unwrap_or(DEFAULT_BASE_URL).trim_end_matches('/').to_string(),
         }
     }
+
+    /// Resolve the model and build the `generateContent` request body.
+    fn build_body(&self, request: &CompletionRequest) -> (String, GeminiRequest) {
+        let model = if request.model.is_empty() {
+            &self.default_model
+        } else {
+            &request.model
+        };
+
+        // Gemini matches function responses to calls by name rather than id.
+        let call_names: HashMap<&str, &str> = request
+            .messages
+            .iter()
+            .flat_map(|m| &m.tool_calls)
+            .map(|call| (call.id.as_str(), call.name.as_str()))
+            .collect();
+
+        // Build conversation contents.
+        let mut contents: Vec<GeminiContent> = Vec::new();
+        for m in request.messages.iter().filter(|m| m.role != Role::System) {
+            if m.role == Role::Tool {
+                let id = m.tool_call_id.as_deref().unwrap_or_default();
+                let part = GeminiPart {
+                    function_response: Some(GeminiFunctionResponse {
+                        name: call_names.get(id).copied().unwrap_or(id).to_string(),
+                        response: serde_json::json!({ "content": m.content }),
+                    }),
+                    ..GeminiPart::default()
+                };
+                // Responses to parallel calls must share one turn.
+                match contents.last_mut() {
+                    Some(last) if last.parts.iter().all(|p| p.function_response.is_some()) => {
+                        last.parts.push(part)
+                    }
+                    _ => contents.push(GeminiContent {
+                        role: Some("user".to_string()),
+                        parts: vec![part],
+                    }),
+                }
+                continue;
+            }
+
+            // Gemini takes any media type inline, so every attachment is sent.
+            let (text, attachments) = split_content(&m.content, &m.attachments);
+            let mut parts: Vec<GeminiPart> = attachments
+                .into_iter()
+                .map(|a| GeminiPart {
+                    inline_data: Some(GeminiBlob {
+                        mime_type: a.media_type.clone(),
+                        data: a.base64(),
+                    }),
+                    ..GeminiPart::default()
+                })
+                .collect();
+            if !text.is_empty() || m.tool_calls.is_empty() {
+                parts.push(GeminiPart::text(&text));
+            }
+            parts.extend(m.tool_calls.iter().map(|call| GeminiPart {
+                function_call: Some(GeminiFunctionCall {
+                    name: call.name.clone(),
+                    args: call.arguments.clone(),
+                }),
+                ..GeminiPart::default()
+            }));
+            contents.push(GeminiContent {
+                role: Some(match m.role {
+                    Role::Assistant => "model".to_string(),
+                    _ => "user".to_string(),
+                }),
+                parts,
+            });
+        }
+
+        let tools = if request.tools.is_empty() {
+            Vec::new()
+        } else {
+            vec![GeminiTool {
+                function_declarations: request
+                    .tools
+                    .iter()
+                    .map(|tool| GeminiFunctionDeclaration {
+                        name: tool.name.clone(),
+                        description: tool.description.clone(),
+                        parameters: tool.input_schema.clone(),
+                    })
+                    .collect(),
+            }]
+        };
+
+        // `responseSchema` only accepts an OpenAPI subset of JSON schema, so
+        // JSON mode is switched on natively but the schema goes in the prompt.
+        let system_prompt = match &request.response_format {
+            Some(format) => format.with_instructions(&request.system_prompt),
+            None => request.system_prompt.clone(),
+        };
+        let system_instruction = if system_prompt.is_empty() {
+            None
+        } else {
+            Some(GeminiContent {
+                role: None,
+                parts: vec![GeminiPart::text(&system_prompt)],
+            })
+        };
+
+        let body = GeminiRequest {
+            contents,
+            system_instruction,
+            tools,
+            generation_config: Some(GeminiGenerationConfig {
+                max_output_tokens: request.max_tokens,
+                temperature: request.temperature,
+                response_mime_type: request
+                    .response_format
+                    .as_ref()
+                    .map(|_| "application/json".to_string()),
+            }),
+        };
+
+        (model.to_string(), body)
+    }
+
+    /// Send the request and return the raw response, mapping HTTP errors.
+    ///
+    /// `url` embeds the API key, so it is never logged.
+    async fn send(
+        &self,
+        url: &str,
+        model: &str,
+        body: &impl Serialize,
+    ) -> NakamaResult<reqwest::Response> {
+        tracing::debug!(
+            provider = "google",
+            model = %model,
+            "Sending request"
+        );
+
+        let response = self
+            .client
+            .post(url)
+            .header("Content-Type", "application/json")
+            .json(body)
+            .send()
+            .await
+            .map_err(|e| NakamaError::Network {
+                message: format!("Google Gemini API request failed: {e}"),
+                source: Some(Box::new(e)),
+            })?;
+
+        let status = response.status();
+        if status.is_success() {
+            return Ok(response);
+        }
+        let retry_after = parse_retry_after(response.headers());
+
+        let response_text = response.text().await.map_err(|e| NakamaError::Network {
+            message: format!("Failed to read Google Gemini response body: {e}"),
+            source: Some(Box::new(e)),
+        })?;
+
+        let error_msg = serde_json::from_str::<GeminiError>(&response_text)
+            .map(|e| e.error.message)
+            .unwrap_or_else(|_| response_text.clone());
+
+        Err(api_error("Google Gemini", status, retry_after, &error_msg))
+    }
 }
 
 // --- Google Gemini API request/response types ---
@@ -37,6 +208,8 @@ struct GeminiRequest {
     contents: Vec<GeminiContent>,
     #[serde(skip_serializing_if = "Option::is_none")]
     system_instruction: Option<GeminiContent>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tools: Vec<GeminiTool>,
     #[serde(skip_serializing_if = "Option::is_none")]
     generation_config: Option<GeminiGenerationConfig>,
 }
@@ -48,9 +221,85 @@ struct GeminiContent {
     parts: Vec<GeminiPart>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// One part of a turn: text, inline file data, a function call, or a
+/// function response.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 struct GeminiPart {
-    text: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    text: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    inline_data: Option<GeminiBlob>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    function_call: Option<GeminiFunctionCall>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    function_response: Option<GeminiFunctionResponse>,
+}
+
+impl GeminiPart {
+    fn text(text: &str) -> Self {
+        Self {
+            text: Some(text.to_string()),
+            ..Self::default()
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GeminiBlob {
+    mime_type: String,
+    data: String,
+}
+
+#[derive(Debug, Serialize)]
+struct GeminiEmbedBatch {
+    requests: Vec<GeminiEmbedRequest>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct GeminiEmbedRequest {
+    model: String,
+    content: GeminiContent,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    output_dimensionality: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GeminiEmbedResponse {
+    embeddings: Vec<GeminiEmbedding>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GeminiEmbedding {
+    values: Vec<f32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct GeminiFunctionCall {
+    name: String,
+    #[serde(default)]
+    args: serde_json::Value,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct GeminiFunctionResponse {
+    name: String,
+    response: serde_json::Value,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct GeminiTool {
+    function_declarations: Vec<GeminiFunctionDeclaration>,
+}
+
+#[derive(Debug, Serialize)]
+struct GeminiFunctionDeclaration {
+    name: String,
+    description: String,
+    parameters: serde_json::Value,
 }
 
 #[derive(Debug, Serialize)]
@@ -58,6 +307,8 @@ struct GeminiPart {
 struct GeminiGenerationConfig {
     max_output_tokens: u32,
     temperature: f32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response_mime_type: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -89,128 +340,287 @@ struct GeminiErrorDetail {
     message: String,
 }
 
-#[async_trait]
-impl AiProvider for GoogleProvider {
-    async fn complete(&self, request: CompletionRequest) -> NakamaResult<CompletionResponse> {
-        let model = if request.model.is_empty() {
-            &self.default_model
-        } else {
-            &request.model
-        };
+/// Concatenate the text parts of the first candidate.
+fn candidate_text(response: &GeminiResponse) -> String {
+    response
+        .candidates
+        .as_ref()
+        .and_then(|c| c.first())
+        .map(|candidate| {
+            candidate
+                .content
+                .parts
+                .iter()
+                .filter_map(|p| p.text.as_deref())
+                .collect::<Vec<_>>()
+                .join("")
+        })
+        .unwrap_or_default()
+}
 
-        // Build conversation contents.
-        let contents: Vec<GeminiContent> = request
-            .messages
-            .iter()
-            .filter(|m| m.role != Role::System)
-            .map(|m| GeminiContent {
-                role: Some(match m.role {
-                    Role::User => "user".to_string(),
-                    Role::Assistant => "model".to_string(),
-                    Role::System => "user".to_string(), // unreachable due to filter
-                }),
-                parts: vec![GeminiPart {
-                    text: m.content.clone(),
-                }],
-            })
-            .collect();
+/// The function calls in the first candidate.  Gemini doesn't assign call
+/// ids, so the calls are numbered.
+fn candidate_tool_calls(response: &GeminiResponse) -> Vec<ToolCall> {
+    response
+        .candidates
+        .iter()
+        .flatten()
+        .take(1)
+        .flat_map(|candidate| &candidate.content.parts)
+        .filter_map(|p| p.function_call.as_ref())
+        .enumerate()
+        .map(|(i, call)| ToolCall {
+            id: format!("call_{i}"),
+            name: call.name.clone(),
+            arguments: call.args.clone(),
+        })
+        .collect()
+}
 
-        let system_instruction = if request.system_prompt.is_empty() {
-            None
+/// Decodes the `streamGenerateContent?alt=sse` stream.
+///
+/// Every event is a full `GenerateContentResponse` carrying the next slice of
+/// text; `usageMetadata` is cumulative, so the last value wins.
+struct GeminiDecoder {
+    sse: SseParser,
+    model: String,
+    usage: TokenUsage,
+}
+
+impl GeminiDecoder {
+    fn new(model: &str) -> Self {
+        Self {
+            sse: SseParser::default(),
+            model: model.to_string(),
+            usage: TokenUsage::default(),
+        }
+    }
+
+    fn handle_event(&mut self, data: &str) -> NakamaResult<Vec<StreamEvent>> {
+        if let Ok(error) = serde_json::from_str::<GeminiError>(data) {
+            return Err(NakamaError::AiProvider {
+                message: format!("Google Gemini stream error: {}", error.error.message),
+                source: None,
+            });
+        }
+
+        let chunk: GeminiResponse =
+            serde_json::from_str(data).map_err(|e| NakamaError::AiProvider {
+                message: format!("Failed to parse Google Gemini stream chunk: {e}"),
+                source: Some(Box::new(e)),
+            })?;
+
+        if let Some(meta) = chunk.usage_metadata.as_ref() {
+            if let Some(input) = meta.prompt_token_count {
+                self.usage.input_tokens = input;
+            }
+            if let Some(output) = meta.candidates_token_count {
+                self.usage.output_tokens = output;
+            }
+        }
+
+        let text = candidate_text(&chunk);
+        if text.is_empty() {
+            Ok(Vec::new())
         } else {
-            Some(GeminiContent {
-                role: None,
-                parts: vec![GeminiPart {
-                    text: request.system_prompt.clone(),
-                }],
-            })
-        };
+            Ok(vec![StreamEvent::Delta { text }])
+        }
+    }
+}
 
-        let body = GeminiRequest {
-            contents,
-            system_instruction,
-            generation_config: Some(GeminiGenerationConfig {
-                max_output_tokens: request.max_tokens,
-                temperature: request.temperature,
-            }),
+impl StreamDecoder for GeminiDecoder {
+    fn decode_line(&mut self, line: &str) -> NakamaResult<Vec<StreamEvent>> {
+        match self.sse.push_line(line) {
+            Some(event) => self.handle_event(&event.data),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    fn finish(&mut self) -> NakamaResult<Vec<StreamEvent>> {
+        let mut events = match self.sse.flush() {
+            Some(event) => self.handle_event(&event.data)?,
+            None => Vec::new(),
         };
+        events.push(StreamEvent::Done {
+            model: std::mem::take(&mut self.model),
+            usage: std::mem::take(&mut self.usage),
+            cached: false,
+        });
+        Ok(events)
+    }
+}
 
+#[async_trait]
+impl AiProvider for GoogleProvider {
+    async fn complete(&self, request: CompletionRequest) -> NakamaResult<CompletionResponse> {
+        let (model, body) = self.build_body(&request);
         let url = format!(
             "{}/v1beta/models/{}:generateContent?key={}",
             self.base_url, model, self.api_key
         );
+        let response = self.send(&url, &model, &body).await?;
 
-        tracing::debug!(
-            provider = "google",
-            model = %model,
-            "Sending completion request"
-        );
-
-        let response = self
-            .client
-            .post(&url)
-            .header("Content-Type", "application/json")
-            .json(&body)
-            .send()
-            .await
-            .map_err(|e| NakamaError::Network {
-                message: format!("Google Gemini API request failed: {e}"),
-                source: Some(Box::new(e)),
-            })?;
-
-        let status = response.status();
         let response_text = response.text().await.map_err(|e| NakamaError::Network {
             message: format!("Failed to read Google Gemini response body: {e}"),
             source: Some(Box::new(e)),
         })?;
 
-        if !status.is_success() {
-            let error_msg = serde_json::from_str::<GeminiError>(&response_text)
-                .map(|e| e.error.message)
-                .unwrap_or_else(|_| response_text.clone());
-
-            return Err(NakamaError::AiProvider {
-                message: format!("Google Gemini API error (HTTP {status}): {error_msg}"),
-                source: None,
-            });
-        }
-
         let api_response: GeminiResponse =
             serde_json::from_str(&response_text).map_err(|e| NakamaError::AiProvider {
                 message: format!("Failed to parse Google Gemini response: {e}"),
                 source: Some(Box::new(e)),
             })?;
 
-        let content = api_response
-            .candidates
-            .as_ref()
-            .and_then(|c| c.first())
-            .map(|candidate| {
-                candidate
-                    .content
-                    .parts
-                    .iter()
-                    .map(|p| p.text.as_str())
-                    .collect::<Vec<_>>()
-                    .join("")
-            })
-            .unwrap_or_default();
+        let content = candidate_text(&api_response);
+        let tool_calls = candidate_tool_calls(&api_response);
 
         let usage_meta = api_response.usage_metadata.as_ref();
 
         Ok(CompletionResponse {
             content,
-            model: model.to_string(),
+            model,
             usage: TokenUsage {
                 input_tokens: usage_meta.and_then(|u| u.prompt_token_count).unwrap_or(0),
                 output_tokens: usage_meta
                     .and_then(|u| u.candidates_token_count)
                     .unwrap_or(0),
             },
+            tool_calls,
+            cached: false,
         })
     }
 
+    /// Function calls are not decoded from the stream, so requests that
+    /// offer tools are answered in one piece.
+    async fn complete_stream(&self, request: CompletionRequest) -> NakamaResult<CompletionStream> {
+        if !request.tools.is_empty() {
+            return Ok(response_stream(self.complete(request).await?));
+        }
+        let (model, body) = self.build_body(&request);
+        let url = format!(
+            "{}/v1beta/models/{}:streamGenerateContent?alt=sse&key={}",
+            self.base_url, model, self.api_key
+        );
+        let response = self.send(&url, &model, &body).await?;
+        Ok(decode_response(response, GeminiDecoder::new(&model), "google"))
+    }
+
+    fn provider_name(&self) -> &str {
+        "google"
+    }
+}
+
+/// Gemini reports no token usage for embeddings.
+#[async_trait]
+impl EmbeddingProvider for GoogleProvider {
+    async fn embed(&self, request: EmbeddingRequest) -> NakamaResult<EmbeddingResponse> {
+        let model = if request.model.is_empty() {
+            DEFAULT_EMBEDDING_MODEL
+        } else {
+            &request.model
+        };
+        let url = format!(
+            "{}/v1beta/models/{}:batchEmbedContents?key={}",
+            self.base_url, model, self.api_key
+        );
+        let body = GeminiEmbedBatch {
+            requests: request
+                .inputs
+                .iter()
+                .map(|input| GeminiEmbedRequest {
+                    model: format!("models/{model}"),
+                    content: GeminiContent {
+                        role: None,
+                        parts: vec![GeminiPart::text(input)],
+                    },
+                    output_dimensionality: request.dimensions,
+                })
+                .collect(),
+        };
+        let response = self.send(&url, model, &body).await?;
+
+        let response_text = response.text().await.map_err(|e| NakamaError::Network {
+            message: format!("Failed to read Google Gemini response body: {e}"),
+            source: Some(Box::new(e)),
+        })?;
+        let api_response: GeminiEmbedResponse =
+            serde_json::from_str(&response_text).map_err(|e| NakamaError::AiProvider {
+                message: format!("Failed to parse Google Gemini embedding response: {e}"),
+                source: Some(Box::new(e)),
+            })?;
+
+        let embeddings = api_response.embeddings.into_iter().map(|e| e.values).collect();
+        EmbeddingResponse::new(
+            "Google Gemini",
+            request.inputs.len(),
+            embeddings,
+            model.to_string(),
+            0,
+        )
+    }
+
+    fn max_batch_size(&self) -> usize {
+        100
+    }
+
+    fn default_embedding_model(&self) -> &str {
+        DEFAULT_EMBEDDING_MODEL
+    }
+
     fn provider_name(&self) -> &str {
         "google"
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stream::collect_stream;
+    use crate::test_support::{serve, MockResponse};
+    use crate::types::Message;
+
+    #[tokio::test]
+    async fn test_complete_stream_against_mock_server() {
+        let body = "data: {\"candidates\":[{\"content\":{\"role\":\"model\",\"parts\":[{\"text\":\"Gem\"}]}}],\"usageMetadata\":{\"promptTokenCount\":4}}\r\n\r\n\
+data: {\"candidates\":[{\"content\":{\"role\":\"model\",\"parts\":[{\"text\":\"ini\"}]}}],\"usageMetadata\":{\"promptTokenCount\":4,\"candidatesTokenCount\":3}}\r\n\r\n";
+        let (base_url, requests) = serve(vec![MockResponse::sse(body)]).await;
+
+        let provider = GoogleProvider::new("g-key", "gemini-test", Some(&base_url));
+        let request = CompletionRequest {
+            system_prompt: String::new(),
+            messages: vec![Message::user("Hello")],
+            model: String::new(),
+            max_tokens: 32,
+            temperature: 0.0,
+            ..Default::default()
+        };
+        let stream = provider.complete_stream(request).await.unwrap();
+        let response = collect_stream(stream, |_| {}).await.unwrap();
+
+        assert_eq!(response.content, "Gemini");
+        assert_eq!(response.model, "gemini-test");
+        assert_eq!(response.usage.input_tokens, 4);
+        assert_eq!(response.usage.output_tokens, 3);
+
+        let raw = requests.await.unwrap();
+        assert!(raw[0].starts_with("POST /v1beta/models/gemini-test:streamGenerateContent?alt=sse"));
+    }
+
+    #[tokio::test]
+    async fn test_embeddings_against_mock_server() {
+        let body = r#"{"embeddings":[{"values":[0.1,0.2,0.3]},{"values":[0.4,0.5,0.6]}]}"#;
+        let (base_url, requests) = serve(vec![MockResponse::json(body)]).await;
+
+        let provider = GoogleProvider::new("g-key", "gemini-test", Some(&base_url));
+        let request = EmbeddingRequest::new(["a", "b"]).with_model("text-embedding-004");
+        let response = provider.embed(request).await.unwrap();
+
+        assert_eq!(response.embeddings.len(), 2);
+        assert_eq!(response.dimensions, 3);
+        assert_eq!(response.model, "text-embedding-004");
+
+        let raw = requests.await.unwrap();
+        assert!(raw[0].starts_with("POST /v1beta/models/text-embedding-004:batchEmbedContents?key=g-key"));
+        assert!(raw[0].contains(r#""model":"models/text-embedding-004""#));
+    }
+}
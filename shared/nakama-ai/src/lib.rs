@@ -1,15 +1,59 @@
 //! Multi-provider AI abstraction for the Nakama CLI Suite.
 //!
 //! Provides a unified [`AiProvider`] trait with implementations for Anthropic,
-//! OpenAI, Google Gemini, and local Ollama models.  Use [`create_provider`] to
-//! obtain a provider instance from a [`Provider`] enum value.
+//! OpenAI, Google Gemini, and local Ollama models.  Tools obtain a configured
+//! provider through [`AiClient`]; [`create_provider`] builds a bare one from a
+//! [`Provider`] enum value.
+//!
+//! Responses can be received whole via [`AiProvider::complete`] or
+//! incrementally via [`AiProvider::complete_stream`].  Requests may offer tools
+//! the model can call (see [`tools`]) or a JSON response matching a schema (see
+//! [`structured`]), and messages may carry image, PDF or text attachments (see
+//! [`attachment`]).  Providers with an embeddings API also implement
+//! [`EmbeddingProvider`] (see [`embedding`]).  Follow-up questions can build
+//! on earlier turns saved in a [`session`].  System prompts come from the
+//! [`prompts`] registry, where users can override them.
 
 pub mod anthropic;
+pub mod attachment;
+pub mod budget;
+pub mod cache;
+pub mod client;
+pub mod embedding;
+pub mod fallback;
 pub mod google;
 pub mod ollama;
 pub mod openai;
+pub mod pricing;
+pub mod prompts;
 pub mod provider;
+pub mod retry;
+pub mod session;
+pub mod stream;
+pub mod structured;
+pub mod tokens;
+pub mod tools;
 pub mod types;
 
-pub use provider::{create_provider, AiProvider};
-pub use types::{CompletionRequest, CompletionResponse, Message, Role, TokenUsage};
+#[cfg(test)]
+mod test_support;
+
+pub use attachment::Attachment;
+pub use budget::{with_budget, BudgetedProvider};
+pub use cache::{with_cache, CachingProvider, ResponseCache};
+pub use client::{AiArgs, AiClient, AttachArgs, ProviderChoice};
+pub use embedding::{embed_batched, EmbeddingProvider, EmbeddingRequest, EmbeddingResponse};
+pub use fallback::{with_fallback, FallbackProvider};
+pub use pricing::PriceTable;
+pub use prompts::{load_prompt, PromptRegistry};
+pub use provider::{create_embedding_provider, create_provider, AiProvider};
+pub use retry::{with_retry, RetryingProvider};
+pub use session::{Conversation, SessionArgs, SessionStore};
+pub use stream::{collect_stream, CompletionStream};
+pub use structured::{complete_json, StructuredResponse};
+pub use tokens::{ContextBudget, ContextWindows};
+pub use tools::{run_tool_loop, ToolRegistry};
+pub use types::{
+    CompletionRequest, CompletionResponse, Message, ResponseFormat, Role, StreamEvent, TokenUsage,
+    ToolCall, ToolDefinition,
+};
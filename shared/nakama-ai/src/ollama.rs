@@ -1,12 +1,18 @@
 //! Ollama (local models) provider implementation.
 
-use crate::provider::AiProvider;
-use crate::types::{CompletionRequest, CompletionResponse, Role, TokenUsage};
+use crate::attachment::split_content;
+use crate::embedding::{EmbeddingProvider, EmbeddingRequest, EmbeddingResponse};
+use crate::provider::{response_stream, AiProvider};
+use crate::retry::{api_error, parse_retry_after};
+use crate::stream::{decode_response, CompletionStream, StreamDecoder};
+use crate::types::{CompletionRequest, CompletionResponse, Role, StreamEvent, TokenUsage, ToolCall};
 use async_trait::async_trait;
 use nakama_core::error::{NakamaError, NakamaResult};
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 
+const DEFAULT_EMBEDDING_MODEL: &str = "nomic-embed-text";
+
 /// Ollama local model provider.
 pub struct OllamaProvider {
     client: Client,
@@ -25,56 +31,9 @@ impl OllamaProvider {
             base_url: base_url.trim_end_matches('/').to_string(),
         }
     }
-}
-
-// --- Ollama API request/response types ---
-
-#[derive(Debug, Serialize)]
-struct OllamaRequest {
-    model: String,
-    messages: Vec<OllamaMessage>,
-    stream: bool,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    options: Option<OllamaOptions>,
-}
-
-#[derive(Debug, Serialize)]
-struct OllamaMessage {
-    role: String,
-    content: String,
-}
-
-#[derive(Debug, Serialize)]
-struct OllamaOptions {
-    #[serde(skip_serializing_if = "Option::is_none")]
-    temperature: Option<f32>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    num_predict: Option<u32>,
-}
 
-#[derive(Debug, Deserialize)]
-struct OllamaResponse {
-    message: OllamaResponseMessage,
-    model: String,
-    #[serde(default)]
-    prompt_eval_count: Option<u32>,
-    #[serde(default)]
-    eval_count: Option<u32>,
-}
-
-#[derive(Debug, Deserialize)]
-struct OllamaResponseMessage {
-    content: String,
-}
-
-#[derive(Debug, Deserialize)]
-struct OllamaError {
-    error: String,
-}
-
-#[async_trait]
-impl AiProvider for OllamaProvider {
-    async fn complete(&self, request: CompletionRequest) -> NakamaResult<CompletionResponse> {
+    /// Build the `/api/chat` request body.
+    fn build_body(&self, request: &CompletionRequest, stream: bool) -> NakamaResult<OllamaRequest> {
         let model = if request.model.is_empty() {
             &self.default_model
         } else {
@@ -88,6 +47,8 @@ impl AiProvider for OllamaProvider {
             messages.push(OllamaMessage {
                 role: "system".to_string(),
                 content: request.system_prompt.clone(),
+                images: Vec::new(),
+                tool_calls: Vec::new(),
             });
         }
 
@@ -96,37 +57,86 @@ impl AiProvider for OllamaProvider {
                 Role::System => "system",
                 Role::User => "user",
                 Role::Assistant => "assistant",
+                Role::Tool => "tool",
             };
+            // Multimodal models take images only; there is no document input.
+            let (content, attachments) = split_content(&msg.content, &msg.attachments);
+            let images = attachments
+                .into_iter()
+                .map(|a| {
+                    if a.is_image() {
+                        Ok(a.base64())
+                    } else {
+                        Err(a.unsupported("Ollama"))
+                    }
+                })
+                .collect::<NakamaResult<Vec<_>>>()?;
             messages.push(OllamaMessage {
                 role: role.to_string(),
-                content: msg.content.clone(),
+                content,
+                images,
+                tool_calls: msg
+                    .tool_calls
+                    .iter()
+                    .map(|call| OllamaToolCall {
+                        function: OllamaFunctionCall {
+                            name: call.name.clone(),
+                            arguments: call.arguments.clone(),
+                        },
+                    })
+                    .collect(),
             });
         }
 
-        let body = OllamaRequest {
+        let tools = request
+            .tools
+            .iter()
+            .map(|tool| OllamaTool {
+                kind: "function".to_string(),
+                function: OllamaFunction {
+                    name: tool.name.clone(),
+                    description: tool.description.clone(),
+                    parameters: tool.input_schema.clone(),
+                },
+            })
+            .collect();
+
+        Ok(OllamaRequest {
             model: model.to_string(),
             messages,
-            stream: false,
+            tools,
+            // Ollama constrains generation to a JSON schema passed as `format`.
+            format: request.response_format.as_ref().map(|f| f.schema.clone()),
+            stream,
             options: Some(OllamaOptions {
                 temperature: Some(request.temperature),
                 num_predict: Some(request.max_tokens),
             }),
-        };
+        })
+    }
 
+    /// Send the request and return the raw response, mapping HTTP errors.
+    async fn send(&self, body: &OllamaRequest) -> NakamaResult<reqwest::Response> {
         let url = format!("{}/api/chat", self.base_url);
 
         tracing::debug!(
             provider = "ollama",
-            model = %model,
+            model = %body.model,
             url = %url,
+            stream = body.stream,
             "Sending completion request"
         );
 
+        self.post(&url, body).await
+    }
+
+    /// POST `body` to `url`, mapping HTTP errors.
+    async fn post(&self, url: &str, body: &impl Serialize) -> NakamaResult<reqwest::Response> {
         let response = self
             .client
-            .post(&url)
+            .post(url)
             .header("Content-Type", "application/json")
-            .json(&body)
+            .json(body)
             .send()
             .await
             .map_err(|e| NakamaError::Network {
@@ -135,28 +145,214 @@ impl AiProvider for OllamaProvider {
             })?;
 
         let status = response.status();
+        if status.is_success() {
+            return Ok(response);
+        }
+        let retry_after = parse_retry_after(response.headers());
+
         let response_text = response.text().await.map_err(|e| NakamaError::Network {
             message: format!("Failed to read Ollama response body: {e}"),
             source: Some(Box::new(e)),
         })?;
 
-        if !status.is_success() {
-            let error_msg = serde_json::from_str::<OllamaError>(&response_text)
-                .map(|e| e.error)
-                .unwrap_or_else(|_| response_text.clone());
+        let error_msg = serde_json::from_str::<OllamaError>(&response_text)
+            .map(|e| e.error)
+            .unwrap_or_else(|_| response_text.clone());
+
+        Err(api_error("Ollama", status, retry_after, &error_msg))
+    }
+}
+
+// --- Ollama API request/response types ---
+
+#[derive(Debug, Serialize)]
+struct OllamaEmbedRequest<'a> {
+    model: &'a str,
+    input: &'a [String],
+}
+
+#[derive(Debug, Deserialize)]
+struct OllamaEmbedResponse {
+    model: String,
+    embeddings: Vec<Vec<f32>>,
+    prompt_eval_count: Option<u32>,
+}
+
+#[derive(Debug, Serialize)]
+struct OllamaRequest {
+    model: String,
+    messages: Vec<OllamaMessage>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tools: Vec<OllamaTool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    format: Option<serde_json::Value>,
+    stream: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    options: Option<OllamaOptions>,
+}
+
+#[derive(Debug, Serialize)]
+struct OllamaMessage {
+    role: String,
+    content: String,
+    /// Base64-encoded images, for multimodal models.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    images: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tool_calls: Vec<OllamaToolCall>,
+}
+
+#[derive(Debug, Serialize)]
+struct OllamaTool {
+    #[serde(rename = "type")]
+    kind: String,
+    function: OllamaFunction,
+}
+
+#[derive(Debug, Serialize)]
+struct OllamaFunction {
+    name: String,
+    description: String,
+    parameters: serde_json::Value,
+}
+
+/// Ollama tool calls carry no id and take arguments as a JSON object.
+#[derive(Debug, Serialize, Deserialize)]
+struct OllamaToolCall {
+    function: OllamaFunctionCall,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct OllamaFunctionCall {
+    name: String,
+    arguments: serde_json::Value,
+}
+
+#[derive(Debug, Serialize)]
+struct OllamaOptions {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    num_predict: Option<u32>,
+}
 
+#[derive(Debug, Deserialize)]
+struct OllamaResponse {
+    message: OllamaResponseMessage,
+    model: String,
+    #[serde(default)]
+    prompt_eval_count: Option<u32>,
+    #[serde(default)]
+    eval_count: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OllamaResponseMessage {
+    content: String,
+    #[serde(default)]
+    tool_calls: Vec<OllamaToolCall>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OllamaStreamChunk {
+    #[serde(default)]
+    model: Option<String>,
+    #[serde(default)]
+    message: Option<OllamaResponseMessage>,
+    #[serde(default)]
+    prompt_eval_count: Option<u32>,
+    #[serde(default)]
+    eval_count: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OllamaError {
+    error: String,
+}
+
+/// Decodes the newline-delimited JSON stream returned by `/api/chat`.
+#[derive(Default)]
+struct OllamaDecoder {
+    model: String,
+    usage: TokenUsage,
+}
+
+impl StreamDecoder for OllamaDecoder {
+    fn decode_line(&mut self, line: &str) -> NakamaResult<Vec<StreamEvent>> {
+        if line.trim().is_empty() {
+            return Ok(Vec::new());
+        }
+
+        if let Ok(error) = serde_json::from_str::<OllamaError>(line) {
             return Err(NakamaError::AiProvider {
-                message: format!("Ollama API error (HTTP {status}): {error_msg}"),
+                message: format!("Ollama stream error: {}", error.error),
                 source: None,
             });
         }
 
+        let chunk: OllamaStreamChunk =
+            serde_json::from_str(line).map_err(|e| NakamaError::AiProvider {
+                message: format!("Failed to parse Ollama stream chunk: {e}"),
+                source: Some(Box::new(e)),
+            })?;
+
+        if let Some(model) = chunk.model {
+            self.model = model;
+        }
+        if let Some(input) = chunk.prompt_eval_count {
+            self.usage.input_tokens = input;
+        }
+        if let Some(output) = chunk.eval_count {
+            self.usage.output_tokens = output;
+        }
+
+        match chunk.message {
+            Some(message) if !message.content.is_empty() => Ok(vec![StreamEvent::Delta {
+                text: message.content,
+            }]),
+            _ => Ok(Vec::new()),
+        }
+    }
+
+    fn finish(&mut self) -> NakamaResult<Vec<StreamEvent>> {
+        Ok(vec![StreamEvent::Done {
+            model: std::mem::take(&mut self.model),
+            usage: std::mem::take(&mut self.usage),
+            cached: false,
+        }])
+    }
+}
+
+#[async_trait]
+impl AiProvider for OllamaProvider {
+    async fn complete(&self, request: CompletionRequest) -> NakamaResult<CompletionResponse> {
+        let body = self.build_body(&request, false)?;
+        let response = self.send(&body).await?;
+
+        let response_text = response.text().await.map_err(|e| NakamaError::Network {
+            message: format!("Failed to read Ollama response body: {e}"),
+            source: Some(Box::new(e)),
+        })?;
+
         let api_response: OllamaResponse =
             serde_json::from_str(&response_text).map_err(|e| NakamaError::AiProvider {
                 message: format!("Failed to parse Ollama response: {e}"),
                 source: Some(Box::new(e)),
             })?;
 
+        // Ollama doesn't assign call ids, so number the calls.
+        let tool_calls = api_response
+            .message
+            .tool_calls
+            .into_iter()
+            .enumerate()
+            .map(|(i, call)| ToolCall {
+                id: format!("call_{i}"),
+                name: call.function.name,
+                arguments: call.function.arguments,
+            })
+            .collect();
+
         Ok(CompletionResponse {
             content: api_response.message.content,
             model: api_response.model,
@@ -164,10 +360,135 @@ impl AiProvider for OllamaProvider {
                 input_tokens: api_response.prompt_eval_count.unwrap_or(0),
                 output_tokens: api_response.eval_count.unwrap_or(0),
             },
+            tool_calls,
+            cached: false,
         })
     }
 
+    /// Tool calls are not decoded from the stream, so requests that offer
+    /// tools are answered in one piece.
+    async fn complete_stream(&self, request: CompletionRequest) -> NakamaResult<CompletionStream> {
+        if !request.tools.is_empty() {
+            return Ok(response_stream(self.complete(request).await?));
+        }
+        let body = self.build_body(&request, true)?;
+        let response = self.send(&body).await?;
+        Ok(decode_response(response, OllamaDecoder::default(), "ollama"))
+    }
+
+    fn provider_name(&self) -> &str {
+        "ollama"
+    }
+}
+
+#[async_trait]
+impl EmbeddingProvider for OllamaProvider {
+    async fn embed(&self, request: EmbeddingRequest) -> NakamaResult<EmbeddingResponse> {
+        let model = if request.model.is_empty() {
+            DEFAULT_EMBEDDING_MODEL
+        } else {
+            &request.model
+        };
+        let url = format!("{}/api/embed", self.base_url);
+
+        tracing::debug!(
+            provider = "ollama",
+            model = %model,
+            inputs = request.inputs.len(),
+            "Sending embedding request"
+        );
+
+        let body = OllamaEmbedRequest {
+            model,
+            input: &request.inputs,
+        };
+        let response = self.post(&url, &body).await?;
+        let response_text = response.text().await.map_err(|e| NakamaError::Network {
+            message: format!("Failed to read Ollama response body: {e}"),
+            source: Some(Box::new(e)),
+        })?;
+        let api_response: OllamaEmbedResponse =
+            serde_json::from_str(&response_text).map_err(|e| NakamaError::AiProvider {
+                message: format!("Failed to parse Ollama embedding response: {e}"),
+                source: Some(Box::new(e)),
+            })?;
+
+        EmbeddingResponse::new(
+            "Ollama",
+            request.inputs.len(),
+            api_response.embeddings,
+            api_response.model,
+            api_response.prompt_eval_count.unwrap_or(0),
+        )
+    }
+
+    /// Ollama takes any number of inputs, but large batches hold the model
+    /// for a long time.
+    fn max_batch_size(&self) -> usize {
+        64
+    }
+
+    fn default_embedding_model(&self) -> &str {
+        DEFAULT_EMBEDDING_MODEL
+    }
+
     fn provider_name(&self) -> &str {
         "ollama"
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stream::collect_stream;
+    use crate::test_support::{serve, MockResponse};
+    use crate::types::Message;
+
+    #[tokio::test]
+    async fn test_complete_stream_against_mock_server() {
+        let body = "{\"model\":\"llama3:8b\",\"message\":{\"role\":\"assistant\",\"content\":\"Lo\"},\"done\":false}\n\
+{\"model\":\"llama3:8b\",\"message\":{\"role\":\"assistant\",\"content\":\"cal\"},\"done\":false}\n\
+{\"model\":\"llama3:8b\",\"message\":{\"role\":\"assistant\",\"content\":\"\"},\"done\":true,\"prompt_eval_count\":9,\"eval_count\":2}";
+        let (base_url, requests) = serve(vec![MockResponse::ndjson(body)]).await;
+
+        let provider = OllamaProvider::new("llama3:8b", &base_url);
+        let request = CompletionRequest {
+            system_prompt: String::new(),
+            messages: vec![Message::user("Hello")],
+            model: String::new(),
+            max_tokens: 32,
+            temperature: 0.0,
+            ..Default::default()
+        };
+        let stream = provider.complete_stream(request).await.unwrap();
+        let response = collect_stream(stream, |_| {}).await.unwrap();
+
+        assert_eq!(response.content, "Local");
+        assert_eq!(response.model, "llama3:8b");
+        assert_eq!(response.usage.input_tokens, 9);
+        assert_eq!(response.usage.output_tokens, 2);
+
+        let raw = requests.await.unwrap();
+        assert!(raw[0].contains("\"stream\":true"));
+    }
+
+    #[tokio::test]
+    async fn test_embeddings_against_mock_server() {
+        let body = r#"{"model":"nomic-embed-text","embeddings":[[0.5,0.5]],"prompt_eval_count":3}"#;
+        let (base_url, requests) = serve(vec![MockResponse::json(body)]).await;
+
+        let provider = OllamaProvider::new("llama3:8b", &base_url);
+        let response = provider.embed(EmbeddingRequest::new(["hello"])).await.unwrap();
+        assert_eq!(response.embeddings, vec![vec![0.5, 0.5]]);
+        assert_eq!(response.usage.input_tokens, 3);
+
+        // A mismatched count is an error rather than a silent misalignment.
+        let (base_url, _) = serve(vec![MockResponse::json(body)]).await;
+        let provider = OllamaProvider::new("llama3:8b", &base_url);
+        assert!(provider.embed(EmbeddingRequest::new(["a", "b"])).await.is_err());
+
+        let raw = requests.await.unwrap();
+        assert!(raw[0].starts_with("POST /api/embed "));
+        assert!(raw[0].contains(r#""model":"nomic-embed-text""#));
+    }
+}
@@ -1,20 +1,37 @@
 //! OpenAI provider implementation.
 
-use crate::provider::AiProvider;
-use crate::types::{CompletionRequest, CompletionResponse, Role, TokenUsage};
+use crate::attachment::split_content;
+use crate::embedding::{EmbeddingProvider, EmbeddingRequest, EmbeddingResponse};
+use crate::provider::{response_stream, AiProvider};
+use crate::retry::{api_error, parse_retry_after};
+use crate::stream::{decode_response, CompletionStream, SseParser, StreamDecoder};
+use crate::types::{CompletionRequest, CompletionResponse, Role, StreamEvent, TokenUsage, ToolCall};
 use async_trait::async_trait;
+use nakama_core::config::OpenAICompatibleConfig;
 use nakama_core::error::{NakamaError, NakamaResult};
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 const DEFAULT_BASE_URL: &str = "https://api.openai.com";
+const DEFAULT_EMBEDDING_MODEL: &str = "text-embedding-3-small";
 
-/// OpenAI provider (GPT models).
+/// OpenAI provider (GPT models), also used for OpenAI-compatible endpoints.
 pub struct OpenAIProvider {
     client: Client,
     api_key: String,
     default_model: String,
     base_url: String,
+    /// Name reported by [`AiProvider::provider_name`] and used in errors.
+    name: String,
+    /// Inserted between `base_url` and the API path (`/v1` for OpenAI
+    /// itself; compatible base URLs include their version).
+    path_prefix: &'static str,
+    auth_header: String,
+    auth_scheme: String,
+    headers: Vec<(String, String)>,
+    api_version: Option<String>,
+    model_aliases: HashMap<String, String>,
 }
 
 impl OpenAIProvider {
@@ -25,8 +42,206 @@ impl OpenAIProvider {
             api_key: api_key.to_string(),
             default_model: model.to_string(),
             base_url: base_url.unwrap_or(DEFAULT_BASE_URL).trim_end_matches('/').to_string(),
+            name: "openai".to_string(),
+            path_prefix: "/v1",
+            auth_header: "Authorization".to_string(),
+            auth_scheme: "Bearer".to_string(),
+            headers: Vec::new(),
+            api_version: None,
+            model_aliases: HashMap::new(),
         }
     }
+
+    /// Create a provider for the OpenAI-compatible endpoint `name`.  An empty
+    /// `api_key` sends no auth header (e.g. for LM Studio).
+    pub fn compatible(
+        name: &str,
+        api_key: &str,
+        model: &str,
+        endpoint: &OpenAICompatibleConfig,
+    ) -> Self {
+        let mut headers: Vec<_> = endpoint
+            .headers
+            .iter()
+            .map(|(name, value)| (name.clone(), value.clone()))
+            .collect();
+        headers.sort();
+        Self {
+            client: Client::new(),
+            api_key: api_key.to_string(),
+            default_model: model.to_string(),
+            base_url: endpoint.base_url.trim_end_matches('/').to_string(),
+            name: name.to_string(),
+            path_prefix: "",
+            auth_header: endpoint.auth_header.clone(),
+            auth_scheme: endpoint.auth_scheme.clone(),
+            headers,
+            api_version: endpoint.api_version.clone(),
+            model_aliases: endpoint.model_aliases.clone(),
+        }
+    }
+
+    /// Name used in error messages.
+    fn label(&self) -> &str {
+        if self.name == "openai" {
+            "OpenAI"
+        } else {
+            &self.name
+        }
+    }
+
+    /// Build the Chat Completions request body.
+    fn build_body(&self, request: &CompletionRequest, stream: bool) -> NakamaResult<OpenAIRequest> {
+        let model = if request.model.is_empty() {
+            &self.default_model
+        } else {
+            &request.model
+        };
+        let model = self.model_aliases.get(model.as_str()).unwrap_or(model);
+
+        // Build message list -- OpenAI expects system messages inline.
+        let mut messages = Vec::new();
+
+        if !request.system_prompt.is_empty() {
+            messages.push(OpenAIMessage::text("system", &request.system_prompt));
+        }
+
+        for msg in &request.messages {
+            let role = match msg.role {
+                Role::System => "system",
+                Role::User => "user",
+                Role::Assistant => "assistant",
+                Role::Tool => "tool",
+            };
+            let mut message = OpenAIMessage::text(role, &msg.content);
+            if !msg.tool_calls.is_empty() {
+                // Assistant turns that only call tools carry no content.
+                if msg.content.is_empty() {
+                    message.content = None;
+                }
+                message.tool_calls = Some(msg.tool_calls.iter().map(OpenAIToolCall::from).collect());
+            }
+            message.tool_call_id = msg.tool_call_id.clone();
+            if !msg.attachments.is_empty() {
+                let (text, attachments) = split_content(&msg.content, &msg.attachments);
+                let mut parts = vec![OpenAIContentPart::Text { text }];
+                for attachment in attachments {
+                    if attachment.is_image() {
+                        parts.push(OpenAIContentPart::ImageUrl {
+                            image_url: OpenAIImageUrl {
+                                url: attachment.data_url(),
+                            },
+                        });
+                    } else if attachment.is_pdf() {
+                        parts.push(OpenAIContentPart::File {
+                            file: OpenAIFile {
+                                filename: attachment.name.clone(),
+                                file_data: attachment.data_url(),
+                            },
+                        });
+                    } else {
+                        return Err(attachment.unsupported(self.label()));
+                    }
+                }
+                message.content = Some(OpenAIContent::Parts(parts));
+            }
+            messages.push(message);
+        }
+
+        let tools = request
+            .tools
+            .iter()
+            .map(|tool| OpenAITool {
+                kind: "function".to_string(),
+                function: OpenAIFunction {
+                    name: tool.name.clone(),
+                    description: tool.description.clone(),
+                    parameters: tool.input_schema.clone(),
+                },
+            })
+            .collect();
+
+        Ok(OpenAIRequest {
+            model: model.to_string(),
+            messages,
+            max_tokens: request.max_tokens,
+            temperature: request.temperature,
+            tools,
+            response_format: request.response_format.as_ref().map(|format| OpenAIResponseFormat {
+                kind: "json_schema".to_string(),
+                json_schema: OpenAIJsonSchema {
+                    name: format.name.clone(),
+                    schema: format.schema.clone(),
+                },
+            }),
+            stream,
+            // Ask for a final usage chunk so streamed requests report tokens.
+            stream_options: stream.then_some(OpenAIStreamOptions {
+                include_usage: true,
+            }),
+        })
+    }
+
+    /// Send the request and return the raw response, mapping HTTP errors.
+    async fn send(&self, body: &OpenAIRequest) -> NakamaResult<reqwest::Response> {
+        let url = self.url("/chat/completions", &body.model);
+
+        tracing::debug!(
+            provider = %self.name,
+            model = %body.model,
+            url = %url,
+            stream = body.stream,
+            "Sending completion request"
+        );
+
+        self.post(&url, body).await
+    }
+
+    /// The URL of `path` for requests to `model`.
+    fn url(&self, path: &str, model: &str) -> String {
+        format!("{}{}{path}", self.base_url.replace("{model}", model), self.path_prefix)
+    }
+
+    /// POST `body` with the configured auth and headers, mapping HTTP errors.
+    async fn post(&self, url: &str, body: &impl Serialize) -> NakamaResult<reqwest::Response> {
+        let mut builder = self.client.post(url).header("Content-Type", "application/json");
+        if !self.api_key.is_empty() {
+            let credential = if self.auth_scheme.is_empty() {
+                self.api_key.clone()
+            } else {
+                format!("{} {}", self.auth_scheme, self.api_key)
+            };
+            builder = builder.header(self.auth_header.as_str(), credential);
+        }
+        for (name, value) in &self.headers {
+            builder = builder.header(name.as_str(), value.as_str());
+        }
+        if let Some(version) = &self.api_version {
+            builder = builder.query(&[("api-version", version)]);
+        }
+
+        let response = builder.json(body).send().await.map_err(|e| NakamaError::Network {
+            message: format!("{} API request failed: {e}", self.label()),
+            source: Some(Box::new(e)),
+        })?;
+
+        let status = response.status();
+        if status.is_success() {
+            return Ok(response);
+        }
+        let retry_after = parse_retry_after(response.headers());
+
+        let response_text = response.text().await.map_err(|e| NakamaError::Network {
+            message: format!("Failed to read {} response body: {e}", self.label()),
+            source: Some(Box::new(e)),
+        })?;
+
+        let error_msg = serde_json::from_str::<OpenAIError>(&response_text)
+            .map(|e| e.error.message)
+            .unwrap_or_else(|_| response_text.clone());
+
+        Err(api_error(self.label(), status, retry_after, &error_msg))
+    }
 }
 
 // --- OpenAI API request/response types ---
@@ -37,12 +252,140 @@ struct OpenAIRequest {
     messages: Vec<OpenAIMessage>,
     max_tokens: u32,
     temperature: f32,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tools: Vec<OpenAITool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response_format: Option<OpenAIResponseFormat>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    stream: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stream_options: Option<OpenAIStreamOptions>,
+}
+
+#[derive(Debug, Serialize)]
+struct OpenAIStreamOptions {
+    include_usage: bool,
 }
 
 #[derive(Debug, Serialize)]
 struct OpenAIMessage {
     role: String,
-    content: String,
+    content: Option<OpenAIContent>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tool_calls: Option<Vec<OpenAIToolCall>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tool_call_id: Option<String>,
+}
+
+impl OpenAIMessage {
+    fn text(role: &str, content: &str) -> Self {
+        Self {
+            role: role.to_string(),
+            content: Some(OpenAIContent::Text(content.to_string())),
+            tool_calls: None,
+            tool_call_id: None,
+        }
+    }
+}
+
+/// Plain text, or content parts when the message has attachments.
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+enum OpenAIContent {
+    Text(String),
+    Parts(Vec<OpenAIContentPart>),
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum OpenAIContentPart {
+    Text { text: String },
+    ImageUrl { image_url: OpenAIImageUrl },
+    File { file: OpenAIFile },
+}
+
+#[derive(Debug, Serialize)]
+struct OpenAIImageUrl {
+    url: String,
+}
+
+#[derive(Debug, Serialize)]
+struct OpenAIFile {
+    filename: String,
+    file_data: String,
+}
+
+#[derive(Debug, Serialize)]
+struct OpenAIResponseFormat {
+    #[serde(rename = "type")]
+    kind: String,
+    json_schema: OpenAIJsonSchema,
+}
+
+#[derive(Debug, Serialize)]
+struct OpenAIJsonSchema {
+    name: String,
+    schema: serde_json::Value,
+}
+
+#[derive(Debug, Serialize)]
+struct OpenAITool {
+    #[serde(rename = "type")]
+    kind: String,
+    function: OpenAIFunction,
+}
+
+#[derive(Debug, Serialize)]
+struct OpenAIFunction {
+    name: String,
+    description: String,
+    parameters: serde_json::Value,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct OpenAIToolCall {
+    id: String,
+    #[serde(rename = "type", default = "function_kind")]
+    kind: String,
+    function: OpenAIFunctionCall,
+}
+
+/// Function arguments travel as a JSON-encoded string.
+#[derive(Debug, Serialize, Deserialize)]
+struct OpenAIFunctionCall {
+    name: String,
+    arguments: String,
+}
+
+fn function_kind() -> String {
+    "function".to_string()
+}
+
+impl From<&ToolCall> for OpenAIToolCall {
+    fn from(call: &ToolCall) -> Self {
+        Self {
+            id: call.id.clone(),
+            kind: function_kind(),
+            function: OpenAIFunctionCall {
+                name: call.name.clone(),
+                arguments: call.arguments.to_string(),
+            },
+        }
+    }
+}
+
+impl From<OpenAIToolCall> for ToolCall {
+    fn from(call: OpenAIToolCall) -> Self {
+        // Models occasionally emit malformed JSON; pass it through as a
+        // string rather than failing the whole response.
+        let arguments = serde_json::from_str(&call.function.arguments)
+            .unwrap_or(serde_json::Value::String(call.function.arguments));
+        Self {
+            id: call.id,
+            name: call.function.name,
+            arguments,
+        }
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -54,7 +397,14 @@ struct OpenAIResponse {
 
 #[derive(Debug, Deserialize)]
 struct OpenAIChoice {
-    message: OpenAIResponseMessage,
+    message: OpenAIChoiceMessage,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAIChoiceMessage {
+    content: Option<String>,
+    #[serde(default)]
+    tool_calls: Vec<OpenAIToolCall>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -65,9 +415,32 @@ struct OpenAIResponseMessage {
 #[derive(Debug, Deserialize)]
 struct OpenAIUsage {
     prompt_tokens: u32,
+    /// Absent from embedding responses.
+    #[serde(default)]
     completion_tokens: u32,
 }
 
+#[derive(Debug, Serialize)]
+struct OpenAIEmbeddingRequest<'a> {
+    model: &'a str,
+    input: &'a [String],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dimensions: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAIEmbeddingResponse {
+    data: Vec<OpenAIEmbedding>,
+    model: String,
+    usage: Option<OpenAIUsage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAIEmbedding {
+    embedding: Vec<f32>,
+    index: usize,
+}
+
 #[derive(Debug, Deserialize)]
 struct OpenAIError {
     error: OpenAIErrorDetail,
@@ -78,96 +451,118 @@ struct OpenAIErrorDetail {
     message: String,
 }
 
-#[async_trait]
-impl AiProvider for OpenAIProvider {
-    async fn complete(&self, request: CompletionRequest) -> NakamaResult<CompletionResponse> {
-        let model = if request.model.is_empty() {
-            &self.default_model
-        } else {
-            &request.model
-        };
+// --- OpenAI streaming chunk types ---
 
-        // Build message list -- OpenAI expects system messages inline.
-        let mut messages = Vec::new();
+#[derive(Debug, Deserialize)]
+struct OpenAIStreamChunk {
+    #[serde(default)]
+    model: Option<String>,
+    #[serde(default)]
+    choices: Vec<OpenAIStreamChoice>,
+    #[serde(default)]
+    usage: Option<OpenAIUsage>,
+}
 
-        if !request.system_prompt.is_empty() {
-            messages.push(OpenAIMessage {
-                role: "system".to_string(),
-                content: request.system_prompt.clone(),
+#[derive(Debug, Deserialize)]
+struct OpenAIStreamChoice {
+    delta: OpenAIResponseMessage,
+}
+
+/// Decodes the Chat Completions SSE stream (terminated by `data: [DONE]`).
+#[derive(Default)]
+struct OpenAIDecoder {
+    sse: SseParser,
+    model: String,
+    usage: TokenUsage,
+}
+
+impl OpenAIDecoder {
+    fn handle_event(&mut self, data: &str) -> NakamaResult<Vec<StreamEvent>> {
+        if data == "[DONE]" {
+            return Ok(Vec::new());
+        }
+
+        // Errors mid-stream arrive as a bare error object.
+        if let Ok(error) = serde_json::from_str::<OpenAIError>(data) {
+            return Err(NakamaError::AiProvider {
+                message: format!("OpenAI stream error: {}", error.error.message),
+                source: None,
             });
         }
 
-        for msg in &request.messages {
-            let role = match msg.role {
-                Role::System => "system",
-                Role::User => "user",
-                Role::Assistant => "assistant",
+        let chunk: OpenAIStreamChunk =
+            serde_json::from_str(data).map_err(|e| NakamaError::AiProvider {
+                message: format!("Failed to parse OpenAI stream chunk: {e}"),
+                source: Some(Box::new(e)),
+            })?;
+
+        if let Some(model) = chunk.model {
+            self.model = model;
+        }
+        if let Some(usage) = chunk.usage {
+            self.usage = TokenUsage {
+                input_tokens: usage.prompt_tokens,
+                output_tokens: usage.completion_tokens,
             };
-            messages.push(OpenAIMessage {
-                role: role.to_string(),
-                content: msg.content.clone(),
-            });
         }
 
-        let body = OpenAIRequest {
-            model: model.to_string(),
-            messages,
-            max_tokens: request.max_tokens,
-            temperature: request.temperature,
-        };
+        Ok(chunk
+            .choices
+            .into_iter()
+            .filter_map(|c| c.delta.content)
+            .filter(|text| !text.is_empty())
+            .map(|text| StreamEvent::Delta { text })
+            .collect())
+    }
+}
 
-        let url = format!("{}/v1/chat/completions", self.base_url);
+impl StreamDecoder for OpenAIDecoder {
+    fn decode_line(&mut self, line: &str) -> NakamaResult<Vec<StreamEvent>> {
+        match self.sse.push_line(line) {
+            Some(event) => self.handle_event(&event.data),
+            None => Ok(Vec::new()),
+        }
+    }
 
-        tracing::debug!(
-            provider = "openai",
-            model = %model,
-            url = %url,
-            "Sending completion request"
-        );
+    fn finish(&mut self) -> NakamaResult<Vec<StreamEvent>> {
+        let mut events = match self.sse.flush() {
+            Some(event) => self.handle_event(&event.data)?,
+            None => Vec::new(),
+        };
+        events.push(StreamEvent::Done {
+            model: std::mem::take(&mut self.model),
+            usage: std::mem::take(&mut self.usage),
+            cached: false,
+        });
+        Ok(events)
+    }
+}
 
-        let response = self
-            .client
-            .post(&url)
-            .header("Authorization", format!("Bearer {}", self.api_key))
-            .header("Content-Type", "application/json")
-            .json(&body)
-            .send()
-            .await
-            .map_err(|e| NakamaError::Network {
-                message: format!("OpenAI API request failed: {e}"),
-                source: Some(Box::new(e)),
-            })?;
+#[async_trait]
+impl AiProvider for OpenAIProvider {
+    async fn complete(&self, request: CompletionRequest) -> NakamaResult<CompletionResponse> {
+        let body = self.build_body(&request, false)?;
+        let response = self.send(&body).await?;
 
-        let status = response.status();
         let response_text = response.text().await.map_err(|e| NakamaError::Network {
-            message: format!("Failed to read OpenAI response body: {e}"),
+            message: format!("Failed to read {} response body: {e}", self.label()),
             source: Some(Box::new(e)),
         })?;
 
-        if !status.is_success() {
-            let error_msg = serde_json::from_str::<OpenAIError>(&response_text)
-                .map(|e| e.error.message)
-                .unwrap_or_else(|_| response_text.clone());
-
-            return Err(NakamaError::AiProvider {
-                message: format!("OpenAI API error (HTTP {status}): {error_msg}"),
-                source: None,
-            });
-        }
-
         let api_response: OpenAIResponse =
             serde_json::from_str(&response_text).map_err(|e| NakamaError::AiProvider {
-                message: format!("Failed to parse OpenAI response: {e}"),
+                message: format!("Failed to parse {} response: {e}", self.label()),
                 source: Some(Box::new(e)),
             })?;
 
-        let content = api_response
-            .choices
-            .first()
-            .and_then(|c| c.message.content.clone())
-            .unwrap_or_default();
-
         let usage = api_response.usage.as_ref();
+        let (content, tool_calls) = match api_response.choices.into_iter().next() {
+            Some(choice) => (
+                choice.message.content.unwrap_or_default(),
+                choice.message.tool_calls.into_iter().map(ToolCall::from).collect(),
+            ),
+            None => (String::new(), Vec::new()),
+        };
 
         Ok(CompletionResponse {
             content,
@@ -176,10 +571,225 @@ impl AiProvider for OpenAIProvider {
                 input_tokens: usage.map(|u| u.prompt_tokens).unwrap_or(0),
                 output_tokens: usage.map(|u| u.completion_tokens).unwrap_or(0),
             },
+            tool_calls,
+            cached: false,
         })
     }
 
+    /// Tool-call deltas are not decoded from the stream, so requests that
+    /// offer tools are answered in one piece.
+    async fn complete_stream(&self, request: CompletionRequest) -> NakamaResult<CompletionStream> {
+        if !request.tools.is_empty() {
+            return Ok(response_stream(self.complete(request).await?));
+        }
+        let body = self.build_body(&request, true)?;
+        let response = self.send(&body).await?;
+        Ok(decode_response(response, OpenAIDecoder::default(), "openai"))
+    }
+
     fn provider_name(&self) -> &str {
-        "openai"
+        &self.name
+    }
+}
+
+#[async_trait]
+impl EmbeddingProvider for OpenAIProvider {
+    async fn embed(&self, request: EmbeddingRequest) -> NakamaResult<EmbeddingResponse> {
+        let model = if request.model.is_empty() {
+            DEFAULT_EMBEDDING_MODEL
+        } else {
+            &request.model
+        };
+        let model = self.model_aliases.get(model).map_or(model, String::as_str);
+        let url = self.url("/embeddings", model);
+
+        tracing::debug!(
+            provider = %self.name,
+            model = %model,
+            inputs = request.inputs.len(),
+            "Sending embedding request"
+        );
+
+        let body = OpenAIEmbeddingRequest {
+            model,
+            input: &request.inputs,
+            dimensions: request.dimensions,
+        };
+        let response = self.post(&url, &body).await?;
+        let response_text = response.text().await.map_err(|e| NakamaError::Network {
+            message: format!("Failed to read {} response body: {e}", self.label()),
+            source: Some(Box::new(e)),
+        })?;
+        let mut api_response: OpenAIEmbeddingResponse = serde_json::from_str(&response_text)
+            .map_err(|e| NakamaError::AiProvider {
+                message: format!("Failed to parse {} embedding response: {e}", self.label()),
+                source: Some(Box::new(e)),
+            })?;
+
+        api_response.data.sort_by_key(|item| item.index);
+        let embeddings = api_response.data.into_iter().map(|item| item.embedding).collect();
+        EmbeddingResponse::new(
+            self.label(),
+            request.inputs.len(),
+            embeddings,
+            api_response.model,
+            api_response.usage.map_or(0, |u| u.prompt_tokens),
+        )
+    }
+
+    fn max_batch_size(&self) -> usize {
+        2048
+    }
+
+    fn default_embedding_model(&self) -> &str {
+        DEFAULT_EMBEDDING_MODEL
+    }
+
+    fn provider_name(&self) -> &str {
+        &self.name
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stream::collect_stream;
+    use crate::attachment::Attachment;
+    use crate::test_support::{serve, MockResponse};
+    use crate::types::{Message, ToolDefinition};
+
+    #[tokio::test]
+    async fn test_complete_stream_against_mock_server() {
+        let body = "data: {\"model\":\"gpt-test\",\"choices\":[{\"delta\":{\"role\":\"assistant\",\"content\":\"\"}}]}\n\n\
+data: {\"model\":\"gpt-test\",\"choices\":[{\"delta\":{\"content\":\"Hi\"}}]}\n\n\
+data: {\"model\":\"gpt-test\",\"choices\":[{\"delta\":{\"content\":\" there\"}}]}\n\n\
+data: {\"model\":\"gpt-test\",\"choices\":[],\"usage\":{\"prompt_tokens\":7,\"completion_tokens\":2}}\n\n\
+data: [DONE]\n\n";
+        let (base_url, requests) = serve(vec![MockResponse::sse(body)]).await;
+
+        let provider = OpenAIProvider::new("sk-test", "gpt-test", Some(&base_url));
+        let request = CompletionRequest {
+            system_prompt: "sys".to_string(),
+            messages: vec![Message::user("Hello")],
+            model: String::new(),
+            max_tokens: 32,
+            temperature: 0.0,
+            ..Default::default()
+        };
+        let stream = provider.complete_stream(request).await.unwrap();
+        let response = collect_stream(stream, |_| {}).await.unwrap();
+
+        assert_eq!(response.content, "Hi there");
+        assert_eq!(response.model, "gpt-test");
+        assert_eq!(response.usage.input_tokens, 7);
+        assert_eq!(response.usage.output_tokens, 2);
+
+        let raw = requests.await.unwrap();
+        assert!(raw[0].contains("\"include_usage\":true"));
+    }
+
+    #[tokio::test]
+    async fn test_function_calls_round_trip() {
+        let body = r#"{"model":"gpt-test","choices":[{"message":{"role":"assistant","content":null,
+            "tool_calls":[{"id":"call_a","type":"function","function":{"name":"lookup","arguments":"{\"q\":\"rust\"}"}}]}}],
+            "usage":{"prompt_tokens":11,"completion_tokens":4}}"#;
+        let (base_url, _) = serve(vec![MockResponse::json(body)]).await;
+
+        let provider = OpenAIProvider::new("sk-test", "gpt-test", Some(&base_url));
+        let mut request = CompletionRequest {
+            messages: vec![Message::user("Search")],
+            max_tokens: 32,
+            tools: vec![ToolDefinition {
+                name: "lookup".to_string(),
+                description: "Search the docs".to_string(),
+                input_schema: serde_json::json!({ "type": "object" }),
+            }],
+            ..Default::default()
+        };
+        let response = provider.complete(request.clone()).await.unwrap();
+        assert_eq!(response.tool_calls[0].name, "lookup");
+        assert_eq!(response.tool_calls[0].arguments["q"], "rust");
+
+        request.messages.push(Message::assistant_tool_calls(response.content, response.tool_calls));
+        request.messages.push(Message::tool_result("call_a", "found"));
+        let body = serde_json::to_value(provider.build_body(&request, false).unwrap()).unwrap();
+        assert_eq!(body["tools"][0]["type"], "function");
+        assert_eq!(body["messages"][1]["tool_calls"][0]["function"]["arguments"], "{\"q\":\"rust\"}");
+        assert_eq!(body["messages"][2]["role"], "tool");
+        assert_eq!(body["messages"][2]["tool_call_id"], "call_a");
+    }
+
+    #[test]
+    fn test_attachments_become_content_parts() {
+        let provider = OpenAIProvider::new("sk-test", "gpt-test", None);
+        let request = CompletionRequest {
+            messages: vec![Message::user("Summarize").with_attachments([
+                Attachment::new("ui.png", "image/png", vec![1, 2, 3]),
+                Attachment::new("spec.pdf", "application/pdf", b"%PDF".to_vec()),
+            ])],
+            max_tokens: 32,
+            ..Default::default()
+        };
+        let body = serde_json::to_value(provider.build_body(&request, false).unwrap()).unwrap();
+        let parts = &body["messages"][0]["content"];
+        assert_eq!(parts[0]["text"], "Summarize");
+        assert_eq!(parts[1]["image_url"]["url"], "data:image/png;base64,AQID");
+        assert_eq!(parts[2]["file"]["filename"], "spec.pdf");
+    }
+
+    #[tokio::test]
+    async fn test_compatible_endpoint_request_shape() {
+        let body = r#"{"model":"team-large","choices":[{"message":{"role":"assistant","content":"ok"}}]}"#;
+        let (base_url, requests) = serve(vec![MockResponse::json(body)]).await;
+
+        let endpoint = OpenAICompatibleConfig {
+            base_url: format!("{base_url}/openai/deployments/{{model}}"),
+            auth_header: "api-key".to_string(),
+            auth_scheme: String::new(),
+            headers: HashMap::from([("X-Team".to_string(), "platform".to_string())]),
+            api_version: Some("2024-10-21".to_string()),
+            model_aliases: HashMap::from([("gpt-4.1".to_string(), "team-large".to_string())]),
+            ..Default::default()
+        };
+        let provider = OpenAIProvider::compatible("azure", "secret", "gpt-4.1", &endpoint);
+        let request = CompletionRequest {
+            messages: vec![Message::user("Hello")],
+            max_tokens: 32,
+            ..Default::default()
+        };
+        let response = provider.complete(request).await.unwrap();
+        assert_eq!(response.content, "ok");
+        assert_eq!(AiProvider::provider_name(&provider), "azure");
+
+        let raw = requests.await.unwrap()[0].to_lowercase();
+        assert!(raw.starts_with(
+            "post /openai/deployments/team-large/chat/completions?api-version=2024-10-21 "
+        ));
+        assert!(raw.contains("api-key: secret\r\n"));
+        assert!(raw.contains("x-team: platform\r\n"));
+        assert!(!raw.contains("authorization"));
+        assert!(raw.contains("\"model\":\"team-large\""));
+    }
+
+    #[tokio::test]
+    async fn test_embeddings_against_mock_server() {
+        let body = r#"{"model":"text-embedding-3-small","data":[
+            {"object":"embedding","index":1,"embedding":[0.0,1.0]},
+            {"object":"embedding","index":0,"embedding":[1.0,0.0]}],
+            "usage":{"prompt_tokens":6,"total_tokens":6}}"#;
+        let (base_url, requests) = serve(vec![MockResponse::json(body)]).await;
+
+        let provider = OpenAIProvider::new("sk-test", "gpt-test", Some(&base_url));
+        let request = EmbeddingRequest::new(["first", "second"]).with_dimensions(2);
+        let response = provider.embed(request).await.unwrap();
+
+        assert_eq!(response.embeddings, vec![vec![1.0, 0.0], vec![0.0, 1.0]]);
+        assert_eq!(response.dimensions, 2);
+        assert_eq!(response.usage.input_tokens, 6);
+
+        let raw = requests.await.unwrap();
+        assert!(raw[0].starts_with("POST /v1/embeddings "));
+        assert!(raw[0].contains(r#""input":["first","second"]"#));
+        assert!(raw[0].contains(r#""dimensions":2"#));
     }
 }
@@ -0,0 +1,138 @@
+//! Per-model token pricing used for cost accounting.
+//!
+//! A built-in table covers the default models of every provider; entries in
+//! `[ai.pricing]` override or extend it.  Models are matched by exact id
+//! first, then by the longest matching prefix, so dated snapshots such as
+//! `claude-haiku-4-5-20251001` resolve to `claude-haiku-4-5`.
+
+use crate::types::TokenUsage;
+use nakama_core::config::{AiConfig, ModelPricing};
+use std::collections::HashMap;
+
+/// Built-in prices in USD per million (input, output) tokens.
+const DEFAULT_PRICES: &[(&str, f64, f64)] = &[
+    // Anthropic
+    ("claude-opus-4", 15.00, 75.00),
+    ("claude-opus-4-5", 5.00, 25.00),
+    ("claude-opus-4-6", 5.00, 25.00),
+    ("claude-sonnet-4", 3.00, 15.00),
+    ("claude-haiku-4-5", 1.00, 5.00),
+    // OpenAI
+    ("gpt-4.1", 2.00, 8.00),
+    ("gpt-4.1-mini", 0.40, 1.60),
+    ("gpt-4.1-nano", 0.10, 0.40),
+    ("gpt-4o", 2.50, 10.00),
+    ("gpt-4o-mini", 0.15, 0.60),
+    ("o4-mini", 1.10, 4.40),
+    // Google
+    ("gemini-2.5-pro", 1.25, 10.00),
+    ("gemini-2.5-flash", 0.30, 2.50),
+];
+
+/// Resolves model ids to prices and computes completion costs.
+#[derive(Debug, Clone)]
+pub struct PriceTable {
+    prices: HashMap<String, ModelPricing>,
+}
+
+impl PriceTable {
+    /// Build the table from the built-in defaults plus `[ai.pricing]` overrides.
+    pub fn from_config(config: &AiConfig) -> Self {
+        let mut table = Self::default();
+        for (model, pricing) in &config.pricing {
+            table.prices.insert(model.clone(), *pricing);
+        }
+        table
+    }
+
+    /// Look up the price for a model, by exact id or longest prefix.
+    pub fn lookup(&self, model: &str) -> Option<ModelPricing> {
+        if let Some(pricing) = self.prices.get(model) {
+            return Some(*pricing);
+        }
+        self.prices
+            .iter()
+            .filter(|(prefix, _)| model.starts_with(prefix.as_str()))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(_, pricing)| *pricing)
+    }
+
+    /// Estimated cost in USD of a completion.
+    ///
+    /// Models without a known price (including local Ollama models) cost
+    /// nothing.
+    pub fn cost_usd(&self, model: &str, usage: &TokenUsage) -> f64 {
+        match self.lookup(model) {
+            Some(pricing) => {
+                (usage.input_tokens as f64 * pricing.input_per_mtok
+                    + usage.output_tokens as f64 * pricing.output_per_mtok)
+                    / 1_000_000.0
+            }
+            None => {
+                tracing::debug!(model, "No price known for model; counting as free");
+                0.0
+            }
+        }
+    }
+}
+
+impl Default for PriceTable {
+    fn default() -> Self {
+        let prices = DEFAULT_PRICES
+            .iter()
+            .map(|&(model, input, output)| {
+                (
+                    model.to_string(),
+                    ModelPricing {
+                        input_per_mtok: input,
+                        output_per_mtok: output,
+                    },
+                )
+            })
+            .collect();
+        Self { prices }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usage(input_tokens: u32, output_tokens: u32) -> TokenUsage {
+        TokenUsage {
+            input_tokens,
+            output_tokens,
+        }
+    }
+
+    #[test]
+    fn test_prefix_lookup_prefers_longest_match() {
+        let table = PriceTable::default();
+        let mini = table.lookup("gpt-4.1-mini-2025-04-14").unwrap();
+        assert_eq!(mini.input_per_mtok, 0.40);
+        let haiku = table.lookup("claude-haiku-4-5-20251001").unwrap();
+        assert_eq!(haiku.output_per_mtok, 5.00);
+        assert!(table.lookup("llama3:8b").is_none());
+    }
+
+    #[test]
+    fn test_cost_and_overrides() {
+        let mut config = AiConfig::default();
+        config.pricing.insert(
+            "llama3".to_string(),
+            ModelPricing {
+                input_per_mtok: 0.5,
+                output_per_mtok: 1.0,
+            },
+        );
+        let table = PriceTable::from_config(&config);
+
+        let cost = table.cost_usd("claude-sonnet-4-6", &usage(1_000_000, 100_000));
+        assert!((cost - 4.5).abs() < 1e-9);
+
+        let cost = table.cost_usd("llama3:70b", &usage(2_000_000, 1_000_000));
+        assert!((cost - 2.0).abs() < 1e-9);
+
+        assert_eq!(table.cost_usd("mistral", &usage(1000, 1000)), 0.0);
+    }
+}
@@ -0,0 +1,559 @@
+//! Prompt templates with stable ids and user overrides.
+//!
+//! Every system prompt the suite sends is a built-in template named
+//! `<tool>/<id>` and compiled into the binary from
+//! `shared/nakama-ai/prompts/`.  A template is replaced by a Markdown file
+//! with the same name under the repository's `.nakama/prompts/` directory or
+//! under `~/.nakama/prompts/`, checked in that order.  A repository override
+//! arrives with the code, so it is only used once the user has trusted that
+//! file's contents (see [`nakama_core::trust`]); [`load_prompt`] asks.
+//!
+//! Templates reference variables as `{{name}}`.  Tools pass the variables a
+//! prompt needs (e.g. zangetsu's `{{shell_context}}`), and every prompt can
+//! also use `{{repo}}`, `{{language}}` and `{{conventions}}`, detected from
+//! the repository in the current directory.  Unknown variables are left
+//! as written.
+
+use nakama_core::error::{NakamaError, NakamaResult};
+use nakama_core::paths;
+use nakama_core::trust;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// A prompt template compiled into the binary.
+#[derive(Debug, Clone, Copy)]
+pub struct BuiltinPrompt {
+    pub tool: &'static str,
+    pub id: &'static str,
+    /// One-line description for `nakama prompts list`.
+    pub description: &'static str,
+    pub template: &'static str,
+}
+
+macro_rules! builtin {
+    ($tool:literal, $id:literal, $description:literal) => {
+        BuiltinPrompt {
+            tool: $tool,
+            id: $id,
+            description: $description,
+            template: include_str!(concat!("../prompts/", $tool, "/", $id, ".md")),
+        }
+    };
+}
+
+const BUILTIN_PROMPTS: &[BuiltinPrompt] = &[
+    builtin!(
+        "byakugan",
+        "security",
+        "Security pass of the multi-pass review"
+    ),
+    builtin!(
+        "byakugan",
+        "performance",
+        "Performance pass of the multi-pass review"
+    ),
+    builtin!(
+        "byakugan",
+        "style",
+        "Style and maintainability pass of the multi-pass review"
+    ),
+    builtin!(
+        "byakugan",
+        "logic",
+        "Logic and correctness pass of the multi-pass review"
+    ),
+    builtin!(
+        "byakugan",
+        "summary",
+        "Overall summary, score and merge readiness"
+    ),
+    builtin!(
+        "byakugan",
+        "suggest",
+        "Improvement suggestions for uncommitted changes"
+    ),
+    builtin!("gate", "explore", "Analysis of an explored API response"),
+    builtin!("gate", "test", "Explanation of an endpoint test response"),
+    builtin!(
+        "itachi",
+        "ask",
+        "Answers from Jira issues and Confluence pages"
+    ),
+    builtin!("itachi", "brief", "Team briefing from recent Jira activity"),
+    builtin!("itachi", "jira", "Natural language to JQL"),
+    builtin!(
+        "itachi",
+        "standup",
+        "Standup summary from the user's Jira issues"
+    ),
+    builtin!("itachi", "wiki", "Natural language to CQL"),
+    builtin!("jogan", "analyze", "Log file analysis"),
+    builtin!(
+        "jogan",
+        "diagnose",
+        "Diagnosis of a symptom from system diagnostics"
+    ),
+    builtin!(
+        "jogan",
+        "explain",
+        "Explanation of an infrastructure error or concept"
+    ),
+    builtin!("kami", "ask", "General questions"),
+    builtin!("kami", "compare", "Side-by-side comparison"),
+    builtin!("kami", "deep", "Deep research report"),
+    builtin!("kami", "grounded", "Fact checking"),
+    builtin!("kami", "search", "Search answers"),
+    builtin!("kami", "summarize", "Summary of a web page"),
+    builtin!("mugen", "cover", "Prioritization of untested files"),
+    builtin!("mugen", "edge", "Edge-case tests for a function"),
+    builtin!("mugen", "fuzz", "Fuzz test harnesses"),
+    builtin!(
+        "mugen",
+        "gen",
+        "Unit test generation ({{language}}, {{test_framework}})"
+    ),
+    builtin!("mugen", "mutate", "Mutation testing suggestions"),
+    builtin!("mugen", "review", "Test quality review"),
+    builtin!(
+        "nakama",
+        "session-summary",
+        "Summary of older turns in a conversation session"
+    ),
+    builtin!("senku", "ask", "Questions about the codebase"),
+    builtin!("sharingan", "analyze", "Log file analysis"),
+    builtin!("sharingan", "correlate", "Correlation of two log files"),
+    builtin!("sharingan", "search", "Explanation of log search results"),
+    builtin!("shinigami", "branch", "Branch name suggestions"),
+    builtin!("shinigami", "changelog", "Changelog from commit messages"),
+    builtin!(
+        "shinigami",
+        "commit",
+        "Conventional commit message for staged changes"
+    ),
+    builtin!("shinigami", "release", "Release notes"),
+    builtin!("shinigami", "review", "Review of staged changes"),
+    builtin!("shinigami", "squash", "Commit message for squashed commits"),
+    builtin!("tensai", "brief", "Morning briefing"),
+    builtin!("tensai", "plan", "Day plan"),
+    builtin!("tensai", "review", "End-of-day review"),
+    builtin!("tensai", "standup", "Standup report"),
+    builtin!(
+        "zangetsu",
+        "ask",
+        "Natural language to shell commands ({{shell_context}})"
+    ),
+    builtin!(
+        "zangetsu",
+        "chain",
+        "Multi-step pipelines ({{shell_context}})"
+    ),
+    builtin!(
+        "zangetsu",
+        "explain",
+        "Explanation of a shell command ({{shell_context}})"
+    ),
+    builtin!(
+        "zangetsu",
+        "fix",
+        "Fix for the last failed command ({{shell_context}})"
+    ),
+];
+
+/// All built-in prompts, sorted by tool.
+pub fn builtin_prompts() -> &'static [BuiltinPrompt] {
+    BUILTIN_PROMPTS
+}
+
+/// The built-in prompt `<tool>/<id>`, if there is one.
+pub fn builtin_prompt(tool: &str, id: &str) -> Option<&'static BuiltinPrompt> {
+    BUILTIN_PROMPTS
+        .iter()
+        .find(|p| p.tool == tool && p.id == id)
+}
+
+/// Where an effective prompt template came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PromptSource {
+    Builtin,
+    /// An override in the repository's `.nakama/prompts/`.
+    Repo(PathBuf),
+    /// An override in `~/.nakama/prompts/`.
+    User(PathBuf),
+}
+
+impl PromptSource {
+    /// The override file, if the template isn't built in.
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            PromptSource::Builtin => None,
+            PromptSource::Repo(path) | PromptSource::User(path) => Some(path),
+        }
+    }
+}
+
+impl fmt::Display for PromptSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PromptSource::Builtin => write!(f, "built-in"),
+            PromptSource::Repo(_) => write!(f, "repo"),
+            PromptSource::User(_) => write!(f, "user"),
+        }
+    }
+}
+
+/// The effective template for one prompt.
+#[derive(Debug, Clone)]
+pub struct Prompt {
+    pub builtin: &'static BuiltinPrompt,
+    pub template: String,
+    pub source: PromptSource,
+    /// A repository override that was skipped because it isn't trusted.
+    pub untrusted: Option<PathBuf>,
+}
+
+impl Prompt {
+    /// The prompt's `<tool>/<id>` name.
+    pub fn name(&self) -> String {
+        format!("{}/{}", self.builtin.tool, self.builtin.id)
+    }
+
+    /// Whether the built-in template has been replaced.
+    pub fn is_overridden(&self) -> bool {
+        self.source != PromptSource::Builtin
+    }
+
+    /// The template with `vars` substituted.
+    pub fn render(&self, vars: &PromptVars) -> String {
+        render(&self.template, vars)
+    }
+}
+
+/// Values for `{{name}}` placeholders.
+#[derive(Debug, Clone, Default)]
+pub struct PromptVars {
+    vars: HashMap<String, String>,
+}
+
+impl PromptVars {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// `repo`, `language` and `conventions` for the repository containing
+    /// the current directory.
+    pub fn detect() -> Self {
+        match std::env::current_dir() {
+            Ok(dir) => Self::detect_in(&dir),
+            Err(_) => Self::default(),
+        }
+    }
+
+    /// `repo`, `language` and `conventions` for the repository containing
+    /// `dir` (or `dir` itself outside a repository).
+    ///
+    /// `conventions` is the contents of `.nakama/conventions.md`, if the
+    /// repository has one.
+    pub fn detect_in(dir: &Path) -> Self {
+        let root = repo_root(dir).unwrap_or_else(|| dir.to_path_buf());
+        let mut vars = Self::new();
+        if let Some(name) = root.file_name() {
+            vars.set("repo", name.to_string_lossy());
+        }
+        vars.set("language", detect_language(&root).unwrap_or_default());
+        let conventions = std::fs::read_to_string(root.join(".nakama").join("conventions.md"))
+            .unwrap_or_default();
+        vars.set("conventions", conventions.trim());
+        vars
+    }
+
+    pub fn set(&mut self, name: &str, value: impl Into<String>) {
+        self.vars.insert(name.to_string(), value.into());
+    }
+
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.vars.get(name).map(String::as_str)
+    }
+}
+
+/// Substitute `{{name}}` placeholders in `template`, leaving unknown ones
+/// untouched.
+pub fn render(template: &str, vars: &PromptVars) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start..].find("}}") else {
+            break;
+        };
+        let name = rest[start + 2..start + len].trim();
+        out.push_str(&rest[..start]);
+        match vars.get(name) {
+            Some(value) => out.push_str(value),
+            None => out.push_str(&rest[start..start + len + 2]),
+        }
+        rest = &rest[start + len + 2..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Looks up the effective template for each prompt.
+#[derive(Debug, Clone)]
+pub struct PromptRegistry {
+    repo_dir: Option<PathBuf>,
+    user_dir: Option<PathBuf>,
+    /// Whether a repository override with the given contents is trusted.
+    repo_trusted: fn(&Path, &str) -> bool,
+}
+
+impl Default for PromptRegistry {
+    /// A registry with no override layers.
+    fn default() -> Self {
+        Self::with_dirs(None, None)
+    }
+}
+
+impl PromptRegistry {
+    /// Overrides from `.nakama/prompts/` in the repository containing the
+    /// current directory and from `~/.nakama/prompts/`.
+    pub fn new() -> Self {
+        let repo_dir = std::env::current_dir()
+            .ok()
+            .and_then(|dir| repo_root(&dir))
+            .map(|root| root.join(".nakama").join("prompts"));
+        Self::with_dirs(repo_dir, paths::prompts_dir().ok())
+    }
+
+    /// Overrides from the given directories; `None` disables a layer.
+    pub fn with_dirs(repo_dir: Option<PathBuf>, user_dir: Option<PathBuf>) -> Self {
+        Self {
+            repo_dir,
+            user_dir,
+            repo_trusted: trust::is_file_trusted,
+        }
+    }
+
+    /// The repository override directory, if there is a repository.
+    pub fn repo_dir(&self) -> Option<&Path> {
+        self.repo_dir.as_deref()
+    }
+
+    /// The user override directory.
+    pub fn user_dir(&self) -> Option<&Path> {
+        self.user_dir.as_deref()
+    }
+
+    /// The effective template for `<tool>/<id>`.
+    ///
+    /// Never asks: a repository override that isn't trusted yet is skipped
+    /// and reported in [`Prompt::untrusted`].
+    pub fn resolve(&self, tool: &str, id: &str) -> NakamaResult<Prompt> {
+        let builtin = builtin_prompt(tool, id).ok_or_else(|| NakamaError::Config {
+            message: format!("Unknown prompt '{tool}/{id}'"),
+            source: None,
+        })?;
+
+        let file = Path::new(tool).join(format!("{id}.md"));
+        let layers = [
+            self.repo_dir
+                .as_ref()
+                .map(|dir| PromptSource::Repo(dir.join(&file))),
+            self.user_dir
+                .as_ref()
+                .map(|dir| PromptSource::User(dir.join(&file))),
+        ];
+        let mut untrusted = None;
+        for source in layers.into_iter().flatten() {
+            let path = source.path().expect("override sources have a path");
+            if !path.is_file() {
+                continue;
+            }
+            let template = std::fs::read_to_string(path).map_err(|e| NakamaError::Config {
+                message: format!("Failed to read prompt override {}", path.display()),
+                source: Some(Box::new(e)),
+            })?;
+            if matches!(source, PromptSource::Repo(_)) && !(self.repo_trusted)(path, &template) {
+                untrusted = Some(path.to_path_buf());
+                continue;
+            }
+            return Ok(Prompt {
+                builtin,
+                template: template.trim_end().to_string(),
+                source,
+                untrusted,
+            });
+        }
+
+        Ok(Prompt {
+            builtin,
+            template: builtin.template.trim_end().to_string(),
+            source: PromptSource::Builtin,
+            untrusted,
+        })
+    }
+
+    /// The effective template of every built-in prompt, optionally only
+    /// those of `tool`.
+    pub fn list(&self, tool: Option<&str>) -> NakamaResult<Vec<Prompt>> {
+        BUILTIN_PROMPTS
+            .iter()
+            .filter(|p| tool.is_none() || tool == Some(p.tool))
+            .map(|p| self.resolve(p.tool, p.id))
+            .collect()
+    }
+}
+
+/// Render the effective `<tool>/<id>` prompt with `vars` and the detected
+/// repository variables.
+///
+/// An override that can't be read is reported and the built-in template is
+/// used instead.  A repository override that isn't trusted yet is only used
+/// if the user trusts it when asked.
+///
+/// # Panics
+///
+/// If `<tool>/<id>` is not a built-in prompt.
+pub fn load_prompt(tool: &str, id: &str, vars: &[(&str, &str)]) -> String {
+    let mut prompt = PromptRegistry::new().resolve(tool, id).unwrap_or_else(|e| {
+        let builtin =
+            builtin_prompt(tool, id).unwrap_or_else(|| panic!("unknown prompt '{tool}/{id}'"));
+        tracing::warn!("{}; using the built-in prompt", e);
+        Prompt {
+            builtin,
+            template: builtin.template.trim_end().to_string(),
+            source: PromptSource::Builtin,
+            untrusted: None,
+        }
+    });
+    if let Some(path) = prompt.untrusted.take() {
+        let description = format!(
+            "{} replaces the built-in {} prompt (see `nakama prompts diff {tool} {id}`).",
+            path.display(),
+            prompt.name()
+        );
+        let contents = std::fs::read_to_string(&path).unwrap_or_default();
+        match trust::trust_file(&path, &contents, &description) {
+            Ok(true) => {
+                prompt.template = contents.trim_end().to_string();
+                prompt.source = PromptSource::Repo(path);
+            }
+            Ok(false) => {}
+            Err(e) => tracing::warn!("Failed to record trust for {}: {e}", path.display()),
+        }
+    }
+    if prompt.is_overridden() {
+        tracing::debug!(prompt = %prompt.name(), source = %prompt.source, "Using prompt override");
+    }
+
+    let mut all_vars = PromptVars::detect();
+    for (name, value) in vars {
+        all_vars.set(name, *value);
+    }
+    prompt.render(&all_vars)
+}
+
+/// The nearest ancestor of `dir` (or `dir` itself) containing `.git`.
+fn repo_root(dir: &Path) -> Option<PathBuf> {
+    dir.ancestors()
+        .find(|d| d.join(".git").exists())
+        .map(Path::to_path_buf)
+}
+
+/// Manifest files that identify a project's main language, most specific
+/// first.
+const LANGUAGE_MARKERS: &[(&str, &str)] = &[
+    ("Cargo.toml", "Rust"),
+    ("go.mod", "Go"),
+    ("tsconfig.json", "TypeScript"),
+    ("package.json", "JavaScript"),
+    ("pyproject.toml", "Python"),
+    ("setup.py", "Python"),
+    ("requirements.txt", "Python"),
+    ("build.gradle.kts", "Kotlin"),
+    ("pom.xml", "Java"),
+    ("build.gradle", "Java"),
+    ("Gemfile", "Ruby"),
+    ("composer.json", "PHP"),
+    ("mix.exs", "Elixir"),
+    ("Package.swift", "Swift"),
+    ("CMakeLists.txt", "C++"),
+];
+
+fn detect_language(root: &Path) -> Option<&'static str> {
+    LANGUAGE_MARKERS
+        .iter()
+        .find(|(marker, _)| root.join(marker).exists())
+        .map(|(_, language)| *language)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_catalogue() {
+        let mut names = std::collections::HashSet::new();
+        for prompt in builtin_prompts() {
+            assert!(
+                !prompt.template.trim().is_empty(),
+                "{}/{} is empty",
+                prompt.tool,
+                prompt.id
+            );
+            assert!(
+                names.insert((prompt.tool, prompt.id)),
+                "{}/{} is listed twice",
+                prompt.tool,
+                prompt.id
+            );
+        }
+        assert!(builtin_prompt("shinigami", "commit").is_some());
+        assert!(builtin_prompt("shinigami", "nope").is_none());
+    }
+
+    #[test]
+    fn test_overrides_and_rendering() {
+        let dir = std::env::temp_dir().join(format!("nakama-prompts-{}", std::process::id()));
+        let (repo, user) = (dir.join("repo"), dir.join("user"));
+        std::fs::create_dir_all(repo.join("kami")).unwrap();
+        std::fs::create_dir_all(user.join("kami")).unwrap();
+        std::fs::write(
+            user.join("kami").join("ask.md"),
+            "User {{repo}} in {{ language }}.\n",
+        )
+        .unwrap();
+        std::fs::write(user.join("kami").join("deep.md"), "User deep").unwrap();
+        std::fs::write(repo.join("kami").join("deep.md"), "Repo deep {{unknown}}").unwrap();
+
+        let mut registry = PromptRegistry::with_dirs(Some(repo.clone()), Some(user.clone()));
+        let ask = registry.resolve("kami", "ask").unwrap();
+        assert_eq!(
+            ask.source,
+            PromptSource::User(user.join("kami").join("ask.md"))
+        );
+
+        let mut vars = PromptVars::new();
+        vars.set("repo", "crate");
+        vars.set("language", "Rust");
+        assert_eq!(ask.render(&vars), "User crate in Rust.");
+
+        // A repository override is skipped until it is trusted.
+        registry.repo_trusted = |_, _| false;
+        let deep = registry.resolve("kami", "deep").unwrap();
+        assert!(matches!(deep.source, PromptSource::User(_)));
+        assert_eq!(deep.untrusted, Some(repo.join("kami").join("deep.md")));
+
+        registry.repo_trusted = |_, contents| contents.starts_with("Repo deep");
+        let deep = registry.resolve("kami", "deep").unwrap();
+        assert!(matches!(deep.source, PromptSource::Repo(_)));
+        assert_eq!(deep.untrusted, None);
+        assert_eq!(deep.render(&vars), "Repo deep {{unknown}}");
+
+        let search = registry.resolve("kami", "search").unwrap();
+        assert!(!search.is_overridden());
+        assert!(registry.resolve("kami", "nope").is_err());
+        assert_eq!(registry.list(Some("kami")).unwrap().len(), 6);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,12 +1,16 @@
-//! The [`AiProvider`] trait and factory function.
+//! The [`AiProvider`] trait and factory functions.
 
 use crate::anthropic::AnthropicProvider;
+use crate::embedding::EmbeddingProvider;
 use crate::google::GoogleProvider;
 use crate::ollama::OllamaProvider;
 use crate::openai::OpenAIProvider;
-use crate::types::{CompletionRequest, CompletionResponse};
+use crate::stream::CompletionStream;
+use crate::types::{CompletionRequest, CompletionResponse, StreamEvent};
 use async_trait::async_trait;
-use nakama_core::error::NakamaResult;
+use futures_util::stream;
+use nakama_core::config::OpenAICompatibleConfig;
+use nakama_core::error::{NakamaError, NakamaResult};
 use nakama_core::types::Provider;
 
 /// Unified interface for AI completion providers.
@@ -15,10 +19,44 @@ pub trait AiProvider: Send + Sync {
     /// Send a completion request and return the generated response.
     async fn complete(&self, request: CompletionRequest) -> NakamaResult<CompletionResponse>;
 
+    /// Send a completion request and return a stream of text deltas, ending
+    /// with a [`StreamEvent::Done`] that carries the final token usage.
+    ///
+    /// The default implementation falls back to [`complete`](Self::complete)
+    /// and emits the whole response as a single delta.
+    async fn complete_stream(&self, request: CompletionRequest) -> NakamaResult<CompletionStream> {
+        let response = self.complete(request).await?;
+        Ok(response_stream(response))
+    }
+
+    /// A response to `request` that can be served without contacting the
+    /// backend, such as a response cache hit.  Most providers have none.
+    fn cached(&self, _request: &CompletionRequest) -> Option<CompletionResponse> {
+        None
+    }
+
     /// Human-readable name of this provider (e.g. `"anthropic"`, `"openai"`).
     fn provider_name(&self) -> &str;
 }
 
+/// Replay a complete response as a stream: its text as a single delta, then
+/// any tool calls, then [`StreamEvent::Done`].
+pub(crate) fn response_stream(response: CompletionResponse) -> CompletionStream {
+    let mut events = Vec::with_capacity(response.tool_calls.len() + 2);
+    if !response.content.is_empty() {
+        events.push(Ok(StreamEvent::Delta {
+            text: response.content,
+        }));
+    }
+    events.extend(response.tool_calls.into_iter().map(|call| Ok(StreamEvent::ToolCall(call))));
+    events.push(Ok(StreamEvent::Done {
+        model: response.model,
+        usage: response.usage,
+        cached: response.cached,
+    }));
+    Box::pin(stream::iter(events))
+}
+
 /// Create a boxed [`AiProvider`] for the given provider enum variant.
 ///
 /// # Arguments
@@ -45,5 +83,53 @@ pub fn create_provider(
             model,
             base_url.unwrap_or("http://localhost:11434"),
         ))),
+        Provider::OpenAICompatible => {
+            let mut endpoint = OpenAICompatibleConfig::default();
+            if let Some(base_url) = base_url {
+                endpoint.base_url = base_url.to_string();
+            }
+            Ok(Box::new(OpenAIProvider::compatible(
+                "openai_compatible",
+                api_key,
+                model,
+                &endpoint,
+            )))
+        }
+    }
+}
+
+/// Create a boxed [`EmbeddingProvider`] for the given provider enum variant.
+///
+/// Anthropic has no embeddings API, so asking for it is an error.
+pub fn create_embedding_provider(
+    provider: Provider,
+    api_key: &str,
+    base_url: Option<&str>,
+) -> NakamaResult<Box<dyn EmbeddingProvider>> {
+    match provider {
+        Provider::Anthropic => Err(NakamaError::AiProvider {
+            message: "Anthropic does not offer an embeddings API; \
+                      use openai, google, ollama or an openai_compatible endpoint"
+                .to_string(),
+            source: None,
+        }),
+        Provider::OpenAI => Ok(Box::new(OpenAIProvider::new(api_key, "", base_url))),
+        Provider::Google => Ok(Box::new(GoogleProvider::new(api_key, "", base_url))),
+        Provider::Ollama => Ok(Box::new(OllamaProvider::new(
+            "",
+            base_url.unwrap_or("http://localhost:11434"),
+        ))),
+        Provider::OpenAICompatible => {
+            let mut endpoint = OpenAICompatibleConfig::default();
+            if let Some(base_url) = base_url {
+                endpoint.base_url = base_url.to_string();
+            }
+            Ok(Box::new(OpenAIProvider::compatible(
+                "openai_compatible",
+                api_key,
+                "",
+                &endpoint,
+            )))
+        }
     }
 }
@@ -0,0 +1,572 @@
+//! Retry, backoff and circuit breaking for AI providers.
+//!
+//! [`RetryingProvider`] wraps any [`AiProvider`] and retries transient
+//! failures (HTTP 429, 5xx, network errors) using jittered exponential backoff
+//! from [`RetryConfig`], honoring `Retry-After` when the provider sends one.
+//! A `Retry-After` longer than `max_backoff_ms` is not waited out: the error
+//! is returned at once so a fallback provider (or the user) can act.
+//! After repeated failures a circuit breaker opens and further calls fail fast
+//! until a cooldown has elapsed.
+
+use crate::provider::AiProvider;
+use crate::stream::CompletionStream;
+use crate::types::{CompletionRequest, CompletionResponse};
+use async_trait::async_trait;
+use nakama_core::config::RetryConfig;
+use nakama_core::error::{NakamaError, NakamaResult};
+use rand::Rng;
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use reqwest::StatusCode;
+use std::fmt;
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+// ---------------------------------------------------------------------------
+// Error classification
+// ---------------------------------------------------------------------------
+
+/// HTTP status details attached as the `source` of provider API errors.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApiStatus {
+    /// The HTTP status code returned by the provider.
+    pub status: u16,
+    /// The delay requested by the provider's `Retry-After` header, if any.
+    pub retry_after: Option<Duration>,
+}
+
+impl fmt::Display for ApiStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "HTTP {}", self.status)
+    }
+}
+
+impl std::error::Error for ApiStatus {}
+
+/// Attached as the `source` of the error returned while a circuit breaker is
+/// open, carrying the time left until the next trial call.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CircuitOpen {
+    pub remaining: Duration,
+}
+
+impl fmt::Display for CircuitOpen {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "circuit open for another {:.0}s", self.remaining.as_secs_f64().ceil())
+    }
+}
+
+impl std::error::Error for CircuitOpen {}
+
+/// Whether a failed request is worth retrying.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorClass {
+    /// Rate limits, server errors and network failures.
+    Transient { retry_after: Option<Duration> },
+    /// Everything else (bad requests, auth failures, unparseable responses).
+    Permanent,
+}
+
+/// Classify an error returned by a provider.
+///
+/// Network errors (connection failures, timeouts, interrupted streams), API
+/// errors with status 408, 429 or 5xx (including Anthropic's 529
+/// "overloaded") and calls rejected by an open circuit breaker are transient.
+/// All other errors are permanent.
+pub fn classify(error: &NakamaError) -> ErrorClass {
+    match error {
+        NakamaError::Network { .. } => ErrorClass::Transient { retry_after: None },
+        NakamaError::AiProvider {
+            source: Some(source),
+            ..
+        } => {
+            if let Some(open) = source.downcast_ref::<CircuitOpen>() {
+                return ErrorClass::Transient {
+                    retry_after: Some(open.remaining),
+                };
+            }
+            match source.downcast_ref::<ApiStatus>() {
+                Some(api) if api.status == 408 || api.status == 429 || api.status >= 500 => {
+                    ErrorClass::Transient {
+                        retry_after: api.retry_after,
+                    }
+                }
+                _ => ErrorClass::Permanent,
+            }
+        }
+        _ => ErrorClass::Permanent,
+    }
+}
+
+/// Build the error returned for a non-success HTTP response.
+pub(crate) fn api_error(
+    provider: &str,
+    status: StatusCode,
+    retry_after: Option<Duration>,
+    error_msg: &str,
+) -> NakamaError {
+    NakamaError::AiProvider {
+        message: format!("{provider} API error (HTTP {status}): {error_msg}"),
+        source: Some(Box::new(ApiStatus {
+            status: status.as_u16(),
+            retry_after,
+        })),
+    }
+}
+
+/// Parse a `Retry-After` header, given either in seconds or as an HTTP date.
+pub(crate) fn parse_retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+
+    let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    let delta = date.with_timezone(&chrono::Utc) - chrono::Utc::now();
+    Some(delta.to_std().unwrap_or(Duration::ZERO))
+}
+
+// ---------------------------------------------------------------------------
+// Circuit breaker
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Default)]
+struct BreakerState {
+    consecutive_failures: u32,
+    opened_at: Option<Instant>,
+}
+
+/// Fails fast after `threshold` consecutive failures until `cooldown` passes.
+///
+/// Once the cooldown has elapsed a single trial call is let through: success
+/// closes the circuit, another failure re-opens it immediately.
+#[derive(Debug)]
+struct CircuitBreaker {
+    threshold: u32,
+    cooldown: Duration,
+    state: Mutex<BreakerState>,
+}
+
+impl CircuitBreaker {
+    fn new(threshold: u32, cooldown: Duration) -> Self {
+        Self {
+            threshold,
+            cooldown,
+            state: Mutex::new(BreakerState::default()),
+        }
+    }
+
+    /// Return an error if the circuit is open.
+    fn check(&self, provider: &str) -> NakamaResult<()> {
+        let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(opened_at) = state.opened_at {
+            let elapsed = opened_at.elapsed();
+            if elapsed < self.cooldown {
+                let remaining = self.cooldown - elapsed;
+                return Err(NakamaError::AiProvider {
+                    message: format!(
+                        "{provider} circuit breaker open after {} consecutive failures; retry in {:.0}s",
+                        state.consecutive_failures,
+                        remaining.as_secs_f64().ceil(),
+                    ),
+                    source: Some(Box::new(CircuitOpen { remaining })),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn record_success(&self) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        *state = BreakerState::default();
+    }
+
+    fn record_failure(&self) {
+        if self.threshold == 0 {
+            return;
+        }
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.consecutive_failures += 1;
+        if state.consecutive_failures >= self.threshold {
+            state.opened_at = Some(Instant::now());
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Retrying provider
+// ---------------------------------------------------------------------------
+
+/// An [`AiProvider`] wrapper that retries transient failures.
+pub struct RetryingProvider {
+    inner: Box<dyn AiProvider>,
+    config: RetryConfig,
+    breaker: CircuitBreaker,
+}
+
+impl RetryingProvider {
+    /// Wrap `inner` with the retry policy described by `config`.
+    pub fn new(inner: Box<dyn AiProvider>, config: &RetryConfig) -> Self {
+        Self {
+            inner,
+            config: config.clone(),
+            breaker: CircuitBreaker::new(
+                config.circuit_breaker_threshold,
+                Duration::from_millis(config.circuit_breaker_cooldown_ms),
+            ),
+        }
+    }
+
+    /// Un-jittered backoff for the given retry attempt (0-based).
+    fn base_backoff(&self, attempt: u32) -> Duration {
+        let factor = self.config.backoff_multiplier.max(1.0).powi(attempt as i32);
+        let ms = (self.config.initial_backoff_ms as f64 * factor)
+            .min(self.config.max_backoff_ms as f64);
+        Duration::from_millis(ms as u64)
+    }
+
+    /// Backoff with "equal jitter": half the base delay plus a random share
+    /// of the other half, so concurrent clients don't retry in lockstep.
+    fn backoff(&self, attempt: u32) -> Duration {
+        let base = self.base_backoff(attempt);
+        let half = base / 2;
+        let jitter = rand::thread_rng().gen_range(0..=half.as_millis() as u64);
+        half + Duration::from_millis(jitter)
+    }
+
+    async fn run<T, F, Fut>(&self, mut op: F) -> NakamaResult<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = NakamaResult<T>>,
+    {
+        let provider = self.inner.provider_name();
+        let mut attempt = 0;
+
+        loop {
+            self.breaker.check(provider)?;
+
+            let error = match op().await {
+                Ok(value) => {
+                    self.breaker.record_success();
+                    return Ok(value);
+                }
+                Err(e) => e,
+            };
+
+            let retry_after = match classify(&error) {
+                ErrorClass::Transient { retry_after } => retry_after,
+                ErrorClass::Permanent => return Err(error),
+            };
+
+            self.breaker.record_failure();
+            if attempt >= self.config.max_retries {
+                return Err(error);
+            }
+
+            let delay = match retry_after {
+                Some(delay) if delay > Duration::from_millis(self.config.max_backoff_ms) => {
+                    tracing::warn!(
+                        provider,
+                        retry_after_ms = delay.as_millis() as u64,
+                        max_backoff_ms = self.config.max_backoff_ms,
+                        "AI provider asked to wait longer than max_backoff_ms, not retrying"
+                    );
+                    return Err(error);
+                }
+                Some(delay) => delay,
+                None => self.backoff(attempt),
+            };
+            tracing::warn!(
+                provider,
+                attempt = attempt + 1,
+                max_retries = self.config.max_retries,
+                delay_ms = delay.as_millis() as u64,
+                error = %error,
+                "Transient AI provider error, retrying"
+            );
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+}
+
+#[async_trait]
+impl AiProvider for RetryingProvider {
+    async fn complete(&self, request: CompletionRequest) -> NakamaResult<CompletionResponse> {
+        self.run(|| self.inner.complete(request.clone())).await
+    }
+
+    /// Only opening the stream is retried; errors after the first event are
+    /// passed through, since part of the response has already been consumed.
+    async fn complete_stream(&self, request: CompletionRequest) -> NakamaResult<CompletionStream> {
+        self.run(|| self.inner.complete_stream(request.clone())).await
+    }
+
+    fn provider_name(&self) -> &str {
+        self.inner.provider_name()
+    }
+}
+
+/// Wrap a provider with the retry policy from `config`.
+pub fn with_retry(provider: Box<dyn AiProvider>, config: &RetryConfig) -> Box<dyn AiProvider> {
+    Box::new(RetryingProvider::new(provider, config))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Message, TokenUsage};
+    use reqwest::header::HeaderValue;
+    use std::collections::VecDeque;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::Arc;
+
+    /// Provider that replays a scripted sequence of outcomes.
+    struct ScriptedProvider {
+        outcomes: Mutex<VecDeque<NakamaResult<CompletionResponse>>>,
+        calls: Arc<AtomicU32>,
+    }
+
+    impl ScriptedProvider {
+        fn new(outcomes: Vec<NakamaResult<CompletionResponse>>) -> (Self, Arc<AtomicU32>) {
+            let calls = Arc::new(AtomicU32::new(0));
+            let provider = Self {
+                outcomes: Mutex::new(outcomes.into()),
+                calls: calls.clone(),
+            };
+            (provider, calls)
+        }
+    }
+
+    #[async_trait]
+    impl AiProvider for ScriptedProvider {
+        async fn complete(&self, _request: CompletionRequest) -> NakamaResult<CompletionResponse> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            self.outcomes
+                .lock()
+                .unwrap()
+                .pop_front()
+                .unwrap_or_else(|| Err(status_error(500, None)))
+        }
+
+        fn provider_name(&self) -> &str {
+            "scripted"
+        }
+    }
+
+    fn status_error(status: u16, retry_after: Option<Duration>) -> NakamaError {
+        api_error(
+            "Scripted",
+            StatusCode::from_u16(status).unwrap(),
+            retry_after,
+            "boom",
+        )
+    }
+
+    fn ok() -> NakamaResult<CompletionResponse> {
+        Ok(CompletionResponse {
+            content: "ok".to_string(),
+            model: "m".to_string(),
+            usage: TokenUsage::default(),
+            tool_calls: Vec::new(),
+            cached: false,
+        })
+    }
+
+    fn request() -> CompletionRequest {
+        CompletionRequest {
+            system_prompt: String::new(),
+            messages: vec![Message::user("hi")],
+            model: String::new(),
+            max_tokens: 16,
+            temperature: 0.0,
+            ..Default::default()
+        }
+    }
+
+    fn config(max_retries: u32, threshold: u32) -> RetryConfig {
+        RetryConfig {
+            max_retries,
+            initial_backoff_ms: 1,
+            max_backoff_ms: 5,
+            backoff_multiplier: 2.0,
+            circuit_breaker_threshold: threshold,
+            circuit_breaker_cooldown_ms: 60_000,
+        }
+    }
+
+    #[test]
+    fn test_classify() {
+        for status in [408, 429, 500, 503, 529] {
+            assert!(matches!(
+                classify(&status_error(status, None)),
+                ErrorClass::Transient { .. }
+            ));
+        }
+        for status in [400, 401, 403, 404, 422] {
+            assert_eq!(classify(&status_error(status, None)), ErrorClass::Permanent);
+        }
+
+        let network = NakamaError::Network {
+            message: "timed out".to_string(),
+            source: None,
+        };
+        assert_eq!(classify(&network), ErrorClass::Transient { retry_after: None });
+
+        let parse = NakamaError::AiProvider {
+            message: "bad json".to_string(),
+            source: None,
+        };
+        assert_eq!(classify(&parse), ErrorClass::Permanent);
+
+        assert_eq!(
+            classify(&status_error(429, Some(Duration::from_secs(7)))),
+            ErrorClass::Transient {
+                retry_after: Some(Duration::from_secs(7))
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_retry_after() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(parse_retry_after(&headers), None);
+
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("12"));
+        assert_eq!(parse_retry_after(&headers), Some(Duration::from_secs(12)));
+
+        // Dates in the past mean "retry now".
+        headers.insert(
+            RETRY_AFTER,
+            HeaderValue::from_static("Wed, 21 Oct 2015 07:28:00 GMT"),
+        );
+        assert_eq!(parse_retry_after(&headers), Some(Duration::ZERO));
+
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("soon"));
+        assert_eq!(parse_retry_after(&headers), None);
+    }
+
+    #[test]
+    fn test_backoff_grows_and_is_capped() {
+        let (inner, _) = ScriptedProvider::new(vec![]);
+        let provider = RetryingProvider::new(
+            Box::new(inner),
+            &RetryConfig {
+                initial_backoff_ms: 100,
+                max_backoff_ms: 1000,
+                backoff_multiplier: 2.0,
+                ..RetryConfig::default()
+            },
+        );
+        assert_eq!(provider.base_backoff(0), Duration::from_millis(100));
+        assert_eq!(provider.base_backoff(2), Duration::from_millis(400));
+        assert_eq!(provider.base_backoff(10), Duration::from_millis(1000));
+
+        for _ in 0..20 {
+            let delay = provider.backoff(1);
+            assert!(delay >= Duration::from_millis(100) && delay <= Duration::from_millis(200));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_retries_transient_errors_until_success() {
+        let (inner, calls) = ScriptedProvider::new(vec![
+            Err(status_error(429, Some(Duration::from_millis(1)))),
+            Err(status_error(529, None)),
+            ok(),
+        ]);
+        let provider = RetryingProvider::new(Box::new(inner), &config(3, 0));
+
+        let response = provider.complete(request()).await.unwrap();
+        assert_eq!(response.content, "ok");
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_gives_up_after_max_retries() {
+        let (inner, calls) = ScriptedProvider::new(vec![]);
+        let provider = RetryingProvider::new(Box::new(inner), &config(2, 0));
+
+        let err = provider.complete(request()).await.unwrap_err();
+        assert!(err.to_string().contains("HTTP 500"));
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_long_retry_after_is_not_waited_out() {
+        let (inner, calls) = ScriptedProvider::new(vec![
+            Err(status_error(429, Some(Duration::from_secs(3600)))),
+            ok(),
+        ]);
+        let provider = RetryingProvider::new(Box::new(inner), &config(3, 0));
+
+        let started = Instant::now();
+        let err = provider.complete(request()).await.unwrap_err();
+        assert!(err.to_string().contains("HTTP 429"));
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_permanent_errors_are_not_retried() {
+        let (inner, calls) = ScriptedProvider::new(vec![Err(status_error(401, None)), ok()]);
+        let provider = RetryingProvider::new(Box::new(inner), &config(3, 0));
+
+        assert!(provider.complete(request()).await.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_circuit_breaker_opens_after_threshold() {
+        let (inner, calls) = ScriptedProvider::new(vec![]);
+        let provider = RetryingProvider::new(Box::new(inner), &config(10, 3));
+
+        let err = provider.complete(request()).await.unwrap_err();
+        assert!(err.to_string().contains("circuit breaker open"));
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+        // Subsequent calls fail fast without reaching the provider.
+        assert!(provider.complete(request()).await.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_retries_rate_limited_http_response() {
+        use crate::anthropic::AnthropicProvider;
+        use crate::test_support::{serve, MockResponse};
+
+        let (base_url, requests) = serve(vec![
+            MockResponse::with_status(
+                429,
+                "application/json",
+                r#"{"type":"error","error":{"type":"rate_limit_error","message":"Slow down"}}"#,
+            )
+            .header("retry-after", "0"),
+            MockResponse::json(
+                r#"{"content":[{"type":"text","text":"Hi"}],"model":"claude-test","usage":{"input_tokens":3,"output_tokens":1}}"#,
+            ),
+        ])
+        .await;
+
+        let inner = AnthropicProvider::new("k", "claude-test", Some(&base_url));
+        let provider = RetryingProvider::new(Box::new(inner), &config(3, 5));
+
+        let response = provider.complete(request()).await.unwrap();
+        assert_eq!(response.content, "Hi");
+        assert_eq!(requests.await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_circuit_breaker_half_open_trial() {
+        let (inner, calls) = ScriptedProvider::new(vec![Err(status_error(503, None)), ok()]);
+        let mut cfg = config(0, 1);
+        cfg.circuit_breaker_cooldown_ms = 0;
+        let provider = RetryingProvider::new(Box::new(inner), &cfg);
+
+        assert!(provider.complete(request()).await.is_err());
+        // Cooldown already elapsed: the trial call goes through and closes the circuit.
+        assert!(provider.complete(request()).await.is_ok());
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+}
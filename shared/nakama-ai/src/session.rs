@@ -0,0 +1,624 @@
+//! Multi-turn conversations that persist between invocations.
+//!
+//! A [`Session`] is the message history of one named conversation with one
+//! tool, stored by [`SessionStore`] in `~/.nakama/sessions/sessions.db`.
+//! Tools flatten [`SessionArgs`] (`--session <name>`, `--continue`) into their
+//! question subcommands and drive each turn through a [`Conversation`]: it
+//! prepends the history to the request, folds the oldest turns into a
+//! summary when the history outgrows its token budget, and saves the reply.
+
+use crate::client::AiClient;
+use crate::prompts::load_prompt;
+use crate::types::{CompletionRequest, Message, Role};
+use chrono::{DateTime, Utc};
+use nakama_core::error::{NakamaError, NakamaResult};
+use nakama_core::paths;
+use rusqlite::{params, Connection, OptionalExtension};
+use std::path::Path;
+use std::sync::Mutex;
+
+/// Most tokens of history (summary plus messages) sent with a question.
+/// Older turns are summarized to stay under it.
+const MAX_HISTORY_TOKENS: usize = 8_000;
+
+/// Response budget for summarizing old turns.
+const SUMMARY_MAX_TOKENS: u32 = 1024;
+
+/// The stored history of one conversation.
+#[derive(Debug, Clone)]
+pub struct Session {
+    /// Tool the conversation is with (e.g. `"kami"`).
+    pub tool: String,
+
+    /// Session name, unique per tool.
+    pub name: String,
+
+    /// Summary of turns that were dropped from `messages`.
+    pub summary: Option<String>,
+
+    /// Remaining turns, oldest first.
+    pub messages: Vec<Message>,
+
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl Session {
+    /// An empty session.
+    pub fn new(tool: &str, name: &str) -> Self {
+        let now = Utc::now();
+        Self {
+            tool: tool.to_string(),
+            name: name.to_string(),
+            summary: None,
+            messages: Vec::new(),
+            created_at: now,
+            updated_at: now,
+        }
+    }
+}
+
+/// One row of [`SessionStore::list`].
+#[derive(Debug, Clone)]
+pub struct SessionInfo {
+    pub tool: String,
+    pub name: String,
+    /// Number of stored messages (user and assistant turns).
+    pub messages: usize,
+    /// Whether older turns have been folded into a summary.
+    pub summarized: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// SQLite store of conversation sessions.
+pub struct SessionStore {
+    conn: Mutex<Connection>,
+}
+
+impl SessionStore {
+    /// Open (or create) the store at `~/.nakama/sessions/sessions.db`.
+    pub fn new() -> NakamaResult<Self> {
+        let dir = paths::sessions_dir()?;
+        paths::ensure_dir(&dir)?;
+        Self::open_at(&dir.join("sessions.db"))
+    }
+
+    /// Open a store at a custom path (useful for testing).
+    pub fn open_at(path: &Path) -> NakamaResult<Self> {
+        let conn = Connection::open(path).map_err(|e| {
+            store_error(format!(
+                "Failed to open session database at {}: {e}",
+                path.display()
+            ))
+        })?;
+
+        conn.execute_batch(
+            "PRAGMA journal_mode = WAL;
+            PRAGMA foreign_keys = ON;
+
+            CREATE TABLE IF NOT EXISTS sessions (
+                id         INTEGER PRIMARY KEY AUTOINCREMENT,
+                tool       TEXT NOT NULL,
+                name       TEXT NOT NULL,
+                summary    TEXT,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                UNIQUE (tool, name)
+            );
+
+            CREATE TABLE IF NOT EXISTS session_messages (
+                session_id INTEGER NOT NULL REFERENCES sessions(id) ON DELETE CASCADE,
+                seq        INTEGER NOT NULL,
+                message    TEXT NOT NULL,
+                PRIMARY KEY (session_id, seq)
+            );",
+        )
+        .map_err(|e| store_error(format!("Failed to initialize session tables: {e}")))?;
+
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    /// Load the session `name` of `tool`, if it exists.
+    pub fn load(&self, tool: &str, name: &str) -> NakamaResult<Option<Session>> {
+        let conn = self.lock()?;
+        let row = conn
+            .query_row(
+                "SELECT id, summary, created_at, updated_at FROM sessions
+                 WHERE tool = ?1 AND name = ?2",
+                params![tool, name],
+                |row| {
+                    Ok((
+                        row.get::<_, i64>(0)?,
+                        row.get::<_, Option<String>>(1)?,
+                        row.get::<_, String>(2)?,
+                        row.get::<_, String>(3)?,
+                    ))
+                },
+            )
+            .optional()
+            .map_err(|e| store_error(format!("Failed to load session '{name}': {e}")))?;
+        let Some((id, summary, created_at, updated_at)) = row else {
+            return Ok(None);
+        };
+
+        let mut stmt = conn
+            .prepare("SELECT message FROM session_messages WHERE session_id = ?1 ORDER BY seq")
+            .map_err(|e| store_error(format!("Failed to load session '{name}': {e}")))?;
+        let messages = stmt
+            .query_map(params![id], |row| row.get::<_, String>(0))
+            .and_then(|rows| rows.collect::<Result<Vec<_>, _>>())
+            .map_err(|e| store_error(format!("Failed to load session '{name}': {e}")))?
+            .iter()
+            .map(|json| serde_json::from_str(json))
+            .collect::<Result<Vec<Message>, _>>()?;
+
+        Ok(Some(Session {
+            tool: tool.to_string(),
+            name: name.to_string(),
+            summary,
+            messages,
+            created_at: parse_time(&created_at),
+            updated_at: parse_time(&updated_at),
+        }))
+    }
+
+    /// The most recently updated session of `tool`.
+    pub fn latest(&self, tool: &str) -> NakamaResult<Option<Session>> {
+        let name = self
+            .lock()?
+            .query_row(
+                "SELECT name FROM sessions WHERE tool = ?1 ORDER BY updated_at DESC LIMIT 1",
+                params![tool],
+                |row| row.get::<_, String>(0),
+            )
+            .optional()
+            .map_err(|e| store_error(format!("Failed to find latest session: {e}")))?;
+        match name {
+            Some(name) => self.load(tool, &name),
+            None => Ok(None),
+        }
+    }
+
+    /// Save `session`, replacing its stored history, and bump `updated_at`.
+    pub fn save(&self, session: &mut Session) -> NakamaResult<()> {
+        session.updated_at = Utc::now();
+        let messages = session
+            .messages
+            .iter()
+            .map(serde_json::to_string)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut conn = self.lock()?;
+        let err = |e: rusqlite::Error| {
+            store_error(format!("Failed to save session '{}': {e}", session.name))
+        };
+        let tx = conn.transaction().map_err(err)?;
+        tx.execute(
+            "INSERT INTO sessions (tool, name, summary, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)
+             ON CONFLICT (tool, name) DO UPDATE
+             SET summary = excluded.summary, updated_at = excluded.updated_at",
+            params![
+                session.tool,
+                session.name,
+                session.summary,
+                session.created_at.to_rfc3339(),
+                session.updated_at.to_rfc3339(),
+            ],
+        )
+        .map_err(err)?;
+        let id: i64 = tx
+            .query_row(
+                "SELECT id FROM sessions WHERE tool = ?1 AND name = ?2",
+                params![session.tool, session.name],
+                |row| row.get(0),
+            )
+            .map_err(err)?;
+        tx.execute(
+            "DELETE FROM session_messages WHERE session_id = ?1",
+            params![id],
+        )
+        .map_err(err)?;
+        for (seq, message) in messages.iter().enumerate() {
+            tx.execute(
+                "INSERT INTO session_messages (session_id, seq, message) VALUES (?1, ?2, ?3)",
+                params![id, seq as i64, message],
+            )
+            .map_err(err)?;
+        }
+        tx.commit().map_err(err)
+    }
+
+    /// Sessions of `tool` (or of every tool), most recently updated first.
+    pub fn list(&self, tool: Option<&str>) -> NakamaResult<Vec<SessionInfo>> {
+        let conn = self.lock()?;
+        let mut stmt = conn
+            .prepare(
+                "SELECT s.tool, s.name, COUNT(m.seq), s.summary IS NOT NULL, s.created_at, s.updated_at
+                 FROM sessions s LEFT JOIN session_messages m ON m.session_id = s.id
+                 WHERE ?1 IS NULL OR s.tool = ?1
+                 GROUP BY s.id
+                 ORDER BY s.updated_at DESC",
+            )
+            .map_err(|e| store_error(format!("Failed to list sessions: {e}")))?;
+        let rows = stmt
+            .query_map(params![tool], |row| {
+                Ok(SessionInfo {
+                    tool: row.get(0)?,
+                    name: row.get(1)?,
+                    messages: row.get::<_, i64>(2)? as usize,
+                    summarized: row.get(3)?,
+                    created_at: parse_time(&row.get::<_, String>(4)?),
+                    updated_at: parse_time(&row.get::<_, String>(5)?),
+                })
+            })
+            .and_then(|rows| rows.collect::<Result<Vec<_>, _>>())
+            .map_err(|e| store_error(format!("Failed to list sessions: {e}")))?;
+        Ok(rows)
+    }
+
+    /// Delete a session; returns whether it existed.
+    pub fn delete(&self, tool: &str, name: &str) -> NakamaResult<bool> {
+        let deleted = self
+            .lock()?
+            .execute(
+                "DELETE FROM sessions WHERE tool = ?1 AND name = ?2",
+                params![tool, name],
+            )
+            .map_err(|e| store_error(format!("Failed to delete session '{name}': {e}")))?;
+        Ok(deleted > 0)
+    }
+
+    fn lock(&self) -> NakamaResult<std::sync::MutexGuard<'_, Connection>> {
+        self.conn
+            .lock()
+            .map_err(|e| store_error(format!("Failed to acquire session database lock: {e}")))
+    }
+}
+
+fn store_error(message: String) -> NakamaError {
+    NakamaError::Tool {
+        tool: "sessions".to_string(),
+        message,
+    }
+}
+
+fn parse_time(value: &str) -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|t| t.with_timezone(&Utc))
+        .unwrap_or_default()
+}
+
+/// Session flags for subcommands that take follow-up questions.
+#[derive(Debug, Clone, Default, clap::Args)]
+pub struct SessionArgs {
+    /// Continue (or start) the named conversation
+    #[arg(long, value_name = "NAME")]
+    pub session: Option<String>,
+
+    /// Continue the most recently used conversation
+    #[arg(long = "continue", conflicts_with = "session")]
+    pub continue_last: bool,
+}
+
+impl SessionArgs {
+    /// The conversation these flags select for `tool`: a stored session, or
+    /// a one-off conversation that is not saved.
+    pub fn open(&self, tool: &str) -> NakamaResult<Conversation> {
+        if let Some(name) = &self.session {
+            let store = SessionStore::new()?;
+            let session = store
+                .load(tool, name)?
+                .unwrap_or_else(|| Session::new(tool, name));
+            return Ok(Conversation::stored(store, session));
+        }
+        if self.continue_last {
+            let store = SessionStore::new()?;
+            let session = store.latest(tool)?.ok_or_else(|| NakamaError::Tool {
+                tool: tool.to_string(),
+                message: "No conversation to continue; start one with --session <name>".to_string(),
+            })?;
+            return Ok(Conversation::stored(store, session));
+        }
+        Ok(Conversation::ephemeral(tool))
+    }
+}
+
+/// One turn of a conversation at a time: [`Conversation::prepare`] the
+/// request, send it, then [`Conversation::record`] the reply.
+pub struct Conversation {
+    store: Option<SessionStore>,
+    session: Session,
+    pending: Option<Message>,
+}
+
+impl Conversation {
+    /// A conversation saved to `store` after every turn.
+    pub fn stored(store: SessionStore, session: Session) -> Self {
+        Self {
+            store: Some(store),
+            session,
+            pending: None,
+        }
+    }
+
+    /// A conversation that is never saved.
+    pub fn ephemeral(tool: &str) -> Self {
+        Self {
+            store: None,
+            session: Session::new(tool, ""),
+            pending: None,
+        }
+    }
+
+    /// Whether turns are saved.
+    pub fn is_stored(&self) -> bool {
+        self.store.is_some()
+    }
+
+    pub fn session(&self) -> &Session {
+        &self.session
+    }
+
+    /// Build the request for `message`, with the conversation so far.
+    ///
+    /// If the history doesn't fit the model's context (or
+    /// `MAX_HISTORY_TOKENS`), the oldest turns are first summarized with
+    /// `ai`.
+    pub async fn prepare(
+        &mut self,
+        ai: &AiClient,
+        system_prompt: &str,
+        message: Message,
+        max_tokens: u32,
+        temperature: f32,
+    ) -> NakamaResult<CompletionRequest> {
+        self.compact(ai, system_prompt, &message, max_tokens)
+            .await?;
+
+        let system_prompt = match &self.session.summary {
+            Some(summary) => {
+                format!("{system_prompt}\n\nSummary of the earlier conversation:\n{summary}")
+            }
+            None => system_prompt.to_string(),
+        };
+        let mut request = ai.request(&system_prompt, message.clone(), max_tokens, temperature);
+        request.messages = self
+            .session
+            .messages
+            .iter()
+            .cloned()
+            .chain([message.clone()])
+            .collect();
+        self.pending = Some(message);
+        Ok(request)
+    }
+
+    /// Record the reply to the prepared message and save the session.
+    pub fn record(&mut self, reply: &str) -> NakamaResult<()> {
+        if let Some(message) = self.pending.take() {
+            self.session.messages.push(message);
+            self.session.messages.push(Message::assistant(reply));
+        }
+        match &self.store {
+            Some(store) => store.save(&mut self.session),
+            None => Ok(()),
+        }
+    }
+
+    /// Fold the oldest turns into the summary if the history is over budget.
+    async fn compact(
+        &mut self,
+        ai: &AiClient,
+        system_prompt: &str,
+        message: &Message,
+        max_tokens: u32,
+    ) -> NakamaResult<()> {
+        let mut budget = ai.context_budget(max_tokens);
+        budget.reserve(system_prompt);
+        budget.reserve(&message.content);
+        let available = budget.clone().at_most(MAX_HISTORY_TOKENS).available();
+
+        let messages = &self.session.messages;
+        let sizes: Vec<usize> = messages.iter().map(|m| budget.count(&m.content)).collect();
+        let summary = self.session.summary.as_deref().unwrap_or_default();
+        if budget.count(summary) + sizes.iter().sum::<usize>() <= available {
+            return Ok(());
+        }
+
+        // Keep the newest turns that fit in half the budget, starting at a
+        // user message so the history still alternates correctly.
+        let mut keep_from = messages.len();
+        let mut kept = 0;
+        for (i, size) in sizes.iter().enumerate().rev() {
+            if kept + size > available / 2 {
+                break;
+            }
+            kept += size;
+            keep_from = i;
+        }
+        while keep_from < messages.len() && messages[keep_from].role != Role::User {
+            keep_from += 1;
+        }
+        if keep_from == 0 {
+            return Ok(());
+        }
+
+        let mut transcript = String::new();
+        if !summary.is_empty() {
+            transcript.push_str(&format!("Earlier summary:\n{summary}\n\n"));
+        }
+        for message in &messages[..keep_from] {
+            let speaker = match message.role {
+                Role::Assistant => "Assistant",
+                _ => "User",
+            };
+            transcript.push_str(&format!("{speaker}: {}\n\n", message.content));
+        }
+
+        let summary_prompt = load_prompt("nakama", "session-summary", &[]);
+        let mut summary_budget = ai.context_budget(SUMMARY_MAX_TOKENS);
+        summary_budget.reserve(&summary_prompt);
+        let transcript = summary_budget.fit_text(&transcript);
+
+        tracing::debug!(
+            session = %self.session.name,
+            messages = keep_from,
+            "Summarizing older conversation turns"
+        );
+        let summary = ai
+            .ask(&summary_prompt, &transcript, SUMMARY_MAX_TOKENS, 0.2)
+            .await?;
+        self.session.summary = Some(summary.trim().to_string());
+        self.session.messages.drain(..keep_from);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::AiProvider;
+    use crate::types::CompletionResponse;
+    use async_trait::async_trait;
+    use nakama_core::types::Provider;
+    use std::sync::Arc;
+
+    fn temp_store() -> (SessionStore, std::path::PathBuf) {
+        let dir = std::env::temp_dir().join(format!("nakama-sessions-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("sessions.db");
+        let _ = std::fs::remove_file(&path);
+        (SessionStore::open_at(&path).unwrap(), dir)
+    }
+
+    #[test]
+    fn test_store_round_trip() {
+        let (store, dir) = temp_store();
+
+        let mut session = Session::new("kami", "rust");
+        session.messages.push(Message::user("What is a trait?"));
+        session.messages.push(Message::assistant("An interface."));
+        store.save(&mut session).unwrap();
+
+        let mut other = Session::new("senku", "arch");
+        other.messages.push(Message::user("Where is main?"));
+        store.save(&mut other).unwrap();
+
+        let loaded = store.load("kami", "rust").unwrap().unwrap();
+        assert_eq!(loaded.messages.len(), 2);
+        assert_eq!(loaded.messages[1].role, Role::Assistant);
+        assert!(store.load("kami", "arch").unwrap().is_none());
+
+        // Saving replaces the history.
+        session.summary = Some("Asked about traits.".to_string());
+        session.messages.drain(..1);
+        store.save(&mut session).unwrap();
+        let loaded = store.latest("kami").unwrap().unwrap();
+        assert_eq!(loaded.messages.len(), 1);
+        assert_eq!(loaded.summary.as_deref(), Some("Asked about traits."));
+
+        let all = store.list(None).unwrap();
+        assert_eq!(all.len(), 2);
+        let kami = store.list(Some("kami")).unwrap();
+        assert_eq!(kami.len(), 1);
+        assert!(kami[0].summarized);
+
+        assert!(store.delete("kami", "rust").unwrap());
+        assert!(!store.delete("kami", "rust").unwrap());
+        assert!(store.latest("kami").unwrap().is_none());
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    /// Replies with a fixed summary and remembers what it was asked.
+    struct Summarizer {
+        prompts: Arc<Mutex<Vec<String>>>,
+    }
+
+    #[async_trait]
+    impl AiProvider for Summarizer {
+        async fn complete(&self, request: CompletionRequest) -> NakamaResult<CompletionResponse> {
+            let prompt = request.messages.last().expect("a message");
+            self.prompts.lock().unwrap().push(prompt.content.clone());
+            Ok(CompletionResponse {
+                content: "  Asked about traits.  ".to_string(),
+                ..Default::default()
+            })
+        }
+
+        fn provider_name(&self) -> &str {
+            "mock"
+        }
+    }
+
+    #[tokio::test]
+    async fn test_compact_summarizes_history_over_budget() {
+        let dir =
+            std::env::temp_dir().join(format!("nakama-sessions-compact-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("sessions.db");
+        let _ = std::fs::remove_file(&path);
+        let store = SessionStore::open_at(&path).unwrap();
+
+        let mut session = Session::new("kami", "rust");
+        for turn in 0..10 {
+            let question = format!("question {turn} {}", "word ".repeat(700));
+            session.messages.push(Message::user(question));
+            let answer = format!("answer {turn} {}", "word ".repeat(200));
+            session.messages.push(Message::assistant(answer));
+        }
+        let mut conversation = Conversation::stored(store, session);
+
+        let prompts = Arc::new(Mutex::new(Vec::new()));
+        let ai = AiClient::from_provider(
+            Box::new(Summarizer {
+                prompts: Arc::clone(&prompts),
+            }),
+            Provider::Anthropic,
+            "claude-sonnet-4-20250514",
+        );
+        let message = Message::user("And what is a lifetime?");
+        let mut budget = ai.context_budget(1024);
+        budget.reserve("You are helpful.");
+        budget.reserve(&message.content);
+        let available = budget.clone().at_most(MAX_HISTORY_TOKENS).available();
+        let size = |messages: &[Message]| -> usize {
+            messages.iter().map(|m| budget.count(&m.content)).sum()
+        };
+        assert!(size(&conversation.session().messages) > available);
+
+        let request = conversation
+            .prepare(&ai, "You are helpful.", message, 1024, 0.0)
+            .await
+            .unwrap();
+
+        // The kept tail starts at a user message and fits half the budget.
+        let kept = conversation.session().messages.clone();
+        assert!(!kept.is_empty() && kept.len() < 20);
+        assert_eq!(kept[0].role, Role::User);
+        assert!(size(&kept) <= available / 2);
+        let summary = conversation.session().summary.as_deref();
+        assert_eq!(summary, Some("Asked about traits."));
+        assert_eq!(request.messages.len(), kept.len() + 1);
+
+        // Only the dropped turns were sent for summarizing.
+        let prompts = prompts.lock().unwrap();
+        assert_eq!(prompts.len(), 1);
+        assert!(prompts[0].contains("User: question 0 "));
+        assert!(!prompts[0].contains(&kept[0].content));
+
+        // The summary replaces the dropped turns in the store.
+        conversation.record("'a names a borrow.").unwrap();
+        let store = SessionStore::open_at(&path).unwrap();
+        let loaded = store.load("kami", "rust").unwrap().unwrap();
+        assert_eq!(loaded.summary.as_deref(), Some("Asked about traits."));
+        assert_eq!(loaded.messages.len(), kept.len() + 2);
+        assert_eq!(loaded.messages[0].content, kept[0].content);
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -0,0 +1,283 @@
+//! Streaming completion support.
+//!
+//! Anthropic, OpenAI and Gemini stream responses as Server-Sent Events while
+//! Ollama streams newline-delimited JSON.  This module splits a raw response
+//! body into lines and hands each one to a provider-specific
+//! [`StreamDecoder`], producing a normalized [`CompletionStream`].
+
+use crate::types::{CompletionResponse, StreamEvent, TokenUsage};
+use futures_util::stream::{self, Stream, StreamExt};
+use nakama_core::error::{NakamaError, NakamaResult};
+use std::collections::VecDeque;
+use std::pin::Pin;
+
+/// A stream of [`StreamEvent`]s produced by [`AiProvider::complete_stream`].
+///
+/// [`AiProvider::complete_stream`]: crate::AiProvider::complete_stream
+pub type CompletionStream = Pin<Box<dyn Stream<Item = NakamaResult<StreamEvent>> + Send>>;
+
+/// Drive a stream to completion, calling `on_delta` for every text chunk, and
+/// return the assembled response.
+///
+/// If the stream ends without a [`StreamEvent::Done`] the returned response
+/// has an empty model name and zero usage.
+pub async fn collect_stream<F>(
+    mut stream: CompletionStream,
+    mut on_delta: F,
+) -> NakamaResult<CompletionResponse>
+where
+    F: FnMut(&str),
+{
+    let mut content = String::new();
+    let mut model = String::new();
+    let mut usage = TokenUsage::default();
+    let mut cached = false;
+    let mut tool_calls = Vec::new();
+
+    while let Some(event) = stream.next().await {
+        match event? {
+            StreamEvent::Delta { text } => {
+                on_delta(&text);
+                content.push_str(&text);
+            }
+            StreamEvent::ToolCall(call) => tool_calls.push(call),
+            StreamEvent::Done {
+                model: done_model,
+                usage: done_usage,
+                cached: done_cached,
+            } => {
+                model = done_model;
+                usage = done_usage;
+                cached = done_cached;
+            }
+        }
+    }
+
+    Ok(CompletionResponse {
+        content,
+        model,
+        usage,
+        tool_calls,
+        cached,
+    })
+}
+
+// ---------------------------------------------------------------------------
+// Body decoding
+// ---------------------------------------------------------------------------
+
+/// Provider-specific decoder that turns body lines into stream events.
+pub(crate) trait StreamDecoder: Send + 'static {
+    /// Handle one complete line of the response body (without the newline).
+    fn decode_line(&mut self, line: &str) -> NakamaResult<Vec<StreamEvent>>;
+
+    /// Called once when the body ends.  Implementations emit the final
+    /// [`StreamEvent::Done`] here.
+    fn finish(&mut self) -> NakamaResult<Vec<StreamEvent>>;
+}
+
+/// A complete Server-Sent Event.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct SseEvent {
+    /// The `event:` field, if one was sent.
+    pub event: Option<String>,
+    /// All `data:` lines of the event joined with `\n`.
+    pub data: String,
+}
+
+/// Accumulates `event:` / `data:` lines into complete [`SseEvent`]s.
+#[derive(Debug, Default)]
+pub(crate) struct SseParser {
+    event: Option<String>,
+    data: Vec<String>,
+}
+
+impl SseParser {
+    /// Feed a single line; returns an event when a blank line terminates one.
+    pub fn push_line(&mut self, line: &str) -> Option<SseEvent> {
+        if line.is_empty() {
+            if self.data.is_empty() {
+                self.event = None;
+                return None;
+            }
+            return Some(SseEvent {
+                event: self.event.take(),
+                data: std::mem::take(&mut self.data).join("\n"),
+            });
+        }
+
+        // Lines starting with a colon are comments (often used as keep-alives).
+        if line.starts_with(':') {
+            return None;
+        }
+
+        let (field, value) = match line.split_once(':') {
+            Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+            None => (line, ""),
+        };
+
+        match field {
+            "event" => self.event = Some(value.to_string()),
+            "data" => self.data.push(value.to_string()),
+            _ => {}
+        }
+
+        None
+    }
+
+    /// Flush an event left over when the body ends without a trailing blank line.
+    pub fn flush(&mut self) -> Option<SseEvent> {
+        self.push_line("")
+    }
+}
+
+type ByteStream = Pin<Box<dyn Stream<Item = reqwest::Result<Vec<u8>>> + Send>>;
+
+struct DecodeState<D> {
+    body: ByteStream,
+    buffer: Vec<u8>,
+    pending: VecDeque<NakamaResult<StreamEvent>>,
+    decoder: D,
+    provider: &'static str,
+    finished: bool,
+}
+
+impl<D: StreamDecoder> DecodeState<D> {
+    /// Decode every complete line currently held in the buffer.
+    fn drain_lines(&mut self) {
+        while let Some(pos) = self.buffer.iter().position(|&b| b == b'\n') {
+            let raw: Vec<u8> = self.buffer.drain(..=pos).collect();
+            let line = String::from_utf8_lossy(&raw);
+            self.decode(line.trim_end_matches(['\n', '\r']));
+            if self.finished {
+                return;
+            }
+        }
+    }
+
+    fn decode(&mut self, line: &str) {
+        match self.decoder.decode_line(line) {
+            Ok(events) => self.pending.extend(events.into_iter().map(Ok)),
+            Err(e) => self.fail(e),
+        }
+    }
+
+    fn finish(&mut self) {
+        if !self.buffer.is_empty() {
+            let rest = std::mem::take(&mut self.buffer);
+            let line = String::from_utf8_lossy(&rest).into_owned();
+            self.decode(line.trim_end_matches('\r'));
+            if self.finished {
+                return;
+            }
+        }
+        match self.decoder.finish() {
+            Ok(events) => self.pending.extend(events.into_iter().map(Ok)),
+            Err(e) => self.pending.push_back(Err(e)),
+        }
+        self.finished = true;
+    }
+
+    fn fail(&mut self, error: NakamaError) {
+        self.pending.push_back(Err(error));
+        self.finished = true;
+    }
+}
+
+/// Turn a successful streaming HTTP response into a [`CompletionStream`].
+pub(crate) fn decode_response<D: StreamDecoder>(
+    response: reqwest::Response,
+    decoder: D,
+    provider: &'static str,
+) -> CompletionStream {
+    let body: ByteStream = Box::pin(response.bytes_stream().map(|r| r.map(|b| b.to_vec())));
+
+    let state = DecodeState {
+        body,
+        buffer: Vec::new(),
+        pending: VecDeque::new(),
+        decoder,
+        provider,
+        finished: false,
+    };
+
+    Box::pin(stream::unfold(state, |mut state| async move {
+        loop {
+            if let Some(item) = state.pending.pop_front() {
+                return Some((item, state));
+            }
+            if state.finished {
+                return None;
+            }
+            match state.body.next().await {
+                Some(Ok(chunk)) => {
+                    state.buffer.extend_from_slice(&chunk);
+                    state.drain_lines();
+                }
+                Some(Err(e)) => {
+                    let provider = state.provider;
+                    state.fail(NakamaError::Network {
+                        message: format!("{provider} stream interrupted: {e}"),
+                        source: Some(Box::new(e)),
+                    });
+                }
+                None => state.finish(),
+            }
+        }
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sse_parser_event_and_data() {
+        let mut parser = SseParser::default();
+        assert!(parser.push_line("event: content_block_delta").is_none());
+        assert!(parser.push_line("data: {\"a\":1}").is_none());
+        let event = parser.push_line("").unwrap();
+        assert_eq!(event.event.as_deref(), Some("content_block_delta"));
+        assert_eq!(event.data, "{\"a\":1}");
+    }
+
+    #[test]
+    fn test_sse_parser_multiline_data_and_comments() {
+        let mut parser = SseParser::default();
+        assert!(parser.push_line(": keep-alive").is_none());
+        parser.push_line("data: line one");
+        parser.push_line("data:line two");
+        let event = parser.flush().unwrap();
+        assert_eq!(event.event, None);
+        assert_eq!(event.data, "line one\nline two");
+        assert!(parser.flush().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_collect_stream_assembles_response() {
+        let events = vec![
+            Ok(StreamEvent::Delta {
+                text: "Hel".to_string(),
+            }),
+            Ok(StreamEvent::Delta {
+                text: "lo".to_string(),
+            }),
+            Ok(StreamEvent::Done {
+                model: "m".to_string(),
+                usage: TokenUsage {
+                    input_tokens: 3,
+                    output_tokens: 2,
+                },
+                cached: false,
+            }),
+        ];
+        let mut seen = Vec::new();
+        let response = collect_stream(Box::pin(stream::iter(events)), |d| seen.push(d.to_string()))
+            .await
+            .unwrap();
+        assert_eq!(seen, vec!["Hel", "lo"]);
+        assert_eq!(response.content, "Hello");
+        assert_eq!(response.model, "m");
+        assert_eq!(response.usage.output_tokens, 2);
+    }
+}
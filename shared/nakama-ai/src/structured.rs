@@ -0,0 +1,157 @@
+//! Typed JSON responses.
+//!
+//! [`complete_json`] sends a request with a [`ResponseFormat`], parses the
+//! reply into a serde type, and if the reply doesn't parse, shows the model
+//! the error and asks once more before giving up.
+
+use crate::provider::AiProvider;
+use crate::stream::collect_stream;
+use crate::types::{CompletionRequest, CompletionResponse, Message, ResponseFormat};
+use nakama_core::error::{NakamaError, NakamaResult};
+use serde::de::DeserializeOwned;
+
+/// A parsed response together with the raw completion it came from.
+#[derive(Debug, Clone)]
+pub struct StructuredResponse<T> {
+    pub value: T,
+    /// The completion that parsed, with token usage summed over every attempt.
+    pub response: CompletionResponse,
+}
+
+/// Complete `request` as JSON conforming to `format` and parse it into `T`.
+///
+/// The response is streamed; `on_delta` is called with each text fragment,
+/// as with [`collect_stream`].  If the reply isn't valid, the model is
+/// re-prompted once with the parse error.
+pub async fn complete_json<T, F>(
+    provider: &dyn AiProvider,
+    mut request: CompletionRequest,
+    format: ResponseFormat,
+    mut on_delta: F,
+) -> NakamaResult<StructuredResponse<T>>
+where
+    T: DeserializeOwned,
+    F: FnMut(&str),
+{
+    request.response_format = Some(format);
+    let mut input_tokens = 0;
+    let mut output_tokens = 0;
+    let mut attempt = 0;
+
+    loop {
+        let stream = provider.complete_stream(request.clone()).await?;
+        let mut response = collect_stream(stream, &mut on_delta).await?;
+        input_tokens += response.usage.input_tokens;
+        output_tokens += response.usage.output_tokens;
+
+        match parse_json::<T>(&response.content) {
+            Ok(value) => {
+                response.usage.input_tokens = input_tokens;
+                response.usage.output_tokens = output_tokens;
+                return Ok(StructuredResponse { value, response });
+            }
+            Err(e) if attempt == 0 => {
+                tracing::warn!(error = %e, "AI response did not match the schema; asking again");
+                attempt += 1;
+                request.messages.push(Message::assistant(response.content));
+                request.messages.push(Message::user(format!(
+                    "That response was not valid: {e}. \
+                     Reply again with only the corrected JSON."
+                )));
+            }
+            Err(e) => {
+                return Err(NakamaError::AiProvider {
+                    message: format!("AI response did not match the expected JSON schema: {e}"),
+                    source: Some(Box::new(e)),
+                });
+            }
+        }
+    }
+}
+
+/// Parse `content` as `T`, tolerating a surrounding code fence or prose.
+pub fn parse_json<T: DeserializeOwned>(content: &str) -> Result<T, serde_json::Error> {
+    let trimmed = content.trim();
+    let err = match serde_json::from_str(trimmed) {
+        Ok(value) => return Ok(value),
+        Err(e) => e,
+    };
+
+    // Fall back to the outermost object or array in the text.
+    let start = trimmed.find(['{', '[']);
+    let end = trimmed.rfind(['}', ']']);
+    match (start, end) {
+        (Some(start), Some(end)) if start < end => {
+            serde_json::from_str(&trimmed[start..=end]).map_err(|_| err)
+        }
+        _ => Err(err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::TokenUsage;
+    use async_trait::async_trait;
+    use serde::Deserialize;
+    use std::sync::Mutex;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Verdict {
+        ok: bool,
+    }
+
+    /// Answers with each canned reply in turn.
+    struct ScriptedModel {
+        replies: Mutex<Vec<&'static str>>,
+    }
+
+    #[async_trait]
+    impl AiProvider for ScriptedModel {
+        async fn complete(&self, request: CompletionRequest) -> NakamaResult<CompletionResponse> {
+            assert!(request.response_format.is_some());
+            Ok(CompletionResponse {
+                content: self.replies.lock().unwrap().remove(0).to_string(),
+                usage: TokenUsage {
+                    input_tokens: 5,
+                    output_tokens: 1,
+                },
+                ..Default::default()
+            })
+        }
+
+        fn provider_name(&self) -> &str {
+            "mock"
+        }
+    }
+
+    fn format() -> ResponseFormat {
+        ResponseFormat::new("verdict", serde_json::json!({ "type": "object" }))
+    }
+
+    #[test]
+    fn test_parse_json_strips_fences() {
+        let fenced = "```json\n{\"ok\": true}\n```";
+        assert_eq!(parse_json::<Verdict>(fenced).unwrap(), Verdict { ok: true });
+        assert!(parse_json::<Verdict>("no json here").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_reprompts_once_on_invalid_output() {
+        let model = ScriptedModel {
+            replies: Mutex::new(vec!["{\"ok\": \"yes\"}", "{\"ok\": false}"]),
+        };
+        let result = complete_json::<Verdict, _>(&model, CompletionRequest::default(), format(), |_| {})
+            .await
+            .unwrap();
+        assert_eq!(result.value, Verdict { ok: false });
+        assert_eq!(result.response.usage.input_tokens, 10);
+
+        let model = ScriptedModel {
+            replies: Mutex::new(vec!["nope", "still nope"]),
+        };
+        let result =
+            complete_json::<Verdict, _>(&model, CompletionRequest::default(), format(), |_| {}).await;
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,115 @@
+//! Minimal local HTTP server used by provider tests.
+//!
+//! Each call to [`serve`] binds an ephemeral port on `127.0.0.1`, answers the
+//! given responses in order (one per connection), and returns the base URL to
+//! point a provider at along with a handle that yields the raw requests seen.
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tokio::task::JoinHandle;
+
+/// A canned HTTP response.
+pub struct MockResponse {
+    pub status: u16,
+    pub content_type: &'static str,
+    pub headers: Vec<(&'static str, String)>,
+    pub body: String,
+}
+
+impl MockResponse {
+    /// A `200 OK` Server-Sent Events response.
+    pub fn sse(body: impl Into<String>) -> Self {
+        Self::with_status(200, "text/event-stream", body)
+    }
+
+    /// A `200 OK` newline-delimited JSON response.
+    pub fn ndjson(body: impl Into<String>) -> Self {
+        Self::with_status(200, "application/x-ndjson", body)
+    }
+
+    /// A `200 OK` JSON response.
+    pub fn json(body: impl Into<String>) -> Self {
+        Self::with_status(200, "application/json", body)
+    }
+
+    /// A response with an explicit status code.
+    pub fn with_status(status: u16, content_type: &'static str, body: impl Into<String>) -> Self {
+        Self {
+            status,
+            content_type,
+            headers: Vec::new(),
+            body: body.into(),
+        }
+    }
+
+    /// Add an extra response header.
+    pub fn header(mut self, name: &'static str, value: impl Into<String>) -> Self {
+        self.headers.push((name, value.into()));
+        self
+    }
+}
+
+/// Serve `responses` in order, one per accepted connection.
+///
+/// Returns the base URL (`http://127.0.0.1:<port>`) and a handle resolving to
+/// the raw text of every request received.
+pub async fn serve(responses: Vec<MockResponse>) -> (String, JoinHandle<Vec<String>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let handle = tokio::spawn(async move {
+        let mut requests = Vec::new();
+        for response in responses {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            requests.push(read_request(&mut socket).await);
+
+            let mut head = format!(
+                "HTTP/1.1 {} MOCK\r\ncontent-type: {}\r\ncontent-length: {}\r\nconnection: close\r\n",
+                response.status,
+                response.content_type,
+                response.body.len()
+            );
+            for (name, value) in &response.headers {
+                head.push_str(&format!("{name}: {value}\r\n"));
+            }
+            head.push_str("\r\n");
+
+            socket.write_all(head.as_bytes()).await.unwrap();
+            socket.write_all(response.body.as_bytes()).await.unwrap();
+            socket.shutdown().await.ok();
+        }
+        requests
+    });
+
+    (format!("http://{addr}"), handle)
+}
+
+/// Read one HTTP request (headers plus `content-length` body) from a socket.
+async fn read_request(socket: &mut tokio::net::TcpStream) -> String {
+    let mut data = Vec::new();
+    let mut buf = [0u8; 4096];
+    loop {
+        let n = socket.read(&mut buf).await.unwrap();
+        if n == 0 {
+            break;
+        }
+        data.extend_from_slice(&buf[..n]);
+
+        let text = String::from_utf8_lossy(&data);
+        if let Some(header_end) = text.find("\r\n\r\n") {
+            let content_length = text[..header_end]
+                .lines()
+                .find_map(|l| {
+                    let (name, value) = l.split_once(':')?;
+                    name.eq_ignore_ascii_case("content-length")
+                        .then(|| value.trim().parse::<usize>().ok())
+                        .flatten()
+                })
+                .unwrap_or(0);
+            if data.len() >= header_end + 4 + content_length {
+                break;
+            }
+        }
+    }
+    String::from_utf8_lossy(&data).into_owned()
+}
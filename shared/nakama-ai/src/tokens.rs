@@ -0,0 +1,346 @@
+//! Token counting and model context windows.
+//!
+//! [`counter_for`] returns the most accurate [`TokenCounter`] available for a
+//! model: OpenAI models are counted exactly with their tiktoken encoding, and
+//! other providers use a [`Heuristic`] scaled to their tokenizers.
+//! [`ContextWindows`] maps model ids to context sizes the same way
+//! [`PriceTable`](crate::PriceTable) maps them to prices, and
+//! [`ContextBudget`] combines the two so tools can fit a prompt to a model.
+
+use nakama_core::config::AiConfig;
+use nakama_core::diff::{compress_diff_to, truncate_diff_to};
+use nakama_core::tokens::{fit_head, TokenCounter};
+use nakama_core::types::Provider;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tiktoken_rs::CoreBPE;
+
+/// Estimates token counts from the shape of the text.
+///
+/// Runs of letters and digits cost about one token per few characters,
+/// punctuation and line breaks about one each, and non-ASCII characters more
+/// than ASCII.  The constants are fitted to OpenAI's `o200k_base` encoding on
+/// English prose and source code, then scaled for each provider's tokenizer.
+#[derive(Debug, Clone, Copy)]
+pub struct Heuristic {
+    scale: f64,
+}
+
+/// Average characters per token within a word.
+const WORD_CHARS_PER_TOKEN: f64 = 5.5;
+/// Tokens per ASCII punctuation character.
+const PUNCT_TOKENS: f64 = 0.45;
+/// Tokens per non-ASCII character.
+const NON_ASCII_TOKENS: f64 = 0.7;
+
+impl Heuristic {
+    /// The heuristic for a provider's tokenizer.
+    pub fn for_provider(provider: Provider) -> Self {
+        let scale = match provider {
+            // Claude's tokenizer produces noticeably more tokens than o200k.
+            Provider::Anthropic => 1.25,
+            Provider::OpenAI | Provider::Google => 1.0,
+            // Local and self-hosted models vary; most are a little less
+            // efficient.
+            Provider::Ollama | Provider::OpenAICompatible => 1.15,
+        };
+        Self { scale }
+    }
+}
+
+impl TokenCounter for Heuristic {
+    fn count(&self, text: &str) -> usize {
+        let mut tokens = 0.0;
+        let mut word = 0usize;
+        let mut spaces = 0usize;
+
+        for c in text.chars() {
+            if c.is_ascii_alphanumeric() {
+                word += 1;
+                continue;
+            }
+            if word > 0 {
+                tokens += (word as f64 / WORD_CHARS_PER_TOKEN).max(1.0);
+                word = 0;
+            }
+            if c == ' ' || c == '\t' {
+                spaces += 1;
+                continue;
+            }
+            // A single space joins the next word's token; longer runs
+            // (indentation) take a token of their own.
+            if spaces > 1 {
+                tokens += 1.0;
+            }
+            spaces = 0;
+
+            if c == '\n' || c == '\r' {
+                tokens += 1.0;
+            } else if c.is_ascii() {
+                tokens += PUNCT_TOKENS;
+            } else {
+                tokens += NON_ASCII_TOKENS;
+            }
+        }
+        if word > 0 {
+            tokens += (word as f64 / WORD_CHARS_PER_TOKEN).max(1.0);
+        }
+
+        (tokens * self.scale).ceil() as usize
+    }
+}
+
+/// Exact counts with a tiktoken encoding.
+pub struct Tiktoken {
+    bpe: &'static CoreBPE,
+}
+
+impl Tiktoken {
+    /// The encoding used by an OpenAI model, or `None` for unknown models.
+    pub fn for_model(model: &str) -> Option<Self> {
+        use tiktoken_rs::tokenizer::{get_tokenizer, Tokenizer};
+
+        let bpe = match get_tokenizer(model)? {
+            Tokenizer::O200kBase => tiktoken_rs::o200k_base_singleton(),
+            Tokenizer::Cl100kBase => tiktoken_rs::cl100k_base_singleton(),
+            Tokenizer::P50kBase => tiktoken_rs::p50k_base_singleton(),
+            Tokenizer::R50kBase | Tokenizer::Gpt2 => tiktoken_rs::r50k_base_singleton(),
+            Tokenizer::P50kEdit => tiktoken_rs::p50k_edit_singleton(),
+        };
+        Some(Self { bpe })
+    }
+}
+
+impl TokenCounter for Tiktoken {
+    fn count(&self, text: &str) -> usize {
+        self.bpe.encode_ordinary(text).len()
+    }
+}
+
+/// The best available counter for `model` from `provider`.
+pub fn counter_for(provider: Provider, model: &str) -> Arc<dyn TokenCounter> {
+    // Gateways often serve OpenAI models under their usual ids.
+    if matches!(provider, Provider::OpenAI | Provider::OpenAICompatible) {
+        if let Some(tiktoken) = Tiktoken::for_model(model) {
+            return Arc::new(tiktoken);
+        }
+    }
+    Arc::new(Heuristic::for_provider(provider))
+}
+
+/// Number of tokens `text` uses with `model` from `provider`.
+pub fn count_tokens(provider: Provider, model: &str, text: &str) -> usize {
+    counter_for(provider, model).count(text)
+}
+
+/// Built-in context windows in tokens, by model id prefix.
+const DEFAULT_CONTEXT_WINDOWS: &[(&str, usize)] = &[
+    // Anthropic
+    ("claude", 200_000),
+    // OpenAI
+    ("gpt-4.1", 1_047_576),
+    ("gpt-4o", 128_000),
+    ("gpt-4-turbo", 128_000),
+    ("gpt-4", 8_192),
+    ("gpt-3.5-turbo", 16_385),
+    ("o1", 200_000),
+    ("o3", 200_000),
+    ("o4-mini", 200_000),
+    // Google
+    ("gemini-1.5-pro", 2_097_152),
+    ("gemini", 1_048_576),
+];
+
+/// Context window assumed for models not in the table.  Ollama's default
+/// `num_ctx` is this small, so it is also the right guess for local models.
+const FALLBACK_CONTEXT_WINDOW: usize = 8_192;
+
+/// Resolves model ids to context window sizes.
+#[derive(Debug, Clone)]
+pub struct ContextWindows {
+    windows: HashMap<String, usize>,
+}
+
+impl ContextWindows {
+    /// Build the table from the built-in defaults plus `[ai.context_windows]`
+    /// overrides.
+    pub fn from_config(config: &AiConfig) -> Self {
+        let mut table = Self::default();
+        for (model, window) in &config.context_windows {
+            table.windows.insert(model.clone(), *window);
+        }
+        table
+    }
+
+    /// Look up the context window for a model, by exact id or longest prefix.
+    pub fn lookup(&self, model: &str) -> Option<usize> {
+        if let Some(window) = self.windows.get(model) {
+            return Some(*window);
+        }
+        self.windows
+            .iter()
+            .filter(|(prefix, _)| model.starts_with(prefix.as_str()))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(_, window)| *window)
+    }
+
+    /// The context window for a model, falling back to a conservative size
+    /// for unknown models.
+    pub fn window(&self, model: &str) -> usize {
+        self.lookup(model).unwrap_or(FALLBACK_CONTEXT_WINDOW)
+    }
+}
+
+impl Default for ContextWindows {
+    fn default() -> Self {
+        let windows = DEFAULT_CONTEXT_WINDOWS
+            .iter()
+            .map(|(model, window)| (model.to_string(), *window))
+            .collect();
+        Self { windows }
+    }
+}
+
+/// How many prompt tokens are left for a request to one model.
+///
+/// Starts from the model's context window minus the tokens reserved for the
+/// response; each [`ContextBudget::reserve`] deducts a fixed part of the
+/// prompt, and the `fit_*` methods trim variable content to what remains.
+#[derive(Clone)]
+pub struct ContextBudget {
+    counter: Arc<dyn TokenCounter>,
+    available: usize,
+}
+
+/// Headroom left for message framing and estimation error.
+const SAFETY_MARGIN: f64 = 0.05;
+
+impl ContextBudget {
+    /// The budget for `model` from `provider`, keeping `max_output_tokens`
+    /// free for the response.
+    pub fn new(config: &AiConfig, provider: Provider, model: &str, max_output_tokens: u32) -> Self {
+        Self::with_windows(&ContextWindows::from_config(config), provider, model, max_output_tokens)
+    }
+
+    /// Like [`ContextBudget::new`], with an already-built window table.
+    pub fn with_windows(
+        windows: &ContextWindows,
+        provider: Provider,
+        model: &str,
+        max_output_tokens: u32,
+    ) -> Self {
+        let window = windows.window(model);
+        let usable = window - (window as f64 * SAFETY_MARGIN) as usize;
+        Self {
+            counter: counter_for(provider, model),
+            available: usable.saturating_sub(max_output_tokens as usize),
+        }
+    }
+
+    /// Limit the budget to at most `tokens`, for callers that want to bound
+    /// cost as well as fit the window.
+    pub fn at_most(mut self, tokens: usize) -> Self {
+        self.available = self.available.min(tokens);
+        self
+    }
+
+    /// Tokens still available for the prompt.
+    pub fn available(&self) -> usize {
+        self.available
+    }
+
+    /// The counter for this budget's model.
+    pub fn counter(&self) -> &dyn TokenCounter {
+        self.counter.as_ref()
+    }
+
+    /// Number of tokens `text` uses with this budget's model.
+    pub fn count(&self, text: &str) -> usize {
+        self.counter.count(text)
+    }
+
+    /// Deduct the fixed parts of a prompt (system prompt, instructions,
+    /// question) from the budget.
+    pub fn reserve(&mut self, text: &str) {
+        self.available = self.available.saturating_sub(self.counter.count(text));
+    }
+
+    /// Compress `diff` to fit the remaining budget (see
+    /// [`nakama_core::diff::compress_diff`]).
+    pub fn fit_diff(&self, diff: &str) -> String {
+        let compressed = compress_diff_to(diff, self.available, self.counter());
+        // Compression keeps whole files, so a single huge file can still
+        // overflow; fall back to a plain cut.
+        if self.count(&compressed) > self.available {
+            return truncate_diff_to(&compressed, self.available, self.counter());
+        }
+        compressed
+    }
+
+    /// Sandwich-truncate `text` to the remaining budget, keeping its start
+    /// and end.
+    pub fn fit_text(&self, text: &str) -> String {
+        truncate_diff_to(text, self.available, self.counter())
+    }
+
+    /// The longest prefix of `text` within the remaining budget.
+    pub fn fit_head<'a>(&self, text: &'a str) -> &'a str {
+        fit_head(text, self.available, self.counter())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nakama_core::config::Config;
+
+    #[test]
+    fn test_heuristic_tracks_tiktoken() {
+        let source = include_str!("openai.rs");
+        let exact = Tiktoken::for_model("gpt-4.1").unwrap().count(source);
+        let estimate = Heuristic::for_provider(Provider::OpenAI).count(source);
+        assert!(estimate >= exact * 95 / 100, "{estimate} vs {exact}");
+        assert!(estimate <= exact * 130 / 100, "{estimate} vs {exact}");
+
+        let claude = Heuristic::for_provider(Provider::Anthropic).count(source);
+        assert!(claude > estimate);
+    }
+
+    #[test]
+    fn test_context_window_lookup() {
+        let mut config = Config::default();
+        config.ai.context_windows.insert("llama3.1".to_string(), 131_072);
+        let windows = ContextWindows::from_config(&config.ai);
+
+        assert_eq!(windows.window("claude-sonnet-4-6"), 200_000);
+        assert_eq!(windows.window("gpt-4.1-mini"), 1_047_576);
+        assert_eq!(windows.window("gpt-4"), 8_192);
+        assert_eq!(windows.window("llama3.1:8b"), 131_072);
+        assert_eq!(windows.window("qwen2.5-coder"), FALLBACK_CONTEXT_WINDOW);
+    }
+
+    #[test]
+    fn test_budget_fits_diff_to_small_model() {
+        let config = Config::default();
+        let mut budget = ContextBudget::new(&config.ai, Provider::Ollama, "llama3.2", 2048);
+        budget.reserve("Review this diff.");
+        assert!(budget.available() < 8_192 - 2048);
+
+        let diff: String = (0..400)
+            .map(|i| {
+                format!(
+                    "diff --git a/src/f{i}.rs b/src/f{i}.rs\n--- a/src/f{i}.rs\n+++ b/src/f{i}.rs\n\
+                     @@ -1 +1 @@\n-let x = {i};\n+let x = {i} + 1; // café\n"
+                )
+            })
+            .collect();
+        let fitted = budget.fit_diff(&diff);
+        assert!(budget.count(&fitted) <= budget.available() + 32);
+        assert!(fitted.contains("src/f0.rs"));
+        assert!(fitted.contains("src/f399.rs"));
+
+        let capped =
+            ContextBudget::new(&config.ai, Provider::Anthropic, "claude-sonnet-4-6", 4096).at_most(500);
+        assert_eq!(capped.available(), 500);
+    }
+}
@@ -0,0 +1,207 @@
+//! Tool calling: registering Rust handlers and running the call loop.
+//!
+//! A [`ToolRegistry`] pairs each [`ToolDefinition`] with a handler.
+//! [`run_tool_loop`] offers the tools to the model, runs every call it makes,
+//! sends the results back, and repeats until the model answers without
+//! calling a tool.
+
+use crate::provider::AiProvider;
+use crate::types::{CompletionRequest, CompletionResponse, Message, ToolCall, ToolDefinition};
+use nakama_core::error::{NakamaError, NakamaResult};
+
+/// A tool implementation: takes the call's arguments, returns the text sent
+/// back to the model.
+pub type ToolHandler = Box<dyn Fn(&serde_json::Value) -> NakamaResult<String> + Send + Sync>;
+
+/// The tools available to a conversation.
+#[derive(Default)]
+pub struct ToolRegistry {
+    tools: Vec<(ToolDefinition, ToolHandler)>,
+}
+
+impl ToolRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a tool.  `input_schema` is a JSON schema for its arguments.
+    pub fn register(
+        mut self,
+        name: &str,
+        description: &str,
+        input_schema: serde_json::Value,
+        handler: impl Fn(&serde_json::Value) -> NakamaResult<String> + Send + Sync + 'static,
+    ) -> Self {
+        let definition = ToolDefinition {
+            name: name.to_string(),
+            description: description.to_string(),
+            input_schema,
+        };
+        self.tools.push((definition, Box::new(handler)));
+        self
+    }
+
+    /// Definitions of every registered tool, for [`CompletionRequest::tools`].
+    pub fn definitions(&self) -> Vec<ToolDefinition> {
+        self.tools.iter().map(|(def, _)| def.clone()).collect()
+    }
+
+    /// Run a tool call and return the text to send back to the model.
+    ///
+    /// Unknown tools and handler errors are reported to the model as text
+    /// rather than failing the conversation, so it can correct itself.
+    pub fn dispatch(&self, call: &ToolCall) -> String {
+        let Some((_, handler)) = self.tools.iter().find(|(def, _)| def.name == call.name) else {
+            tracing::warn!(tool = %call.name, "Model called an unknown tool");
+            return format!("Error: unknown tool '{}'", call.name);
+        };
+
+        tracing::debug!(tool = %call.name, arguments = %call.arguments, "Running tool call");
+        match handler(&call.arguments) {
+            Ok(output) => output,
+            Err(e) => {
+                tracing::warn!(tool = %call.name, error = %e, "Tool call failed");
+                format!("Error: {e}")
+            }
+        }
+    }
+}
+
+/// Complete `request` with `tools` available, running tool calls until the
+/// model produces a final answer.
+///
+/// Gives up with an error after `max_rounds` rounds of tool calls.  The
+/// returned response carries the token usage summed over every round.
+pub async fn run_tool_loop(
+    provider: &dyn AiProvider,
+    mut request: CompletionRequest,
+    tools: &ToolRegistry,
+    max_rounds: usize,
+) -> NakamaResult<CompletionResponse> {
+    request.tools = tools.definitions();
+    let mut input_tokens = 0;
+    let mut output_tokens = 0;
+
+    for _ in 0..=max_rounds {
+        let mut response = provider.complete(request.clone()).await?;
+        input_tokens += response.usage.input_tokens;
+        output_tokens += response.usage.output_tokens;
+
+        if response.tool_calls.is_empty() {
+            response.usage.input_tokens = input_tokens;
+            response.usage.output_tokens = output_tokens;
+            return Ok(response);
+        }
+
+        let results: Vec<Message> = response
+            .tool_calls
+            .iter()
+            .map(|call| Message::tool_result(&call.id, tools.dispatch(call)))
+            .collect();
+        request.messages.push(Message::assistant_tool_calls(
+            response.content,
+            response.tool_calls,
+        ));
+        request.messages.extend(results);
+    }
+
+    Err(NakamaError::AiProvider {
+        message: format!("Model was still calling tools after {max_rounds} rounds"),
+        source: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Role, TokenUsage};
+    use async_trait::async_trait;
+    use std::sync::Mutex;
+
+    /// Calls `add` once, then answers with the last tool result it saw.
+    struct CalculatorModel {
+        requests: Mutex<Vec<CompletionRequest>>,
+    }
+
+    #[async_trait]
+    impl AiProvider for CalculatorModel {
+        async fn complete(&self, request: CompletionRequest) -> NakamaResult<CompletionResponse> {
+            let last = request.messages.last().cloned().unwrap();
+            self.requests.lock().unwrap().push(request);
+
+            let usage = TokenUsage {
+                input_tokens: 10,
+                output_tokens: 2,
+            };
+            if last.role == Role::Tool {
+                return Ok(CompletionResponse {
+                    content: format!("The answer is {}", last.content),
+                    usage,
+                    ..Default::default()
+                });
+            }
+            Ok(CompletionResponse {
+                usage,
+                tool_calls: vec![ToolCall {
+                    id: "call_1".to_string(),
+                    name: "add".to_string(),
+                    arguments: serde_json::json!({ "a": 2, "b": 3 }),
+                }],
+                ..Default::default()
+            })
+        }
+
+        fn provider_name(&self) -> &str {
+            "mock"
+        }
+    }
+
+    fn registry() -> ToolRegistry {
+        ToolRegistry::new().register(
+            "add",
+            "Add two integers",
+            serde_json::json!({
+                "type": "object",
+                "properties": { "a": { "type": "integer" }, "b": { "type": "integer" } },
+                "required": ["a", "b"],
+            }),
+            |args| {
+                let a = args["a"].as_i64().unwrap_or(0);
+                let b = args["b"].as_i64().unwrap_or(0);
+                Ok((a + b).to_string())
+            },
+        )
+    }
+
+    #[tokio::test]
+    async fn test_tool_loop_dispatches_calls() {
+        let model = CalculatorModel {
+            requests: Mutex::new(Vec::new()),
+        };
+        let request = CompletionRequest {
+            messages: vec![Message::user("What is 2 + 3?")],
+            max_tokens: 64,
+            ..Default::default()
+        };
+
+        let response = run_tool_loop(&model, request, &registry(), 4).await.unwrap();
+        assert_eq!(response.content, "The answer is 5");
+        assert_eq!(response.usage.input_tokens, 20);
+
+        let requests = model.requests.lock().unwrap();
+        assert_eq!(requests[0].tools[0].name, "add");
+        let second = &requests[1].messages;
+        assert_eq!(second[1].tool_calls[0].id, "call_1");
+        assert_eq!(second[2].tool_call_id.as_deref(), Some("call_1"));
+    }
+
+    #[test]
+    fn test_dispatch_reports_unknown_tools() {
+        let call = ToolCall {
+            id: "x".to_string(),
+            name: "rm_rf".to_string(),
+            arguments: serde_json::Value::Null,
+        };
+        assert!(registry().dispatch(&call).contains("unknown tool"));
+    }
+}
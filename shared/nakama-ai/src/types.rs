@@ -1,9 +1,13 @@
 //! Common types shared by all AI provider implementations.
 
+use crate::attachment::Attachment;
 use serde::{Deserialize, Serialize};
 
 /// A completion request sent to any AI provider.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+///
+/// Optional capabilities default to off, so requests are usually built with
+/// `..Default::default()`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct CompletionRequest {
     /// System-level prompt that sets context/behaviour.
     pub system_prompt: String,
@@ -19,6 +23,56 @@ pub struct CompletionRequest {
 
     /// Sampling temperature (0.0 = deterministic, 1.0 = creative).
     pub temperature: f32,
+
+    /// Tools the model may call instead of (or before) answering.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tools: Vec<ToolDefinition>,
+
+    /// Ask for a JSON response matching a schema instead of free text.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response_format: Option<ResponseFormat>,
+}
+
+/// A JSON schema the response must conform to.
+///
+/// Providers with a native JSON mode are given the schema directly; for the
+/// others it is spelled out in the system prompt.  Use
+/// [`crate::structured::complete_json`] to validate the result.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResponseFormat {
+    /// Short identifier for the schema (e.g. `"review_findings"`).
+    pub name: String,
+
+    /// JSON schema of the expected response.
+    pub schema: serde_json::Value,
+}
+
+impl ResponseFormat {
+    pub fn new(name: impl Into<String>, schema: serde_json::Value) -> Self {
+        Self {
+            name: name.into(),
+            schema,
+        }
+    }
+
+    /// Instructions appended to the system prompt for providers without a
+    /// native schema mode.
+    pub fn instructions(&self) -> String {
+        format!(
+            "Respond with a single JSON value and nothing else (no prose, no code fences). \
+             It must conform to this JSON schema:\n{}",
+            self.schema
+        )
+    }
+
+    /// `system_prompt` followed by [`Self::instructions`].
+    pub(crate) fn with_instructions(&self, system_prompt: &str) -> String {
+        if system_prompt.is_empty() {
+            self.instructions()
+        } else {
+            format!("{system_prompt}\n\n{}", self.instructions())
+        }
+    }
 }
 
 /// A single message in a conversation.
@@ -29,6 +83,18 @@ pub struct Message {
 
     /// The textual content of the message.
     pub content: String,
+
+    /// Files (images, PDFs, text) sent along with the text.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attachments: Vec<Attachment>,
+
+    /// Tool calls requested by the assistant in this turn.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tool_calls: Vec<ToolCall>,
+
+    /// For [`Role::Tool`] messages, the id of the call this result answers.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_call_id: Option<String>,
 }
 
 /// Participant role in a conversation.
@@ -41,10 +107,38 @@ pub enum Role {
     User,
     /// The AI assistant.
     Assistant,
+    /// The result of a tool call, sent back to the model.
+    Tool,
+}
+
+/// A tool the model may call, described by a JSON schema for its input.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolDefinition {
+    /// Unique tool name (e.g. `"read_file"`).
+    pub name: String,
+
+    /// What the tool does and when to use it.
+    pub description: String,
+
+    /// JSON schema of the tool's arguments (an `"object"` schema).
+    pub input_schema: serde_json::Value,
+}
+
+/// A tool invocation requested by the model.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolCall {
+    /// Provider-assigned id, echoed back in the matching tool result.
+    pub id: String,
+
+    /// Name of the tool to call.
+    pub name: String,
+
+    /// Arguments, matching the tool's input schema.
+    pub arguments: serde_json::Value,
 }
 
 /// The response returned by an AI provider after a completion request.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct CompletionResponse {
     /// The generated text content.
     pub content: String,
@@ -55,6 +149,16 @@ pub struct CompletionResponse {
 
     /// Token usage statistics.
     pub usage: TokenUsage,
+
+    /// Tools the model asked to call.  When non-empty, `content` may be empty
+    /// and the caller is expected to reply with the tool results.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tool_calls: Vec<ToolCall>,
+
+    /// Whether the response was served from the local response cache rather
+    /// than the provider.
+    #[serde(default)]
+    pub cached: bool,
 }
 
 /// Token usage statistics for a single completion.
@@ -67,12 +171,35 @@ pub struct TokenUsage {
     pub output_tokens: u32,
 }
 
+/// A single event emitted by a streaming completion.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StreamEvent {
+    /// An incremental chunk of generated text.
+    Delta { text: String },
+
+    /// A complete tool call requested by the model.
+    ToolCall(ToolCall),
+
+    /// The final event of a stream, carrying the serving model and the total
+    /// token usage for the request.
+    Done {
+        model: String,
+        usage: TokenUsage,
+        #[serde(default)]
+        cached: bool,
+    },
+}
+
 impl Message {
     /// Convenience constructor.
     pub fn new(role: Role, content: impl Into<String>) -> Self {
         Self {
             role,
             content: content.into(),
+            attachments: Vec::new(),
+            tool_calls: Vec::new(),
+            tool_call_id: None,
         }
     }
 
@@ -90,6 +217,28 @@ impl Message {
     pub fn system(content: impl Into<String>) -> Self {
         Self::new(Role::System, content)
     }
+
+    /// Attach files to this message.
+    pub fn with_attachments(mut self, attachments: impl IntoIterator<Item = Attachment>) -> Self {
+        self.attachments.extend(attachments);
+        self
+    }
+
+    /// Create an assistant message that requests tool calls.
+    pub fn assistant_tool_calls(content: impl Into<String>, tool_calls: Vec<ToolCall>) -> Self {
+        Self {
+            tool_calls,
+            ..Self::assistant(content)
+        }
+    }
+
+    /// Create a message carrying the result of the tool call `call_id`.
+    pub fn tool_result(call_id: impl Into<String>, content: impl Into<String>) -> Self {
+        Self {
+            tool_call_id: Some(call_id.into()),
+            ..Self::new(Role::Tool, content)
+        }
+    }
 }
 
 #[cfg(test)]
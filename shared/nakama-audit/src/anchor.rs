@@ -0,0 +1,506 @@
+//! Externally signed anchors over the head of the hash chain.
+//!
+//! The chain alone only proves internal consistency: anyone who can rewrite
+//! `audit.db` can recompute every checksum.  An [`Anchor`] records the head
+//! entry and its checksum at a point in time, signed with an Ed25519 key held
+//! in the credential vault rather than next to the database.  Anchors are
+//! appended to `~/.nakama/audit/anchors.jsonl`, one JSON object per line.
+//!
+//! [`AuditLog::verify_anchors`] checks each anchor against the database: a
+//! correctly signed anchor whose head entry is missing or has a different
+//! checksum means history up to that entry was rewritten.  Entries after the
+//! last trusted anchor are covered by the chain only.
+//!
+//! This crate does not read the vault itself (the vault depends on it); the
+//! key is loaded by `nakama_vault::audit_anchor` and passed in.
+
+use crate::retention::{self, hex_decode, hex_encode};
+use crate::store::AuditLog;
+use chrono::{DateTime, Duration, Utc};
+use ed25519_dalek::{Signature, Signer, Verifier};
+use nakama_core::error::{NakamaError, NakamaResult};
+use nakama_core::permissions;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use rusqlite::{params, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+
+pub use ed25519_dalek::{SigningKey, VerifyingKey};
+
+/// Anchor file, next to the database.
+pub const ANCHOR_FILE: &str = "anchors.jsonl";
+
+/// Format version written to new anchors.
+pub const ANCHOR_VERSION: u32 = 1;
+
+/// A signed statement that the chain had `head_checksum` at `head_id`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Anchor {
+    pub version: u32,
+    pub created_at: DateTime<Utc>,
+    /// Entries in the chain (from its start or retention checkpoint).
+    pub entries: u64,
+    /// Id of the newest entry at anchoring time.
+    pub head_id: String,
+    /// That entry's chain checksum.
+    pub head_checksum: String,
+    /// Hex-encoded Ed25519 public key of the signer.
+    pub public_key: String,
+    /// Hex-encoded Ed25519 signature over [`Anchor::signed_payload`].
+    pub signature: String,
+}
+
+impl Anchor {
+    /// Sign the given chain head.
+    pub fn sign(
+        key: &SigningKey,
+        head_id: &str,
+        head_checksum: &str,
+        entries: u64,
+        now: DateTime<Utc>,
+    ) -> Self {
+        let mut anchor = Self {
+            version: ANCHOR_VERSION,
+            created_at: now,
+            entries,
+            head_id: head_id.to_string(),
+            head_checksum: head_checksum.to_string(),
+            public_key: hex_encode(key.verifying_key().as_bytes()),
+            signature: String::new(),
+        };
+        anchor.signature = hex_encode(&key.sign(anchor.signed_payload().as_bytes()).to_bytes());
+        anchor
+    }
+
+    /// The bytes covered by the signature.
+    pub fn signed_payload(&self) -> String {
+        format!(
+            "nakama-audit-anchor|v{}|{}|{}|{}|{}",
+            self.version,
+            self.created_at.to_rfc3339(),
+            self.entries,
+            self.head_id,
+            self.head_checksum
+        )
+    }
+
+    /// Whether this anchor was signed by `key`.
+    pub fn is_signed_by(&self, key: &VerifyingKey) -> bool {
+        let Some(signature) = hex_decode(&self.signature)
+            .and_then(|bytes| <[u8; 64]>::try_from(bytes).ok())
+            .map(|bytes| Signature::from_bytes(&bytes))
+        else {
+            return false;
+        };
+        self.public_key == hex_encode(key.as_bytes())
+            && key
+                .verify(self.signed_payload().as_bytes(), &signature)
+                .is_ok()
+    }
+}
+
+/// Generate a new anchor signing key.
+pub fn generate_key() -> SigningKey {
+    let mut seed = [0u8; 32];
+    OsRng.fill_bytes(&mut seed);
+    SigningKey::from_bytes(&seed)
+}
+
+/// Hex-encode a signing key's seed for storage in the vault.
+pub fn key_to_hex(key: &SigningKey) -> String {
+    hex_encode(key.as_bytes())
+}
+
+/// Parse a signing key stored with [`key_to_hex`].
+pub fn key_from_hex(hex: &str) -> NakamaResult<SigningKey> {
+    hex_decode(hex.trim())
+        .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+        .map(|seed| SigningKey::from_bytes(&seed))
+        .ok_or_else(|| NakamaError::Audit {
+            message: "Audit anchor key is not a 32-byte hex seed".to_string(),
+        })
+}
+
+/// The append-only file of anchors.
+pub struct AnchorLog {
+    path: PathBuf,
+}
+
+impl AnchorLog {
+    /// The anchor log at `~/.nakama/audit/anchors.jsonl`.
+    pub fn open() -> NakamaResult<Self> {
+        Ok(Self::open_at(
+            &nakama_core::paths::audit_dir()?.join(ANCHOR_FILE),
+        ))
+    }
+
+    /// An anchor log at a custom path (useful for testing).
+    pub fn open_at(path: &Path) -> Self {
+        Self {
+            path: path.to_path_buf(),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Append `anchor` as one line.  Existing lines are never rewritten.
+    pub fn append(&self, anchor: &Anchor) -> NakamaResult<()> {
+        let io_err = |e: std::io::Error| NakamaError::Audit {
+            message: format!(
+                "Failed to write audit anchor to {}: {e}",
+                self.path.display()
+            ),
+        };
+        let line = serde_json::to_string(anchor).map_err(|e| NakamaError::Audit {
+            message: format!("Failed to serialize audit anchor: {e}"),
+        })?;
+
+        let created = !self.path.exists();
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(io_err)?;
+        if created {
+            permissions::set_file_permissions(&self.path)?;
+        }
+        file.write_all(format!("{line}\n").as_bytes())
+            .and_then(|()| file.sync_data())
+            .map_err(io_err)
+    }
+
+    /// Every anchor, oldest first.  A missing file has none.
+    pub fn read(&self) -> NakamaResult<Vec<Anchor>> {
+        let file = match std::fs::File::open(&self.path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => {
+                return Err(NakamaError::Audit {
+                    message: format!("Failed to read {}: {e}", self.path.display()),
+                })
+            }
+        };
+
+        let mut anchors = Vec::new();
+        for (number, line) in std::io::BufReader::new(file).lines().enumerate() {
+            let line = line.map_err(|e| NakamaError::Audit {
+                message: format!("Failed to read {}: {e}", self.path.display()),
+            })?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            anchors.push(serde_json::from_str(&line).map_err(|e| NakamaError::Audit {
+                message: format!(
+                    "{} line {} is not a valid anchor: {e}",
+                    self.path.display(),
+                    number + 1
+                ),
+            })?);
+        }
+        Ok(anchors)
+    }
+
+    /// Whether the newest anchor is at least `interval_hours` old (or there
+    /// is none).
+    pub fn is_due(&self, interval_hours: u32, now: DateTime<Utc>) -> NakamaResult<bool> {
+        Ok(match self.read()?.last() {
+            Some(last) => now - last.created_at >= Duration::hours(i64::from(interval_hours)),
+            None => true,
+        })
+    }
+}
+
+/// How an anchor relates to the current database.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AnchorStatus {
+    /// Signed by the vault key; the head entry is present and unchanged.
+    Trusted,
+    /// Signed by the vault key; the head entry has since been pruned and
+    /// its archived copy is unchanged.
+    Archived,
+    /// Signed by the vault key, but the head entry is missing or has a
+    /// different checksum: history was rewritten.
+    Diverged,
+    /// Not signed by the vault key (written by another key, or forged).
+    Untrusted,
+}
+
+/// One anchor and its status.
+#[derive(Debug, Clone, Serialize)]
+pub struct AnchorCheck {
+    pub anchor: Anchor,
+    pub status: AnchorStatus,
+}
+
+/// Result of [`AuditLog::verify_anchors`].
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct AnchorReport {
+    /// Every anchor, oldest first.
+    pub checks: Vec<AnchorCheck>,
+    /// The trusted anchor covering the most entries.
+    pub last_trusted: Option<Anchor>,
+    /// Entries written after `last_trusted` (all of them if there is none).
+    pub unanchored: u64,
+}
+
+impl AnchorReport {
+    /// Anchors whose head no longer matches the database.
+    pub fn diverged(&self) -> impl Iterator<Item = &AnchorCheck> {
+        self.checks
+            .iter()
+            .filter(|check| check.status == AnchorStatus::Diverged)
+    }
+
+    /// Number of anchors not signed by the vault key.
+    pub fn untrusted(&self) -> usize {
+        self.checks
+            .iter()
+            .filter(|check| check.status == AnchorStatus::Untrusted)
+            .count()
+    }
+
+    /// Whether no anchor signed by the vault key disagrees with the database.
+    pub fn is_valid(&self) -> bool {
+        self.diverged().next().is_none()
+    }
+}
+
+impl AuditLog {
+    /// Sign the current chain head with `key` and append the anchor to
+    /// `anchors`.
+    ///
+    /// Refuses to anchor a broken chain.  Returns `None` if there are no
+    /// entries or the head is already anchored.
+    pub fn anchor(
+        &self,
+        key: &SigningKey,
+        anchors: &AnchorLog,
+        now: DateTime<Utc>,
+    ) -> NakamaResult<Option<Anchor>> {
+        let chain = self.verify_chain_report()?;
+        if let Some(id) = &chain.broken_at {
+            return Err(NakamaError::Audit {
+                message: format!("Refusing to anchor a broken audit chain (broken at {id})"),
+            });
+        }
+        let Some(head_checksum) = chain.head else {
+            return Ok(None);
+        };
+        if anchors
+            .read()?
+            .last()
+            .is_some_and(|last| last.head_checksum == head_checksum)
+        {
+            return Ok(None);
+        }
+
+        let head_id: String = self
+            .lock()?
+            .query_row(
+                "SELECT id FROM audit_entries WHERE checksum = ?1 ORDER BY rowid DESC LIMIT 1",
+                params![head_checksum],
+                |row| row.get(0),
+            )
+            .map_err(|e| NakamaError::Audit {
+                message: format!("Failed to read audit chain head: {e}"),
+            })?;
+
+        let anchor = Anchor::sign(key, &head_id, &head_checksum, chain.entries, now);
+        anchors.append(&anchor)?;
+        tracing::info!(head = %head_id, entries = chain.entries, "Anchored audit chain");
+        Ok(Some(anchor))
+    }
+
+    /// Check `anchors` against the database, trusting only signatures made
+    /// by `key`.
+    pub fn verify_anchors(
+        &self,
+        anchors: &[Anchor],
+        key: &VerifyingKey,
+    ) -> NakamaResult<AnchorReport> {
+        self.flush()?;
+        let conn = self.lock()?;
+        let lookup = |id: &str| -> NakamaResult<Option<(i64, String)>> {
+            conn.query_row(
+                "SELECT rowid, checksum FROM audit_entries WHERE id = ?1",
+                params![id],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()
+            .map_err(|e| NakamaError::Audit {
+                message: format!("Failed to look up anchored entry {id}: {e}"),
+            })
+        };
+
+        let mut report = AnchorReport::default();
+        let mut archived: Option<HashMap<String, String>> = None;
+        let mut trusted_rowid = 0;
+        for anchor in anchors {
+            let status = if !anchor.is_signed_by(key) {
+                AnchorStatus::Untrusted
+            } else if let Some((rowid, checksum)) = lookup(&anchor.head_id)? {
+                if checksum != anchor.head_checksum {
+                    AnchorStatus::Diverged
+                } else {
+                    if rowid >= trusted_rowid {
+                        trusted_rowid = rowid;
+                        report.last_trusted = Some(anchor.clone());
+                    }
+                    AnchorStatus::Trusted
+                }
+            } else {
+                let archived = archived.get_or_insert_with(|| self.archived_checksums());
+                if archived.get(&anchor.head_id) == Some(&anchor.head_checksum) {
+                    AnchorStatus::Archived
+                } else {
+                    AnchorStatus::Diverged
+                }
+            };
+            report.checks.push(AnchorCheck {
+                anchor: anchor.clone(),
+                status,
+            });
+        }
+
+        let unanchored: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM audit_entries WHERE rowid > ?1",
+                params![trusted_rowid],
+                |row| row.get(0),
+            )
+            .map_err(|e| NakamaError::Audit {
+                message: format!("Failed to count unanchored audit entries: {e}"),
+            })?;
+        report.unanchored = unanchored as u64;
+        Ok(report)
+    }
+
+    /// Checksums of every entry in the retention archive, by id.
+    fn archived_checksums(&self) -> HashMap<String, String> {
+        let Some(dir) = self.dir() else {
+            return HashMap::new();
+        };
+        let Ok(segments) = std::fs::read_dir(dir.join(retention::ARCHIVE_DIR)) else {
+            return HashMap::new();
+        };
+        let mut checksums = HashMap::new();
+        for segment in segments.flatten() {
+            match retention::read_segment(&segment.path()) {
+                Ok(entries) => checksums.extend(
+                    entries
+                        .into_iter()
+                        .map(|archived| (archived.entry.id, archived.checksum)),
+                ),
+                Err(e) => tracing::warn!("Skipping unreadable audit archive: {e}"),
+            }
+        }
+        checksums
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entry::{AuditEntry, Category, Outcome};
+
+    fn setup(name: &str) -> (PathBuf, AuditLog, AnchorLog) {
+        let dir =
+            std::env::temp_dir().join(format!("nakama-anchor-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let log = AuditLog::open_at(&dir.join("audit.db")).unwrap();
+        let anchors = AnchorLog::open_at(&dir.join(ANCHOR_FILE));
+        (dir, log, anchors)
+    }
+
+    fn entry(action: &str) -> AuditEntry {
+        AuditEntry::new(
+            "tr_anchor",
+            "kami",
+            "ask",
+            Category::AiInteraction,
+            action,
+            serde_json::json!({}),
+            Outcome::Success,
+            0,
+        )
+    }
+
+    #[test]
+    fn test_anchor_round_trip() {
+        let (dir, log, anchors) = setup("roundtrip");
+        let key = generate_key();
+        assert!(log.anchor(&key, &anchors, Utc::now()).unwrap().is_none());
+
+        log.log(entry("first")).unwrap();
+        let anchor = log.anchor(&key, &anchors, Utc::now()).unwrap().unwrap();
+        assert!(anchor.is_signed_by(&key.verifying_key()));
+        // Nothing new to anchor.
+        assert!(log.anchor(&key, &anchors, Utc::now()).unwrap().is_none());
+
+        log.log(entry("second")).unwrap();
+        let report = log
+            .verify_anchors(&anchors.read().unwrap(), &key.verifying_key())
+            .unwrap();
+        assert!(report.is_valid());
+        assert_eq!(report.last_trusted, Some(anchor));
+        assert_eq!(report.unanchored, 1);
+
+        // Another key's anchors are untrusted, not divergent.
+        let other = generate_key();
+        let report = log
+            .verify_anchors(&anchors.read().unwrap(), &other.verifying_key())
+            .unwrap();
+        assert!(report.is_valid());
+        assert_eq!(report.untrusted(), 1);
+        assert!(report.last_trusted.is_none());
+
+        assert_eq!(
+            key_from_hex(&key_to_hex(&key)).unwrap().to_bytes(),
+            key.to_bytes()
+        );
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_rewritten_history_diverges() {
+        let (dir, log, anchors) = setup("rewrite");
+        let key = generate_key();
+        log.log(entry("original")).unwrap();
+        log.anchor(&key, &anchors, Utc::now()).unwrap().unwrap();
+
+        // Rewrite the entry and recompute a self-consistent chain.
+        let rewritten = entry("rewritten");
+        {
+            let conn = log.lock().unwrap();
+            conn.execute("DELETE FROM audit_entries", []).unwrap();
+        }
+        log.log(AuditEntry {
+            id: anchors.read().unwrap()[0].head_id.clone(),
+            ..rewritten
+        })
+        .unwrap();
+        assert!(log.verify_chain().unwrap());
+
+        let report = log
+            .verify_anchors(&anchors.read().unwrap(), &key.verifying_key())
+            .unwrap();
+        assert!(!report.is_valid());
+        assert!(report.last_trusted.is_none());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_tampered_anchor_is_untrusted() {
+        let key = generate_key();
+        let mut anchor = Anchor::sign(&key, "aud_1", "abc", 1, Utc::now());
+        anchor.entries = 2;
+        assert!(!anchor.is_signed_by(&key.verifying_key()));
+    }
+}
@@ -36,11 +36,27 @@ pub struct AuditEntry {
 
     /// Wall-clock duration of the action in milliseconds.
     pub duration_ms: u64,
+
+    /// Tokens sent to an AI provider, for AI completions.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub input_tokens: Option<u64>,
+
+    /// Tokens received from an AI provider, for AI completions.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output_tokens: Option<u64>,
+
+    /// Estimated cost of an AI completion in USD.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cost_usd: Option<f64>,
+
+    /// Root of the git work tree the tool ran in, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repo: Option<String>,
 }
 
 impl AuditEntry {
-    /// Create a new audit entry with a fresh `aud_<uuid>` identifier and the
-    /// current UTC timestamp.
+    /// Create a new audit entry with a fresh `aud_<uuid>` identifier, the
+    /// current UTC timestamp and the repository of the working directory.
     pub fn new(
         trace_id: &str,
         tool: &str,
@@ -62,8 +78,28 @@ impl AuditEntry {
             detail,
             outcome,
             duration_ms,
+            input_tokens: None,
+            output_tokens: None,
+            cost_usd: None,
+            repo: current_repo(),
         }
     }
+
+    /// Record the token usage and estimated cost of an AI completion.
+    pub fn with_usage(mut self, input_tokens: u64, output_tokens: u64, cost_usd: f64) -> Self {
+        self.input_tokens = Some(input_tokens);
+        self.output_tokens = Some(output_tokens);
+        self.cost_usd = Some(cost_usd);
+        self
+    }
+}
+
+/// The nearest ancestor of the working directory containing `.git`.
+fn current_repo() -> Option<String> {
+    let cwd = std::env::current_dir().ok()?;
+    cwd.ancestors()
+        .find(|dir| dir.join(".git").exists())
+        .map(|dir| dir.display().to_string())
 }
 
 /// High-level classification of an audited action.
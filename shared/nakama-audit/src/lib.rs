@@ -2,12 +2,23 @@
 //!
 //! Every significant action taken by a Nakama tool is recorded as an
 //! [`AuditEntry`] in a local SQLite database with SHA-256 hash chaining to
-//! detect retroactive tampering.
+//! detect retroactive tampering.  AI token usage and spend are kept in the
+//! same database by the [`UsageLedger`].  Expired entries are archived and
+//! replaced by a signed checkpoint (see [`retention`]), and the chain head
+//! is periodically signed with a vault-held key (see [`anchor`]).
 
+pub mod anchor;
 pub mod entry;
 pub mod query;
+pub mod retention;
+pub mod schema;
 pub mod store;
+pub mod usage;
+mod writer;
 
+pub use anchor::{Anchor, AnchorLog, AnchorReport, AnchorStatus};
 pub use entry::{AuditEntry, Category, Outcome};
 pub use query::AuditFilter;
-pub use store::AuditLog;
+pub use retention::{maybe_prune, PruneReport};
+pub use store::{AuditLog, ChainReport};
+pub use usage::{UsageLedger, UsageRecord, UsageReport, UsageTotals};
@@ -0,0 +1,543 @@
+//! Retention: pruning expired entries without breaking the hash chain.
+//!
+//! Entries older than `retention_days` are moved, oldest first, into a
+//! gzip-compressed JSONL segment under `~/.nakama/audit/archive/` (each line
+//! keeps its checksum, so segments can be verified on their own).  They are
+//! replaced by a single *checkpoint* entry that takes the place of the last
+//! pruned row: its checksum is the last pruned checksum, so the remaining
+//! chain links to it unchanged, and it carries an Ed25519 signature over
+//! that anchor.  The signing key is the audit anchor key held in the vault
+//! (see [`crate::anchor`]), so a checkpoint hiding deleted history cannot be
+//! forged by someone who can only write `audit.db`.
+//!
+//! Tools run `nakama_vault::startup` after parsing their arguments, which
+//! runs [`maybe_prune`] at most once per [`CLEANUP_COOLDOWN_HOURS`].
+//! `nakama audit prune` runs it on demand.
+
+use crate::anchor::{SigningKey, VerifyingKey};
+use crate::entry::{AuditEntry, Category, Outcome};
+use crate::store::AuditLog;
+use chrono::{DateTime, Duration, Utc};
+use ed25519_dalek::{Signature, Signer, Verifier};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use nakama_core::config::AuditConfig;
+use nakama_core::error::{NakamaError, NakamaResult};
+use nakama_core::permissions;
+use nakama_core::trace::TraceContext;
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Tool name recorded on checkpoint entries.
+pub const CHECKPOINT_TOOL: &str = "nakama-audit";
+
+/// Action recorded on checkpoint entries.
+pub const CHECKPOINT_ACTION: &str = "checkpoint";
+
+/// Minimum time between automatic cleanups.
+pub const CLEANUP_COOLDOWN_HOURS: i64 = 24;
+
+/// Archive directory, next to the database.
+pub(crate) const ARCHIVE_DIR: &str = "archive";
+
+/// Records when automatic cleanup last ran, next to the database.
+const CLEANUP_STAMP_FILE: &str = "last-cleanup";
+
+/// Outcome of [`AuditLog::prune`].
+#[derive(Debug, Clone, Default)]
+pub struct PruneReport {
+    /// Number of entries moved to the archive.
+    pub pruned: usize,
+    /// The archive segment written, if any entries were pruned.
+    pub archive: Option<PathBuf>,
+    /// Id of the checkpoint entry that replaced them.
+    pub checkpoint: Option<String>,
+}
+
+/// A pruned entry as written to an archive segment.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchivedEntry {
+    #[serde(flatten)]
+    pub entry: AuditEntry,
+    /// The entry's chain checksum.
+    pub checksum: String,
+    /// Checksum formula the entry was written with; absent for version 1.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chain_version: Option<u32>,
+}
+
+/// The fields of a checkpoint entry's `detail`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct CheckpointDetail {
+    /// Checksum of the last pruned entry; also the checkpoint's own checksum.
+    pub anchor: String,
+    /// Number of entries pruned.
+    pub pruned: usize,
+    /// First and last pruned entry ids.
+    pub first_id: String,
+    pub last_id: String,
+    /// Archive segment file name.
+    pub archive: String,
+    /// Hex-encoded Ed25519 public key of the signer.
+    pub public_key: String,
+    /// Hex-encoded Ed25519 signature over [`CheckpointDetail::signed_payload`].
+    pub signature: String,
+}
+
+impl CheckpointDetail {
+    /// The bytes covered by the signature.
+    pub(crate) fn signed_payload(&self, id: &str, timestamp: &str) -> String {
+        format!(
+            "nakama-audit-checkpoint|{id}|{timestamp}|{}|{}|{}|{}",
+            self.anchor, self.pruned, self.first_id, self.last_id
+        )
+    }
+
+    /// The key that signed this checkpoint, if the signature verifies.
+    ///
+    /// Only says the checkpoint is intact; whether the signer is trusted is
+    /// up to the caller, who compares it with the vault's anchor key.
+    pub(crate) fn signer(&self, id: &str, timestamp: &str) -> Option<VerifyingKey> {
+        let key = hex_decode(&self.public_key)
+            .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+            .and_then(|bytes| VerifyingKey::from_bytes(&bytes).ok())?;
+        let signature = hex_decode(&self.signature)
+            .and_then(|bytes| <[u8; 64]>::try_from(bytes).ok())
+            .map(|bytes| Signature::from_bytes(&bytes))?;
+        key.verify(self.signed_payload(id, timestamp).as_bytes(), &signature)
+            .is_ok()
+            .then_some(key)
+    }
+}
+
+impl AuditLog {
+    /// Number of entries [`prune`](Self::prune) would archive for `cutoff`.
+    pub fn count_prunable(&self, cutoff: DateTime<Utc>) -> NakamaResult<usize> {
+        self.flush()?;
+        let conn = self.lock()?;
+        let boundary = prune_boundary(&conn, cutoff)?;
+        let count: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM audit_entries WHERE rowid <= ?1 AND NOT (tool = ?2 AND action = ?3)",
+                params![boundary, CHECKPOINT_TOOL, CHECKPOINT_ACTION],
+                |row| row.get(0),
+            )
+            .map_err(|e| NakamaError::Audit {
+                message: format!("Failed to count expired audit entries: {e}"),
+            })?;
+        Ok(count as usize)
+    }
+
+    /// Archive every entry older than `cutoff` and replace them with a
+    /// checkpoint signed with `key` (the vault's audit anchor key).
+    ///
+    /// Only the oldest run of expired entries is pruned, so an entry with a
+    /// skewed timestamp never splits the chain.  Requires a file-backed
+    /// database: the archive lives next to it.
+    pub fn prune(&self, cutoff: DateTime<Utc>, key: &SigningKey) -> NakamaResult<PruneReport> {
+        let Some(dir) = self.dir() else {
+            return Err(NakamaError::Audit {
+                message: "Cannot prune an in-memory audit log".to_string(),
+            });
+        };
+        self.flush()?;
+        let start = std::time::Instant::now();
+        let mut conn = self.lock()?;
+        let tx = conn
+            .transaction_with_behavior(rusqlite::TransactionBehavior::Immediate)
+            .map_err(|e| NakamaError::Audit {
+                message: format!("Failed to start prune transaction: {e}"),
+            })?;
+
+        let boundary = prune_boundary(&tx, cutoff)?;
+        let expired = {
+            let mut stmt = tx
+                .prepare(&format!(
+                    "SELECT {}, checksum, chain_version FROM audit_entries WHERE rowid <= ?1 ORDER BY rowid ASC",
+                    crate::store::ENTRY_COLUMNS
+                ))
+                .map_err(|e| NakamaError::Audit {
+                    message: format!("Failed to prepare prune query: {e}"),
+                })?;
+            let rows = stmt
+                .query_map(params![boundary], |row| {
+                    Ok(ArchivedEntry {
+                        entry: AuditLog::row_to_entry(row)?,
+                        checksum: row.get("checksum")?,
+                        chain_version: row.get("chain_version")?,
+                    })
+                })
+                .and_then(|rows| rows.collect::<Result<Vec<_>, _>>())
+                .map_err(|e| NakamaError::Audit {
+                    message: format!("Failed to read expired audit entries: {e}"),
+                })?;
+            rows
+        };
+
+        // Nothing to do if only an earlier checkpoint has expired.
+        let (Some(first), Some(last)) = (expired.first(), expired.last()) else {
+            return Ok(PruneReport::default());
+        };
+        let pruned = expired.iter().filter(|e| !is_checkpoint(&e.entry)).count();
+        if pruned == 0 {
+            return Ok(PruneReport::default());
+        }
+
+        let archive_dir = dir.join(ARCHIVE_DIR);
+        let archive = write_segment(&archive_dir, &expired)?;
+
+        let mut checkpoint = AuditEntry::new(
+            &TraceContext::new(CHECKPOINT_TOOL, "prune").trace_id,
+            CHECKPOINT_TOOL,
+            "prune",
+            Category::DataModification,
+            CHECKPOINT_ACTION,
+            serde_json::Value::Null,
+            Outcome::Success,
+            0,
+        );
+        let timestamp = checkpoint.timestamp.to_rfc3339();
+        let mut detail = CheckpointDetail {
+            anchor: last.checksum.clone(),
+            pruned,
+            first_id: first.entry.id.clone(),
+            last_id: last.entry.id.clone(),
+            archive: archive
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            public_key: hex_encode(key.verifying_key().as_bytes()),
+            signature: String::new(),
+        };
+        let payload = detail.signed_payload(&checkpoint.id, &timestamp);
+        detail.signature = hex_encode(&key.sign(payload.as_bytes()).to_bytes());
+        checkpoint.detail = serde_json::to_value(&detail).unwrap_or_default();
+        checkpoint.duration_ms = start.elapsed().as_millis() as u64;
+
+        tx.execute(
+            "DELETE FROM audit_entries WHERE rowid <= ?1",
+            params![boundary],
+        )
+        .map_err(|e| NakamaError::Audit {
+            message: format!("Failed to delete expired audit entries: {e}"),
+        })?;
+        // Reuse the last pruned rowid so the checkpoint heads the chain.
+        tx.execute(
+            "INSERT INTO audit_entries (rowid, id, timestamp, trace_id, tool, command, category, action, detail, outcome, duration_ms, checksum)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+            params![
+                boundary,
+                checkpoint.id,
+                timestamp,
+                checkpoint.trace_id,
+                checkpoint.tool,
+                checkpoint.command,
+                checkpoint.category.to_string(),
+                checkpoint.action,
+                checkpoint.detail.to_string(),
+                checkpoint.outcome.to_string(),
+                checkpoint.duration_ms as i64,
+                detail.anchor,
+            ],
+        )
+        .map_err(|e| NakamaError::Audit {
+            message: format!("Failed to insert audit checkpoint: {e}"),
+        })?;
+        tx.commit().map_err(|e| NakamaError::Audit {
+            message: format!("Failed to commit audit prune: {e}"),
+        })?;
+
+        tracing::info!(
+            pruned,
+            archive = %archive.display(),
+            "Pruned expired audit entries"
+        );
+        Ok(PruneReport {
+            pruned,
+            archive: Some(archive),
+            checkpoint: Some(checkpoint.id),
+        })
+    }
+}
+
+/// Prune entries older than `config.retention_days` if automatic cleanup is
+/// enabled and has not run within [`CLEANUP_COOLDOWN_HOURS`].  `load_key`
+/// supplies the signing key and is only called when cleanup is due; if it
+/// fails, cleanup is retried on the next start.
+///
+/// Failures are logged, never returned: cleanup must not stop a tool.
+pub fn maybe_prune(config: &AuditConfig, load_key: impl FnOnce() -> NakamaResult<SigningKey>) {
+    if !config.enabled || !config.auto_cleanup || config.retention_days == 0 {
+        return;
+    }
+    let Ok(dir) = nakama_core::paths::audit_dir() else {
+        return;
+    };
+    let stamp = dir.join(CLEANUP_STAMP_FILE);
+    let now = Utc::now();
+    let last_run = std::fs::read_to_string(&stamp)
+        .ok()
+        .and_then(|s| DateTime::parse_from_rfc3339(s.trim()).ok());
+    if let Some(last_run) = last_run {
+        if now.signed_duration_since(last_run) < Duration::hours(CLEANUP_COOLDOWN_HOURS) {
+            return;
+        }
+    }
+    if !dir.join("audit.db").exists() {
+        return;
+    }
+    let key = match load_key() {
+        Ok(key) => key,
+        Err(e) => {
+            tracing::debug!("Skipping audit cleanup: {e}");
+            return;
+        }
+    };
+
+    // Stamp first so concurrently starting tools don't all prune.
+    if let Err(e) = std::fs::write(&stamp, now.to_rfc3339()) {
+        tracing::warn!("Failed to record audit cleanup time: {e}");
+        return;
+    }
+    let cutoff = now - Duration::days(i64::from(config.retention_days));
+    match AuditLog::new(config).and_then(|log| log.prune(cutoff, &key)) {
+        Ok(report) if report.pruned > 0 => {
+            tracing::debug!(pruned = report.pruned, "Automatic audit cleanup finished");
+        }
+        Ok(_) => {}
+        Err(e) => tracing::warn!("Automatic audit cleanup failed: {e}"),
+    }
+}
+
+/// Read every entry of a gzip-compressed archive segment.
+pub fn read_segment(path: &Path) -> NakamaResult<Vec<ArchivedEntry>> {
+    use std::io::BufRead;
+
+    let file = std::fs::File::open(path).map_err(|e| NakamaError::Audit {
+        message: format!("Failed to open audit archive {}: {e}", path.display()),
+    })?;
+    std::io::BufReader::new(flate2::read::GzDecoder::new(file))
+        .lines()
+        .filter(|line| !matches!(line, Ok(l) if l.trim().is_empty()))
+        .map(|line| {
+            let line = line.map_err(|e| NakamaError::Audit {
+                message: format!("Failed to read audit archive {}: {e}", path.display()),
+            })?;
+            serde_json::from_str(&line).map_err(|e| NakamaError::Audit {
+                message: format!("Malformed entry in audit archive {}: {e}", path.display()),
+            })
+        })
+        .collect()
+}
+
+pub(crate) fn is_checkpoint(entry: &AuditEntry) -> bool {
+    entry.tool == CHECKPOINT_TOOL && entry.action == CHECKPOINT_ACTION
+}
+
+/// Rowid of the last entry in the oldest run of entries before `cutoff`, or
+/// 0 if the oldest entry has not expired.
+fn prune_boundary(conn: &rusqlite::Connection, cutoff: DateTime<Utc>) -> NakamaResult<i64> {
+    conn.query_row(
+        "SELECT COALESCE(
+             (SELECT MIN(rowid) - 1 FROM audit_entries
+              WHERE timestamp >= ?1 AND NOT (tool = ?2 AND action = ?3)),
+             (SELECT MAX(rowid) FROM audit_entries),
+             0)",
+        params![cutoff.to_rfc3339(), CHECKPOINT_TOOL, CHECKPOINT_ACTION],
+        |row| row.get(0),
+    )
+    .map_err(|e| NakamaError::Audit {
+        message: format!("Failed to find expired audit entries: {e}"),
+    })
+}
+
+/// Write `entries` to a new gzip-compressed JSONL segment in `dir`.
+fn write_segment(dir: &Path, entries: &[ArchivedEntry]) -> NakamaResult<PathBuf> {
+    let io_err = |path: &Path, e: std::io::Error| NakamaError::Audit {
+        message: format!("Failed to write audit archive {}: {e}", path.display()),
+    };
+
+    std::fs::create_dir_all(dir).map_err(|e| io_err(dir, e))?;
+    permissions::set_dir_permissions(dir)?;
+
+    // Name the segment after the time span of the pruned entries; a carried
+    // over checkpoint is newer than the entries it follows.
+    let timestamps = entries
+        .iter()
+        .filter(|e| !is_checkpoint(&e.entry))
+        .map(|e| e.entry.timestamp);
+    let (first, last) = (
+        timestamps.clone().min().unwrap_or_default(),
+        timestamps.max().unwrap_or_default(),
+    );
+    let name = format!(
+        "audit-{}-{}.jsonl.gz",
+        first.format("%Y%m%dT%H%M%SZ"),
+        last.format("%Y%m%dT%H%M%SZ"),
+    );
+    let path = dir.join(name);
+    let tmp = path.with_extension("gz.tmp");
+
+    let file = std::fs::File::create(&tmp).map_err(|e| io_err(&tmp, e))?;
+    let mut encoder = GzEncoder::new(file, Compression::default());
+    for entry in entries {
+        let line = serde_json::to_string(entry).map_err(|e| NakamaError::Audit {
+            message: format!("Failed to serialize archived audit entry: {e}"),
+        })?;
+        writeln!(encoder, "{line}").map_err(|e| io_err(&tmp, e))?;
+    }
+    let file = encoder.finish().map_err(|e| io_err(&tmp, e))?;
+    file.sync_all().map_err(|e| io_err(&tmp, e))?;
+    permissions::set_file_permissions(&tmp)?;
+    std::fs::rename(&tmp, &path).map_err(|e| io_err(&path, e))?;
+    Ok(path)
+}
+
+pub(crate) fn hex_encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+pub(crate) fn hex_decode(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_log(name: &str) -> (PathBuf, AuditLog) {
+        let dir =
+            std::env::temp_dir().join(format!("nakama-retention-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let log = AuditLog::open_at(&dir.join("audit.db")).unwrap();
+        (dir, log)
+    }
+
+    fn entry_at(action: &str, timestamp: DateTime<Utc>) -> AuditEntry {
+        let mut entry = AuditEntry::new(
+            "tr_retention",
+            "zangetsu",
+            "ask",
+            Category::ToolExecution,
+            action,
+            serde_json::json!({}),
+            Outcome::Success,
+            0,
+        );
+        entry.timestamp = timestamp;
+        entry
+    }
+
+    #[test]
+    fn test_prune_keeps_chain_valid() {
+        let (dir, log) = temp_log("chain");
+        let key = crate::anchor::generate_key();
+        let now = Utc::now();
+        for days in [100, 95, 91, 10, 1] {
+            log.log(entry_at(
+                &format!("{days} days ago"),
+                now - Duration::days(days),
+            ))
+            .unwrap();
+        }
+
+        let cutoff = now - Duration::days(90);
+        assert_eq!(log.count_prunable(cutoff).unwrap(), 3);
+        let report = log.prune(cutoff, &key).unwrap();
+        assert_eq!(report.pruned, 3);
+
+        let archived = read_segment(report.archive.as_ref().unwrap()).unwrap();
+        assert_eq!(archived.len(), 3);
+        assert_eq!(archived[0].entry.action, "100 days ago");
+
+        let chain = log.verify_chain_report().unwrap();
+        assert!(chain.is_valid(), "{chain:?}");
+        assert_eq!(chain.checkpoint, report.checkpoint);
+        assert_eq!(chain.checkpoint_key, Some(key.verifying_key()));
+        assert_eq!(chain.entries, 3);
+
+        // New entries chain on, and a second prune replaces the checkpoint.
+        log.log(entry_at("now", now)).unwrap();
+        assert!(log.verify_chain().unwrap());
+        let report = log.prune(now - Duration::days(5), &key).unwrap();
+        assert_eq!(report.pruned, 1);
+        assert!(log.verify_chain().unwrap());
+        assert_eq!(log.query(&crate::AuditFilter::new()).unwrap().len(), 3);
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_prune_nothing_expired() {
+        let (dir, log) = temp_log("noop");
+        let key = crate::anchor::generate_key();
+        log.log(entry_at("fresh", Utc::now())).unwrap();
+        let report = log.prune(Utc::now() - Duration::days(90), &key).unwrap();
+        assert_eq!(report.pruned, 0);
+        assert!(report.archive.is_none());
+        let tomorrow = Utc::now() + Duration::days(1);
+        assert_eq!(log.prune(tomorrow, &key).unwrap().pruned, 1);
+        // Only the checkpoint has expired now, so nothing changes.
+        let later = Utc::now() + Duration::days(2);
+        assert_eq!(log.prune(later, &key).unwrap().pruned, 0);
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_forged_checkpoint_detected() {
+        let (dir, log) = temp_log("forged");
+        let key = crate::anchor::generate_key();
+        let now = Utc::now();
+        for days in [100, 50] {
+            log.log(entry_at("entry", now - Duration::days(days)))
+                .unwrap();
+        }
+        log.prune(now - Duration::days(90), &key).unwrap();
+
+        // Pretend more history was pruned than really was.
+        let conn = log.lock().unwrap();
+        conn.execute(
+            "UPDATE audit_entries SET detail = json_set(detail, '$.pruned', 7) WHERE tool = ?1",
+            params![CHECKPOINT_TOOL],
+        )
+        .unwrap();
+        drop(conn);
+
+        let chain = log.verify_chain_report().unwrap();
+        assert!(!chain.is_valid());
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_checkpoint_reports_its_signer() {
+        let (dir, log) = temp_log("signer");
+        let now = Utc::now();
+        log.log(entry_at("entry", now - Duration::days(100)))
+            .unwrap();
+
+        // A checkpoint signed with some other key still chains, but the
+        // report names that key so callers can refuse to trust it.
+        let forger = crate::anchor::generate_key();
+        log.prune(now - Duration::days(90), &forger).unwrap();
+        let chain = log.verify_chain_report().unwrap();
+        assert!(chain.is_valid());
+        assert_eq!(chain.checkpoint_key, Some(forger.verifying_key()));
+        assert_ne!(
+            chain.checkpoint_key,
+            Some(crate::anchor::generate_key().verifying_key())
+        );
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
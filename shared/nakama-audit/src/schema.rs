@@ -0,0 +1,286 @@
+//! Versioned schema migrations for the audit database.
+//!
+//! The schema version lives in SQLite's `user_version` pragma.  Opening a
+//! database applies every [`MIGRATIONS`] entry newer than that version, in
+//! order, each in its own transaction together with the version bump.  A
+//! database that already holds entries is first copied to
+//! `audit.db.v<N>.bak` so a failed or unwanted upgrade can be rolled back by
+//! hand.
+//!
+//! Databases created before versioning report version 0; the first migration
+//! uses `IF NOT EXISTS` so it adopts them unchanged.  Migrations must only
+//! add: older tools keep writing to a database a newer one has upgraded.
+
+use nakama_core::error::{NakamaError, NakamaResult};
+use rusqlite::{Connection, OpenFlags, TransactionBehavior};
+use std::path::{Path, PathBuf};
+
+/// One step in the schema history.
+pub struct Migration {
+    /// Schema version after this migration runs.
+    pub version: u32,
+    pub description: &'static str,
+    pub sql: &'static str,
+}
+
+/// Every migration, oldest first.  Never edit a released migration; append a
+/// new one.
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "audit entries",
+        sql: "CREATE TABLE IF NOT EXISTS audit_entries (
+                id          TEXT PRIMARY KEY,
+                timestamp   TEXT NOT NULL,
+                trace_id    TEXT NOT NULL,
+                tool        TEXT NOT NULL,
+                command     TEXT NOT NULL,
+                category    TEXT NOT NULL,
+                action      TEXT NOT NULL,
+                detail      TEXT NOT NULL,
+                outcome     TEXT NOT NULL,
+                duration_ms INTEGER NOT NULL,
+                checksum    TEXT NOT NULL
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_audit_tool      ON audit_entries(tool);
+            CREATE INDEX IF NOT EXISTS idx_audit_category   ON audit_entries(category);
+            CREATE INDEX IF NOT EXISTS idx_audit_timestamp  ON audit_entries(timestamp);
+            CREATE INDEX IF NOT EXISTS idx_audit_trace_id   ON audit_entries(trace_id);
+            CREATE INDEX IF NOT EXISTS idx_audit_outcome    ON audit_entries(outcome);",
+    },
+    Migration {
+        version: 2,
+        description: "token usage, cost and repository columns",
+        sql: "ALTER TABLE audit_entries ADD COLUMN input_tokens INTEGER;
+            ALTER TABLE audit_entries ADD COLUMN output_tokens INTEGER;
+            ALTER TABLE audit_entries ADD COLUMN cost_usd REAL;
+            ALTER TABLE audit_entries ADD COLUMN repo TEXT;
+
+            UPDATE audit_entries SET
+                input_tokens  = json_extract(detail, '$.input_tokens'),
+                output_tokens = json_extract(detail, '$.output_tokens'),
+                cost_usd      = json_extract(detail, '$.cost_usd')
+            WHERE category = 'ai_interaction' AND json_valid(detail);
+
+            CREATE INDEX IF NOT EXISTS idx_audit_repo ON audit_entries(repo);",
+    },
+    Migration {
+        version: 3,
+        description: "checksum chain version",
+        sql: "ALTER TABLE audit_entries ADD COLUMN chain_version INTEGER;",
+    },
+];
+
+/// The schema version this build writes.
+pub fn latest_version() -> u32 {
+    MIGRATIONS.last().map_or(0, |m| m.version)
+}
+
+/// The schema version of the database behind `conn`.
+pub fn current_version(conn: &Connection) -> NakamaResult<u32> {
+    conn.query_row("PRAGMA user_version", [], |row| row.get(0))
+        .map_err(|e| NakamaError::Audit {
+            message: format!("Failed to read audit schema version: {e}"),
+        })
+}
+
+/// Bring the database at `path` (open as `conn`) up to [`latest_version`].
+///
+/// Returns the backup written before migrating, if any.
+pub(crate) fn migrate(conn: &mut Connection, path: &Path) -> NakamaResult<Option<PathBuf>> {
+    let version = current_version(conn)?;
+    if version > latest_version() {
+        tracing::warn!(
+            version,
+            supported = latest_version(),
+            "Audit database was upgraded by a newer Nakama; newer columns are left untouched"
+        );
+        return Ok(None);
+    }
+    if version == latest_version() {
+        return Ok(None);
+    }
+
+    let backup = backup(conn, path, version)?;
+    for migration in MIGRATIONS.iter().filter(|m| m.version > version) {
+        apply(conn, migration)?;
+    }
+    Ok(backup)
+}
+
+fn apply(conn: &mut Connection, migration: &Migration) -> NakamaResult<()> {
+    let tx = conn
+        .transaction_with_behavior(TransactionBehavior::Immediate)
+        .map_err(|e| NakamaError::Audit {
+            message: format!("Failed to start audit migration: {e}"),
+        })?;
+
+    // Another process may have migrated while we waited for the lock.
+    if current_version(&tx)? >= migration.version {
+        return Ok(());
+    }
+    tx.execute_batch(migration.sql)
+        .and_then(|()| tx.execute_batch(&format!("PRAGMA user_version = {}", migration.version)))
+        .map_err(|e| NakamaError::Audit {
+            message: format!(
+                "Audit schema migration {} ({}) failed: {e}",
+                migration.version, migration.description
+            ),
+        })?;
+    tx.commit().map_err(|e| NakamaError::Audit {
+        message: format!(
+            "Failed to commit audit migration {}: {e}",
+            migration.version
+        ),
+    })?;
+
+    tracing::info!(
+        version = migration.version,
+        description = migration.description,
+        "Migrated audit database"
+    );
+    Ok(())
+}
+
+/// Copy a database that already holds audit data to `<path>.v<version>.bak`.
+fn backup(conn: &Connection, path: &Path, version: u32) -> NakamaResult<Option<PathBuf>> {
+    let in_memory = path.as_os_str().is_empty() || path == Path::new(":memory:");
+    let has_table: bool = conn
+        .query_row(
+            "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'audit_entries')",
+            [],
+            |row| row.get(0),
+        )
+        .map_err(|e| NakamaError::Audit {
+            message: format!("Failed to inspect audit database: {e}"),
+        })?;
+    if in_memory || !has_table {
+        return Ok(None);
+    }
+
+    let backup_path = |suffix: &str| {
+        let mut backup = path.as_os_str().to_owned();
+        backup.push(suffix);
+        PathBuf::from(backup)
+    };
+    let mut backup = backup_path(&format!(".v{version}.bak"));
+
+    // VACUUM INTO takes a consistent copy, including pages still in the WAL.
+    let copied = conn.execute("VACUUM INTO ?1", [backup.to_string_lossy()]);
+    if copied.is_err() {
+        // VACUUM INTO refuses to overwrite an existing file.  Reuse it if an
+        // earlier attempt, or a tool starting alongside this one, finished a
+        // backup of this version there; otherwise leave it alone and save
+        // under a name of our own.
+        if is_backup_of(&backup, version) {
+            return Ok(Some(backup));
+        }
+        backup = backup_path(&format!(".v{version}.{}.bak", std::process::id()));
+        let _ = std::fs::remove_file(&backup);
+        conn.execute("VACUUM INTO ?1", [backup.to_string_lossy()])
+            .map_err(|e| NakamaError::Audit {
+                message: format!(
+                    "Failed to back up audit database to {} before migrating: {e}",
+                    backup.display()
+                ),
+            })?;
+    }
+    nakama_core::permissions::set_file_permissions(&backup)?;
+    tracing::info!(backup = %backup.display(), "Backed up audit database before migrating");
+    Ok(Some(backup))
+}
+
+/// Whether `path` is an intact audit database at schema `version`.
+fn is_backup_of(path: &Path, version: u32) -> bool {
+    let Ok(conn) = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY) else {
+        return false;
+    };
+    let intact = conn
+        .query_row("PRAGMA quick_check", [], |row| row.get::<_, String>(0))
+        .is_ok_and(|result| result == "ok");
+    intact && current_version(&conn).is_ok_and(|v| v == version)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_versions_are_ordered() {
+        for pair in MIGRATIONS.windows(2) {
+            assert_eq!(pair[1].version, pair[0].version + 1);
+        }
+        assert_eq!(MIGRATIONS[0].version, 1);
+    }
+
+    #[test]
+    fn test_migrates_unversioned_database() {
+        let dir = std::env::temp_dir().join(format!("nakama-schema-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("audit.db");
+
+        // A database as written before versioning, with one AI entry.
+        let mut conn = Connection::open(&path).unwrap();
+        conn.execute_batch(MIGRATIONS[0].sql).unwrap();
+        conn.execute(
+            "INSERT INTO audit_entries VALUES ('aud_1', '2026-01-01T00:00:00+00:00', 'tr_1',
+             'kami', 'ask', 'ai_interaction', 'Completion', ?1, 'success', 5, 'abc')",
+            [r#"{"input_tokens": 120, "output_tokens": 30, "cost_usd": 0.25}"#],
+        )
+        .unwrap();
+        assert_eq!(current_version(&conn).unwrap(), 0);
+
+        let backup = migrate(&mut conn, &path).unwrap().expect("backup written");
+        assert!(backup.exists());
+        assert_eq!(current_version(&conn).unwrap(), latest_version());
+        let (input, cost): (i64, f64) = conn
+            .query_row(
+                "SELECT input_tokens, cost_usd FROM audit_entries WHERE id = 'aud_1'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!((input, cost), (120, 0.25));
+
+        // The backup keeps the old schema; migrating again is a no-op.
+        let old = Connection::open(&backup).unwrap();
+        assert_eq!(current_version(&old).unwrap(), 0);
+        assert!(migrate(&mut conn, &path).unwrap().is_none());
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_existing_backup_reused_only_if_intact() {
+        let dir = std::env::temp_dir().join(format!("nakama-schema-kept-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("audit.db");
+        let saved = dir.join("audit.db.v0.bak");
+
+        // A truncated or unrelated file is left alone.
+        let mut conn = Connection::open(&path).unwrap();
+        conn.execute_batch(MIGRATIONS[0].sql).unwrap();
+        std::fs::write(&saved, b"not a database").unwrap();
+        let backup = migrate(&mut conn, &path).unwrap().expect("backup written");
+        assert_ne!(backup, saved);
+        assert!(is_backup_of(&backup, 0));
+        assert_eq!(std::fs::read(&saved).unwrap(), b"not a database");
+        drop(conn);
+
+        // A finished backup of the same version, as left by a tool that
+        // started at the same time, is reused.
+        std::fs::remove_file(&path).unwrap();
+        std::fs::remove_file(&saved).unwrap();
+        let mut conn = Connection::open(&path).unwrap();
+        conn.execute_batch(MIGRATIONS[0].sql).unwrap();
+        conn.execute("VACUUM INTO ?1", [saved.to_string_lossy()])
+            .unwrap();
+        assert_eq!(migrate(&mut conn, &path).unwrap(), Some(saved.clone()));
+        assert_eq!(current_version(&conn).unwrap(), latest_version());
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -1,14 +1,59 @@
 //! SQLite-backed audit log with SHA-256 hash chaining.
 
+use crate::anchor::VerifyingKey;
 use crate::entry::{AuditEntry, Category, Outcome};
 use crate::query::AuditFilter;
+use crate::retention;
+use crate::schema;
+use crate::writer::BatchWriter;
 use chrono::{DateTime, Utc};
 use nakama_core::config::AuditConfig;
 use nakama_core::error::{NakamaError, NakamaResult};
-use rusqlite::{params, Connection};
+use rusqlite::types::Value;
+use rusqlite::{params, params_from_iter, Connection, TransactionBehavior};
 use sha2::{Digest, Sha256};
-use std::path::PathBuf;
-use std::sync::Mutex;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+/// Columns selected for an [`AuditEntry`], in the order `row_to_entry` reads them.
+pub(crate) const ENTRY_COLUMNS: &str = "id, timestamp, trace_id, tool, command, category, action, \
+     detail, outcome, duration_ms, input_tokens, output_tokens, cost_usd, repo";
+
+/// Number of columns in [`ENTRY_COLUMNS`].
+const ENTRY_COLUMN_COUNT: usize = 14;
+
+/// Checksum formula used for new entries, recorded per row in
+/// `chain_version`.  Version 1 (`NULL`) covered only the id, timestamp,
+/// tool, action and outcome; version 2 covers every column.
+pub(crate) const CHAIN_VERSION: u32 = 2;
+
+/// How long a write waits for another process's transaction to finish.
+pub(crate) const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Result of walking the hash chain with [`AuditLog::verify_chain_report`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChainReport {
+    /// Entries whose checksum matched, from the start of the chain.
+    pub entries: u64,
+    /// Checksum of the last verified entry.
+    pub head: Option<String>,
+    /// Id of the first entry whose checksum does not match, if any.
+    pub broken_at: Option<String>,
+    /// Id of the retention checkpoint the chain starts from, if older
+    /// entries have been pruned.
+    pub checkpoint: Option<String>,
+    /// Key that signed that checkpoint.  The pruned history is only as
+    /// trustworthy as this key: compare it with the vault's anchor key.
+    pub checkpoint_key: Option<VerifyingKey>,
+}
+
+impl ChainReport {
+    /// Whether every entry in the chain verified.
+    pub fn is_valid(&self) -> bool {
+        self.broken_at.is_none()
+    }
+}
 
 /// The tamper-evident audit log backed by SQLite.
 ///
@@ -16,15 +61,21 @@ use std::sync::Mutex;
 /// entry's checksum, forming a hash chain that makes retroactive modification
 /// detectable.
 pub struct AuditLog {
-    conn: Mutex<Connection>,
+    conn: Arc<Mutex<Connection>>,
+    /// Directory holding the database and its archive; `None` for
+    /// in-memory databases.
+    dir: Option<PathBuf>,
+    /// Background writer, when `[audit] batch_write` is on.
+    batch: Option<BatchWriter>,
 }
 
 impl AuditLog {
     /// Open (or create) the audit database.
     ///
     /// The database is stored at `~/.nakama/audit/audit.db` and uses WAL mode
-    /// for concurrent read access.
-    pub fn new(_config: &AuditConfig) -> NakamaResult<Self> {
+    /// for concurrent read access.  With `batch_write` on, entries are written
+    /// by a background thread (see [`with_batching`](Self::with_batching)).
+    pub fn new(config: &AuditConfig) -> NakamaResult<Self> {
         let db_path = Self::db_path()?;
 
         // Ensure the parent directory exists.
@@ -34,96 +85,95 @@ impl AuditLog {
             })?;
         }
 
-        let conn = Connection::open(&db_path).map_err(|e| NakamaError::Audit {
-            message: format!("Failed to open audit database at {}: {e}", db_path.display()),
-        })?;
-
-        // Enable WAL mode for better concurrent read performance.
-        conn.execute_batch("PRAGMA journal_mode = WAL;")
-            .map_err(|e| NakamaError::Audit {
-                message: format!("Failed to enable WAL mode: {e}"),
-            })?;
-
-        // Create the audit_entries table if it does not exist.
-        conn.execute_batch(
-            "CREATE TABLE IF NOT EXISTS audit_entries (
-                id          TEXT PRIMARY KEY,
-                timestamp   TEXT NOT NULL,
-                trace_id    TEXT NOT NULL,
-                tool        TEXT NOT NULL,
-                command     TEXT NOT NULL,
-                category    TEXT NOT NULL,
-                action      TEXT NOT NULL,
-                detail      TEXT NOT NULL,
-                outcome     TEXT NOT NULL,
-                duration_ms INTEGER NOT NULL,
-                checksum    TEXT NOT NULL
-            );
-
-            CREATE INDEX IF NOT EXISTS idx_audit_tool      ON audit_entries(tool);
-            CREATE INDEX IF NOT EXISTS idx_audit_category   ON audit_entries(category);
-            CREATE INDEX IF NOT EXISTS idx_audit_timestamp  ON audit_entries(timestamp);
-            CREATE INDEX IF NOT EXISTS idx_audit_trace_id   ON audit_entries(trace_id);
-            CREATE INDEX IF NOT EXISTS idx_audit_outcome    ON audit_entries(outcome);",
+        let log = Self::open_at(&db_path)?;
+        if !config.batch_write {
+            return Ok(log);
+        }
+        log.with_batching(
+            Duration::from_millis(config.batch_flush_interval_ms),
+            config.max_batch_size,
         )
-        .map_err(|e| NakamaError::Audit {
-            message: format!("Failed to initialize audit tables: {e}"),
-        })?;
-
-        Ok(Self {
-            conn: Mutex::new(conn),
-        })
     }
 
-    /// Open an audit database at a custom path (useful for testing).
+    /// Open an audit database at a custom path (useful for testing),
+    /// migrating it to the current schema first (see [`crate::schema`]).
     pub fn open_at(path: &std::path::Path) -> NakamaResult<Self> {
-        let conn = Connection::open(path).map_err(|e| NakamaError::Audit {
+        let mut conn = Connection::open(path).map_err(|e| NakamaError::Audit {
             message: format!("Failed to open audit database at {}: {e}", path.display()),
         })?;
 
+        // Enable WAL mode for better concurrent read performance.
         conn.execute_batch("PRAGMA journal_mode = WAL;")
             .map_err(|e| NakamaError::Audit {
                 message: format!("Failed to enable WAL mode: {e}"),
             })?;
 
-        conn.execute_batch(
-            "CREATE TABLE IF NOT EXISTS audit_entries (
-                id          TEXT PRIMARY KEY,
-                timestamp   TEXT NOT NULL,
-                trace_id    TEXT NOT NULL,
-                tool        TEXT NOT NULL,
-                command     TEXT NOT NULL,
-                category    TEXT NOT NULL,
-                action      TEXT NOT NULL,
-                detail      TEXT NOT NULL,
-                outcome     TEXT NOT NULL,
-                duration_ms INTEGER NOT NULL,
-                checksum    TEXT NOT NULL
-            );
+        // Wait for other processes' writes instead of failing with SQLITE_BUSY.
+        conn.busy_timeout(BUSY_TIMEOUT)
+            .map_err(|e| NakamaError::Audit {
+                message: format!("Failed to set audit busy timeout: {e}"),
+            })?;
 
-            CREATE INDEX IF NOT EXISTS idx_audit_tool      ON audit_entries(tool);
-            CREATE INDEX IF NOT EXISTS idx_audit_category   ON audit_entries(category);
-            CREATE INDEX IF NOT EXISTS idx_audit_timestamp  ON audit_entries(timestamp);
-            CREATE INDEX IF NOT EXISTS idx_audit_trace_id   ON audit_entries(trace_id);
-            CREATE INDEX IF NOT EXISTS idx_audit_outcome    ON audit_entries(outcome);",
-        )
-        .map_err(|e| NakamaError::Audit {
-            message: format!("Failed to initialize audit tables: {e}"),
-        })?;
+        schema::migrate(&mut conn, path)?;
 
         Ok(Self {
-            conn: Mutex::new(conn),
+            conn: Arc::new(Mutex::new(conn)),
+            dir: path
+                .parent()
+                .filter(|p| !p.as_os_str().is_empty())
+                .map(PathBuf::from),
+            batch: None,
         })
     }
 
-    /// Insert a new audit entry and compute the SHA-256 chain link.
+    /// Queue entries for a background writer that appends them in batches of
+    /// up to `max_batch_size`, at most `interval` after the first is queued.
+    pub fn with_batching(
+        mut self,
+        interval: Duration,
+        max_batch_size: usize,
+    ) -> NakamaResult<Self> {
+        self.batch = Some(BatchWriter::spawn(
+            Arc::clone(&self.conn),
+            interval,
+            max_batch_size,
+        )?);
+        Ok(self)
+    }
+
+    /// Record a new audit entry.
+    ///
+    /// With batching on, the entry is queued and written by the background
+    /// writer; otherwise it is appended before this returns.
     pub fn log(&self, entry: AuditEntry) -> NakamaResult<()> {
-        let conn = self.conn.lock().map_err(|e| NakamaError::Audit {
-            message: format!("Failed to acquire audit database lock: {e}"),
-        })?;
+        match &self.batch {
+            Some(batch) => batch.send(entry),
+            None => Self::append(&mut *self.lock()?, std::slice::from_ref(&entry)),
+        }
+    }
+
+    /// Write any entries still queued by the background writer.
+    pub fn flush(&self) -> NakamaResult<()> {
+        match &self.batch {
+            Some(batch) => batch.flush(),
+            None => Ok(()),
+        }
+    }
+
+    /// Append `entries` to the chain in one transaction.
+    ///
+    /// The transaction takes SQLite's write lock before reading the chain
+    /// head, so several processes appending to the same database each link
+    /// to the entry actually written before theirs.
+    pub(crate) fn append(conn: &mut Connection, entries: &[AuditEntry]) -> NakamaResult<()> {
+        let tx = conn
+            .transaction_with_behavior(TransactionBehavior::Immediate)
+            .map_err(|e| NakamaError::Audit {
+                message: format!("Failed to start audit transaction: {e}"),
+            })?;
 
         // Fetch the previous checksum to chain.
-        let prev_checksum: String = conn
+        let mut prev_checksum: String = tx
             .query_row(
                 "SELECT checksum FROM audit_entries ORDER BY rowid DESC LIMIT 1",
                 [],
@@ -131,54 +181,41 @@ impl AuditLog {
             )
             .unwrap_or_else(|_| "GENESIS".to_string());
 
-        let detail_str = serde_json::to_string(&entry.detail).unwrap_or_default();
-        let timestamp_str = entry.timestamp.to_rfc3339();
-
-        // Compute the checksum: SHA-256(prev_checksum || id || timestamp || tool || action || outcome).
-        let checksum = Self::compute_checksum(
-            &prev_checksum,
-            &entry.id,
-            &timestamp_str,
-            &entry.tool,
-            &entry.action,
-            &entry.outcome.to_string(),
-        );
-
-        conn.execute(
-            "INSERT INTO audit_entries (id, timestamp, trace_id, tool, command, category, action, detail, outcome, duration_ms, checksum)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
-            params![
-                entry.id,
-                timestamp_str,
-                entry.trace_id,
-                entry.tool,
-                entry.command,
-                entry.category.to_string(),
-                entry.action,
-                detail_str,
-                entry.outcome.to_string(),
-                entry.duration_ms as i64,
-                checksum,
-            ],
-        )
-        .map_err(|e| NakamaError::Audit {
-            message: format!("Failed to insert audit entry: {e}"),
-        })?;
+        for entry in entries {
+            let columns = Self::entry_values(entry);
+            let checksum = Self::compute_checksum_v2(&prev_checksum, &columns);
+
+            tx.execute(
+                &format!(
+                    "INSERT INTO audit_entries ({ENTRY_COLUMNS}, checksum, chain_version)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16)"
+                ),
+                params_from_iter(columns.iter().chain([
+                    &Value::Text(checksum.clone()),
+                    &Value::Integer(i64::from(CHAIN_VERSION)),
+                ])),
+            )
+            .map_err(|e| NakamaError::Audit {
+                message: format!("Failed to insert audit entry: {e}"),
+            })?;
 
-        tracing::debug!(
-            audit_id = %entry.id,
-            checksum = %checksum,
-            "Audit entry recorded"
-        );
+            tracing::debug!(
+                audit_id = %entry.id,
+                checksum = %checksum,
+                "Audit entry recorded"
+            );
+            prev_checksum = checksum;
+        }
 
-        Ok(())
+        tx.commit().map_err(|e| NakamaError::Audit {
+            message: format!("Failed to commit audit entries: {e}"),
+        })
     }
 
     /// Query audit entries matching the given filter.
     pub fn query(&self, filter: &AuditFilter) -> NakamaResult<Vec<AuditEntry>> {
-        let conn = self.conn.lock().map_err(|e| NakamaError::Audit {
-            message: format!("Failed to acquire audit database lock: {e}"),
-        })?;
+        self.flush()?;
+        let conn = self.lock()?;
 
         // Build a dynamic WHERE clause.
         let mut clauses: Vec<String> = Vec::new();
@@ -221,7 +258,7 @@ impl AuditLog {
         };
 
         let sql = format!(
-            "SELECT id, timestamp, trace_id, tool, command, category, action, detail, outcome, duration_ms
+            "SELECT {ENTRY_COLUMNS}
              FROM audit_entries
              {where_clause}
              ORDER BY timestamp DESC
@@ -236,32 +273,7 @@ impl AuditLog {
         })?;
 
         let entries = stmt
-            .query_map(params_refs.as_slice(), |row| {
-                let timestamp_str: String = row.get(1)?;
-                let category_str: String = row.get(5)?;
-                let detail_str: String = row.get(7)?;
-                let outcome_str: String = row.get(8)?;
-                let duration: i64 = row.get(9)?;
-
-                Ok(AuditEntry {
-                    id: row.get(0)?,
-                    timestamp: DateTime::parse_from_rfc3339(&timestamp_str)
-                        .map(|dt| dt.with_timezone(&Utc))
-                        .unwrap_or_else(|_| Utc::now()),
-                    trace_id: row.get(2)?,
-                    tool: row.get(3)?,
-                    command: row.get(4)?,
-                    category: category_str
-                        .parse::<Category>()
-                        .unwrap_or(Category::ToolExecution),
-                    action: row.get(6)?,
-                    detail: serde_json::from_str(&detail_str).unwrap_or(serde_json::Value::Null),
-                    outcome: outcome_str
-                        .parse::<Outcome>()
-                        .unwrap_or(Outcome::Failure),
-                    duration_ms: duration as u64,
-                })
-            })
+            .query_map(params_refs.as_slice(), Self::row_to_entry)
             .map_err(|e| NakamaError::Audit {
                 message: format!("Failed to execute audit query: {e}"),
             })?;
@@ -277,48 +289,110 @@ impl AuditLog {
         Ok(results)
     }
 
+    /// Look up a single entry by its id, or by a prefix of the id that
+    /// matches exactly one entry.
+    ///
+    /// Returns `Ok(None)` if nothing matches and an error if the prefix is
+    /// ambiguous.
+    pub fn get(&self, id: &str) -> NakamaResult<Option<AuditEntry>> {
+        self.flush()?;
+        let conn = self.lock()?;
+
+        let mut stmt = conn
+            .prepare(&format!(
+                "SELECT {ENTRY_COLUMNS} FROM audit_entries
+                 WHERE id = ?1 OR substr(id, 1, length(?1)) = ?1
+                 ORDER BY id = ?1 DESC
+                 LIMIT 2"
+            ))
+            .map_err(|e| NakamaError::Audit {
+                message: format!("Failed to prepare audit lookup: {e}"),
+            })?;
+
+        let mut matches = stmt
+            .query_map(params![id], Self::row_to_entry)
+            .and_then(|rows| rows.collect::<Result<Vec<_>, _>>())
+            .map_err(|e| NakamaError::Audit {
+                message: format!("Failed to look up audit entry {id}: {e}"),
+            })?;
+
+        match matches.len() {
+            0 => Ok(None),
+            1 => Ok(matches.pop()),
+            _ if matches[0].id == id => Ok(Some(matches.swap_remove(0))),
+            _ => Err(NakamaError::Audit {
+                message: format!("Audit entry id prefix '{id}' is ambiguous"),
+            }),
+        }
+    }
+
     /// Verify the integrity of the hash chain.
     ///
     /// Returns `Ok(true)` if the chain is valid, `Ok(false)` if tampering
     /// is detected, or an error if the database cannot be read.
     pub fn verify_chain(&self) -> NakamaResult<bool> {
-        let conn = self.conn.lock().map_err(|e| NakamaError::Audit {
-            message: format!("Failed to acquire audit database lock: {e}"),
-        })?;
+        Ok(self.verify_chain_report()?.is_valid())
+    }
+
+    /// Verify the hash chain and report how many entries were checked and
+    /// the first entry whose checksum does not match.
+    pub fn verify_chain_report(&self) -> NakamaResult<ChainReport> {
+        self.flush()?;
+        let conn = self.lock()?;
 
         let mut stmt = conn
-            .prepare(
-                "SELECT id, timestamp, tool, action, outcome, checksum
-                 FROM audit_entries ORDER BY rowid ASC",
-            )
+            .prepare(&format!(
+                "SELECT {ENTRY_COLUMNS}, checksum, chain_version FROM audit_entries ORDER BY rowid ASC"
+            ))
             .map_err(|e| NakamaError::Audit {
                 message: format!("Failed to prepare verification query: {e}"),
             })?;
-
         let rows = stmt
             .query_map([], |row| {
+                let columns = (0..ENTRY_COLUMN_COUNT)
+                    .map(|idx| row.get::<_, Value>(idx))
+                    .collect::<rusqlite::Result<Vec<_>>>()?;
                 Ok((
-                    row.get::<_, String>(0)?,
-                    row.get::<_, String>(1)?,
-                    row.get::<_, String>(2)?,
-                    row.get::<_, String>(3)?,
-                    row.get::<_, String>(4)?,
-                    row.get::<_, String>(5)?,
+                    columns,
+                    row.get::<_, String>(ENTRY_COLUMN_COUNT)?,
+                    row.get::<_, Option<u32>>(ENTRY_COLUMN_COUNT + 1)?,
                 ))
             })
             .map_err(|e| NakamaError::Audit {
                 message: format!("Failed to query for verification: {e}"),
             })?;
 
+        let mut report = ChainReport::default();
         let mut prev_checksum = "GENESIS".to_string();
         for row_result in rows {
-            let (id, timestamp, tool, action, outcome, stored_checksum) =
+            let (columns, stored_checksum, version) =
                 row_result.map_err(|e| NakamaError::Audit {
                     message: format!("Failed to read verification row: {e}"),
                 })?;
-
-            let expected =
-                Self::compute_checksum(&prev_checksum, &id, &timestamp, &tool, &action, &outcome);
+            let column = |idx: usize| match &columns[idx] {
+                Value::Text(text) => text.as_str(),
+                _ => "",
+            };
+            let (id, timestamp, tool, action) = (column(0), column(1), column(3), column(6));
+
+            // A retention checkpoint may only head the chain; it stands in
+            // for the pruned entries and carries their last checksum.
+            let is_checkpoint =
+                tool == retention::CHECKPOINT_TOOL && action == retention::CHECKPOINT_ACTION;
+            let expected = if is_checkpoint && report.entries == 0 {
+                report.checkpoint = Some(id.to_string());
+                let signed = serde_json::from_str::<retention::CheckpointDetail>(column(7))
+                    .ok()
+                    .and_then(|detail| Some((detail.signer(id, timestamp)?, detail.anchor)));
+                let (key, anchor) = signed.unzip();
+                report.checkpoint_key = key;
+                anchor.unwrap_or_default()
+            } else if version.is_some_and(|v| v >= 2) {
+                Self::compute_checksum_v2(&prev_checksum, &columns)
+            } else {
+                Self::compute_checksum(&prev_checksum, id, timestamp, tool, action, column(8))
+            };
+            let id = id.to_string();
 
             if expected != stored_checksum {
                 tracing::warn!(
@@ -327,22 +401,90 @@ impl AuditLog {
                     stored = %stored_checksum,
                     "Audit chain integrity violation detected"
                 );
-                return Ok(false);
+                report.broken_at = Some(id);
+                return Ok(report);
             }
 
+            report.entries += 1;
+            report.head = Some(stored_checksum.clone());
             prev_checksum = stored_checksum;
         }
 
-        Ok(true)
+        Ok(report)
     }
 
     // --- Private helpers ---
 
-    fn db_path() -> NakamaResult<PathBuf> {
+    pub(crate) fn lock(&self) -> NakamaResult<MutexGuard<'_, Connection>> {
+        self.conn.lock().map_err(|e| NakamaError::Audit {
+            message: format!("Failed to acquire audit database lock: {e}"),
+        })
+    }
+
+    pub(crate) fn dir(&self) -> Option<&Path> {
+        self.dir.as_deref()
+    }
+
+    /// The values stored in [`ENTRY_COLUMNS`] for `entry`, in that order.
+    fn entry_values(entry: &AuditEntry) -> [Value; ENTRY_COLUMN_COUNT] {
+        let text = |s: &str| Value::Text(s.to_string());
+        let integer = |n: Option<u64>| n.map_or(Value::Null, |n| Value::Integer(n as i64));
+        [
+            text(&entry.id),
+            text(&entry.timestamp.to_rfc3339()),
+            text(&entry.trace_id),
+            text(&entry.tool),
+            text(&entry.command),
+            text(&entry.category.to_string()),
+            text(&entry.action),
+            text(&serde_json::to_string(&entry.detail).unwrap_or_default()),
+            text(&entry.outcome.to_string()),
+            Value::Integer(entry.duration_ms as i64),
+            integer(entry.input_tokens),
+            integer(entry.output_tokens),
+            entry.cost_usd.map_or(Value::Null, Value::Real),
+            entry.repo.as_deref().map_or(Value::Null, text),
+        ]
+    }
+
+    /// Map a row selected with [`ENTRY_COLUMNS`] to an entry.
+    pub(crate) fn row_to_entry(row: &rusqlite::Row<'_>) -> rusqlite::Result<AuditEntry> {
+        let timestamp_str: String = row.get(1)?;
+        let category_str: String = row.get(5)?;
+        let detail_str: String = row.get(7)?;
+        let outcome_str: String = row.get(8)?;
+        let duration: i64 = row.get(9)?;
+
+        Ok(AuditEntry {
+            id: row.get(0)?,
+            timestamp: DateTime::parse_from_rfc3339(&timestamp_str)
+                .map(|dt| dt.with_timezone(&Utc))
+                .unwrap_or_else(|_| Utc::now()),
+            trace_id: row.get(2)?,
+            tool: row.get(3)?,
+            command: row.get(4)?,
+            category: category_str
+                .parse::<Category>()
+                .unwrap_or(Category::ToolExecution),
+            action: row.get(6)?,
+            detail: serde_json::from_str(&detail_str).unwrap_or(serde_json::Value::Null),
+            outcome: outcome_str.parse::<Outcome>().unwrap_or(Outcome::Failure),
+            duration_ms: duration as u64,
+            input_tokens: row.get::<_, Option<i64>>(10)?.map(|t| t as u64),
+            output_tokens: row.get::<_, Option<i64>>(11)?.map(|t| t as u64),
+            cost_usd: row.get(12)?,
+            repo: row.get(13)?,
+        })
+    }
+
+    pub(crate) fn db_path() -> NakamaResult<PathBuf> {
         let audit_dir = nakama_core::paths::audit_dir()?;
         Ok(audit_dir.join("audit.db"))
     }
 
+    /// Version 1 checksum: SHA-256(prev_checksum || id || timestamp || tool
+    /// || action || outcome).  Only verified for entries written before
+    /// [`CHAIN_VERSION`] 2.
     fn compute_checksum(
         prev_checksum: &str,
         id: &str,
@@ -365,6 +507,32 @@ impl AuditLog {
         hasher.update(outcome.as_bytes());
         format!("{:x}", hasher.finalize())
     }
+
+    /// Version 2 checksum, over the previous checksum and every column of
+    /// the entry as stored.  Each value is tagged with its type and text is
+    /// length-prefixed, so distinct rows never hash the same bytes.
+    fn compute_checksum_v2(prev_checksum: &str, columns: &[Value]) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(b"v2|");
+        hasher.update(prev_checksum.as_bytes());
+        for column in columns {
+            hasher.update(b"|");
+            match column {
+                Value::Null => hasher.update(b"n"),
+                Value::Integer(n) => hasher.update(format!("i{n}")),
+                Value::Real(x) => hasher.update(format!("r{:016x}", x.to_bits())),
+                Value::Text(text) => {
+                    hasher.update(format!("t{}:", text.len()));
+                    hasher.update(text.as_bytes());
+                }
+                Value::Blob(bytes) => {
+                    hasher.update(format!("b{}:", bytes.len()));
+                    hasher.update(bytes);
+                }
+            }
+        }
+        format!("{:x}", hasher.finalize())
+    }
 }
 
 #[cfg(test)]
@@ -457,6 +625,131 @@ mod tests {
         assert!(log.verify_chain().unwrap());
     }
 
+    #[test]
+    fn test_chain_report_finds_tampered_entry() {
+        let log = test_log();
+        for i in 0..3 {
+            log.log(AuditEntry::new(
+                &format!("tr_{i}"),
+                "test",
+                "cmd",
+                Category::ToolExecution,
+                &format!("action {i}"),
+                serde_json::json!({}),
+                Outcome::Success,
+                0,
+            ))
+            .unwrap();
+        }
+        let second = log.query(&AuditFilter::new()).unwrap()[1].id.clone();
+        log.conn
+            .lock()
+            .unwrap()
+            .execute(
+                "UPDATE audit_entries SET action = 'rewritten' WHERE id = ?1",
+                params![second],
+            )
+            .unwrap();
+
+        let report = log.verify_chain_report().unwrap();
+        assert_eq!(report.entries, 1);
+        assert_eq!(report.broken_at, Some(second));
+        assert!(!log.verify_chain().unwrap());
+    }
+
+    #[test]
+    fn test_chain_detects_rewritten_cost() {
+        let log = test_log();
+        for i in 0..3 {
+            log.log(
+                AuditEntry::new(
+                    &format!("tr_{i}"),
+                    "byakugan",
+                    "review",
+                    Category::AiInteraction,
+                    "Completion",
+                    serde_json::json!({}),
+                    Outcome::Success,
+                    0,
+                )
+                .with_usage(1200, 300, 0.0125),
+            )
+            .unwrap();
+        }
+        assert!(log.verify_chain().unwrap());
+        let second = log.query(&AuditFilter::new()).unwrap()[1].id.clone();
+
+        let conn = log.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE audit_entries SET cost_usd = 0.0001 WHERE id = ?1",
+            params![second],
+        )
+        .unwrap();
+        drop(conn);
+        assert_eq!(
+            log.verify_chain_report().unwrap().broken_at,
+            Some(second.clone())
+        );
+
+        // Claiming the entry predates version 2 does not help either.
+        let conn = log.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE audit_entries SET chain_version = NULL WHERE id = ?1",
+            params![second],
+        )
+        .unwrap();
+        drop(conn);
+        assert_eq!(log.verify_chain_report().unwrap().broken_at, Some(second));
+    }
+
+    #[test]
+    fn test_legacy_entries_verify_with_version_1() {
+        let log = test_log();
+        let mut prev = "GENESIS".to_string();
+        for i in 0..2 {
+            let id = format!("aud_legacy{i}");
+            let timestamp = Utc::now().to_rfc3339();
+            let checksum =
+                AuditLog::compute_checksum(&prev, &id, &timestamp, "kami", "ask", "success");
+            log.conn
+                .lock()
+                .unwrap()
+                .execute(
+                    "INSERT INTO audit_entries (id, timestamp, trace_id, tool, command, category, action, detail, outcome, duration_ms, checksum, cost_usd)
+                     VALUES (?1, ?2, 'tr', 'kami', 'ask', 'ai_interaction', 'ask', '{}', 'success', 0, ?3, 0.5)",
+                    params![id, timestamp, checksum],
+                )
+                .unwrap();
+            prev = checksum;
+        }
+        log.log(numbered(0)).unwrap();
+
+        let report = log.verify_chain_report().unwrap();
+        assert!(report.is_valid());
+        assert_eq!(report.entries, 3);
+    }
+
+    #[test]
+    fn test_get_by_id_and_prefix() {
+        let log = test_log();
+        let entry = AuditEntry::new(
+            "tr_get",
+            "zangetsu",
+            "ask",
+            Category::AiInteraction,
+            "Asked a question",
+            serde_json::json!({}),
+            Outcome::Success,
+            5,
+        );
+        let id = entry.id.clone();
+        log.log(entry).unwrap();
+
+        assert_eq!(log.get(&id).unwrap().unwrap().trace_id, "tr_get");
+        assert_eq!(log.get(&id[..12]).unwrap().unwrap().id, id);
+        assert!(log.get("aud_missing").unwrap().is_none());
+    }
+
     #[test]
     fn test_limit_filter() {
         let log = test_log();
@@ -477,4 +770,111 @@ mod tests {
         let results = log.query(&filter).unwrap();
         assert_eq!(results.len(), 3);
     }
+
+    fn numbered(i: usize) -> AuditEntry {
+        AuditEntry::new(
+            &format!("tr_{i}"),
+            "test",
+            "cmd",
+            Category::ToolExecution,
+            &format!("action {i}"),
+            serde_json::json!({}),
+            Outcome::Success,
+            0,
+        )
+    }
+
+    fn temp_db(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("nakama-store-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir.join("audit.db")
+    }
+
+    #[test]
+    fn test_batched_writes_flush_on_size_and_drop() {
+        let path = temp_db("batch");
+        let log = AuditLog::open_at(&path)
+            .unwrap()
+            .with_batching(Duration::from_secs(3600), 3)
+            .unwrap();
+        let reader = AuditLog::open_at(&path).unwrap();
+
+        for i in 0..4 {
+            log.log(numbered(i)).unwrap();
+        }
+        // A full batch is written without waiting for the interval.
+        let deadline = std::time::Instant::now() + Duration::from_secs(5);
+        while reader.query(&AuditFilter::new()).unwrap().len() < 3 {
+            assert!(
+                std::time::Instant::now() < deadline,
+                "batch was not written"
+            );
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(reader.query(&AuditFilter::new()).unwrap().len(), 3);
+
+        // Reads through the batching handle see its own queued entries.
+        assert_eq!(log.query(&AuditFilter::new()).unwrap().len(), 4);
+        log.log(numbered(4)).unwrap();
+        drop(log);
+        assert_eq!(reader.query(&AuditFilter::new()).unwrap().len(), 5);
+        assert!(reader.verify_chain().unwrap());
+
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn test_flush_persists_entries_when_drop_is_skipped() {
+        let path = temp_db("flush-exit");
+        let log = AuditLog::open_at(&path)
+            .unwrap()
+            .with_batching(Duration::from_secs(3600), 100)
+            .unwrap();
+        let mut failed = numbered(0);
+        failed.outcome = Outcome::Denied;
+        log.log(failed).unwrap();
+
+        // An error path that calls `process::exit` never runs `Drop`.
+        log.flush().unwrap();
+        std::mem::forget(log);
+
+        let reader = AuditLog::open_at(&path).unwrap();
+        let entries = reader.query(&AuditFilter::new()).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].outcome, Outcome::Denied);
+
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn test_concurrent_writers_keep_one_chain() {
+        let path = temp_db("concurrent");
+        let writers: Vec<_> = (0..4)
+            .map(|w| {
+                let path = path.clone();
+                std::thread::spawn(move || {
+                    // Separate connections, as separate processes would have.
+                    let log = AuditLog::open_at(&path)
+                        .unwrap()
+                        .with_batching(Duration::from_millis(5), 4)
+                        .unwrap();
+                    for i in 0..25 {
+                        log.log(numbered(w * 100 + i)).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for writer in writers {
+            writer.join().unwrap();
+        }
+
+        let log = AuditLog::open_at(&path).unwrap();
+        let report = log.verify_chain_report().unwrap();
+        assert!(report.is_valid(), "{report:?}");
+        assert_eq!(report.entries, 100);
+
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
 }
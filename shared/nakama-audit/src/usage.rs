@@ -0,0 +1,399 @@
+//! AI usage and spend ledger.
+//!
+//! Every AI completion is recorded as a [`UsageRecord`] in the `ai_usage`
+//! table of the audit database, so spend can be summed for budget
+//! enforcement, and as an `ai_interaction` audit entry whose detail carries
+//! the same fields.  [`UsageReport`] aggregates those audit entries for the
+//! `nakama usage` dashboard.
+
+use crate::entry::{AuditEntry, Category, Outcome};
+use crate::store::AuditLog;
+use chrono::{DateTime, NaiveDate, Utc};
+use nakama_core::config::AuditConfig;
+use nakama_core::error::{NakamaError, NakamaResult};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::Mutex;
+
+/// Token usage and estimated cost of a single AI completion.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UsageRecord {
+    /// When the completion finished.
+    pub timestamp: DateTime<Utc>,
+
+    /// Which Nakama tool made the request.
+    pub tool: String,
+
+    /// Provider that served the request (e.g. `"anthropic"`).
+    pub provider: String,
+
+    /// Model that served the request.
+    pub model: String,
+
+    /// Number of prompt tokens.
+    pub input_tokens: u32,
+
+    /// Number of generated tokens.
+    pub output_tokens: u32,
+
+    /// Estimated cost in USD.
+    pub cost_usd: f64,
+}
+
+impl UsageRecord {
+    /// The audit entry `detail` describing this completion.
+    pub fn to_audit_detail(&self) -> serde_json::Value {
+        serde_json::json!({
+            "provider": self.provider,
+            "model": self.model,
+            "input_tokens": self.input_tokens,
+            "output_tokens": self.output_tokens,
+            "cost_usd": self.cost_usd,
+        })
+    }
+
+    /// Recover a usage record from a successful `ai_interaction` audit entry.
+    ///
+    /// Returns `None` for entries that don't carry token counts, such as
+    /// per-command summaries logged by the tools themselves.
+    pub fn from_audit_entry(entry: &AuditEntry) -> Option<Self> {
+        if entry.category != Category::AiInteraction || entry.outcome != Outcome::Success {
+            return None;
+        }
+        let detail = &entry.detail;
+        Some(Self {
+            timestamp: entry.timestamp,
+            tool: entry.tool.clone(),
+            provider: detail.get("provider")?.as_str()?.to_string(),
+            model: detail.get("model")?.as_str()?.to_string(),
+            input_tokens: detail.get("input_tokens")?.as_u64()? as u32,
+            output_tokens: detail.get("output_tokens")?.as_u64()? as u32,
+            cost_usd: detail.get("cost_usd").and_then(|c| c.as_f64()).unwrap_or(0.0),
+        })
+    }
+}
+
+/// Aggregated usage for one group (a tool, a model, or everything).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct UsageTotals {
+    pub requests: u64,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cost_usd: f64,
+}
+
+impl UsageTotals {
+    fn add(&mut self, record: &UsageRecord) {
+        self.requests += 1;
+        self.input_tokens += record.input_tokens as u64;
+        self.output_tokens += record.output_tokens as u64;
+        self.cost_usd += record.cost_usd;
+    }
+
+    /// Input plus output tokens.
+    pub fn total_tokens(&self) -> u64 {
+        self.input_tokens + self.output_tokens
+    }
+}
+
+/// Usage of one tool with one provider and model.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UsageRow {
+    pub tool: String,
+    pub provider: String,
+    pub model: String,
+    #[serde(flatten)]
+    pub totals: UsageTotals,
+}
+
+/// Spend on a single (UTC) day.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DailyUsage {
+    pub date: NaiveDate,
+    #[serde(flatten)]
+    pub totals: UsageTotals,
+}
+
+/// AI usage over a time window, grouped for display.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UsageReport {
+    pub since: DateTime<Utc>,
+    pub until: DateTime<Utc>,
+    pub total: UsageTotals,
+    /// One row per (tool, provider, model), most expensive first.
+    pub rows: Vec<UsageRow>,
+    /// Totals per tool, most expensive first.
+    pub by_tool: Vec<(String, UsageTotals)>,
+    /// Totals per model, most expensive first.
+    pub by_model: Vec<(String, UsageTotals)>,
+    /// One entry per day in the window, including days without usage.
+    pub daily: Vec<DailyUsage>,
+}
+
+impl UsageReport {
+    /// Build a report from audit entries; entries without usage data and
+    /// entries outside `since..=until` are ignored.
+    pub fn from_entries(entries: &[AuditEntry], since: DateTime<Utc>, until: DateTime<Utc>) -> Self {
+        let mut total = UsageTotals::default();
+        let mut rows: BTreeMap<(String, String, String), UsageTotals> = BTreeMap::new();
+        let mut by_tool: BTreeMap<String, UsageTotals> = BTreeMap::new();
+        let mut by_model: BTreeMap<String, UsageTotals> = BTreeMap::new();
+        let mut daily: BTreeMap<NaiveDate, UsageTotals> = BTreeMap::new();
+
+        let mut day = since.date_naive();
+        while day <= until.date_naive() {
+            daily.insert(day, UsageTotals::default());
+            day = match day.succ_opt() {
+                Some(next) => next,
+                None => break,
+            };
+        }
+
+        let records = entries
+            .iter()
+            .filter(|e| e.timestamp >= since && e.timestamp <= until)
+            .filter_map(UsageRecord::from_audit_entry);
+
+        for record in records {
+            total.add(&record);
+            rows.entry((record.tool.clone(), record.provider.clone(), record.model.clone()))
+                .or_default()
+                .add(&record);
+            by_tool.entry(record.tool.clone()).or_default().add(&record);
+            by_model.entry(record.model.clone()).or_default().add(&record);
+            daily.entry(record.timestamp.date_naive()).or_default().add(&record);
+        }
+
+        let mut rows: Vec<UsageRow> = rows
+            .into_iter()
+            .map(|((tool, provider, model), totals)| UsageRow {
+                tool,
+                provider,
+                model,
+                totals,
+            })
+            .collect();
+        rows.sort_by(|a, b| b.totals.cost_usd.total_cmp(&a.totals.cost_usd));
+
+        Self {
+            since,
+            until,
+            total,
+            rows,
+            by_tool: sorted_by_cost(by_tool),
+            by_model: sorted_by_cost(by_model),
+            daily: daily
+                .into_iter()
+                .map(|(date, totals)| DailyUsage { date, totals })
+                .collect(),
+        }
+    }
+}
+
+fn sorted_by_cost(groups: BTreeMap<String, UsageTotals>) -> Vec<(String, UsageTotals)> {
+    let mut groups: Vec<(String, UsageTotals)> = groups.into_iter().collect();
+    groups.sort_by(|a, b| b.1.cost_usd.total_cmp(&a.1.cost_usd));
+    groups
+}
+
+/// SQLite-backed ledger of AI usage, stored alongside the audit log.
+pub struct UsageLedger {
+    conn: Mutex<Connection>,
+}
+
+impl UsageLedger {
+    /// Open (or create) the ledger in the audit database at
+    /// `~/.nakama/audit/audit.db`.
+    pub fn new(_config: &AuditConfig) -> NakamaResult<Self> {
+        let db_path = AuditLog::db_path()?;
+
+        if let Some(parent) = db_path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| NakamaError::Audit {
+                message: format!("Failed to create audit directory: {e}"),
+            })?;
+        }
+
+        Self::open_at(&db_path)
+    }
+
+    /// Open a ledger at a custom path (useful for testing).
+    pub fn open_at(path: &Path) -> NakamaResult<Self> {
+        let conn = Connection::open(path).map_err(|e| NakamaError::Audit {
+            message: format!("Failed to open usage database at {}: {e}", path.display()),
+        })?;
+        conn.busy_timeout(crate::store::BUSY_TIMEOUT)
+            .map_err(|e| NakamaError::Audit {
+                message: format!("Failed to set usage busy timeout: {e}"),
+            })?;
+
+        conn.execute_batch(
+            "PRAGMA journal_mode = WAL;
+
+            CREATE TABLE IF NOT EXISTS ai_usage (
+                id            INTEGER PRIMARY KEY AUTOINCREMENT,
+                timestamp     TEXT NOT NULL,
+                tool          TEXT NOT NULL,
+                provider      TEXT NOT NULL,
+                model         TEXT NOT NULL,
+                input_tokens  INTEGER NOT NULL,
+                output_tokens INTEGER NOT NULL,
+                cost_usd      REAL NOT NULL
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_usage_timestamp ON ai_usage(timestamp);",
+        )
+        .map_err(|e| NakamaError::Audit {
+            message: format!("Failed to initialize usage table: {e}"),
+        })?;
+
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    /// Append a usage record.
+    pub fn record(&self, record: &UsageRecord) -> NakamaResult<()> {
+        let conn = self.lock()?;
+        conn.execute(
+            "INSERT INTO ai_usage (timestamp, tool, provider, model, input_tokens, output_tokens, cost_usd)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                record.timestamp.to_rfc3339(),
+                record.tool,
+                record.provider,
+                record.model,
+                record.input_tokens,
+                record.output_tokens,
+                record.cost_usd,
+            ],
+        )
+        .map_err(|e| NakamaError::Audit {
+            message: format!("Failed to insert usage record: {e}"),
+        })?;
+        Ok(())
+    }
+
+    /// Total estimated spend (USD) of all completions since `since`.
+    pub fn total_cost_since(&self, since: DateTime<Utc>) -> NakamaResult<f64> {
+        let conn = self.lock()?;
+        conn.query_row(
+            "SELECT COALESCE(SUM(cost_usd), 0.0) FROM ai_usage WHERE timestamp >= ?1",
+            params![since.to_rfc3339()],
+            |row| row.get(0),
+        )
+        .map_err(|e| NakamaError::Audit {
+            message: format!("Failed to sum usage costs: {e}"),
+        })
+    }
+
+    fn lock(&self) -> NakamaResult<std::sync::MutexGuard<'_, Connection>> {
+        self.conn.lock().map_err(|e| NakamaError::Audit {
+            message: format!("Failed to acquire usage database lock: {e}"),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn record(timestamp: DateTime<Utc>, cost_usd: f64) -> UsageRecord {
+        UsageRecord {
+            timestamp,
+            tool: "byakugan".to_string(),
+            provider: "anthropic".to_string(),
+            model: "claude-sonnet-4-6".to_string(),
+            input_tokens: 1000,
+            output_tokens: 200,
+            cost_usd,
+        }
+    }
+
+    #[test]
+    fn test_total_cost_since() {
+        let ledger = UsageLedger::open_at(Path::new(":memory:")).unwrap();
+        assert_eq!(ledger.total_cost_since(Utc::now() - Duration::days(7)).unwrap(), 0.0);
+
+        let now = Utc::now();
+        ledger.record(&record(now - Duration::days(10), 5.0)).unwrap();
+        ledger.record(&record(now - Duration::days(1), 0.25)).unwrap();
+        ledger.record(&record(now, 0.5)).unwrap();
+
+        let total = ledger.total_cost_since(now - Duration::days(7)).unwrap();
+        assert!((total - 0.75).abs() < 1e-9);
+    }
+
+    fn audit_entry(record: &UsageRecord) -> AuditEntry {
+        let mut entry = AuditEntry::new(
+            "tr_usage",
+            &record.tool,
+            "ai_completion",
+            Category::AiInteraction,
+            "Completion",
+            record.to_audit_detail(),
+            Outcome::Success,
+            100,
+        );
+        entry.timestamp = record.timestamp;
+        entry
+    }
+
+    #[test]
+    fn test_record_round_trips_through_audit_entry() {
+        let original = record(Utc::now(), 0.125);
+        let parsed = UsageRecord::from_audit_entry(&audit_entry(&original)).unwrap();
+        assert_eq!(parsed.model, original.model);
+        assert_eq!(parsed.input_tokens, 1000);
+        assert_eq!(parsed.cost_usd, 0.125);
+
+        // Tool-level summaries without token counts are not usage records.
+        let summary = AuditEntry::new(
+            "tr_x",
+            "kami",
+            "ask",
+            Category::AiInteraction,
+            "Q&A",
+            serde_json::json!({"model": "m", "provider": "p"}),
+            Outcome::Success,
+            5,
+        );
+        assert!(UsageRecord::from_audit_entry(&summary).is_none());
+    }
+
+    #[test]
+    fn test_usage_report_groups_and_fills_days() {
+        let until = Utc::now();
+        let since = until - Duration::days(6);
+
+        let mut senku = record(until - Duration::days(2), 0.10);
+        senku.tool = "senku".to_string();
+        let mut haiku = record(until, 0.40);
+        haiku.model = "claude-haiku-4-5".to_string();
+        let entries: Vec<AuditEntry> = [
+            record(until - Duration::days(30), 9.0), // outside the window
+            record(until - Duration::days(2), 0.25),
+            senku,
+            haiku,
+        ]
+        .iter()
+        .map(audit_entry)
+        .collect();
+
+        let report = UsageReport::from_entries(&entries, since, until);
+        assert_eq!(report.total.requests, 3);
+        assert_eq!(report.total.total_tokens(), 3600);
+        assert!((report.total.cost_usd - 0.75).abs() < 1e-9);
+
+        assert_eq!(report.by_tool[0].0, "byakugan");
+        assert!((report.by_tool[0].1.cost_usd - 0.65).abs() < 1e-9);
+        assert_eq!(report.by_model.len(), 2);
+        assert_eq!(report.rows.len(), 3);
+        assert_eq!(report.rows[0].model, "claude-haiku-4-5");
+
+        assert_eq!(report.daily.len(), 7);
+        assert_eq!(report.daily.iter().filter(|d| d.totals.requests > 0).count(), 2);
+    }
+}
@@ -0,0 +1,145 @@
+//! Background writer for batched audit appends.
+//!
+//! With `[audit] batch_write` on, `AuditLog::log` only queues the entry.  A
+//! writer thread appends queued entries in a single transaction once
+//! `max_batch_size` are waiting or
+//! `batch_flush_interval_ms` has passed since the first of them, whichever
+//! comes first.  Reads flush the queue before they run, and dropping the
+//! `AuditLog` flushes whatever is left and waits for the writer to finish.
+
+use crate::entry::AuditEntry;
+use crate::store::AuditLog;
+use nakama_core::error::{NakamaError, NakamaResult};
+use rusqlite::Connection;
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+enum Message {
+    Append(Box<AuditEntry>),
+    /// Write everything queued so far and report the result.
+    Flush(Sender<NakamaResult<()>>),
+}
+
+/// Handle to the writer thread; dropping it flushes and joins the thread.
+pub(crate) struct BatchWriter {
+    sender: Option<Sender<Message>>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl BatchWriter {
+    pub(crate) fn spawn(
+        conn: Arc<Mutex<Connection>>,
+        interval: Duration,
+        max_batch_size: usize,
+    ) -> NakamaResult<Self> {
+        let (sender, receiver) = mpsc::channel();
+        let handle = std::thread::Builder::new()
+            .name("nakama-audit-writer".to_string())
+            .spawn(move || run(&conn, &receiver, interval, max_batch_size.max(1)))
+            .map_err(|e| NakamaError::Audit {
+                message: format!("Failed to start audit writer: {e}"),
+            })?;
+        Ok(Self {
+            sender: Some(sender),
+            handle: Some(handle),
+        })
+    }
+
+    /// Queue `entry`; returns without waiting for the write.
+    pub(crate) fn send(&self, entry: AuditEntry) -> NakamaResult<()> {
+        self.sender
+            .as_ref()
+            .and_then(|sender| sender.send(Message::Append(Box::new(entry))).ok())
+            .ok_or_else(stopped)
+    }
+
+    /// Write every queued entry and wait for the result.
+    pub(crate) fn flush(&self) -> NakamaResult<()> {
+        let (ack, result) = mpsc::channel();
+        self.sender
+            .as_ref()
+            .and_then(|sender| sender.send(Message::Flush(ack)).ok())
+            .ok_or_else(stopped)?;
+        result.recv().map_err(|_| stopped())?
+    }
+}
+
+impl Drop for BatchWriter {
+    fn drop(&mut self) {
+        // Closing the channel makes the writer flush and exit.
+        drop(self.sender.take());
+        if let Some(handle) = self.handle.take() {
+            if handle.join().is_err() {
+                tracing::error!("Audit writer panicked; queued entries may be lost");
+            }
+        }
+    }
+}
+
+fn run(
+    conn: &Mutex<Connection>,
+    receiver: &mpsc::Receiver<Message>,
+    interval: Duration,
+    max_batch_size: usize,
+) {
+    let mut pending: Vec<AuditEntry> = Vec::new();
+    let mut deadline: Option<Instant> = None;
+
+    loop {
+        let message = match deadline {
+            Some(deadline) => {
+                receiver.recv_timeout(deadline.saturating_duration_since(Instant::now()))
+            }
+            None => receiver.recv().map_err(|_| RecvTimeoutError::Disconnected),
+        };
+
+        match message {
+            Ok(Message::Append(entry)) => {
+                deadline.get_or_insert_with(|| Instant::now() + interval);
+                pending.push(*entry);
+                if pending.len() < max_batch_size {
+                    continue;
+                }
+                let _ = write(conn, &mut pending);
+            }
+            Ok(Message::Flush(ack)) => {
+                let _ = ack.send(write(conn, &mut pending));
+            }
+            Err(RecvTimeoutError::Timeout) => {
+                let _ = write(conn, &mut pending);
+            }
+            Err(RecvTimeoutError::Disconnected) => {
+                let _ = write(conn, &mut pending);
+                return;
+            }
+        }
+        deadline = None;
+    }
+}
+
+/// Append and clear `pending`.  A failed batch is dropped (and logged) rather
+/// than retried, as a synchronous write that failed would be.
+fn write(conn: &Mutex<Connection>, pending: &mut Vec<AuditEntry>) -> NakamaResult<()> {
+    if pending.is_empty() {
+        return Ok(());
+    }
+    let entries = std::mem::take(pending);
+    let result = conn
+        .lock()
+        .map_err(|e| NakamaError::Audit {
+            message: format!("Failed to acquire audit database lock: {e}"),
+        })
+        .and_then(|mut conn| AuditLog::append(&mut conn, &entries));
+    if let Err(e) = &result {
+        tracing::error!(lost = entries.len(), "Failed to write audit batch: {e}");
+    }
+    result
+}
+
+fn stopped() -> NakamaError {
+    NakamaError::Audit {
+        message: "Audit writer has stopped".to_string(),
+    }
+}
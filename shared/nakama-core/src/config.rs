@@ -2,6 +2,8 @@ use crate::error::{NakamaError, NakamaResult};
 use crate::paths;
 use crate::types::*;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 
 /// Global Nakama configuration (loaded from ~/.nakama/config.toml).
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -13,6 +15,7 @@ pub struct Config {
     pub audit: AuditConfig,
     pub ipc: IpcConfig,
     pub updates: UpdatesConfig,
+    pub vault: VaultConfig,
     pub platforms: PlatformsConfig,
     pub byakugan: ByakuganConfig,
 }
@@ -107,12 +110,29 @@ fn default_rule_severity() -> String {
 #[serde(default)]
 pub struct AiConfig {
     pub default_provider: Provider,
+    /// Providers to try, in order, when the default provider is unavailable.
+    pub fallback_providers: Vec<Provider>,
+    /// Exact model to use with the default provider, bypassing the tier
+    /// mapping (set per tool or with `--ai-model`).
+    pub model: Option<String>,
     pub anthropic: ProviderModels,
     pub openai: ProviderModels,
     pub google: ProviderModels,
     pub ollama: OllamaConfig,
+    /// OpenAI-compatible endpoints, by name.
+    pub openai_compatible: HashMap<String, OpenAICompatibleConfig>,
+    /// Which `openai_compatible` endpoint to use; may be omitted when only
+    /// one is configured.
+    pub endpoint: Option<String>,
     pub retry: RetryConfig,
     pub budget: Option<BudgetConfig>,
+    pub cache: CacheConfig,
+    /// Per-model price overrides (USD per million tokens), keyed by model id
+    /// or model id prefix.
+    pub pricing: HashMap<String, ModelPricing>,
+    /// Per-model context window overrides (in tokens), keyed by model id or
+    /// model id prefix.
+    pub context_windows: HashMap<String, usize>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -132,6 +152,30 @@ pub struct OllamaConfig {
     pub model_balanced: String,
 }
 
+/// An endpoint that speaks the OpenAI Chat Completions API.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OpenAICompatibleConfig {
+    /// URL that `/chat/completions` is appended to, usually ending in `/v1`.
+    /// `{model}` is replaced with the model id (for Azure deployment URLs).
+    pub base_url: String,
+    pub model_fast: String,
+    pub model_balanced: String,
+    pub model_powerful: String,
+    /// Header carrying the API key (`"api-key"` for Azure).
+    pub auth_header: String,
+    /// Scheme written before the key in `auth_header`; empty sends the bare
+    /// key.
+    pub auth_scheme: String,
+    /// Extra headers sent with every request.
+    pub headers: HashMap<String, String>,
+    /// Sent as the `api-version` query parameter when set.
+    pub api_version: Option<String>,
+    /// Model ids to send in place of the ones requested, e.g. to map
+    /// `gpt-4.1` to a gateway's route name.
+    pub model_aliases: HashMap<String, String>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
 pub struct RetryConfig {
@@ -139,6 +183,10 @@ pub struct RetryConfig {
     pub initial_backoff_ms: u64,
     pub max_backoff_ms: u64,
     pub backoff_multiplier: f64,
+    /// Consecutive failed attempts before the circuit breaker opens (0 disables it).
+    pub circuit_breaker_threshold: u32,
+    /// How long an open circuit rejects requests before allowing a trial call.
+    pub circuit_breaker_cooldown_ms: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -148,6 +196,23 @@ pub struct BudgetConfig {
     pub hard_limit: bool,
 }
 
+/// On-disk cache of AI responses (`~/.nakama/cache/ai/`), opt-in.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CacheConfig {
+    pub enabled: bool,
+    /// How long a cached response is served before it is discarded.
+    pub ttl_hours: u64,
+    /// Total size of the cache directory; the oldest entries are evicted first.
+    pub max_size_mb: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ModelPricing {
+    pub input_per_mtok: f64,
+    pub output_per_mtok: f64,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
 pub struct LoggingConfig {
@@ -172,8 +237,20 @@ pub struct UiConfig {
 #[serde(default)]
 pub struct AuditConfig {
     pub enabled: bool,
+    /// Entries older than this are archived; `0` keeps everything.
     pub retention_days: u32,
+    /// Prune expired entries when a tool starts (at most once a day).
+    pub auto_cleanup: bool,
     pub chain_verification: bool,
+    /// Queue entries and write them from a background thread in batches.
+    pub batch_write: bool,
+    /// Longest an entry waits in the queue before its batch is written.
+    pub batch_flush_interval_ms: u64,
+    /// Write a batch as soon as this many entries are queued.
+    pub max_batch_size: usize,
+    /// Sign the chain head with the vault-held anchor key at most this
+    /// often when a tool starts; `0` disables automatic anchoring.
+    pub anchor_interval_hours: u32,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -190,6 +267,13 @@ pub struct UpdatesConfig {
     pub check_interval_hours: u64,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VaultConfig {
+    /// Warn at tool startup about credentials older than this (0 disables).
+    pub rotation_warn_days: u32,
+}
+
 // --- Defaults ---
 
 impl Default for Config {
@@ -201,6 +285,7 @@ impl Default for Config {
             audit: AuditConfig::default(),
             ipc: IpcConfig::default(),
             updates: UpdatesConfig::default(),
+            vault: VaultConfig::default(),
             platforms: PlatformsConfig::default(),
             byakugan: ByakuganConfig::default(),
         }
@@ -293,6 +378,8 @@ impl Default for AiConfig {
     fn default() -> Self {
         Self {
             default_provider: Provider::Anthropic,
+            fallback_providers: Vec::new(),
+            model: None,
             anthropic: ProviderModels {
                 model_fast: "claude-haiku-4-5-20251001".to_string(),
                 model_balanced: "claude-sonnet-4-6".to_string(),
@@ -312,8 +399,13 @@ impl Default for AiConfig {
                 base_url: None,
             },
             ollama: OllamaConfig::default(),
+            openai_compatible: HashMap::new(),
+            endpoint: None,
             retry: RetryConfig::default(),
             budget: None,
+            cache: CacheConfig::default(),
+            pricing: HashMap::new(),
+            context_windows: HashMap::new(),
         }
     }
 }
@@ -339,6 +431,22 @@ impl Default for OllamaConfig {
     }
 }
 
+impl Default for OpenAICompatibleConfig {
+    fn default() -> Self {
+        Self {
+            base_url: "http://localhost:4000/v1".to_string(),
+            model_fast: String::new(),
+            model_balanced: String::new(),
+            model_powerful: String::new(),
+            auth_header: "Authorization".to_string(),
+            auth_scheme: "Bearer".to_string(),
+            headers: HashMap::new(),
+            api_version: None,
+            model_aliases: HashMap::new(),
+        }
+    }
+}
+
 impl Default for RetryConfig {
     fn default() -> Self {
         Self {
@@ -346,6 +454,18 @@ impl Default for RetryConfig {
             initial_backoff_ms: 1000,
             max_backoff_ms: 30000,
             backoff_multiplier: 2.0,
+            circuit_breaker_threshold: 5,
+            circuit_breaker_cooldown_ms: 30000,
+        }
+    }
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            ttl_hours: 24,
+            max_size_mb: 100,
         }
     }
 }
@@ -379,7 +499,12 @@ impl Default for AuditConfig {
         Self {
             enabled: true,
             retention_days: 90,
+            auto_cleanup: true,
             chain_verification: true,
+            batch_write: true,
+            batch_flush_interval_ms: 1000,
+            max_batch_size: 100,
+            anchor_interval_hours: 24,
         }
     }
 }
@@ -402,51 +527,270 @@ impl Default for UpdatesConfig {
     }
 }
 
+impl Default for VaultConfig {
+    fn default() -> Self {
+        Self {
+            rotation_warn_days: 90,
+        }
+    }
+}
+
+/// Per-project config files, looked for in the current directory and each
+/// of its parents.
+pub const PROJECT_CONFIG_FILES: &[&str] = &[".nakama.toml", ".nakama/config.toml"];
+
+/// A commented config file listing every setting with its default, written
+/// by `nakama config init`.
+pub const DEFAULT_CONFIG_TEMPLATE: &str = include_str!("default_config.toml");
+
+/// Prefix of environment variables that override config keys.
+const ENV_PREFIX: &str = "NAKAMA_";
+
+/// Where a configuration value came from.  Layers are merged in this order,
+/// later ones overriding earlier ones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigSource {
+    Default,
+    Global,
+    Project,
+    Tool,
+    Env,
+}
+
+impl std::fmt::Display for ConfigSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigSource::Default => write!(f, "default"),
+            ConfigSource::Global => write!(f, "global"),
+            ConfigSource::Project => write!(f, "project"),
+            ConfigSource::Tool => write!(f, "tool"),
+            ConfigSource::Env => write!(f, "env"),
+        }
+    }
+}
+
+/// One set of settings merged into the configuration.
+#[derive(Debug, Clone)]
+pub struct ConfigLayer {
+    pub source: ConfigSource,
+    /// File the layer was read from; `None` for defaults and the environment.
+    pub path: Option<PathBuf>,
+    pub value: toml::Value,
+}
+
+impl ConfigLayer {
+    /// The layer's source and file, e.g. `global (/home/me/.nakama/config.toml)`.
+    pub fn describe(&self) -> String {
+        match &self.path {
+            Some(path) => format!("{} ({})", self.source, path.display()),
+            None => self.source.to_string(),
+        }
+    }
+
+    /// Whether this layer sets the key at `path`.
+    pub fn sets(&self, path: &[String]) -> bool {
+        let mut current = &self.value;
+        for key in path {
+            match current.get(key.as_str()) {
+                Some(next) => current = next,
+                None => return false,
+            }
+        }
+        true
+    }
+}
+
+/// A configuration together with the layers it was merged from and what
+/// was wrong with them.
+#[derive(Debug, Clone)]
+pub struct LoadedConfig {
+    pub config: Config,
+    /// The layers that were merged, in order, starting with the defaults.
+    pub layers: Vec<ConfigLayer>,
+    /// Files (or environment overrides) that could not be used and were
+    /// left out, as `path:line:column: message` where the position is known.
+    pub errors: Vec<String>,
+    /// Keys that no tool reads, most likely typos.
+    pub warnings: Vec<String>,
+}
+
+impl LoadedConfig {
+    /// The layer whose value is in effect for the key at `path`: the last
+    /// one that sets it.
+    pub fn source_of(&self, path: &[String]) -> Option<&ConfigLayer> {
+        self.layers.iter().rev().find(|layer| layer.sets(path))
+    }
+
+    /// The errors and warnings, worded for a tool that carries on without
+    /// the broken layers.
+    pub fn diagnostics(&self) -> ConfigDiagnostics {
+        let errors = self.errors.iter().map(|e| format!("{} (ignored)", e));
+        ConfigDiagnostics {
+            errors: errors.collect(),
+            warnings: self.warnings.clone(),
+        }
+    }
+}
+
+/// Problems found by [`Config::load_or_default`], for the caller to show
+/// (tools render them with `NakamaUI::config_diagnostics`).
+#[derive(Debug, Clone, Default)]
+pub struct ConfigDiagnostics {
+    /// Configuration that could not be used.
+    pub errors: Vec<String>,
+    /// Keys that no tool reads, most likely typos.
+    pub warnings: Vec<String>,
+}
+
+impl ConfigDiagnostics {
+    pub fn is_empty(&self) -> bool {
+        self.errors.is_empty() && self.warnings.is_empty()
+    }
+}
+
 impl Config {
-    /// Load configuration with merge: global config → tool config → defaults.
+    /// Load configuration with merge: defaults → global config → project
+    /// config → tool config → `NAKAMA_*` environment variables.
+    ///
+    /// The project config is the nearest `.nakama.toml` or
+    /// `.nakama/config.toml` above the current directory.  Its provider and
+    /// endpoint settings only apply once trusted (see [`crate::trust`]).
+    ///
+    /// Fails on the first file that cannot be parsed; see
+    /// [`Config::load_layered`] to load around broken files instead.
     pub fn load(tool_name: &str) -> NakamaResult<Self> {
+        let loaded = Self::load_layered(tool_name)?;
+        if let Some(error) = loaded.errors.into_iter().next() {
+            return Err(NakamaError::Config {
+                message: error,
+                source: None,
+            });
+        }
+        for warning in &loaded.warnings {
+            tracing::warn!("{}", warning);
+        }
+        Ok(loaded.config)
+    }
+
+    /// Load configuration like [`Config::load`], returning problems instead
+    /// of failing.  Broken files are skipped, so the other layers still
+    /// apply.
+    pub fn load_or_default(tool_name: &str) -> (Self, ConfigDiagnostics) {
+        match Self::load_layered(tool_name) {
+            Ok(loaded) => {
+                let diagnostics = loaded.diagnostics();
+                (loaded.config, diagnostics)
+            }
+            Err(e) => {
+                let diagnostics = ConfigDiagnostics {
+                    errors: vec![format!("{}; using the default configuration", e)],
+                    warnings: Vec::new(),
+                };
+                (Self::default(), diagnostics)
+            }
+        }
+    }
+
+    /// Load every configuration layer, keeping track of where each setting
+    /// came from.  A layer that cannot be read, parsed or deserialized is
+    /// left out and recorded in [`LoadedConfig::errors`]; unknown keys are
+    /// recorded in [`LoadedConfig::warnings`].
+    pub fn load_layered(tool_name: &str) -> NakamaResult<LoadedConfig> {
         // Ensure directory structure exists
         paths::ensure_nakama_dirs()?;
 
-        let mut config = Config::default();
-
-        // Load global config if it exists
         let global_path = paths::global_config_path()?;
-        if global_path.exists() {
-            let contents = std::fs::read_to_string(&global_path).map_err(|e| {
-                NakamaError::Config {
-                    message: format!("Failed to read {}", global_path.display()),
-                    source: Some(Box::new(e)),
-                }
-            })?;
-            config = toml::from_str(&contents).map_err(|e| NakamaError::Config {
-                message: format!("Failed to parse {}", global_path.display()),
+        let project_path = std::env::current_dir()
+            .ok()
+            .and_then(|dir| find_project_config(&dir, &global_path));
+        let tool_path = paths::tool_config_dir(tool_name)?.join("config.toml");
+
+        let mut files = vec![(ConfigSource::Global, global_path.clone())];
+        files.extend(project_path.map(|path| (ConfigSource::Project, path)));
+        // `nakama` itself has ~/.nakama as its tool directory
+        if tool_path != global_path {
+            files.push((ConfigSource::Tool, tool_path));
+        }
+
+        let mut layers = vec![ConfigLayer {
+            source: ConfigSource::Default,
+            path: None,
+            value: toml::Value::try_from(Config::default()).map_err(|e| NakamaError::Config {
+                message: "Failed to serialize default config".to_string(),
                 source: Some(Box::new(e)),
-            })?;
+            })?,
+        }];
+        let mut errors = Vec::new();
+
+        for (source, path) in files {
+            if !path.exists() {
+                continue;
+            }
+            let mut value = match read_config_file(&path) {
+                Ok(value) => value,
+                Err(e) => {
+                    errors.push(e);
+                    continue;
+                }
+            };
+            // Drop any untrusted provider settings from the project config
+            if source == ConfigSource::Project {
+                crate::trust::filter_project_config(&path, &mut value)?;
+            }
+            layers.push(ConfigLayer {
+                source,
+                path: Some(path),
+                value,
+            });
+        }
+
+        let mut env = toml::Value::Table(toml::Table::new());
+        apply_env_overrides(&mut env, std::env::vars());
+        if env.as_table().is_some_and(|table| !table.is_empty()) {
+            layers.push(ConfigLayer {
+                source: ConfigSource::Env,
+                path: None,
+                value: env,
+            });
         }
 
-        // Load tool-specific config if it exists (overrides global)
-        let tool_config_path = paths::tool_config_dir(tool_name)?.join("config.toml");
-        if tool_config_path.exists() {
-            let contents = std::fs::read_to_string(&tool_config_path).map_err(|e| {
-                NakamaError::Config {
-                    message: format!("Failed to read {}", tool_config_path.display()),
-                    source: Some(Box::new(e)),
+        // Merge, leaving out layers that make the result invalid (a file
+        // that is fine on its own can't, but an environment override can)
+        let mut merged = toml::Value::Table(toml::Table::new());
+        let mut config = Config::default();
+        layers.retain(|layer| {
+            let mut candidate = merged.clone();
+            merge_toml(&mut candidate, &layer.value);
+            match candidate.clone().try_into::<Config>() {
+                Ok(valid) => {
+                    merged = candidate;
+                    config = valid;
+                    true
                 }
-            })?;
-            // Merge tool config over global (tool config takes priority)
-            let tool_config: toml::Value = toml::from_str(&contents).map_err(|e| {
-                NakamaError::Config {
-                    message: format!("Failed to parse {}", tool_config_path.display()),
-                    source: Some(Box::new(e)),
+                Err(e) => {
+                    errors.push(format!("{}: {}", layer.describe(), e.message().trim()));
+                    false
                 }
-            })?;
-            let mut global_value = toml::Value::try_from(&config).unwrap_or(toml::Value::Table(toml::map::Map::new()));
-            merge_toml(&mut global_value, &tool_config);
-            config = global_value.try_into().unwrap_or_default();
+            }
+        });
+
+        let mut warnings = Vec::new();
+        for layer in &layers[1..] {
+            for key in unknown_keys(&layer.value) {
+                let location = match layer.source {
+                    ConfigSource::Env => format!("environment variable {}{}", ENV_PREFIX, key.replace('.', "__").to_uppercase()),
+                    _ => layer.describe(),
+                };
+                warnings.push(format!("{}: unknown key `{}`", location, key));
+            }
         }
 
-        Ok(config)
+        Ok(LoadedConfig {
+            config,
+            layers,
+            errors,
+            warnings,
+        })
     }
 
     /// Resolve a model ID for a given provider and tier.
@@ -472,8 +816,158 @@ impl Config {
                 ModelTier::Balanced => self.ai.ollama.model_balanced.clone(),
                 ModelTier::Powerful => self.ai.ollama.model_balanced.clone(),
             },
+            Provider::OpenAICompatible => match self.compatible_endpoint() {
+                Ok((_, endpoint)) => match tier {
+                    ModelTier::Fast => endpoint.model_fast.clone(),
+                    ModelTier::Balanced => endpoint.model_balanced.clone(),
+                    ModelTier::Powerful => endpoint.model_powerful.clone(),
+                },
+                Err(_) => String::new(),
+            },
         }
     }
+
+    /// The selected `openai_compatible` endpoint and its name: the one named
+    /// by `ai.endpoint`, or the only one configured.
+    pub fn compatible_endpoint(&self) -> NakamaResult<(&str, &OpenAICompatibleConfig)> {
+        let endpoints = &self.ai.openai_compatible;
+        let found = match &self.ai.endpoint {
+            Some(name) => endpoints.get_key_value(name),
+            None if endpoints.len() == 1 => endpoints.iter().next(),
+            None => None,
+        };
+        if let Some((name, endpoint)) = found {
+            return Ok((name.as_str(), endpoint));
+        }
+
+        let mut names: Vec<&str> = endpoints.keys().map(String::as_str).collect();
+        names.sort_unstable();
+        let message = match (&self.ai.endpoint, names.is_empty()) {
+            (_, true) => "No OpenAI-compatible endpoints configured; \
+                          add one under [ai.openai_compatible.<name>]"
+                .to_string(),
+            (Some(name), false) => format!(
+                "No OpenAI-compatible endpoint named '{name}' (configured: {})",
+                names.join(", ")
+            ),
+            (None, false) => format!(
+                "Several OpenAI-compatible endpoints are configured ({}); \
+                 choose one with ai.endpoint or --ai-provider openai_compatible:<name>",
+                names.join(", ")
+            ),
+        };
+        Err(NakamaError::Config {
+            message,
+            source: None,
+        })
+    }
+}
+
+/// Read a config file and check that it deserializes, so that type errors
+/// are reported with their position in the file.
+fn read_config_file(path: &Path) -> Result<toml::Value, String> {
+    let contents = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let value = toml::from_str::<toml::Value>(&contents).map_err(|e| describe_toml_error(path, &contents, &e))?;
+    toml::from_str::<Config>(&contents).map_err(|e| describe_toml_error(path, &contents, &e))?;
+    Ok(value)
+}
+
+/// `path:line:column: message` for a TOML error in `contents`.
+fn describe_toml_error(path: &Path, contents: &str, error: &toml::de::Error) -> String {
+    let message = error.message().trim();
+    match error.span() {
+        Some(span) => {
+            let before = &contents[..span.start.min(contents.len())];
+            let line = before.matches('\n').count() + 1;
+            let column = before.rsplit('\n').next().unwrap_or_default().chars().count() + 1;
+            format!("{}:{}:{}: {}", path.display(), line, column, message)
+        }
+        None => format!("{}: {}", path.display(), message),
+    }
+}
+
+/// Dotted keys in `value` that are not part of [`Config`].  Known keys
+/// survive a round trip through `Config`; anything serde ignored does not.
+fn unknown_keys(value: &toml::Value) -> Vec<String> {
+    let known = value
+        .clone()
+        .try_into::<Config>()
+        .ok()
+        .and_then(|config| toml::Value::try_from(config).ok());
+    let mut unknown = Vec::new();
+    if let Some(known) = known {
+        collect_unknown(value, &known, &mut Vec::new(), &mut unknown);
+    }
+    unknown
+}
+
+fn collect_unknown(value: &toml::Value, known: &toml::Value, path: &mut Vec<String>, unknown: &mut Vec<String>) {
+    match (value, known) {
+        (toml::Value::Table(table), toml::Value::Table(known_table)) => {
+            for (key, value) in table {
+                path.push(key.clone());
+                match known_table.get(key) {
+                    Some(known) => collect_unknown(value, known, path, unknown),
+                    None => unknown.push(path.join(".")),
+                }
+                path.pop();
+            }
+        }
+        (toml::Value::Array(items), toml::Value::Array(known_items)) => {
+            // Written `byakugan.rules[0].name`
+            let name = path.pop().unwrap_or_default();
+            for (index, (item, known)) in items.iter().zip(known_items).enumerate() {
+                path.push(format!("{}[{}]", name, index));
+                collect_unknown(item, known, path, unknown);
+                path.pop();
+            }
+            path.push(name);
+        }
+        _ => {}
+    }
+}
+
+/// The nearest project config file in `start` or one of its parents.
+/// `~/.nakama/config.toml` is the global config, not a project one.
+pub fn find_project_config(start: &Path, global_path: &Path) -> Option<PathBuf> {
+    start.ancestors().find_map(|dir| {
+        PROJECT_CONFIG_FILES
+            .iter()
+            .map(|name| dir.join(name))
+            .find(|path| path.is_file() && path != global_path)
+    })
+}
+
+/// Apply `NAKAMA_<SECTION>__<KEY>=value` overrides, with `__` separating
+/// nested keys (e.g. `NAKAMA_AI__OLLAMA__BASE_URL`).  Values are parsed as
+/// TOML where possible (numbers, booleans, arrays) and used as strings
+/// otherwise.  Variables without `__`, such as vault fallbacks, are skipped.
+fn apply_env_overrides(config: &mut toml::Value, vars: impl IntoIterator<Item = (String, String)>) {
+    for (name, raw) in vars {
+        let Some(key) = name.strip_prefix(ENV_PREFIX) else {
+            continue;
+        };
+        if !key.contains("__") {
+            continue;
+        }
+        let path: Vec<String> = key.split("__").map(str::to_lowercase).collect();
+        if path.iter().any(String::is_empty) {
+            continue;
+        }
+
+        let value = toml::from_str::<toml::Table>(&format!("v = {raw}"))
+            .ok()
+            .and_then(|mut table| table.remove("v"))
+            .unwrap_or(toml::Value::String(raw));
+
+        let mut override_value = value;
+        for segment in path.iter().rev() {
+            let mut table = toml::map::Map::new();
+            table.insert(segment.clone(), override_value);
+            override_value = toml::Value::Table(table);
+        }
+        merge_toml(config, &override_value);
+    }
 }
 
 /// Deep merge two TOML values (source overrides target).
@@ -519,6 +1013,92 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_compatible_endpoint_selection() {
+        let mut config: Config = toml::from_str(r#"
+            [ai]
+            default_provider = "openai_compatible"
+            [ai.openai_compatible.gateway]
+            base_url = "https://llm.internal/v1"
+            model_fast = "small"
+        "#).unwrap();
+
+        let (name, endpoint) = config.compatible_endpoint().unwrap();
+        assert_eq!(name, "gateway");
+        assert_eq!(endpoint.auth_header, "Authorization");
+        assert_eq!(config.resolve_model(Provider::OpenAICompatible, ModelTier::Fast), "small");
+
+        config.ai.openai_compatible.insert("studio".to_string(), OpenAICompatibleConfig::default());
+        assert!(config.compatible_endpoint().is_err());
+        config.ai.endpoint = Some("studio".to_string());
+        assert_eq!(config.compatible_endpoint().unwrap().0, "studio");
+    }
+
+    #[test]
+    fn test_env_overrides() {
+        let mut value = toml::Value::try_from(Config::default()).unwrap();
+        let vars = [
+            ("NAKAMA_AI__DEFAULT_PROVIDER", "openai"),
+            ("NAKAMA_AI__RETRY__MAX_RETRIES", "7"),
+            ("NAKAMA_BYAKUGAN__PASSES", r#"["security", "logic"]"#),
+            ("NAKAMA_AI__OLLAMA__BASE_URL", "http://gpu-box:11434"),
+            ("NAKAMA_ANTHROPIC_API_KEY", "sk-not-config"),
+        ];
+        apply_env_overrides(&mut value, vars.map(|(k, v)| (k.to_string(), v.to_string())));
+
+        let config: Config = value.try_into().unwrap();
+        assert_eq!(config.ai.default_provider, Provider::OpenAI);
+        assert_eq!(config.ai.retry.max_retries, 7);
+        assert_eq!(config.byakugan.passes, vec!["security", "logic"]);
+        assert_eq!(config.ai.ollama.base_url, "http://gpu-box:11434");
+    }
+
+    #[test]
+    fn test_find_project_config() {
+        let root = std::env::temp_dir().join(format!("nakama-project-{}", std::process::id()));
+        let nested = root.join("src").join("deep");
+        std::fs::create_dir_all(&nested).unwrap();
+        std::fs::create_dir_all(root.join(".nakama")).unwrap();
+        std::fs::write(root.join(".nakama").join("config.toml"), "").unwrap();
+        let global = Path::new("/nonexistent/config.toml");
+
+        assert_eq!(find_project_config(&nested, global), Some(root.join(".nakama").join("config.toml")));
+        std::fs::write(root.join("src").join(".nakama.toml"), "").unwrap();
+        assert_eq!(find_project_config(&nested, global), Some(root.join("src").join(".nakama.toml")));
+        assert_eq!(find_project_config(&root, &root.join(".nakama").join("config.toml")), None);
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_default_config_template() {
+        let template: toml::Value = toml::from_str(DEFAULT_CONFIG_TEMPLATE).unwrap();
+        assert!(unknown_keys(&template).is_empty());
+        let defaults = toml::Value::try_from(Config::default()).unwrap();
+        assert_eq!(toml::Value::try_from(template.try_into::<Config>().unwrap()).unwrap(), defaults);
+    }
+
+    #[test]
+    fn test_config_file_problems() {
+        let value: toml::Value = toml::from_str(r#"
+            [ai]
+            defualt_provider = "openai"
+            [ai.retry]
+            max_retries = 2
+            [[byakugan.rules]]
+            name = "todo"
+            pattern = "TODO"
+            severty = "low"
+        "#).unwrap();
+        assert_eq!(unknown_keys(&value), vec!["ai.defualt_provider", "byakugan.rules[0].severty"]);
+
+        let path = Path::new("config.toml");
+        let contents = "[ai]\ndefault_provider = \"openai\"\n\n[ai.retry]\nmax_retries = \"many\"\n";
+        let error = toml::from_str::<Config>(contents).unwrap_err();
+        let message = describe_toml_error(path, contents, &error);
+        assert!(message.starts_with("config.toml:5:15: "), "{message}");
+    }
+
     #[test]
     fn test_merge_toml() {
         let mut target: toml::Value = toml::from_str(r#"
@@ -2,6 +2,10 @@
 //!
 //! Provides smart diff compression (stripping noise files, binary diffs,
 //! reducing context lines) and simple sandwich truncation for non-diff text.
+//! Budgets are in characters, or in tokens with the `*_to` variants and a
+//! [`TokenCounter`] for the target model.
+
+use crate::tokens::{fit_head, truncate_middle, Chars, TokenCounter};
 
 /// File patterns to exclude from diffs (lock files, minified assets, generated code).
 const EXCLUDED_PATTERNS: &[&str] = &[
@@ -26,12 +30,18 @@ const EXCLUDED_PATTERNS: &[&str] = &[
 /// 4. Sandwich-truncates at file boundaries if still over `max_chars`
 /// 5. Appends a stats footer
 pub fn compress_diff(raw_diff: &str, max_chars: usize) -> String {
-    if raw_diff.len() <= max_chars {
+    compress_diff_to(raw_diff, max_chars, &Chars)
+}
+
+/// [`compress_diff`] with the budget measured by `counter` (e.g. in model
+/// tokens).
+pub fn compress_diff_to(raw_diff: &str, budget: usize, counter: &dyn TokenCounter) -> String {
+    let original_size = counter.count(raw_diff);
+    if original_size <= budget {
         return raw_diff.to_string();
     }
 
     let mut files_stripped = 0usize;
-    let original_len = raw_diff.len();
 
     // Split diff into per-file sections
     let file_sections = split_into_file_sections(raw_diff);
@@ -59,16 +69,18 @@ pub fn compress_diff(raw_diff: &str, max_chars: usize) -> String {
     let mut result = reduced.join("");
 
     // Step 4: Sandwich-truncate at file boundaries if still over limit
-    if result.len() > max_chars {
-        result = truncate_at_file_boundaries(&result, max_chars);
+    if counter.count(&result) > budget {
+        result = truncate_at_file_boundaries(&result, budget, counter);
     }
 
     // Step 5: Stats footer
-    let chars_saved = original_len.saturating_sub(result.len());
-    if files_stripped > 0 || chars_saved > 0 {
+    let saved = original_size.saturating_sub(counter.count(&result));
+    if files_stripped > 0 || saved > 0 {
         result.push_str(&format!(
-            "\n[compressed: {} file(s) stripped, {} chars saved]\n",
-            files_stripped, chars_saved
+            "\n[compressed: {} file(s) stripped, {} {} saved]\n",
+            files_stripped,
+            saved,
+            counter.unit()
         ));
     }
 
@@ -80,33 +92,12 @@ pub fn compress_diff(raw_diff: &str, max_chars: usize) -> String {
 /// Keeps the first and last portions with a marker in between.
 /// Preserves whole lines at the cut points.
 pub fn truncate_diff(text: &str, max_chars: usize) -> String {
-    if text.len() <= max_chars {
-        return text.to_string();
-    }
+    truncate_middle(text, max_chars, &Chars)
+}
 
-    let marker = "\n\n... [truncated for AI context window] ...\n\n";
-    let available = max_chars.saturating_sub(marker.len());
-    let head_budget = available * 2 / 3;
-    let tail_budget = available.saturating_sub(head_budget);
-
-    // Find the last newline within head_budget
-    let head_end = text[..head_budget.min(text.len())]
-        .rfind('\n')
-        .map(|i| i + 1)
-        .unwrap_or(head_budget.min(text.len()));
-
-    // Find the first newline in the tail region
-    let tail_start_min = text.len().saturating_sub(tail_budget);
-    let tail_start = text[tail_start_min..]
-        .find('\n')
-        .map(|i| tail_start_min + i + 1)
-        .unwrap_or(tail_start_min);
-
-    let mut result = String::with_capacity(max_chars + marker.len());
-    result.push_str(&text[..head_end]);
-    result.push_str(marker);
-    result.push_str(&text[tail_start..]);
-    result
+/// [`truncate_diff`] with the budget measured by `counter`.
+pub fn truncate_diff_to(text: &str, budget: usize, counter: &dyn TokenCounter) -> String {
+    truncate_middle(text, budget, counter)
 }
 
 /// Split a unified diff into per-file sections.
@@ -248,38 +239,42 @@ fn reduce_context_lines(section: &str, context: usize) -> String {
 }
 
 /// Truncate at file boundaries (never split mid-file).
-fn truncate_at_file_boundaries(diff: &str, max_chars: usize) -> String {
+fn truncate_at_file_boundaries(diff: &str, budget: usize, counter: &dyn TokenCounter) -> String {
     let marker = "\n\n... [remaining files truncated] ...\n\n";
     let sections = split_into_file_sections(diff);
 
     if sections.is_empty() {
-        return diff[..max_chars.min(diff.len())].to_string();
+        return fit_head(diff, budget, counter).to_string();
     }
 
     // Strategy: keep files from the start as many as fit, then append tail files
-    let budget = max_chars.saturating_sub(marker.len());
+    let budget = budget.saturating_sub(counter.count(marker));
     let head_budget = budget * 2 / 3;
     let tail_budget = budget.saturating_sub(head_budget);
 
     let mut head = String::new();
+    let mut head_size = 0;
     let mut head_count = 0;
     for section in &sections {
-        if head.len() + section.len() > head_budget && head_count > 0 {
+        let size = counter.count(section);
+        if head_size + size > head_budget && head_count > 0 {
             break;
         }
         head.push_str(section);
+        head_size += size;
         head_count += 1;
     }
 
     // Tail: work backwards
     let mut tail_sections: Vec<&str> = Vec::new();
-    let mut tail_len = 0;
+    let mut tail_size = 0;
     for section in sections[head_count..].iter().rev() {
-        if tail_len + section.len() > tail_budget && !tail_sections.is_empty() {
+        let size = counter.count(section);
+        if tail_size + size > tail_budget && !tail_sections.is_empty() {
             break;
         }
         tail_sections.push(section);
-        tail_len += section.len();
+        tail_size += size;
     }
     tail_sections.reverse();
 
@@ -14,6 +14,9 @@ pub enum NakamaError {
     #[error("AI provider error: {message}")]
     AiProvider { message: String, #[source] source: Option<Box<dyn std::error::Error + Send + Sync>> },
 
+    #[error("AI budget exceeded: {message}")]
+    Budget { message: String },
+
     #[error("Network error: {message}")]
     Network { message: String, #[source] source: Option<Box<dyn std::error::Error + Send + Sync>> },
 
@@ -5,6 +5,8 @@ pub mod trace;
 pub mod types;
 pub mod paths;
 pub mod permissions;
+pub mod tokens;
+pub mod trust;
 
 pub use config::Config;
 pub use error::NakamaError;
@@ -30,6 +30,21 @@ pub fn vault_dir() -> NakamaResult<PathBuf> {
     Ok(nakama_home()?.join("vault"))
 }
 
+/// Get the cache directory (~/.nakama/cache/).
+pub fn cache_dir() -> NakamaResult<PathBuf> {
+    Ok(nakama_home()?.join("cache"))
+}
+
+/// Get the conversation sessions directory (~/.nakama/sessions/).
+pub fn sessions_dir() -> NakamaResult<PathBuf> {
+    Ok(nakama_home()?.join("sessions"))
+}
+
+/// Get the prompt overrides directory (~/.nakama/prompts/).
+pub fn prompts_dir() -> NakamaResult<PathBuf> {
+    Ok(nakama_home()?.join("prompts"))
+}
+
 /// Get a tool-specific config directory (~/.nakama/<tool>/ or ~/.<tool>/).
 pub fn tool_config_dir(tool: &str) -> NakamaResult<PathBuf> {
     let home = dirs::home_dir().ok_or_else(|| NakamaError::Config {
@@ -0,0 +1,193 @@
+//! Measuring and trimming text against a size budget.
+//!
+//! Budgets are counted by a [`TokenCounter`]: [`Chars`] counts bytes, and
+//! `nakama_ai::tokens` provides estimators for each model's tokenizer.  The
+//! trimming helpers cut at line boundaries where they can and never split a
+//! UTF-8 character.
+
+/// Something that measures how much of a budget a piece of text uses.
+pub trait TokenCounter: Send + Sync {
+    /// Size of `text` in this counter's units.
+    fn count(&self, text: &str) -> usize;
+
+    /// Name of the unit, for messages such as "1200 tokens saved".
+    fn unit(&self) -> &'static str {
+        "tokens"
+    }
+}
+
+/// Counts bytes, for callers that budget by length rather than tokens.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Chars;
+
+impl TokenCounter for Chars {
+    fn count(&self, text: &str) -> usize {
+        text.len()
+    }
+
+    fn unit(&self) -> &'static str {
+        "chars"
+    }
+}
+
+/// Marker inserted where [`truncate_middle`] removed text.
+pub const TRUNCATION_MARKER: &str = "\n\n... [truncated for AI context window] ...\n\n";
+
+/// The largest char boundary of `s` at or before `index`.
+pub fn floor_char_boundary(s: &str, index: usize) -> usize {
+    if index >= s.len() {
+        return s.len();
+    }
+    let mut i = index;
+    while !s.is_char_boundary(i) {
+        i -= 1;
+    }
+    i
+}
+
+/// The smallest char boundary of `s` at or after `index`.
+pub fn ceil_char_boundary(s: &str, index: usize) -> usize {
+    if index >= s.len() {
+        return s.len();
+    }
+    let mut i = index;
+    while !s.is_char_boundary(i) {
+        i += 1;
+    }
+    i
+}
+
+/// The longest prefix of `text` that fits in `budget`.
+///
+/// Whole lines are kept; a line is only split when not even the first one
+/// fits.
+pub fn fit_head<'a>(text: &'a str, budget: usize, counter: &dyn TokenCounter) -> &'a str {
+    let mut end = 0;
+    let mut used = 0;
+    for line in text.split_inclusive('\n') {
+        let size = counter.count(line);
+        if used + size > budget {
+            if end == 0 {
+                return &line[..longest_prefix(line, budget, counter)];
+            }
+            break;
+        }
+        used += size;
+        end += line.len();
+    }
+    &text[..end]
+}
+
+/// The longest suffix of `text` that fits in `budget`, keeping whole lines
+/// like [`fit_head`].
+pub fn fit_tail<'a>(text: &'a str, budget: usize, counter: &dyn TokenCounter) -> &'a str {
+    let mut start = text.len();
+    let mut used = 0;
+    for line in text.split_inclusive('\n').rev() {
+        let size = counter.count(line);
+        if used + size > budget {
+            if start == text.len() {
+                let line_start = text.len() - line.len();
+                return &text[line_start + longest_suffix_start(line, budget, counter)..];
+            }
+            break;
+        }
+        used += size;
+        start -= line.len();
+    }
+    &text[start..]
+}
+
+/// Sandwich-truncate `text` to `budget`: keep the first two thirds and the
+/// last third of the budget, with [`TRUNCATION_MARKER`] in between.
+pub fn truncate_middle(text: &str, budget: usize, counter: &dyn TokenCounter) -> String {
+    if counter.count(text) <= budget {
+        return text.to_string();
+    }
+
+    let available = budget.saturating_sub(counter.count(TRUNCATION_MARKER));
+    let head_budget = available * 2 / 3;
+    let tail_budget = available - head_budget;
+
+    let head = fit_head(text, head_budget, counter);
+    let tail = fit_tail(&text[head.len()..], tail_budget, counter);
+
+    let mut result = String::with_capacity(head.len() + TRUNCATION_MARKER.len() + tail.len());
+    result.push_str(head);
+    result.push_str(TRUNCATION_MARKER);
+    result.push_str(tail);
+    result
+}
+
+/// Byte length of the longest prefix of `line` within `budget`.
+fn longest_prefix(line: &str, budget: usize, counter: &dyn TokenCounter) -> usize {
+    let (mut lo, mut hi) = (0, line.len());
+    while lo < hi {
+        let mid = floor_char_boundary(line, (lo + hi).div_ceil(2));
+        if mid <= lo {
+            // No char boundary strictly between lo and hi.
+            break;
+        }
+        if counter.count(&line[..mid]) <= budget {
+            lo = mid;
+        } else {
+            hi = mid - 1;
+        }
+    }
+    floor_char_boundary(line, lo)
+}
+
+/// Byte offset where the longest suffix of `line` within `budget` starts.
+fn longest_suffix_start(line: &str, budget: usize, counter: &dyn TokenCounter) -> usize {
+    let (mut lo, mut hi) = (0, line.len());
+    while lo < hi {
+        let mid = ceil_char_boundary(line, (lo + hi) / 2);
+        if mid >= hi {
+            break;
+        }
+        if counter.count(&line[mid..]) <= budget {
+            hi = mid;
+        } else {
+            lo = mid + 1;
+        }
+    }
+    ceil_char_boundary(line, hi)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_char_boundaries() {
+        let s = "añb";
+        assert_eq!(floor_char_boundary(s, 2), 1);
+        assert_eq!(ceil_char_boundary(s, 2), 3);
+        assert_eq!(floor_char_boundary(s, 99), s.len());
+    }
+
+    #[test]
+    fn test_fit_keeps_whole_lines() {
+        let text = "alpha\nbeta\ngamma\n";
+        assert_eq!(fit_head(text, 12, &Chars), "alpha\nbeta\n");
+        assert_eq!(fit_tail(text, 12, &Chars), "beta\ngamma\n");
+        assert_eq!(fit_head(text, 100, &Chars), text);
+    }
+
+    #[test]
+    fn test_fit_splits_long_lines_on_char_boundaries() {
+        let text = "ééééé";
+        assert_eq!(fit_head(text, 5, &Chars), "éé");
+        assert_eq!(fit_tail(text, 5, &Chars), "éé");
+    }
+
+    #[test]
+    fn test_truncate_middle_multibyte() {
+        let text: String = (0..200).map(|i| format!("ligne {i} — données\n")).collect();
+        let result = truncate_middle(&text, 300, &Chars);
+        assert!(result.len() <= 300);
+        assert!(result.starts_with("ligne 0 "));
+        assert!(result.ends_with("ligne 199 — données\n"));
+        assert!(result.contains(TRUNCATION_MARKER));
+    }
+}
@@ -0,0 +1,243 @@
+//! Trust decisions for per-project config files.
+//!
+//! A `.nakama.toml` arrives with the repository, so the settings that decide
+//! where prompts, code and API keys are sent — the AI provider, fallback
+//! providers, OpenAI-compatible endpoints and any URL — are only honored once
+//! the user has trusted the file with those exact values.  Decisions are kept
+//! in `~/.nakama/trusted_projects.json` and asked again when the values
+//! change.  Without a terminal to ask on, the settings are ignored unless
+//! `NAKAMA_TRUST_PROJECT_CONFIG=1` is set.
+//!
+//! Other repository files that change what the tools do, such as prompt
+//! overrides, are trusted the same way with [`trust_file`], keyed on a hash
+//! of their contents.
+
+use crate::error::{NakamaError, NakamaResult};
+use crate::paths;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::io::{BufRead, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+
+/// Set to `1` to trust project configs without asking (e.g. in CI).
+pub const TRUST_ENV_VAR: &str = "NAKAMA_TRUST_PROJECT_CONFIG";
+
+/// `ai.*` keys that choose where requests go.
+const SENSITIVE_AI_KEYS: &[&str] = &["default_provider", "fallback_providers", "endpoint", "openai_compatible"];
+
+/// A setting in a project config that needs the user's trust.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SensitiveSetting {
+    pub path: Vec<String>,
+    pub value: toml::Value,
+}
+
+impl SensitiveSetting {
+    /// The setting's dotted key, e.g. `ai.ollama.base_url`.
+    pub fn key(&self) -> String {
+        self.path.join(".")
+    }
+}
+
+/// The settings in `config` that change providers, endpoints or URLs.
+pub fn sensitive_settings(config: &toml::Value) -> Vec<SensitiveSetting> {
+    let mut found = Vec::new();
+    collect_sensitive(config, &mut Vec::new(), &mut found);
+    found
+}
+
+fn collect_sensitive(value: &toml::Value, path: &mut Vec<String>, found: &mut Vec<SensitiveSetting>) {
+    let Some(table) = value.as_table() else {
+        return;
+    };
+    for (key, value) in table {
+        path.push(key.clone());
+        if is_sensitive(path) {
+            found.push(SensitiveSetting {
+                path: path.clone(),
+                value: value.clone(),
+            });
+        } else {
+            collect_sensitive(value, path, found);
+        }
+        path.pop();
+    }
+}
+
+fn is_sensitive(path: &[String]) -> bool {
+    let in_ai = path.len() == 2 && path[0] == "ai" && SENSITIVE_AI_KEYS.contains(&path[1].as_str());
+    let is_url = path.last().is_some_and(|key| key.ends_with("url"));
+    in_ai || is_url
+}
+
+/// Check the sensitive settings of the project config at `path`, asking
+/// the user if needed, and remove them from `config` unless trusted.
+pub fn filter_project_config(path: &Path, config: &mut toml::Value) -> NakamaResult<()> {
+    let settings = sensitive_settings(config);
+    if settings.is_empty() || std::env::var(TRUST_ENV_VAR).is_ok_and(|v| v == "1") {
+        return Ok(());
+    }
+
+    let fingerprint = fingerprint(&settings);
+    let store_path = trust_store_path()?;
+    let mut store = load_store(&store_path);
+    let key = path.display().to_string();
+    if store.get(&key) == Some(&fingerprint) {
+        return Ok(());
+    }
+
+    if ask(path, &settings)? {
+        store.insert(key, fingerprint);
+        save_store(&store_path, &store)?;
+        return Ok(());
+    }
+
+    eprintln!(
+        "Ignoring provider and endpoint settings in {} (not trusted).",
+        path.display()
+    );
+    for setting in &settings {
+        remove_path(config, &setting.path);
+    }
+    Ok(())
+}
+
+/// Whether the repository file at `path` has been trusted with exactly
+/// `contents`, without asking.
+pub fn is_file_trusted(path: &Path, contents: &str) -> bool {
+    if std::env::var(TRUST_ENV_VAR).is_ok_and(|v| v == "1") {
+        return true;
+    }
+    let key = path.display().to_string();
+    trust_store_path().is_ok_and(|store_path| {
+        load_store(&store_path).get(&key) == Some(&file_fingerprint(contents))
+    })
+}
+
+/// Check that the repository file at `path` is trusted with its current
+/// `contents`, asking the user if needed.  `description` says what the file
+/// does, e.g. which prompt it replaces.
+pub fn trust_file(path: &Path, contents: &str, description: &str) -> NakamaResult<bool> {
+    if is_file_trusted(path, contents) {
+        return Ok(true);
+    }
+    if !confirm(&[description.to_string()], "Trust this file?")? {
+        eprintln!("Ignoring {} (not trusted).", path.display());
+        return Ok(false);
+    }
+
+    let store_path = trust_store_path()?;
+    let mut store = load_store(&store_path);
+    store.insert(path.display().to_string(), file_fingerprint(contents));
+    save_store(&store_path, &store)?;
+    Ok(true)
+}
+
+/// Ask on the terminal whether to trust `settings`; `false` without one.
+fn ask(path: &Path, settings: &[SensitiveSetting]) -> NakamaResult<bool> {
+    let header = format!("{} changes where AI requests are sent:", path.display());
+    let mut lines = vec![header];
+    for setting in settings {
+        lines.push(format!("  {} = {}", setting.key(), setting.value));
+    }
+    confirm(&lines, "Trust these settings?")
+}
+
+/// Print `lines` and ask `question` on the terminal; `false` without one.
+fn confirm(lines: &[String], question: &str) -> NakamaResult<bool> {
+    if !std::io::stdin().is_terminal() || !std::io::stderr().is_terminal() {
+        return Ok(false);
+    }
+
+    let mut stderr = std::io::stderr();
+    for line in lines {
+        writeln!(stderr, "{line}")?;
+    }
+    write!(stderr, "{question} [y/N] ")?;
+    stderr.flush()?;
+
+    let mut answer = String::new();
+    std::io::stdin().lock().read_line(&mut answer)?;
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+fn file_fingerprint(contents: &str) -> String {
+    format!("sha256:{:x}", Sha256::digest(contents.as_bytes()))
+}
+
+fn fingerprint(settings: &[SensitiveSetting]) -> String {
+    settings
+        .iter()
+        .map(|s| format!("{} = {}", s.key(), s.value))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn remove_path(value: &mut toml::Value, path: &[String]) {
+    let Some((last, parents)) = path.split_last() else {
+        return;
+    };
+    let mut current = value;
+    for key in parents {
+        match current.get_mut(key) {
+            Some(next) => current = next,
+            None => return,
+        }
+    }
+    if let Some(table) = current.as_table_mut() {
+        table.remove(last);
+    }
+}
+
+fn trust_store_path() -> NakamaResult<PathBuf> {
+    Ok(paths::nakama_home()?.join("trusted_projects.json"))
+}
+
+/// Trusted settings and file fingerprints by file path.
+fn load_store(path: &Path) -> BTreeMap<String, String> {
+    std::fs::read_to_string(path)
+        .ok()
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+fn save_store(path: &Path, store: &BTreeMap<String, String>) -> NakamaResult<()> {
+    std::fs::write(path, serde_json::to_string_pretty(store)?).map_err(|e| NakamaError::Config {
+        message: format!("Failed to write {}", path.display()),
+        source: Some(Box::new(e)),
+    })?;
+    crate::permissions::set_file_permissions(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sensitive_settings() {
+        let mut config: toml::Value = toml::from_str(
+            r#"
+            [ai]
+            default_provider = "ollama"
+            [ai.ollama]
+            base_url = "http://gpu-box:11434"
+            model_fast = "llama3.2"
+            [ai.openai_compatible.gateway]
+            base_url = "https://llm.example.com/v1"
+            [byakugan]
+            passes = ["security"]
+            "#,
+        )
+        .unwrap();
+
+        let keys: Vec<String> = sensitive_settings(&config).iter().map(SensitiveSetting::key).collect();
+        assert_eq!(keys, vec!["ai.default_provider", "ai.ollama.base_url", "ai.openai_compatible"]);
+
+        for setting in sensitive_settings(&config) {
+            remove_path(&mut config, &setting.path);
+        }
+        assert!(sensitive_settings(&config).is_empty());
+        assert_eq!(config["ai"]["ollama"]["model_fast"].as_str(), Some("llama3.2"));
+        assert!(config["byakugan"].get("passes").is_some());
+    }
+}
@@ -8,6 +8,11 @@ pub enum Provider {
     OpenAI,
     Google,
     Ollama,
+    /// Any endpoint speaking the OpenAI Chat Completions API (LiteLLM, vLLM,
+    /// LM Studio, Azure OpenAI, ...), configured under
+    /// `[ai.openai_compatible.<name>]`.
+    #[serde(rename = "openai_compatible")]
+    OpenAICompatible,
 }
 
 impl std::fmt::Display for Provider {
@@ -17,6 +22,7 @@ impl std::fmt::Display for Provider {
             Provider::OpenAI => write!(f, "openai"),
             Provider::Google => write!(f, "google"),
             Provider::Ollama => write!(f, "ollama"),
+            Provider::OpenAICompatible => write!(f, "openai_compatible"),
         }
     }
 }
@@ -2,6 +2,7 @@ pub mod ui;
 pub mod spinner;
 pub mod table;
 pub mod panel;
+pub mod stream;
 pub mod theme;
 
 pub use ui::NakamaUI;
@@ -58,6 +58,11 @@ impl Spinner {
         );
     }
 
+    /// Stop the spinner and clear its line without printing a message.
+    pub fn finish_and_clear(self) {
+        self.bar.finish_and_clear();
+    }
+
     /// Update the spinner's message text without stopping it.
     pub fn update_message(&self, message: &str) {
         self.bar.set_message(message.to_string());
@@ -0,0 +1,128 @@
+use crate::theme;
+use owo_colors::OwoColorize;
+use std::io::{self, Write};
+
+/// Width of the top and bottom borders of a streaming panel.
+const BORDER_WIDTH: usize = 60;
+
+/// A panel whose body is written progressively, e.g. while an AI response
+/// streams in.
+///
+/// Unlike [`Panel`](crate::panel::Panel), the content width is not known up
+/// front, so only the left border is drawn next to the text.
+///
+/// # Example output
+/// ```text
+/// +-- Answer -----------------------------------
+/// |  First line of the answer
+/// |  second line, still arriving...
+/// +---------------------------------------------
+/// ```
+pub struct StreamPanel {
+    /// Whether output is printed at all (suppressed in quiet mode).
+    enabled: bool,
+    /// Whether the next character starts a new line (and needs a border).
+    at_line_start: bool,
+    /// Whether the bottom border has been printed.
+    finished: bool,
+}
+
+impl StreamPanel {
+    /// Print the top border and return a panel ready to receive text.
+    pub fn new(title: &str, enabled: bool) -> Self {
+        if enabled {
+            let title_display = format!(" {} ", title);
+            let remaining = BORDER_WIDTH.saturating_sub(title_display.chars().count() + 2);
+            let top = format!(
+                "{}{}{}{}",
+                theme::symbols::BOX_TOP_LEFT,
+                theme::symbols::BOX_HORIZONTAL,
+                title_display,
+                theme::symbols::BOX_HORIZONTAL.repeat(remaining.max(1)),
+            );
+            println!("{}", top.style(theme::primary()));
+        }
+
+        Self {
+            enabled,
+            at_line_start: true,
+            finished: false,
+        }
+    }
+
+    /// Append a chunk of text to the panel body.
+    pub fn push(&mut self, text: &str) {
+        if !self.enabled || self.finished {
+            return;
+        }
+
+        let mut stdout = io::stdout().lock();
+        for segment in text.split_inclusive('\n') {
+            if self.at_line_start {
+                let _ = write!(
+                    stdout,
+                    "{} ",
+                    theme::symbols::BOX_VERTICAL.style(theme::primary())
+                );
+            }
+            let _ = write!(stdout, "{}", segment);
+            self.at_line_start = segment.ends_with('\n');
+        }
+        let _ = stdout.flush();
+    }
+
+    /// Print the bottom border.  Called automatically on drop.
+    pub fn finish(&mut self) {
+        if self.finished {
+            return;
+        }
+        self.finished = true;
+
+        if !self.enabled {
+            return;
+        }
+        if !self.at_line_start {
+            println!();
+        }
+        let bottom = format!(
+            "{}{}",
+            theme::symbols::BOX_BOTTOM_LEFT,
+            theme::symbols::BOX_HORIZONTAL.repeat(BORDER_WIDTH - 1),
+        );
+        println!("{}", bottom.style(theme::primary()));
+    }
+}
+
+impl Drop for StreamPanel {
+    fn drop(&mut self) {
+        self.finish();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tracks_line_starts() {
+        let mut panel = StreamPanel::new("Streaming", false);
+        assert!(panel.at_line_start);
+        panel.enabled = true;
+        panel.push("partial");
+        assert!(!panel.at_line_start);
+        panel.push(" line\nnext");
+        assert!(!panel.at_line_start);
+        panel.push("\n");
+        assert!(panel.at_line_start);
+    }
+
+    #[test]
+    fn test_finish_is_idempotent() {
+        let mut panel = StreamPanel::new("Done", false);
+        panel.finish();
+        panel.finish();
+        assert!(panel.finished);
+        // Pushing after finish is ignored.
+        panel.push("ignored");
+    }
+}
@@ -1,9 +1,12 @@
 use crate::panel::Panel;
 use crate::spinner::Spinner;
+use crate::stream::StreamPanel;
 use crate::table::NakamaTable;
 use crate::theme;
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::terminal;
 use crossterm::tty::IsTty;
-use nakama_core::config::{Config, UiConfig};
+use nakama_core::config::{Config, ConfigDiagnostics, UiConfig};
 use nakama_core::types::{ColorMode, Verbosity};
 use owo_colors::OwoColorize;
 use std::io::{self, Write};
@@ -112,6 +115,19 @@ impl NakamaUI {
         );
     }
 
+    /// Show the problems found while loading the configuration.
+    pub fn config_diagnostics(&self, diagnostics: &ConfigDiagnostics) {
+        for error in &diagnostics.errors {
+            self.error(&format!("Config error: {}", error));
+        }
+        for warning in &diagnostics.warnings {
+            self.warn(&format!("Config warning: {}", warning));
+        }
+        if !diagnostics.is_empty() {
+            self.warn("Run `nakama config validate` for details.");
+        }
+    }
+
     /// Print an informational message in dim/gray text.
     pub fn info(&self, message: &str) {
         if self.verbosity >= Verbosity::Verbose {
@@ -156,6 +172,13 @@ impl NakamaUI {
         }
     }
 
+    /// Open a panel whose content is printed incrementally as it arrives.
+    ///
+    /// The bottom border is drawn by [`StreamPanel::finish`] or on drop.
+    pub fn stream_panel(&self, title: &str) -> StreamPanel {
+        StreamPanel::new(title, self.verbosity >= Verbosity::Normal)
+    }
+
     /// Prompt the user for a Y/n confirmation.
     ///
     /// Returns `Ok(true)` if the user confirms, `Ok(false)` if they decline.
@@ -184,6 +207,33 @@ impl NakamaUI {
         Ok(trimmed.is_empty() || trimmed == "y" || trimmed == "yes")
     }
 
+    /// Prompt the user for a secret without echoing it to the terminal.
+    ///
+    /// Returns an error if stdin is not a TTY; callers should read the
+    /// secret from stdin instead.  Ctrl-C and Esc cancel the prompt.
+    pub fn prompt_secret(&self, message: &str) -> Result<String, io::Error> {
+        if !io::stdin().is_tty() {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "Cannot prompt for a secret: stdin is not a TTY",
+            ));
+        }
+
+        eprint!(
+            "  {} {}: ",
+            theme::symbols::ARROW.style(theme::primary()),
+            message,
+        );
+        io::stderr().flush()?;
+
+        terminal::enable_raw_mode()?;
+        let result = read_hidden_line();
+        let _ = terminal::disable_raw_mode();
+        eprintln!();
+
+        result
+    }
+
     /// Check whether the terminal is interactive (TTY).
     pub fn is_tty(&self) -> bool {
         self.is_tty
@@ -200,6 +250,33 @@ impl NakamaUI {
     }
 }
 
+/// Read key events in raw mode until Enter, without echoing them.
+fn read_hidden_line() -> Result<String, io::Error> {
+    let mut input = String::new();
+    loop {
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind == KeyEventKind::Release {
+            continue;
+        }
+        match key.code {
+            KeyCode::Enter => return Ok(input),
+            KeyCode::Backspace => {
+                input.pop();
+            }
+            KeyCode::Esc => {
+                return Err(io::Error::new(io::ErrorKind::Interrupted, "Input cancelled"));
+            }
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                return Err(io::Error::new(io::ErrorKind::Interrupted, "Input cancelled"));
+            }
+            KeyCode::Char(c) => input.push(c),
+            _ => {}
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,110 @@
+//! The audit anchor signing key.
+//!
+//! Audit anchors (see `nakama_audit::anchor`) are signed with an Ed25519 key
+//! kept in the vault as `nakama-audit/anchor-signing-key`, so rewriting the
+//! audit database also requires access to the vault.  The key is created on
+//! first use and travels with vault bundles.  It also signs retention
+//! checkpoints.  [`crate::startup`] calls [`maybe_prune`] and
+//! [`maybe_anchor`] to prune expired entries and to anchor the chain every
+//! `[audit] anchor_interval_hours`.
+
+use crate::encrypted_file::EncryptedFileBackend;
+use crate::secret::SecretValue;
+use crate::vault::{CredentialStore, Vault};
+use chrono::Utc;
+use nakama_audit::anchor::{self, AnchorLog, SigningKey};
+use nakama_audit::AuditLog;
+use nakama_core::config::AuditConfig;
+use nakama_core::error::{NakamaError, NakamaResult};
+
+/// Vault service holding the anchor key.
+pub const SERVICE: &str = "nakama-audit";
+
+/// Vault key name of the anchor key.
+pub const KEY: &str = "anchor-signing-key";
+
+/// The anchor key, if one has been created.
+pub fn load(store: &dyn CredentialStore) -> NakamaResult<Option<SigningKey>> {
+    if !store.list_keys(SERVICE)?.iter().any(|k| k == KEY) {
+        return Ok(None);
+    }
+    let seed = store.retrieve(SERVICE, KEY)?;
+    anchor::key_from_hex(seed.expose_secret()).map(Some)
+}
+
+/// The anchor key, creating and storing one if there is none yet.
+///
+/// Refuses to create a key while the encrypted file store is locked, since
+/// an existing key may be sitting in it.
+pub fn load_or_create(vault: &Vault) -> NakamaResult<SigningKey> {
+    if let Some(key) = load(vault)? {
+        return Ok(key);
+    }
+    if EncryptedFileBackend::is_locked()? {
+        return Err(NakamaError::Vault {
+            message: "Vault is locked; unlock it to use the audit anchor key".to_string(),
+            source: None,
+        });
+    }
+
+    let key = anchor::generate_key();
+    vault.store(SERVICE, KEY, &SecretValue::new(anchor::key_to_hex(&key)))?;
+    // Not an issued credential: rotating it only distrusts existing anchors,
+    // so keep it out of the stale-credential warnings.
+    if let Some(log) = vault.rotation_log() {
+        let _ = log.remove(SERVICE, KEY);
+    }
+    tracing::info!("Created audit anchor signing key");
+    Ok(key)
+}
+
+/// Run automatic audit cleanup (`nakama_audit::maybe_prune`), signing the
+/// checkpoint with the anchor key.
+///
+/// Never prompts: while the key cannot be loaded, cleanup is skipped.
+pub fn maybe_prune(config: &AuditConfig) {
+    nakama_audit::maybe_prune(config, || {
+        Vault::new().and_then(|vault| load_or_create(&vault))
+    });
+}
+
+/// Anchor the audit chain if the last anchor is older than
+/// `config.anchor_interval_hours`.  A value of `0` disables anchoring.
+///
+/// Never prompts: a locked vault or any other failure is logged and skipped.
+pub fn maybe_anchor(config: &AuditConfig) {
+    if !config.enabled || config.anchor_interval_hours == 0 {
+        return;
+    }
+    let Ok(dir) = nakama_core::paths::audit_dir() else {
+        return;
+    };
+    if !dir.join("audit.db").exists() {
+        return;
+    }
+    let anchors = AnchorLog::open_at(&dir.join(anchor::ANCHOR_FILE));
+    let now = Utc::now();
+    match anchors.is_due(config.anchor_interval_hours, now) {
+        Ok(true) => {}
+        Ok(false) => return,
+        Err(e) => {
+            tracing::warn!("Skipping audit anchoring: {e}");
+            return;
+        }
+    }
+
+    let key = match Vault::new().and_then(|vault| load_or_create(&vault)) {
+        Ok(key) => key,
+        Err(e) => {
+            tracing::debug!("Skipping audit anchoring: {e}");
+            return;
+        }
+    };
+    match AuditLog::new(config).and_then(|log| log.anchor(&key, &anchors, now)) {
+        Ok(Some(anchor)) => {
+            tracing::debug!(head = %anchor.head_id, "Automatic audit anchoring finished");
+        }
+        Ok(None) => {}
+        Err(e) => tracing::warn!("Automatic audit anchoring failed: {e}"),
+    }
+}
@@ -0,0 +1,238 @@
+//! `nakama-vault doctor` — diagnose credential storage problems.
+//!
+//! Each check reports `ok`, `warn` or `fail`; any failure makes the command
+//! exit non-zero so it can gate scripts and CI.
+
+use crate::Auditor;
+use anyhow::{bail, Result};
+use nakama_audit::{Category, Outcome};
+use nakama_core::config::Config;
+use nakama_core::{paths, permissions};
+use nakama_ui::NakamaUI;
+use nakama_vault::encrypted_file::EncryptedFileBackend;
+use nakama_vault::keychain::KeychainBackend;
+use nakama_vault::unlock;
+use nakama_vault::{CredentialStore, KeyMode, SecretValue, Vault};
+use std::path::Path;
+use std::time::Instant;
+
+/// Services whose `api_key` the AI providers look up.
+const AI_PROVIDERS: &[&str] = &["anthropic", "openai", "google"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Status {
+    Ok,
+    Warn,
+    Fail,
+}
+
+impl Status {
+    fn label(self) -> &'static str {
+        match self {
+            Status::Ok => "ok",
+            Status::Warn => "warn",
+            Status::Fail => "fail",
+        }
+    }
+}
+
+struct Check {
+    name: String,
+    status: Status,
+    detail: String,
+}
+
+impl Check {
+    fn new(name: impl Into<String>, status: Status, detail: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            status,
+            detail: detail.into(),
+        }
+    }
+}
+
+pub fn run(config: &Config, ui: &NakamaUI, vault: &Vault, auditor: &Auditor) -> Result<()> {
+    let start = Instant::now();
+
+    let mut checks = vec![
+        roundtrip_check("keychain", KeychainBackend::new().map(boxed)),
+        roundtrip_check("encrypted_file", EncryptedFileBackend::new().map(boxed)),
+        key_mode_check(),
+        permissions_check(),
+    ];
+    checks.extend(provider_checks(vault));
+    checks.push(audit_check(config));
+
+    let failures = checks.iter().filter(|c| c.status == Status::Fail).count();
+    let warnings = checks.iter().filter(|c| c.status == Status::Warn).count();
+
+    auditor.record(
+        Category::CredentialAccess,
+        "doctor",
+        "Checked credential storage",
+        serde_json::json!({ "failures": failures, "warnings": warnings }),
+        if failures == 0 {
+            Outcome::Success
+        } else {
+            Outcome::Failure
+        },
+        start,
+    );
+
+    ui.table(
+        &["Check", "Status", "Detail"],
+        checks
+            .into_iter()
+            .map(|c| vec![c.name, c.status.label().to_string(), c.detail])
+            .collect(),
+    );
+
+    if failures > 0 {
+        bail!("{failures} check(s) failed");
+    }
+    if warnings > 0 {
+        ui.warn(&format!("{warnings} warning(s)"));
+    } else {
+        ui.success("Credential storage looks healthy");
+    }
+    Ok(())
+}
+
+fn boxed<B: CredentialStore + 'static>(backend: B) -> Box<dyn CredentialStore> {
+    Box::new(backend)
+}
+
+/// Store, read back and delete a sentinel value in a single backend.
+fn roundtrip_check(
+    name: &str,
+    backend: nakama_core::error::NakamaResult<Box<dyn CredentialStore>>,
+) -> Check {
+    let label = format!("{name} backend");
+    let backend = match backend {
+        Ok(backend) => backend,
+        Err(e) => return Check::new(label, Status::Warn, format!("unavailable: {e}")),
+    };
+
+    let sentinel = SecretValue::new("nakama-vault-doctor".to_string());
+    let result = backend
+        .store("nakama-doctor", "probe", &sentinel)
+        .and_then(|()| backend.retrieve("nakama-doctor", "probe"));
+    let _ = backend.delete("nakama-doctor", "probe");
+
+    match result {
+        Ok(value) if value.expose_secret() == sentinel.expose_secret() => Check::new(
+            label,
+            Status::Ok,
+            "store/retrieve/delete round trip succeeded",
+        ),
+        Ok(_) => Check::new(
+            label,
+            Status::Fail,
+            "read back a different value than was stored",
+        ),
+        Err(e) => Check::new(label, Status::Warn, format!("round trip failed: {e}")),
+    }
+}
+
+/// The machine-derived key only protects against other machines; a master
+/// password also protects against anyone who can read the vault directory.
+fn key_mode_check() -> Check {
+    let label = "encryption key";
+    match EncryptedFileBackend::key_mode() {
+        Ok(KeyMode::Machine) => Check::new(
+            label,
+            Status::Warn,
+            "derived from user name, home and hostname; run `nakama-vault migrate --to master-password`",
+        ),
+        Ok(KeyMode::MasterPassword) => match EncryptedFileBackend::is_locked() {
+            Ok(true) => Check::new(label, Status::Ok, "master password (locked)"),
+            Ok(false) => Check::new(label, Status::Ok, "master password (unlocked)"),
+            Err(e) => Check::new(label, Status::Fail, e.to_string()),
+        },
+        Err(e) => Check::new(label, Status::Fail, e.to_string()),
+    }
+}
+
+/// Every directory and file under `~/.nakama/vault/`, and the unlock
+/// session, must be owner-only.
+fn permissions_check() -> Check {
+    let label = "vault permissions";
+    let vault_dir = match paths::vault_dir() {
+        Ok(dir) => dir,
+        Err(e) => return Check::new(label, Status::Fail, e.to_string()),
+    };
+    if !vault_dir.exists() {
+        return Check::new(label, Status::Ok, "no vault directory yet");
+    }
+
+    let mut insecure = Vec::new();
+    collect_insecure(&vault_dir, &mut insecure);
+    let session_dir = unlock::session_path()
+        .ok()
+        .and_then(|session| session.parent().map(Path::to_path_buf));
+    if let Some(dir) = session_dir.filter(|dir| dir.exists()) {
+        collect_insecure(&dir, &mut insecure);
+    }
+    if insecure.is_empty() {
+        Check::new(
+            label,
+            Status::Ok,
+            format!("{} is owner-only", vault_dir.display()),
+        )
+    } else {
+        Check::new(
+            label,
+            Status::Fail,
+            format!("readable by group/others: {}", insecure.join(", ")),
+        )
+    }
+}
+
+fn collect_insecure(path: &Path, insecure: &mut Vec<String>) {
+    if !permissions::check_secure_permissions(path).unwrap_or(false) {
+        insecure.push(path.display().to_string());
+    }
+    if path.is_dir() {
+        if let Ok(entries) = std::fs::read_dir(path) {
+            for entry in entries.flatten() {
+                collect_insecure(&entry.path(), insecure);
+            }
+        }
+    }
+}
+
+/// Report where each AI provider key is found; keys coming from plain
+/// environment variables are flagged.
+fn provider_checks(vault: &Vault) -> Vec<Check> {
+    AI_PROVIDERS
+        .iter()
+        .map(|provider| {
+            let label = format!("{provider} api_key");
+            match vault.retrieve_with_backend(provider, "api_key") {
+                Ok(("env", _)) => Check::new(
+                    label,
+                    Status::Warn,
+                    "read from an environment variable; store it with `nakama-vault store`",
+                ),
+                Ok((backend, _)) => Check::new(label, Status::Ok, format!("in {backend} backend")),
+                Err(_) => Check::new(label, Status::Ok, "not configured"),
+            }
+        })
+        .collect()
+}
+
+fn audit_check(config: &Config) -> Check {
+    let label = "audit log";
+    if !config.audit.enabled {
+        return Check::new(
+            label,
+            Status::Warn,
+            "disabled; credential access is not recorded",
+        );
+    }
+    match nakama_audit::AuditLog::new(&config.audit) {
+        Ok(_) => Check::new(label, Status::Ok, "credential access is recorded"),
+        Err(e) => Check::new(label, Status::Fail, e.to_string()),
+    }
+}
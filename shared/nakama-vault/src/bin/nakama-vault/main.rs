@@ -0,0 +1,862 @@
+//! `nakama-vault` — manage the credentials used by the Nakama tools.
+//!
+//! Secrets are read from a hidden prompt or from stdin, never from the
+//! command line where they would show up in `ps` and shell history.  Every
+//! access is recorded in the audit log as a `credential_access` entry; the
+//! audit detail names the service, key and backend but never the value.
+
+mod doctor;
+
+use anyhow::{bail, Context, Result};
+use chrono::Utc;
+use clap::{Parser, Subcommand, ValueEnum};
+use nakama_audit::{AuditEntry, AuditLog, Category, Outcome};
+use nakama_core::permissions;
+use nakama_core::trace::TraceContext;
+use nakama_core::Config;
+use nakama_log::init_logging;
+use nakama_ui::NakamaUI;
+use nakama_vault::bundle::{self, Bundle, ConflictPolicy};
+use nakama_vault::encrypted_file::EncryptedFileBackend;
+use nakama_vault::keychain::KeychainBackend;
+use nakama_vault::{CredentialStore, KeyMode, SecretValue, Vault};
+use std::io::{IsTerminal, Read};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+const TOOL_NAME: &str = "nakama-vault";
+
+/// How long `unlock` keeps a master-password vault open by default.
+const DEFAULT_UNLOCK_MINUTES: u64 = 15;
+
+/// nakama-vault - Secure credential storage for the Nakama CLI Suite
+#[derive(Parser, Debug)]
+#[command(name = TOOL_NAME, version, about, long_about = None)]
+struct Cli {
+    #[command(subcommand)]
+    command: Commands,
+}
+
+#[derive(Subcommand, Debug)]
+enum Commands {
+    /// Store a credential (the value is prompted for, or read from stdin)
+    Store {
+        /// Service the credential belongs to (e.g. anthropic, github)
+        service: String,
+
+        /// Credential name within the service (e.g. api_key)
+        key: String,
+
+        /// Read the value from stdin even when it is a terminal
+        #[arg(long)]
+        stdin: bool,
+    },
+
+    /// Look up a credential and report which backend holds it
+    Get {
+        service: String,
+        key: String,
+
+        /// Print the secret value to stdout
+        #[arg(long)]
+        reveal: bool,
+    },
+
+    /// Delete a credential from every backend that holds it
+    Delete {
+        service: String,
+        key: String,
+
+        /// Skip the confirmation prompt
+        #[arg(short, long)]
+        yes: bool,
+    },
+
+    /// List stored credential names (never values)
+    List {
+        /// Only list keys of this service
+        service: Option<String>,
+    },
+
+    /// Show the storage backends and which one new credentials go to
+    Backends,
+
+    /// Check backends, file permissions and provider keys for problems
+    Doctor,
+
+    /// Unlock a master-password vault for a while so tools can read it
+    Unlock {
+        /// Minutes until the vault locks again
+        #[arg(long, default_value_t = DEFAULT_UNLOCK_MINUTES)]
+        timeout: u64,
+    },
+
+    /// Lock a master-password vault now
+    Lock,
+
+    /// Re-encrypt the encrypted file store under a new key
+    Migrate {
+        /// Key derivation to migrate to; `master-password` on a vault that
+        /// already uses one changes the passphrase
+        #[arg(long, value_enum)]
+        to: MigrateTarget,
+    },
+
+    /// Write every stored credential to a passphrase-encrypted bundle
+    Export {
+        /// Bundle file to write
+        file: PathBuf,
+
+        /// Overwrite the file if it exists
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Store the credentials from a bundle written by `export`
+    Import {
+        /// Bundle file to read
+        file: PathBuf,
+
+        /// What to do with credentials that already exist with another value
+        #[arg(long, value_enum, default_value_t = OnConflict::Skip)]
+        on_conflict: OnConflict,
+    },
+
+    /// Replace a credential with a new value and record the rotation
+    Rotate {
+        service: String,
+        key: String,
+
+        /// Read the new value from stdin even when it is a terminal
+        #[arg(long)]
+        stdin: bool,
+    },
+
+    /// Show when each credential was last rotated
+    Rotation,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum MigrateTarget {
+    /// Key derived from user name, home directory and hostname
+    Machine,
+    /// Key derived from a passphrase with Argon2id
+    MasterPassword,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum OnConflict {
+    /// Keep the existing value
+    Skip,
+    /// Replace the existing value
+    Overwrite,
+    /// Import nothing if any credential conflicts
+    Abort,
+}
+
+impl From<OnConflict> for ConflictPolicy {
+    fn from(on_conflict: OnConflict) -> Self {
+        match on_conflict {
+            OnConflict::Skip => ConflictPolicy::Skip,
+            OnConflict::Overwrite => ConflictPolicy::Overwrite,
+            OnConflict::Abort => ConflictPolicy::Abort,
+        }
+    }
+}
+
+impl From<MigrateTarget> for KeyMode {
+    fn from(target: MigrateTarget) -> Self {
+        match target {
+            MigrateTarget::Machine => KeyMode::Machine,
+            MigrateTarget::MasterPassword => KeyMode::MasterPassword,
+        }
+    }
+}
+
+impl Commands {
+    fn name(&self) -> &'static str {
+        match self {
+            Commands::Store { .. } => "store",
+            Commands::Get { .. } => "get",
+            Commands::Delete { .. } => "delete",
+            Commands::List { .. } => "list",
+            Commands::Backends => "backends",
+            Commands::Doctor => "doctor",
+            Commands::Unlock { .. } => "unlock",
+            Commands::Lock => "lock",
+            Commands::Migrate { .. } => "migrate",
+            Commands::Export { .. } => "export",
+            Commands::Import { .. } => "import",
+            Commands::Rotate { .. } => "rotate",
+            Commands::Rotation => "rotation",
+        }
+    }
+
+    /// Whether the command reads or writes credentials, and so should offer
+    /// to unlock a locked vault first.
+    fn needs_credentials(&self) -> bool {
+        matches!(
+            self,
+            Commands::Store { .. }
+                | Commands::Get { .. }
+                | Commands::Delete { .. }
+                | Commands::List { .. }
+                | Commands::Export { .. }
+                | Commands::Import { .. }
+                | Commands::Rotate { .. }
+                | Commands::Rotation
+        )
+    }
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let (config, diagnostics) = Config::load_or_default(TOOL_NAME);
+    let _log_guard = init_logging(TOOL_NAME, &config.logging)?;
+    let ui = NakamaUI::from_config(&config);
+    ui.config_diagnostics(&diagnostics);
+    let trace = TraceContext::new(TOOL_NAME, cli.command.name());
+
+    let auditor = Auditor::new(&config, &trace);
+    let result = prepare(&ui, &auditor, &cli.command)
+        .and_then(|()| Vault::new().context("Failed to initialize vault"))
+        .and_then(|vault| match cli.command {
+            Commands::Store {
+                service,
+                key,
+                stdin,
+            } => store(&ui, &vault, &auditor, &service, &key, stdin),
+            Commands::Get {
+                service,
+                key,
+                reveal,
+            } => get(&ui, &vault, &auditor, &service, &key, reveal),
+            Commands::Delete { service, key, yes } => {
+                delete(&ui, &vault, &auditor, &service, &key, yes)
+            }
+            Commands::List { service } => list(&ui, &vault, &auditor, service.as_deref()),
+            Commands::Backends => backends(&ui, &vault),
+            Commands::Doctor => doctor::run(&config, &ui, &vault, &auditor),
+            Commands::Unlock { timeout } => unlock(&ui, &auditor, timeout),
+            Commands::Lock => lock(&ui, &auditor),
+            Commands::Migrate { to } => migrate(&ui, &auditor, to.into()),
+            Commands::Export { file, force } => export(&ui, &vault, &auditor, &file, force),
+            Commands::Import { file, on_conflict } => {
+                import(&ui, &vault, &auditor, &file, on_conflict.into())
+            }
+            Commands::Rotate {
+                service,
+                key,
+                stdin,
+            } => rotate(&ui, &vault, &auditor, &service, &key, stdin),
+            Commands::Rotation => rotation(&ui, &vault, config.vault.rotation_warn_days),
+        });
+
+    if let Err(e) = result {
+        ui.error(&format!("{:#}", e));
+        // `exit` skips destructors, so write the failure's queued entries now.
+        auditor.flush();
+        std::process::exit(1);
+    }
+
+    Ok(())
+}
+
+/// Offer to unlock a locked master-password vault before a command that
+/// needs credentials, so they are not silently looked up elsewhere.
+fn prepare(ui: &NakamaUI, auditor: &Auditor, command: &Commands) -> Result<()> {
+    if !command.needs_credentials()
+        || !std::io::stdin().is_terminal()
+        || !EncryptedFileBackend::is_locked().unwrap_or(false)
+    {
+        return Ok(());
+    }
+
+    ui.warn("The vault is locked");
+    unlock(ui, auditor, DEFAULT_UNLOCK_MINUTES)
+}
+
+fn store(
+    ui: &NakamaUI,
+    vault: &Vault,
+    auditor: &Auditor,
+    service: &str,
+    key: &str,
+    from_stdin: bool,
+) -> Result<()> {
+    let value = read_secret(ui, &format!("Value for {service}/{key}"), true, from_stdin)?;
+    let start = Instant::now();
+    let result = vault.store_with_backend(service, key, &value);
+
+    let detail = |backend: Option<&str>| serde_json::json!({ "service": service, "key": key, "backend": backend });
+    match result {
+        Ok("env") => {
+            auditor.record(
+                Category::CredentialAccess,
+                "store",
+                &format!("Store {service}/{key}"),
+                detail(None),
+                Outcome::Failure,
+                start,
+            );
+            bail!(
+                "No persistent backend could store {service}/{key}. \
+                 Set it as an environment variable instead (see `nakama-vault backends`)"
+            );
+        }
+        Ok(backend) => {
+            auditor.record(
+                Category::CredentialAccess,
+                "store",
+                &format!("Store {service}/{key}"),
+                detail(Some(backend)),
+                Outcome::Success,
+                start,
+            );
+            ui.success(&format!("Stored {service}/{key} in the {backend} backend"));
+            Ok(())
+        }
+        Err(e) => {
+            auditor.record(
+                Category::CredentialAccess,
+                "store",
+                &format!("Store {service}/{key}"),
+                detail(None),
+                Outcome::Failure,
+                start,
+            );
+            Err(e.into())
+        }
+    }
+}
+
+fn get(
+    ui: &NakamaUI,
+    vault: &Vault,
+    auditor: &Auditor,
+    service: &str,
+    key: &str,
+    reveal: bool,
+) -> Result<()> {
+    let start = Instant::now();
+    let result = vault.retrieve_with_backend(service, key);
+
+    let action = if reveal {
+        format!("Reveal {service}/{key}")
+    } else {
+        format!("Look up {service}/{key}")
+    };
+    let backend = result.as_ref().ok().map(|(backend, _)| *backend);
+    let outcome = if result.is_ok() {
+        Outcome::Success
+    } else {
+        Outcome::Failure
+    };
+    auditor.record(
+        Category::CredentialAccess,
+        "get",
+        &action,
+        serde_json::json!({ "service": service, "key": key, "backend": backend, "revealed": reveal }),
+        outcome,
+        start,
+    );
+
+    let (backend, value) = result?;
+    if reveal {
+        println!("{}", value.expose_secret());
+    } else {
+        ui.success(&format!(
+            "{service}/{key} is stored in the {backend} backend ({} characters)",
+            value.expose_secret().chars().count()
+        ));
+        ui.info("Use --reveal to print the value");
+    }
+    Ok(())
+}
+
+fn delete(
+    ui: &NakamaUI,
+    vault: &Vault,
+    auditor: &Auditor,
+    service: &str,
+    key: &str,
+    yes: bool,
+) -> Result<()> {
+    if !yes && !ui.confirm(&format!("Delete {service}/{key} from every backend?"))? {
+        ui.warn("Aborted");
+        return Ok(());
+    }
+
+    let start = Instant::now();
+    let result = vault.delete(service, key);
+    let outcome = if result.is_ok() {
+        Outcome::Success
+    } else {
+        Outcome::Failure
+    };
+    auditor.record(
+        Category::CredentialAccess,
+        "delete",
+        &format!("Delete {service}/{key}"),
+        serde_json::json!({ "service": service, "key": key }),
+        outcome,
+        start,
+    );
+
+    result?;
+    ui.success(&format!("Deleted {service}/{key}"));
+    Ok(())
+}
+
+fn list(ui: &NakamaUI, vault: &Vault, auditor: &Auditor, service: Option<&str>) -> Result<()> {
+    let start = Instant::now();
+    let services = match service {
+        Some(service) => vec![service.to_string()],
+        None => vault.list_services()?,
+    };
+
+    let mut rows = Vec::new();
+    for service in &services {
+        for key in vault.list_keys(service)? {
+            rows.push(vec![service.clone(), key]);
+        }
+    }
+
+    auditor.record(
+        Category::CredentialAccess,
+        "list",
+        "List credential names",
+        serde_json::json!({ "service": service, "count": rows.len() }),
+        Outcome::Success,
+        start,
+    );
+
+    if rows.is_empty() {
+        ui.warn("No stored credentials found (environment variables are not listed)");
+        return Ok(());
+    }
+    ui.table(&["Service", "Key"], rows);
+    Ok(())
+}
+
+fn backends(ui: &NakamaUI, vault: &Vault) -> Result<()> {
+    let primary = vault.primary_backend_name();
+    let rows = vault
+        .backend_statuses()
+        .into_iter()
+        .map(|status| {
+            let state = if !status.available {
+                "unavailable"
+            } else if status.name == primary {
+                "primary"
+            } else {
+                "available"
+            };
+            let notes = match (status.name, status.error) {
+                ("env", _) => "read-only, NAKAMA_<SERVICE>_<KEY> variables".to_string(),
+                ("encrypted_file", None) => match EncryptedFileBackend::key_mode() {
+                    Ok(KeyMode::MasterPassword) => "master-password key".to_string(),
+                    _ => "machine-derived key".to_string(),
+                },
+                (_, error) => error.unwrap_or_default(),
+            };
+            vec![status.name.to_string(), state.to_string(), notes]
+        })
+        .collect();
+
+    ui.table(&["Backend", "Status", "Notes"], rows);
+    Ok(())
+}
+
+fn unlock(ui: &NakamaUI, auditor: &Auditor, timeout_minutes: u64) -> Result<()> {
+    if EncryptedFileBackend::key_mode()? != KeyMode::MasterPassword {
+        bail!("The vault does not use a master password (see `nakama-vault migrate --to master-password`)");
+    }
+
+    let passphrase = read_secret(ui, "Vault passphrase", false, false)?;
+    let start = Instant::now();
+    let result =
+        EncryptedFileBackend::unlock(&passphrase, Duration::from_secs(timeout_minutes * 60));
+    let outcome = if result.is_ok() {
+        Outcome::Success
+    } else {
+        Outcome::Denied
+    };
+    auditor.record(
+        Category::Authentication,
+        "unlock",
+        "Unlock vault",
+        serde_json::json!({ "timeout_minutes": timeout_minutes }),
+        outcome,
+        start,
+    );
+
+    result?;
+    ui.success(&format!("Vault unlocked for {timeout_minutes} minutes"));
+    Ok(())
+}
+
+fn lock(ui: &NakamaUI, auditor: &Auditor) -> Result<()> {
+    let start = Instant::now();
+    let was_unlocked = EncryptedFileBackend::lock()?;
+    auditor.record(
+        Category::Authentication,
+        "lock",
+        "Lock vault",
+        serde_json::json!({ "was_unlocked": was_unlocked }),
+        Outcome::Success,
+        start,
+    );
+
+    if was_unlocked {
+        ui.success("Vault locked");
+    } else {
+        ui.info("No unlock session was active");
+    }
+    Ok(())
+}
+
+fn migrate(ui: &NakamaUI, auditor: &Auditor, target: KeyMode) -> Result<()> {
+    let current = EncryptedFileBackend::key_mode()?;
+    if current == KeyMode::Machine && target == KeyMode::Machine {
+        ui.info("The vault already uses the machine key");
+        return Ok(());
+    }
+    if current == KeyMode::MasterPassword && EncryptedFileBackend::is_locked()? {
+        unlock(ui, auditor, DEFAULT_UNLOCK_MINUTES)?;
+    }
+    let backend = EncryptedFileBackend::new().context("Failed to open the encrypted file store")?;
+
+    let passphrase = match target {
+        KeyMode::MasterPassword => Some(read_secret(ui, "New vault passphrase", true, false)?),
+        KeyMode::Machine => None,
+    };
+
+    let start = Instant::now();
+    let result = backend.migrate(target, passphrase.as_ref());
+    let outcome = if result.is_ok() {
+        Outcome::Success
+    } else {
+        Outcome::Failure
+    };
+    auditor.record(
+        Category::CredentialAccess,
+        "migrate",
+        &format!("Migrate vault to {target} mode"),
+        serde_json::json!({ "from": current, "to": target, "entries": result.as_ref().ok() }),
+        outcome,
+        start,
+    );
+
+    let count = result?;
+    ui.success(&format!(
+        "Re-encrypted {count} credential(s) with the {target} key"
+    ));
+    if let Some(passphrase) = passphrase {
+        EncryptedFileBackend::unlock(
+            &passphrase,
+            Duration::from_secs(DEFAULT_UNLOCK_MINUTES * 60),
+        )?;
+        ui.info(&format!(
+            "Vault unlocked for {DEFAULT_UNLOCK_MINUTES} minutes; tools will ask for `nakama-vault unlock` after that"
+        ));
+    }
+    Ok(())
+}
+
+fn export(ui: &NakamaUI, vault: &Vault, auditor: &Auditor, file: &Path, force: bool) -> Result<()> {
+    if file.exists() && !force {
+        bail!(
+            "{} already exists (use --force to overwrite)",
+            file.display()
+        );
+    }
+
+    // The keychain can't be enumerated, so entries stored before its name
+    // index existed are only found under the names the tools use.
+    if let Ok(keychain) = KeychainBackend::new() {
+        for name in keychain.index_well_known() {
+            ui.info(&format!("Found {name} in the keychain"));
+        }
+        ui.warn(
+            "Keychain entries stored before the keychain index existed are only exported under \
+             well-known names; store any others again with `nakama-vault store` to include them",
+        );
+    }
+
+    let credentials = bundle::collect(vault, vault.rotation_log())?;
+    if credentials.is_empty() {
+        bail!("No stored credentials to export (environment variables are not exported)");
+    }
+    let passphrase = read_secret(ui, "Bundle passphrase", true, false)?;
+
+    let start = Instant::now();
+    let result = Bundle::seal(&credentials, &passphrase)
+        .and_then(|bundle| bundle.to_json())
+        .map_err(anyhow::Error::from)
+        .and_then(|json| {
+            std::fs::write(file, json)
+                .with_context(|| format!("Failed to write {}", file.display()))?;
+            permissions::set_file_permissions(file)?;
+            Ok(())
+        });
+    auditor.record(
+        Category::CredentialAccess,
+        "export",
+        &format!("Export {} credential(s)", credentials.len()),
+        serde_json::json!({ "file": file, "count": credentials.len() }),
+        if result.is_ok() {
+            Outcome::Success
+        } else {
+            Outcome::Failure
+        },
+        start,
+    );
+
+    result?;
+    ui.success(&format!(
+        "Exported {} credential(s) to {}",
+        credentials.len(),
+        file.display()
+    ));
+    ui.warn("The bundle is only as strong as its passphrase; delete it once imported");
+    Ok(())
+}
+
+fn import(
+    ui: &NakamaUI,
+    vault: &Vault,
+    auditor: &Auditor,
+    file: &Path,
+    policy: ConflictPolicy,
+) -> Result<()> {
+    let json = std::fs::read_to_string(file)
+        .with_context(|| format!("Failed to read {}", file.display()))?;
+    let bundle = Bundle::from_json(&json)?;
+    let passphrase = read_secret(ui, "Bundle passphrase", false, false)?;
+
+    let start = Instant::now();
+    let result = bundle
+        .open(&passphrase)
+        .and_then(|credentials| bundle::import(vault, vault.rotation_log(), &credentials, policy));
+    let counts = result.as_ref().ok().map(|report| {
+        serde_json::json!({
+            "added": report.added.len(),
+            "overwritten": report.overwritten.len(),
+            "skipped": report.skipped.len(),
+            "unchanged": report.unchanged.len(),
+        })
+    });
+    auditor.record(
+        Category::CredentialAccess,
+        "import",
+        &format!("Import credentials from {}", file.display()),
+        serde_json::json!({ "file": file, "on_conflict": format!("{policy:?}").to_lowercase(), "result": counts }),
+        if result.is_ok() {
+            Outcome::Success
+        } else {
+            Outcome::Failure
+        },
+        start,
+    );
+
+    let report = result?;
+    ui.success(&format!(
+        "Imported {} new and {} overwritten credential(s); {} already up to date",
+        report.added.len(),
+        report.overwritten.len(),
+        report.unchanged.len()
+    ));
+    if !report.skipped.is_empty() {
+        ui.warn(&format!(
+            "Kept existing values for {} (use --on-conflict overwrite to replace them)",
+            report.skipped.join(", ")
+        ));
+    }
+    Ok(())
+}
+
+fn rotate(
+    ui: &NakamaUI,
+    vault: &Vault,
+    auditor: &Auditor,
+    service: &str,
+    key: &str,
+    from_stdin: bool,
+) -> Result<()> {
+    let (_, current) = vault.retrieve_with_backend(service, key).with_context(|| {
+        format!("Nothing to rotate; store it with `nakama-vault store {service} {key}`")
+    })?;
+    let value = read_secret(
+        ui,
+        &format!("New value for {service}/{key}"),
+        true,
+        from_stdin,
+    )?;
+    if value.expose_secret() == current.expose_secret() {
+        bail!("The new value is the same as the current one");
+    }
+
+    let start = Instant::now();
+    let previous = vault
+        .rotation_log()
+        .and_then(|log| log.last_rotated(service, key));
+    let result = vault.store_with_backend(service, key, &value);
+    let backend = result
+        .as_ref()
+        .ok()
+        .copied()
+        .filter(|backend| *backend != "env");
+    auditor.record(
+        Category::CredentialAccess,
+        "rotate",
+        &format!("Rotate {service}/{key}"),
+        serde_json::json!({ "service": service, "key": key, "backend": backend, "previous_rotation": previous }),
+        if backend.is_some() {
+            Outcome::Success
+        } else {
+            Outcome::Failure
+        },
+        start,
+    );
+
+    match (result?, previous) {
+        ("env", _) => bail!("No persistent backend could store {service}/{key}"),
+        (backend, Some(previous)) => ui.success(&format!(
+            "Rotated {service}/{key} in the {backend} backend (previous value was {} days old)",
+            (Utc::now() - previous).num_days()
+        )),
+        (backend, None) => ui.success(&format!("Rotated {service}/{key} in the {backend} backend")),
+    }
+    ui.info("Revoke the old value with its provider if you have not already");
+    Ok(())
+}
+
+fn rotation(ui: &NakamaUI, vault: &Vault, warn_days: u32) -> Result<()> {
+    let now = Utc::now();
+    let mut rows = Vec::new();
+    for service in vault.list_services()? {
+        for key in vault.list_keys(&service)? {
+            let rotated_at = vault
+                .rotation_log()
+                .and_then(|log| log.last_rotated(&service, &key));
+            let row = match rotated_at {
+                Some(at) => {
+                    let age = (now - at).num_days();
+                    let status = if warn_days > 0 && age > i64::from(warn_days) {
+                        "stale"
+                    } else {
+                        "ok"
+                    };
+                    vec![
+                        service.clone(),
+                        key,
+                        at.format("%Y-%m-%d").to_string(),
+                        format!("{age}d"),
+                        status.to_string(),
+                    ]
+                }
+                None => vec![
+                    service.clone(),
+                    key,
+                    "-".to_string(),
+                    "-".to_string(),
+                    "unknown".to_string(),
+                ],
+            };
+            rows.push(row);
+        }
+    }
+
+    if rows.is_empty() {
+        ui.warn("No stored credentials found (environment variables are not listed)");
+        return Ok(());
+    }
+    ui.table(&["Service", "Key", "Last rotated", "Age", "Status"], rows);
+    if warn_days > 0 {
+        ui.info(&format!(
+            "Credentials older than {warn_days} days are flagged ([vault] rotation_warn_days)"
+        ));
+    }
+    Ok(())
+}
+
+/// Read a secret from a hidden prompt (asked twice when `confirm` is set)
+/// or, when stdin is not a terminal or `--stdin` is given, from stdin with
+/// the trailing newline removed.
+fn read_secret(ui: &NakamaUI, label: &str, confirm: bool, from_stdin: bool) -> Result<SecretValue> {
+    let value = if from_stdin || !std::io::stdin().is_terminal() {
+        let mut input = String::new();
+        std::io::stdin()
+            .read_to_string(&mut input)
+            .context("Failed to read secret from stdin")?;
+        input.trim_end_matches(['\r', '\n']).to_string()
+    } else {
+        let first = ui.prompt_secret(label)?;
+        if confirm && ui.prompt_secret("Confirm")? != first {
+            bail!("Values do not match");
+        }
+        first
+    };
+
+    if value.is_empty() {
+        bail!("Refusing to use an empty value");
+    }
+    Ok(SecretValue::new(value))
+}
+
+/// Writes the audit entries for one invocation.
+pub(crate) struct Auditor {
+    log: Option<AuditLog>,
+    trace_id: String,
+}
+
+impl Auditor {
+    fn new(config: &Config, trace: &TraceContext) -> Self {
+        let log = if config.audit.enabled {
+            AuditLog::new(&config.audit)
+                .map_err(|e| tracing::warn!("Failed to open audit log: {}", e))
+                .ok()
+        } else {
+            None
+        };
+        Self {
+            log,
+            trace_id: trace.trace_id.clone(),
+        }
+    }
+
+    pub(crate) fn record(
+        &self,
+        category: Category,
+        command: &str,
+        action: &str,
+        detail: serde_json::Value,
+        outcome: Outcome,
+        start: Instant,
+    ) {
+        let Some(log) = &self.log else {
+            return;
+        };
+        let entry = AuditEntry::new(
+            &self.trace_id,
+            TOOL_NAME,
+            command,
+            category,
+            action,
+            detail,
+            outcome,
+            start.elapsed().as_millis() as u64,
+        );
+        if let Err(e) = log.log(entry) {
+            tracing::warn!("Failed to write audit log: {}", e);
+        }
+    }
+
+    /// Write any entries still queued by the batching writer.
+    fn flush(&self) {
+        if let Some(Err(e)) = self.log.as_ref().map(AuditLog::flush) {
+            tracing::warn!("Failed to write audit log: {}", e);
+        }
+    }
+}
@@ -0,0 +1,468 @@
+//! Passphrase-encrypted credential bundles for moving a vault between
+//! machines.
+//!
+//! A bundle is a single JSON file holding every credential of a
+//! [`CredentialStore`], sealed with AES-256-GCM under a key derived from a
+//! passphrase with Argon2id.  Only the format marker, version, creation time
+//! and KDF parameters are stored in the clear; service and key names are
+//! encrypted along with the values.
+
+use crate::encrypted_file::{decode_nonce, hex_decode, hex_encode};
+use crate::header::KdfParams;
+use crate::rotation::RotationLog;
+use crate::secret::SecretValue;
+use crate::vault::CredentialStore;
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Nonce};
+use chrono::{DateTime, Utc};
+use nakama_core::error::{NakamaError, NakamaResult};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+
+/// Format marker written to every bundle.
+pub const BUNDLE_FORMAT: &str = "nakama-vault-bundle";
+
+/// Current bundle format version.
+pub const BUNDLE_VERSION: u32 = 1;
+
+/// A credential read from or destined for a bundle.
+#[derive(Debug, Clone)]
+pub struct BundledCredential {
+    pub service: String,
+    pub key: String,
+    pub value: SecretValue,
+    /// When the credential was last rotated on the exporting machine.
+    pub rotated_at: Option<DateTime<Utc>>,
+}
+
+/// What to do when an imported credential already exists with a different
+/// value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictPolicy {
+    /// Keep the existing value.
+    Skip,
+    /// Replace the existing value.
+    Overwrite,
+    /// Import nothing if any credential conflicts.
+    Abort,
+}
+
+/// Outcome of [`import`], as `service/key` names.
+#[derive(Debug, Default)]
+pub struct ImportReport {
+    /// Credentials that did not exist before.
+    pub added: Vec<String>,
+    /// Existing credentials replaced by the bundle's value.
+    pub overwritten: Vec<String>,
+    /// Existing credentials kept despite a different value in the bundle.
+    pub skipped: Vec<String>,
+    /// Credentials that already had the bundle's value.
+    pub unchanged: Vec<String>,
+}
+
+/// On-disk bundle.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Bundle {
+    /// Always [`BUNDLE_FORMAT`].
+    pub format: String,
+    /// Bundle format version.
+    pub version: u32,
+    /// When the bundle was written.
+    pub created_at: DateTime<Utc>,
+    /// Passphrase KDF parameters.
+    pub kdf: KdfParams,
+    /// AES-256-GCM nonce (hex-encoded).
+    nonce: String,
+    /// Sealed credential list (hex-encoded).
+    ciphertext: String,
+}
+
+/// Plaintext credential as serialized inside the sealed payload.
+#[derive(Serialize, Deserialize)]
+struct PayloadEntry {
+    service: String,
+    key: String,
+    value: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    rotated_at: Option<DateTime<Utc>>,
+}
+
+impl Bundle {
+    /// Seal `credentials` under `passphrase` with fresh KDF parameters.
+    pub fn seal(credentials: &[BundledCredential], passphrase: &SecretValue) -> NakamaResult<Self> {
+        Self::seal_with(credentials, passphrase, KdfParams::generate())
+    }
+
+    fn seal_with(
+        credentials: &[BundledCredential],
+        passphrase: &SecretValue,
+        kdf: KdfParams,
+    ) -> NakamaResult<Self> {
+        let payload: Vec<PayloadEntry> = credentials
+            .iter()
+            .map(|c| PayloadEntry {
+                service: c.service.clone(),
+                key: c.key.clone(),
+                value: c.value.expose_secret().to_string(),
+                rotated_at: c.rotated_at,
+            })
+            .collect();
+        let json = serde_json::to_string(&payload).map_err(|e| NakamaError::Vault {
+            message: format!("Failed to serialize bundle: {}", e),
+            source: Some(Box::new(e)),
+        })?;
+        let plaintext = SecretValue::new(json);
+
+        let key = kdf.derive(passphrase)?;
+        let mut nonce = [0u8; 12];
+        OsRng.fill_bytes(&mut nonce);
+        let ciphertext = cipher(&key)?
+            .encrypt(
+                Nonce::from_slice(&nonce),
+                plaintext.expose_secret().as_bytes(),
+            )
+            .map_err(|e| NakamaError::Vault {
+                message: format!("Failed to encrypt bundle: {}", e),
+                source: None,
+            })?;
+
+        Ok(Self {
+            format: BUNDLE_FORMAT.to_string(),
+            version: BUNDLE_VERSION,
+            created_at: Utc::now(),
+            kdf,
+            nonce: hex_encode(&nonce),
+            ciphertext: hex_encode(&ciphertext),
+        })
+    }
+
+    /// Decrypt the bundle's credentials.
+    pub fn open(&self, passphrase: &SecretValue) -> NakamaResult<Vec<BundledCredential>> {
+        let nonce = decode_nonce(&self.nonce)?;
+        let key = self.kdf.derive(passphrase)?;
+        let plaintext = cipher(&key)?
+            .decrypt(
+                Nonce::from_slice(&nonce),
+                hex_decode(&self.ciphertext)?.as_ref(),
+            )
+            .map_err(|_| NakamaError::Vault {
+                message: "Wrong bundle passphrase or corrupted bundle".to_string(),
+                source: None,
+            })?;
+        let json = String::from_utf8(plaintext).map_err(|e| NakamaError::Vault {
+            message: format!("Bundle payload is not valid UTF-8: {}", e),
+            source: Some(Box::new(e)),
+        })?;
+        let plaintext = SecretValue::new(json);
+
+        let payload: Vec<PayloadEntry> =
+            serde_json::from_str(plaintext.expose_secret()).map_err(|e| NakamaError::Vault {
+                message: format!("Failed to parse bundle payload: {}", e),
+                source: Some(Box::new(e)),
+            })?;
+        Ok(payload
+            .into_iter()
+            .map(|entry| BundledCredential {
+                service: entry.service,
+                key: entry.key,
+                value: SecretValue::new(entry.value),
+                rotated_at: entry.rotated_at,
+            })
+            .collect())
+    }
+
+    /// Serialize the bundle for writing to disk.
+    pub fn to_json(&self) -> NakamaResult<String> {
+        serde_json::to_string_pretty(self).map_err(|e| NakamaError::Vault {
+            message: format!("Failed to serialize bundle: {}", e),
+            source: Some(Box::new(e)),
+        })
+    }
+
+    /// Parse a bundle, rejecting other formats and newer versions.
+    pub fn from_json(json: &str) -> NakamaResult<Self> {
+        let bundle: Self = serde_json::from_str(json).map_err(|e| NakamaError::Vault {
+            message: format!("Not a nakama vault bundle: {}", e),
+            source: Some(Box::new(e)),
+        })?;
+        if bundle.format != BUNDLE_FORMAT {
+            return Err(NakamaError::Vault {
+                message: format!("Not a nakama vault bundle (format '{}')", bundle.format),
+                source: None,
+            });
+        }
+        if bundle.version > BUNDLE_VERSION {
+            return Err(NakamaError::Vault {
+                message: format!(
+                    "Bundle version {} is newer than this nakama-vault supports ({}); upgrade first",
+                    bundle.version, BUNDLE_VERSION
+                ),
+                source: None,
+            });
+        }
+        Ok(bundle)
+    }
+}
+
+/// Read every credential in `store`, with rotation times from `rotation`.
+pub fn collect(
+    store: &dyn CredentialStore,
+    rotation: Option<&RotationLog>,
+) -> NakamaResult<Vec<BundledCredential>> {
+    let mut credentials = Vec::new();
+    for service in store.list_services()? {
+        for key in store.list_keys(&service)? {
+            let value = store.retrieve(&service, &key)?;
+            let rotated_at = rotation.and_then(|log| log.last_rotated(&service, &key));
+            credentials.push(BundledCredential {
+                service: service.clone(),
+                key,
+                value,
+                rotated_at,
+            });
+        }
+    }
+    Ok(credentials)
+}
+
+/// Store `credentials` in `store`, resolving conflicts with `policy`.
+///
+/// Rotation times carried by the bundle are written to `rotation` so that
+/// importing does not reset the age of old credentials.
+pub fn import(
+    store: &dyn CredentialStore,
+    rotation: Option<&RotationLog>,
+    credentials: &[BundledCredential],
+    policy: ConflictPolicy,
+) -> NakamaResult<ImportReport> {
+    let mut report = ImportReport::default();
+    let mut pending = Vec::new();
+
+    for credential in credentials {
+        let name = format!("{}/{}", credential.service, credential.key);
+        match store.retrieve(&credential.service, &credential.key) {
+            Ok(existing) if existing.expose_secret() == credential.value.expose_secret() => {
+                report.unchanged.push(name);
+            }
+            Ok(_) => match policy {
+                ConflictPolicy::Skip => report.skipped.push(name),
+                ConflictPolicy::Overwrite | ConflictPolicy::Abort => {
+                    report.overwritten.push(name);
+                    pending.push(credential);
+                }
+            },
+            Err(_) => {
+                report.added.push(name);
+                pending.push(credential);
+            }
+        }
+    }
+
+    if policy == ConflictPolicy::Abort && !report.overwritten.is_empty() {
+        return Err(NakamaError::Vault {
+            message: format!(
+                "Import aborted, these credentials already exist with different values: {}",
+                report.overwritten.join(", ")
+            ),
+            source: None,
+        });
+    }
+
+    for credential in pending {
+        store.store(&credential.service, &credential.key, &credential.value)?;
+        if let (Some(log), Some(at)) = (rotation, credential.rotated_at) {
+            log.record(&credential.service, &credential.key, at)?;
+        }
+    }
+    Ok(report)
+}
+
+fn cipher(key: &[u8]) -> NakamaResult<Aes256Gcm> {
+    Aes256Gcm::new_from_slice(key).map_err(|e| NakamaError::Vault {
+        message: format!("Failed to create cipher: {}", e),
+        source: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::collections::BTreeMap;
+
+    /// In-memory store for exercising import/export.
+    #[derive(Default)]
+    struct MemoryStore {
+        entries: RefCell<BTreeMap<(String, String), String>>,
+    }
+
+    impl CredentialStore for MemoryStore {
+        fn store(&self, service: &str, key: &str, value: &SecretValue) -> NakamaResult<()> {
+            self.entries.borrow_mut().insert(
+                (service.to_string(), key.to_string()),
+                value.expose_secret().to_string(),
+            );
+            Ok(())
+        }
+
+        fn retrieve(&self, service: &str, key: &str) -> NakamaResult<SecretValue> {
+            self.entries
+                .borrow()
+                .get(&(service.to_string(), key.to_string()))
+                .map(|v| SecretValue::new(v.clone()))
+                .ok_or_else(|| NakamaError::Vault {
+                    message: "not found".to_string(),
+                    source: None,
+                })
+        }
+
+        fn delete(&self, service: &str, key: &str) -> NakamaResult<()> {
+            self.entries
+                .borrow_mut()
+                .remove(&(service.to_string(), key.to_string()));
+            Ok(())
+        }
+
+        fn list_keys(&self, service: &str) -> NakamaResult<Vec<String>> {
+            Ok(self
+                .entries
+                .borrow()
+                .keys()
+                .filter(|(s, _)| s == service)
+                .map(|(_, k)| k.clone())
+                .collect())
+        }
+
+        fn list_services(&self) -> NakamaResult<Vec<String>> {
+            let mut services: Vec<String> = self
+                .entries
+                .borrow()
+                .keys()
+                .map(|(s, _)| s.clone())
+                .collect();
+            services.dedup();
+            Ok(services)
+        }
+    }
+
+    fn cheap_kdf() -> KdfParams {
+        KdfParams {
+            memory_kib: 1024,
+            iterations: 1,
+            ..KdfParams::generate()
+        }
+    }
+
+    fn secret(value: &str) -> SecretValue {
+        SecretValue::new(value.to_string())
+    }
+
+    #[test]
+    fn test_bundle_roundtrip() {
+        let source = MemoryStore::default();
+        source
+            .store("anthropic", "api_key", &secret("sk-ant"))
+            .unwrap();
+        source.store("github", "api_key", &secret("ghp")).unwrap();
+
+        let credentials = collect(&source, None).unwrap();
+        let bundle = Bundle::seal_with(&credentials, &secret("pw"), cheap_kdf()).unwrap();
+        let json = bundle.to_json().unwrap();
+        assert!(!json.contains("sk-ant"));
+        assert!(!json.contains("anthropic"));
+
+        let opened = Bundle::from_json(&json)
+            .unwrap()
+            .open(&secret("pw"))
+            .unwrap();
+        let target = MemoryStore::default();
+        let report = import(&target, None, &opened, ConflictPolicy::Skip).unwrap();
+        assert_eq!(report.added.len(), 2);
+        assert_eq!(
+            target
+                .retrieve("anthropic", "api_key")
+                .unwrap()
+                .expose_secret(),
+            "sk-ant"
+        );
+    }
+
+    #[test]
+    fn test_wrong_passphrase_rejected() {
+        let bundle = Bundle::seal_with(&[], &secret("right"), cheap_kdf()).unwrap();
+        assert!(bundle.open(&secret("wrong")).is_err());
+    }
+
+    #[test]
+    fn test_newer_version_rejected() {
+        let mut bundle = Bundle::seal_with(&[], &secret("pw"), cheap_kdf()).unwrap();
+        bundle.version = BUNDLE_VERSION + 1;
+        assert!(Bundle::from_json(&bundle.to_json().unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_malformed_bundle_rejected() {
+        let mut bundle = Bundle::seal_with(&[], &secret("pw"), cheap_kdf()).unwrap();
+        bundle.nonce = "00ff".to_string();
+        assert!(bundle.open(&secret("pw")).is_err());
+
+        let mut bundle = Bundle::seal_with(&[], &secret("pw"), cheap_kdf()).unwrap();
+        bundle.kdf.memory_kib = u32::MAX;
+        assert!(bundle.open(&secret("pw")).is_err());
+        bundle.kdf.memory_kib = 1024;
+        bundle.kdf.iterations = u32::MAX;
+        assert!(bundle.open(&secret("pw")).is_err());
+    }
+
+    #[test]
+    fn test_import_conflict_policies() {
+        let incoming = vec![
+            BundledCredential {
+                service: "openai".to_string(),
+                key: "api_key".to_string(),
+                value: secret("new"),
+                rotated_at: None,
+            },
+            BundledCredential {
+                service: "github".to_string(),
+                key: "api_key".to_string(),
+                value: secret("same"),
+                rotated_at: None,
+            },
+        ];
+        let target = MemoryStore::default();
+        target.store("openai", "api_key", &secret("old")).unwrap();
+        target.store("github", "api_key", &secret("same")).unwrap();
+
+        let report = import(&target, None, &incoming, ConflictPolicy::Skip).unwrap();
+        assert_eq!(report.skipped, vec!["openai/api_key"]);
+        assert_eq!(report.unchanged, vec!["github/api_key"]);
+        assert_eq!(
+            target
+                .retrieve("openai", "api_key")
+                .unwrap()
+                .expose_secret(),
+            "old"
+        );
+
+        assert!(import(&target, None, &incoming, ConflictPolicy::Abort).is_err());
+        assert_eq!(
+            target
+                .retrieve("openai", "api_key")
+                .unwrap()
+                .expose_secret(),
+            "old"
+        );
+
+        let report = import(&target, None, &incoming, ConflictPolicy::Overwrite).unwrap();
+        assert_eq!(report.overwritten, vec!["openai/api_key"]);
+        assert_eq!(
+            target
+                .retrieve("openai", "api_key")
+                .unwrap()
+                .expose_secret(),
+            "new"
+        );
+    }
+}
@@ -1,4 +1,6 @@
+use crate::header::{KdfParams, KeyMode, VaultHeader, HEADER_VERSION};
 use crate::secret::SecretValue;
+use crate::unlock;
 use crate::vault::CredentialStore;
 use aes_gcm::aead::{Aead, KeyInit};
 use aes_gcm::{Aes256Gcm, Nonce};
@@ -9,23 +11,37 @@ use nakama_core::permissions;
 use rand::rngs::OsRng;
 use rand::RngCore;
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 use tracing::debug;
 
+/// Name of the vault header file inside the vault directory.
+const HEADER_FILE: &str = "vault.json";
+
+/// Environment variable that can supply the master passphrase when no
+/// unlock session is active (e.g. in CI).
+pub const PASSPHRASE_ENV: &str = "NAKAMA_VAULT_PASSPHRASE";
+
 /// Encrypted file-based credential backend.
 ///
 /// Stores credentials in `~/.nakama/vault/<service>/<key>.enc`, where each
-/// file contains an AES-256-GCM encrypted payload. The encryption key is
-/// derived from machine-specific data using Argon2id, providing a portable
-/// fallback when the OS keychain is unavailable.
+/// file contains an AES-256-GCM encrypted payload. Per-entry keys are derived
+/// with Argon2id from either machine-specific data (the default) or, in
+/// master-password mode, from a master key derived from the user's
+/// passphrase with the parameters recorded in `~/.nakama/vault/vault.json`.
+///
+/// The backend refuses to open a vault whose directory, header, or entry
+/// files can be read by other users.
 pub struct EncryptedFileBackend {
     vault_dir: PathBuf,
-    machine_key: Vec<u8>,
+    /// Input to the per-entry key derivation: the machine identity or the
+    /// master key.
+    key_material: Vec<u8>,
 }
 
 /// On-disk format for an encrypted credential.
 #[derive(Serialize, Deserialize)]
-struct EncryptedEntry {
+pub(crate) struct EncryptedEntry {
     /// Argon2id salt (16 bytes, hex-encoded)
     salt: String,
     /// AES-256-GCM nonce (12 bytes, hex-encoded)
@@ -37,20 +53,189 @@ struct EncryptedEntry {
 impl EncryptedFileBackend {
     /// Create a new encrypted file backend.
     ///
-    /// Ensures the vault directory exists and derives a machine-specific key.
+    /// Ensures the vault directory exists and resolves the vault key. In
+    /// master-password mode the master key comes from an active unlock
+    /// session or the `NAKAMA_VAULT_PASSPHRASE` variable; without either the
+    /// vault is locked and this returns an error.
     pub fn new() -> NakamaResult<Self> {
         let vault_dir = paths::vault_dir()?;
         paths::ensure_dir(&vault_dir)?;
+        Self::open_at(vault_dir)
+    }
 
+    /// Open the encrypted file store in `vault_dir`.
+    fn open_at(vault_dir: PathBuf) -> NakamaResult<Self> {
+        ensure_secure(&vault_dir)?;
         let machine_key = Self::derive_machine_identity()?;
 
+        let key_material = match read_header(&vault_dir)? {
+            Some(header) if header.mode == KeyMode::MasterPassword => {
+                if let Some(master_key) = unlock::load(&unlock::session_path()?, &machine_key)? {
+                    master_key
+                } else if let Ok(passphrase) = std::env::var(PASSPHRASE_ENV) {
+                    header.unlock(&SecretValue::new(passphrase))?
+                } else {
+                    return Err(NakamaError::Vault {
+                        message: format!(
+                            "Vault is locked. Run `nakama-vault unlock` or set {}",
+                            PASSPHRASE_ENV
+                        ),
+                        source: None,
+                    });
+                }
+            }
+            _ => machine_key,
+        };
+
         debug!("Encrypted file backend initialized at {}", vault_dir.display());
         Ok(Self {
             vault_dir,
-            machine_key,
+            key_material,
         })
     }
 
+    /// The key derivation mode of the vault at `~/.nakama/vault/`.
+    pub fn key_mode() -> NakamaResult<KeyMode> {
+        let vault_dir = paths::vault_dir()?;
+        Ok(read_header(&vault_dir)?.map_or(KeyMode::Machine, |header| header.mode))
+    }
+
+    /// Whether the vault is in master-password mode with no way to obtain
+    /// the master key without prompting.
+    pub fn is_locked() -> NakamaResult<bool> {
+        if Self::key_mode()? != KeyMode::MasterPassword || std::env::var_os(PASSPHRASE_ENV).is_some() {
+            return Ok(false);
+        }
+        let machine_key = Self::derive_machine_identity()?;
+        Ok(unlock::load(&unlock::session_path()?, &machine_key)?.is_none())
+    }
+
+    /// Check `passphrase` against the vault header and cache the master key
+    /// for `timeout`.
+    pub fn unlock(passphrase: &SecretValue, timeout: Duration) -> NakamaResult<()> {
+        let vault_dir = paths::vault_dir()?;
+        Self::unlock_at(&vault_dir, &unlock::session_path()?, passphrase, timeout)
+    }
+
+    fn unlock_at(
+        vault_dir: &Path,
+        session_path: &Path,
+        passphrase: &SecretValue,
+        timeout: Duration,
+    ) -> NakamaResult<()> {
+        ensure_secure(vault_dir)?;
+        let header = match read_header(vault_dir)? {
+            Some(header) if header.mode == KeyMode::MasterPassword => header,
+            _ => {
+                return Err(NakamaError::Vault {
+                    message: "Vault is not in master-password mode; nothing to unlock".to_string(),
+                    source: None,
+                })
+            }
+        };
+        let master_key = header.unlock(passphrase)?;
+        unlock::remove_legacy(vault_dir)?;
+        unlock::save(session_path, &master_key, &Self::derive_machine_identity()?, timeout)
+    }
+
+    /// End the current unlock session.  Returns whether one was active;
+    /// an expired session is removed too.
+    pub fn lock() -> NakamaResult<bool> {
+        let session_path = unlock::session_path()?;
+        let active = unlock::remove_expired(&session_path, &Self::derive_machine_identity()?)?;
+        unlock::clear(&session_path)?;
+        unlock::remove_legacy(&paths::vault_dir()?)?;
+        Ok(active)
+    }
+
+    /// Re-encrypt every stored entry under a new key.
+    ///
+    /// Migrating to [`KeyMode::MasterPassword`] requires the new passphrase
+    /// and also changes the passphrase of a vault already in that mode.
+    /// Entries are written next to the originals first.  The originals and
+    /// the old header are then kept as `.bak` files while the new ones are
+    /// swapped in, and restored if any step fails; the header is swapped
+    /// last.  A process killed mid-swap leaves those backups in place for
+    /// manual recovery.  Any unlock session is ended.  Returns the number of
+    /// re-encrypted entries.
+    pub fn migrate(&self, target: KeyMode, passphrase: Option<&SecretValue>) -> NakamaResult<usize> {
+        let session_path = unlock::session_path()?;
+        self.migrate_with(target, passphrase, KdfParams::generate(), &session_path)
+    }
+
+    fn migrate_with(
+        &self,
+        target: KeyMode,
+        passphrase: Option<&SecretValue>,
+        kdf: KdfParams,
+        session_path: &Path,
+    ) -> NakamaResult<usize> {
+        unlock::remove_expired(session_path, &Self::derive_machine_identity()?)?;
+        unlock::remove_legacy(&self.vault_dir)?;
+        let (header, new_material) = match target {
+            KeyMode::Machine => (None, Self::derive_machine_identity()?),
+            KeyMode::MasterPassword => {
+                let passphrase = passphrase.ok_or_else(|| NakamaError::Vault {
+                    message: "A passphrase is required for master-password mode".to_string(),
+                    source: None,
+                })?;
+                let master_key = kdf.derive(passphrase)?;
+                (Some(VaultHeader::master_password(kdf, &master_key)?), master_key)
+            }
+        };
+
+        // Decrypt everything up front so a bad entry aborts before any write.
+        let mut staged = Vec::new();
+        let result = self.stage_migration(&new_material, &mut staged);
+        let header_path = self.vault_dir.join(HEADER_FILE);
+        let result = result.and_then(|()| swap_in(&staged, &header_path, header.as_ref()));
+        if let Err(e) = result {
+            for (tmp, path) in &staged {
+                let _ = std::fs::remove_file(tmp);
+                let backup = path.with_extension("enc.bak");
+                if backup.exists() {
+                    let _ = std::fs::rename(&backup, path);
+                }
+            }
+            let header_backup = header_path.with_extension("json.bak");
+            if header_backup.exists() {
+                let _ = std::fs::rename(&header_backup, &header_path);
+            } else if header.is_some() {
+                let _ = std::fs::remove_file(&header_path);
+            }
+            return Err(e);
+        }
+
+        for (_, path) in &staged {
+            let _ = std::fs::remove_file(path.with_extension("enc.bak"));
+        }
+        let _ = std::fs::remove_file(header_path.with_extension("json.bak"));
+        unlock::clear(session_path)?;
+
+        debug!("Migrated {} vault entries to {} mode", staged.len(), target);
+        Ok(staged.len())
+    }
+
+    /// Re-encrypt every entry under `new_material` into `<key>.enc.tmp`,
+    /// recording each staged `(tmp, path)` pair in `staged`.
+    fn stage_migration(
+        &self,
+        new_material: &[u8],
+        staged: &mut Vec<(PathBuf, PathBuf)>,
+    ) -> NakamaResult<()> {
+        for service in self.list_services()? {
+            for key in self.list_keys(&service)? {
+                let value = self.retrieve(&service, &key)?;
+                let entry = seal(new_material, value.expose_secret().as_bytes())?;
+                let path = self.credential_path(&service, &key);
+                let tmp = path.with_extension("enc.tmp");
+                write_json(&tmp, &entry)?;
+                staged.push((tmp, path));
+            }
+        }
+        Ok(())
+    }
+
     /// Derive a stable machine-specific identity for use as Argon2id input.
     ///
     /// This combines several sources to create a key that is unique to the
@@ -89,19 +274,6 @@ impl EncryptedFileBackend {
         Ok(identity)
     }
 
-    /// Derive an AES-256 key from the machine identity and a per-entry salt.
-    fn derive_key(&self, salt: &[u8]) -> NakamaResult<[u8; 32]> {
-        let mut key = [0u8; 32];
-        let argon2 = Argon2::default();
-        argon2
-            .hash_password_into(&self.machine_key, salt, &mut key)
-            .map_err(|e| NakamaError::Vault {
-                message: format!("Argon2id key derivation failed: {}", e),
-                source: None,
-            })?;
-        Ok(key)
-    }
-
     /// Get the directory path for a service: `~/.nakama/vault/<service>/`
     fn service_dir(&self, service: &str) -> PathBuf {
         self.vault_dir.join(service)
@@ -114,59 +286,173 @@ impl EncryptedFileBackend {
 
     /// Encrypt a plaintext value and return the serialized entry.
     fn encrypt(&self, plaintext: &str) -> NakamaResult<EncryptedEntry> {
-        // Generate random salt (16 bytes) and nonce (12 bytes)
-        let mut salt = [0u8; 16];
-        let mut nonce_bytes = [0u8; 12];
-        OsRng.fill_bytes(&mut salt);
-        OsRng.fill_bytes(&mut nonce_bytes);
-
-        // Derive key from machine identity + salt
-        let key = self.derive_key(&salt)?;
-        let cipher = Aes256Gcm::new_from_slice(&key).map_err(|e| NakamaError::Vault {
-            message: format!("Failed to create cipher: {}", e),
-            source: None,
-        })?;
-
-        let nonce = Nonce::from_slice(&nonce_bytes);
-        let ciphertext = cipher
-            .encrypt(nonce, plaintext.as_bytes())
-            .map_err(|e| NakamaError::Vault {
-                message: format!("AES-256-GCM encryption failed: {}", e),
-                source: None,
-            })?;
-
-        Ok(EncryptedEntry {
-            salt: hex_encode(&salt),
-            nonce: hex_encode(&nonce_bytes),
-            ciphertext: hex_encode(&ciphertext),
-        })
+        seal(&self.key_material, plaintext.as_bytes())
     }
 
     /// Decrypt an encrypted entry and return the plaintext.
     fn decrypt(&self, entry: &EncryptedEntry) -> NakamaResult<String> {
-        let salt = hex_decode(&entry.salt)?;
-        let nonce_bytes = hex_decode(&entry.nonce)?;
-        let ciphertext = hex_decode(&entry.ciphertext)?;
+        let plaintext = open_sealed(&self.key_material, entry)?;
+        String::from_utf8(plaintext).map_err(|e| NakamaError::Vault {
+            message: format!("Decrypted value is not valid UTF-8: {}", e),
+            source: Some(Box::new(e)),
+        })
+    }
+}
 
-        let key = self.derive_key(&salt)?;
-        let cipher = Aes256Gcm::new_from_slice(&key).map_err(|e| NakamaError::Vault {
-            message: format!("Failed to create cipher: {}", e),
+/// Derive an AES-256 key from key material and a per-entry salt.
+fn derive_key(key_material: &[u8], salt: &[u8]) -> NakamaResult<[u8; 32]> {
+    let mut key = [0u8; 32];
+    let argon2 = Argon2::default();
+    argon2
+        .hash_password_into(key_material, salt, &mut key)
+        .map_err(|e| NakamaError::Vault {
+            message: format!("Argon2id key derivation failed: {}", e),
             source: None,
         })?;
+    Ok(key)
+}
 
-        let nonce = Nonce::from_slice(&nonce_bytes);
-        let plaintext = cipher
-            .decrypt(nonce, ciphertext.as_ref())
-            .map_err(|e| NakamaError::Vault {
-                message: format!("AES-256-GCM decryption failed (wrong machine or corrupted file): {}", e),
-                source: None,
-            })?;
+/// Encrypt `plaintext` under a key derived from `key_material` and a fresh
+/// random salt.
+pub(crate) fn seal(key_material: &[u8], plaintext: &[u8]) -> NakamaResult<EncryptedEntry> {
+    // Generate random salt (16 bytes) and nonce (12 bytes)
+    let mut salt = [0u8; 16];
+    let mut nonce_bytes = [0u8; 12];
+    OsRng.fill_bytes(&mut salt);
+    OsRng.fill_bytes(&mut nonce_bytes);
+
+    // Derive key from key material + salt
+    let key = derive_key(key_material, &salt)?;
+    let cipher = Aes256Gcm::new_from_slice(&key).map_err(|e| NakamaError::Vault {
+        message: format!("Failed to create cipher: {}", e),
+        source: None,
+    })?;
+
+    let nonce = Nonce::from_slice(&nonce_bytes);
+    let ciphertext = cipher
+        .encrypt(nonce, plaintext)
+        .map_err(|e| NakamaError::Vault {
+            message: format!("AES-256-GCM encryption failed: {}", e),
+            source: None,
+        })?;
 
-        String::from_utf8(plaintext).map_err(|e| NakamaError::Vault {
-            message: format!("Decrypted value is not valid UTF-8: {}", e),
-            source: Some(Box::new(e)),
+    Ok(EncryptedEntry {
+        salt: hex_encode(&salt),
+        nonce: hex_encode(&nonce_bytes),
+        ciphertext: hex_encode(&ciphertext),
+    })
+}
+
+/// Decrypt an entry produced by [`seal`] with the same key material.
+pub(crate) fn open_sealed(key_material: &[u8], entry: &EncryptedEntry) -> NakamaResult<Vec<u8>> {
+    let salt = hex_decode(&entry.salt)?;
+    let nonce_bytes = decode_nonce(&entry.nonce)?;
+    let ciphertext = hex_decode(&entry.ciphertext)?;
+
+    let key = derive_key(key_material, &salt)?;
+    let cipher = Aes256Gcm::new_from_slice(&key).map_err(|e| NakamaError::Vault {
+        message: format!("Failed to create cipher: {}", e),
+        source: None,
+    })?;
+
+    let nonce = Nonce::from_slice(&nonce_bytes);
+    cipher
+        .decrypt(nonce, ciphertext.as_ref())
+        .map_err(|e| NakamaError::Vault {
+            message: format!("AES-256-GCM decryption failed (wrong machine, wrong passphrase or corrupted file): {}", e),
+            source: None,
         })
+}
+
+/// Refuse to use a vault path that group or others can access.
+pub(crate) fn ensure_secure(path: &Path) -> NakamaResult<()> {
+    if path.exists() && !permissions::check_secure_permissions(path)? {
+        let mode = if path.is_dir() { "700" } else { "600" };
+        return Err(NakamaError::Vault {
+            message: format!(
+                "{} is accessible by other users; refusing to use it. Fix with: chmod {} {}",
+                path.display(),
+                mode,
+                path.display()
+            ),
+            source: None,
+        });
     }
+    Ok(())
+}
+
+fn remove_if_exists(path: &Path) -> NakamaResult<()> {
+    match std::fs::remove_file(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+/// Move staged entries and the new header into place, keeping each
+/// original as a `.bak` file for the caller to restore or delete.
+fn swap_in(
+    staged: &[(PathBuf, PathBuf)],
+    header_path: &Path,
+    header: Option<&VaultHeader>,
+) -> NakamaResult<()> {
+    // Backups left by an interrupted earlier run are stale: every entry was
+    // just read under the current key.  Clear them so a rollback cannot
+    // restore one.
+    for (_, path) in staged {
+        remove_if_exists(&path.with_extension("enc.bak"))?;
+    }
+    remove_if_exists(&header_path.with_extension("json.bak"))?;
+
+    for (tmp, path) in staged {
+        std::fs::rename(path, path.with_extension("enc.bak"))?;
+        std::fs::rename(tmp, path)?;
+    }
+
+    if header_path.exists() {
+        std::fs::rename(header_path, header_path.with_extension("json.bak"))?;
+    }
+    if let Some(header) = header {
+        let tmp = header_path.with_extension("json.tmp");
+        write_json(&tmp, header)?;
+        std::fs::rename(&tmp, header_path)?;
+    }
+    Ok(())
+}
+
+/// Read the vault header, if the vault has one.
+fn read_header(vault_dir: &Path) -> NakamaResult<Option<VaultHeader>> {
+    let path = vault_dir.join(HEADER_FILE);
+    if !path.exists() {
+        return Ok(None);
+    }
+    ensure_secure(&path)?;
+
+    let json = std::fs::read_to_string(&path)?;
+    let header: VaultHeader = serde_json::from_str(&json).map_err(|e| NakamaError::Vault {
+        message: format!("Failed to parse vault header at {}: {}", path.display(), e),
+        source: Some(Box::new(e)),
+    })?;
+    if header.version > HEADER_VERSION {
+        return Err(NakamaError::Vault {
+            message: format!(
+                "Vault header version {} is newer than supported ({}); upgrade nakama-vault",
+                header.version, HEADER_VERSION
+            ),
+            source: None,
+        });
+    }
+    Ok(Some(header))
+}
+
+/// Write `value` as pretty JSON with owner-only permissions.
+fn write_json<T: Serialize>(path: &Path, value: &T) -> NakamaResult<()> {
+    let json = serde_json::to_string_pretty(value).map_err(|e| NakamaError::Vault {
+        message: format!("Failed to serialize {}: {}", path.display(), e),
+        source: Some(Box::new(e)),
+    })?;
+    std::fs::write(path, json)?;
+    permissions::set_file_permissions(path)?;
+    Ok(())
 }
 
 impl CredentialStore for EncryptedFileBackend {
@@ -175,14 +461,8 @@ impl CredentialStore for EncryptedFileBackend {
         paths::ensure_dir(&svc_dir)?;
 
         let entry = self.encrypt(value.expose_secret())?;
-        let json = serde_json::to_string_pretty(&entry).map_err(|e| NakamaError::Vault {
-            message: format!("Failed to serialize encrypted entry: {}", e),
-            source: Some(Box::new(e)),
-        })?;
-
         let path = self.credential_path(service, key);
-        std::fs::write(&path, json)?;
-        permissions::set_file_permissions(&path)?;
+        write_json(&path, &entry)?;
 
         debug!("Stored {}/{} to encrypted file at {}", service, key, path.display());
         Ok(())
@@ -197,6 +477,8 @@ impl CredentialStore for EncryptedFileBackend {
             });
         }
 
+        ensure_secure(&path)?;
+
         let json = std::fs::read_to_string(&path)?;
         let entry: EncryptedEntry = serde_json::from_str(&json).map_err(|e| NakamaError::Vault {
             message: format!("Failed to parse encrypted entry at {}: {}", path.display(), e),
@@ -212,6 +494,8 @@ impl CredentialStore for EncryptedFileBackend {
         let path = self.credential_path(service, key);
         if path.exists() {
             std::fs::remove_file(&path)?;
+            // Drop the service directory once its last key is gone
+            let _ = std::fs::remove_dir(self.service_dir(service));
             debug!("Deleted encrypted file for {}/{}", service, key);
         }
         Ok(())
@@ -238,15 +522,33 @@ impl CredentialStore for EncryptedFileBackend {
         debug!("Listed {} keys for service {} in encrypted file store", keys.len(), service);
         Ok(keys)
     }
+
+    fn list_services(&self) -> NakamaResult<Vec<String>> {
+        let mut services = Vec::new();
+        for entry in std::fs::read_dir(&self.vault_dir)? {
+            let path = entry?.path();
+            if !path.is_dir() {
+                continue;
+            }
+            if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
+                if !self.list_keys(name)?.is_empty() {
+                    services.push(name.to_string());
+                }
+            }
+        }
+
+        services.sort();
+        Ok(services)
+    }
 }
 
 /// Hex-encode bytes to a lowercase hex string.
-fn hex_encode(bytes: &[u8]) -> String {
+pub(crate) fn hex_encode(bytes: &[u8]) -> String {
     bytes.iter().map(|b| format!("{:02x}", b)).collect()
 }
 
 /// Hex-decode a hex string to bytes.
-fn hex_decode(hex: &str) -> NakamaResult<Vec<u8>> {
+pub(crate) fn hex_decode(hex: &str) -> NakamaResult<Vec<u8>> {
     if hex.len() % 2 != 0 {
         return Err(NakamaError::Vault {
             message: "Invalid hex string (odd length)".to_string(),
@@ -265,6 +567,14 @@ fn hex_decode(hex: &str) -> NakamaResult<Vec<u8>> {
         .collect()
 }
 
+/// Hex-decode an AES-GCM nonce, rejecting any that is not 12 bytes.
+pub(crate) fn decode_nonce(hex: &str) -> NakamaResult<[u8; 12]> {
+    <[u8; 12]>::try_from(hex_decode(hex)?).map_err(|bytes| NakamaError::Vault {
+        message: format!("Invalid nonce: expected 12 bytes, got {}", bytes.len()),
+        source: None,
+    })
+}
+
 /// Get the system hostname.
 fn hostname() -> Result<String, std::io::Error> {
     #[cfg(unix)]
@@ -320,4 +630,124 @@ mod tests {
         let decrypted = backend.decrypt(&entry).unwrap();
         assert_eq!(decrypted, plaintext);
     }
+
+    fn temp_backend(name: &str) -> EncryptedFileBackend {
+        let vault_dir = std::env::temp_dir().join(format!("nakama-vault-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&vault_dir);
+        paths::ensure_dir(&vault_dir).unwrap();
+        EncryptedFileBackend {
+            vault_dir,
+            key_material: EncryptedFileBackend::derive_machine_identity().unwrap(),
+        }
+    }
+
+    fn cheap_kdf() -> KdfParams {
+        KdfParams {
+            memory_kib: 1024,
+            iterations: 1,
+            ..KdfParams::generate()
+        }
+    }
+
+    #[test]
+    fn test_migrate_to_master_password_and_back() {
+        let machine = temp_backend("migrate");
+        let session = machine.vault_dir.join("session.json");
+        machine
+            .store("anthropic", "api_key", &SecretValue::new("sk-ant-1".to_string()))
+            .unwrap();
+        machine
+            .store("github", "api_key", &SecretValue::new("ghp-2".to_string()))
+            .unwrap();
+
+        let passphrase = SecretValue::new("correct horse".to_string());
+        let migrated = machine
+            .migrate_with(KeyMode::MasterPassword, Some(&passphrase), cheap_kdf(), &session)
+            .unwrap();
+        assert_eq!(migrated, 2);
+
+        // The machine key no longer opens the entries; the passphrase does.
+        assert!(machine.retrieve("anthropic", "api_key").is_err());
+        let header = read_header(&machine.vault_dir).unwrap().unwrap();
+        assert_eq!(header.mode, KeyMode::MasterPassword);
+        let master = EncryptedFileBackend {
+            vault_dir: machine.vault_dir.clone(),
+            key_material: header.unlock(&passphrase).unwrap(),
+        };
+        assert_eq!(master.retrieve("github", "api_key").unwrap().expose_secret(), "ghp-2");
+
+        master
+            .migrate_with(KeyMode::Machine, None, cheap_kdf(), &session)
+            .unwrap();
+        assert!(read_header(&machine.vault_dir).unwrap().is_none());
+        assert_eq!(machine.retrieve("anthropic", "api_key").unwrap().expose_secret(), "sk-ant-1");
+
+        let _ = std::fs::remove_dir_all(&machine.vault_dir);
+    }
+
+    #[test]
+    fn test_failed_migration_restores_originals() {
+        let machine = temp_backend("migrate-fail");
+        let session = machine.vault_dir.join("session.json");
+        machine
+            .store("anthropic", "api_key", &SecretValue::new("sk-ant-1".to_string()))
+            .unwrap();
+        machine
+            .store("github", "api_key", &SecretValue::new("ghp-2".to_string()))
+            .unwrap();
+
+        // Entries are swapped in, then writing the new header fails.
+        std::fs::create_dir(machine.vault_dir.join(HEADER_FILE).with_extension("json.tmp")).unwrap();
+        let passphrase = SecretValue::new("pw".to_string());
+        assert!(machine
+            .migrate_with(KeyMode::MasterPassword, Some(&passphrase), cheap_kdf(), &session)
+            .is_err());
+
+        assert!(read_header(&machine.vault_dir).unwrap().is_none());
+        assert_eq!(machine.retrieve("anthropic", "api_key").unwrap().expose_secret(), "sk-ant-1");
+        assert_eq!(machine.retrieve("github", "api_key").unwrap().expose_secret(), "ghp-2");
+
+        let _ = std::fs::remove_dir_all(&machine.vault_dir);
+    }
+
+    #[test]
+    fn test_unlock_caches_master_key() {
+        let machine = temp_backend("unlock");
+        let session = machine.vault_dir.join("session.json");
+        let passphrase = SecretValue::new("pw".to_string());
+        machine
+            .migrate_with(KeyMode::MasterPassword, Some(&passphrase), cheap_kdf(), &session)
+            .unwrap();
+
+        let wrong = SecretValue::new("nope".to_string());
+        assert!(EncryptedFileBackend::unlock_at(&machine.vault_dir, &session, &wrong, Duration::from_secs(60)).is_err());
+        EncryptedFileBackend::unlock_at(&machine.vault_dir, &session, &passphrase, Duration::from_secs(60)).unwrap();
+
+        let identity = EncryptedFileBackend::derive_machine_identity().unwrap();
+        let cached = unlock::load(&session, &identity).unwrap();
+        assert_eq!(cached.as_deref().map(<[u8]>::len), Some(32));
+
+        let _ = std::fs::remove_dir_all(&machine.vault_dir);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_refuses_insecure_vault() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let backend = temp_backend("insecure");
+        std::fs::set_permissions(&backend.vault_dir, std::fs::Permissions::from_mode(0o755)).unwrap();
+        let err = EncryptedFileBackend::open_at(backend.vault_dir.clone()).err().unwrap();
+        assert!(err.to_string().contains("chmod 700"));
+
+        std::fs::set_permissions(&backend.vault_dir, std::fs::Permissions::from_mode(0o700)).unwrap();
+        backend
+            .store("svc", "key", &SecretValue::new("v".to_string()))
+            .unwrap();
+        let path = backend.credential_path("svc", "key");
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644)).unwrap();
+        assert!(backend.retrieve("svc", "key").is_err());
+
+        let _ = std::fs::remove_dir_all(&backend.vault_dir);
+    }
 }
@@ -0,0 +1,263 @@
+//! Vault header for the encrypted file store.
+//!
+//! The header lives at `~/.nakama/vault/vault.json` and records how the
+//! store's key is derived.  Without a header the store uses the legacy
+//! machine-derived key; in master-password mode it carries the Argon2id
+//! parameters and salt for the passphrase plus a verifier blob, so a wrong
+//! passphrase is rejected before any entry is touched.
+
+use crate::encrypted_file::{decode_nonce, hex_decode, hex_encode};
+use crate::secret::SecretValue;
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Nonce};
+use argon2::{Algorithm, Argon2, Params, Version};
+use nakama_core::error::{NakamaError, NakamaResult};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+
+/// Current header format version.
+pub const HEADER_VERSION: u32 = 1;
+
+/// Largest Argon2id memory cost accepted from a header or bundle (1 GiB).
+const MAX_MEMORY_KIB: u32 = 1024 * 1024;
+
+/// Largest Argon2id pass count accepted from a header or bundle.
+const MAX_ITERATIONS: u32 = 64;
+
+/// Largest Argon2id parallelism accepted from a header or bundle.
+const MAX_PARALLELISM: u32 = 16;
+
+/// Plaintext sealed in the header to check a passphrase.
+const VERIFIER_PLAINTEXT: &[u8] = b"nakama-vault-master-key-verifier";
+
+/// How the encrypted file store derives its encryption key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyMode {
+    /// Key derived from the user name, home directory and hostname.
+    Machine,
+    /// Key derived from a user passphrase with Argon2id.
+    MasterPassword,
+}
+
+impl std::fmt::Display for KeyMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            KeyMode::Machine => write!(f, "machine"),
+            KeyMode::MasterPassword => write!(f, "master_password"),
+        }
+    }
+}
+
+/// Argon2id parameters used to turn the passphrase into the master key.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KdfParams {
+    /// Always `argon2id`.
+    pub algorithm: String,
+    /// Memory cost in KiB.
+    pub memory_kib: u32,
+    /// Number of passes.
+    pub iterations: u32,
+    /// Degree of parallelism.
+    pub parallelism: u32,
+    /// Salt (16 bytes, hex-encoded).
+    pub salt: String,
+}
+
+impl KdfParams {
+    /// Fresh parameters with a random salt.
+    ///
+    /// Costs are above the `argon2` crate defaults since the master key is
+    /// derived once per unlock rather than once per entry.
+    pub fn generate() -> Self {
+        let mut salt = [0u8; 16];
+        OsRng.fill_bytes(&mut salt);
+        Self {
+            algorithm: "argon2id".to_string(),
+            memory_kib: 64 * 1024,
+            iterations: 3,
+            parallelism: 1,
+            salt: hex_encode(&salt),
+        }
+    }
+
+    /// Derive the 32-byte master key from a passphrase.
+    ///
+    /// Parameters are read from files that may come from another machine,
+    /// so costs beyond what [`generate`](Self::generate) could plausibly
+    /// have written are rejected rather than attempted.
+    pub fn derive(&self, passphrase: &SecretValue) -> NakamaResult<Vec<u8>> {
+        if self.algorithm != "argon2id" {
+            return Err(NakamaError::Vault {
+                message: format!("Unsupported vault key derivation: {}", self.algorithm),
+                source: None,
+            });
+        }
+        if self.memory_kib > MAX_MEMORY_KIB
+            || self.iterations > MAX_ITERATIONS
+            || self.parallelism > MAX_PARALLELISM
+        {
+            return Err(NakamaError::Vault {
+                message: format!(
+                    "Argon2id parameters out of range (memory {} KiB, {} passes, parallelism {})",
+                    self.memory_kib, self.iterations, self.parallelism
+                ),
+                source: None,
+            });
+        }
+
+        let params = Params::new(self.memory_kib, self.iterations, self.parallelism, Some(32))
+            .map_err(|e| NakamaError::Vault {
+                message: format!("Invalid Argon2id parameters in vault header: {}", e),
+                source: None,
+            })?;
+        let salt = hex_decode(&self.salt)?;
+
+        let mut key = vec![0u8; 32];
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+            .hash_password_into(passphrase.expose_secret().as_bytes(), &salt, &mut key)
+            .map_err(|e| NakamaError::Vault {
+                message: format!("Argon2id key derivation failed: {}", e),
+                source: None,
+            })?;
+        Ok(key)
+    }
+}
+
+/// On-disk vault header.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VaultHeader {
+    /// Header format version.
+    pub version: u32,
+    /// Key derivation mode.
+    pub mode: KeyMode,
+    /// Passphrase KDF parameters (master-password mode only).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kdf: Option<KdfParams>,
+    /// AES-256-GCM nonce of the verifier (hex-encoded).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub verifier_nonce: Option<String>,
+    /// Verifier sealed under the master key (hex-encoded).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub verifier: Option<String>,
+}
+
+impl VaultHeader {
+    /// Create a master-password header for `master_key`, derived with `kdf`.
+    pub fn master_password(kdf: KdfParams, master_key: &[u8]) -> NakamaResult<Self> {
+        let mut nonce = [0u8; 12];
+        OsRng.fill_bytes(&mut nonce);
+        let verifier = cipher(master_key)?
+            .encrypt(Nonce::from_slice(&nonce), VERIFIER_PLAINTEXT)
+            .map_err(|e| NakamaError::Vault {
+                message: format!("Failed to seal vault verifier: {}", e),
+                source: None,
+            })?;
+
+        Ok(Self {
+            version: HEADER_VERSION,
+            mode: KeyMode::MasterPassword,
+            kdf: Some(kdf),
+            verifier_nonce: Some(hex_encode(&nonce)),
+            verifier: Some(hex_encode(&verifier)),
+        })
+    }
+
+    /// Derive the master key from `passphrase` and check it against the
+    /// verifier.
+    pub fn unlock(&self, passphrase: &SecretValue) -> NakamaResult<Vec<u8>> {
+        let (Some(kdf), Some(nonce), Some(verifier)) =
+            (&self.kdf, &self.verifier_nonce, &self.verifier)
+        else {
+            return Err(NakamaError::Vault {
+                message: "Vault header has no master-password parameters".to_string(),
+                source: None,
+            });
+        };
+
+        let nonce = decode_nonce(nonce)?;
+        let master_key = kdf.derive(passphrase)?;
+        let plaintext = cipher(&master_key)?
+            .decrypt(
+                Nonce::from_slice(&nonce),
+                hex_decode(verifier)?.as_ref(),
+            )
+            .map_err(|_| NakamaError::Vault {
+                message: "Wrong vault passphrase".to_string(),
+                source: None,
+            })?;
+        if plaintext != VERIFIER_PLAINTEXT {
+            return Err(NakamaError::Vault {
+                message: "Wrong vault passphrase".to_string(),
+                source: None,
+            });
+        }
+        Ok(master_key)
+    }
+}
+
+fn cipher(key: &[u8]) -> NakamaResult<Aes256Gcm> {
+    Aes256Gcm::new_from_slice(key).map_err(|e| NakamaError::Vault {
+        message: format!("Failed to create cipher: {}", e),
+        source: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cheap_kdf() -> KdfParams {
+        KdfParams {
+            memory_kib: 1024,
+            iterations: 1,
+            ..KdfParams::generate()
+        }
+    }
+
+    #[test]
+    fn test_unlock_with_correct_passphrase() {
+        let kdf = cheap_kdf();
+        let passphrase = SecretValue::new("correct horse".to_string());
+        let master_key = kdf.derive(&passphrase).unwrap();
+        let header = VaultHeader::master_password(kdf, &master_key).unwrap();
+
+        assert_eq!(header.unlock(&passphrase).unwrap(), master_key);
+    }
+
+    #[test]
+    fn test_unlock_rejects_wrong_passphrase() {
+        let kdf = cheap_kdf();
+        let master_key = kdf.derive(&SecretValue::new("right".to_string())).unwrap();
+        let header = VaultHeader::master_password(kdf, &master_key).unwrap();
+
+        assert!(header
+            .unlock(&SecretValue::new("wrong".to_string()))
+            .is_err());
+    }
+
+    #[test]
+    fn test_unlock_rejects_malformed_nonce() {
+        let kdf = cheap_kdf();
+        let passphrase = SecretValue::new("pw".to_string());
+        let master_key = kdf.derive(&passphrase).unwrap();
+        let mut header = VaultHeader::master_password(kdf, &master_key).unwrap();
+        header.verifier_nonce = Some("0011".to_string());
+
+        assert!(header.unlock(&passphrase).is_err());
+    }
+
+    #[test]
+    fn test_header_serde_roundtrip() {
+        let kdf = cheap_kdf();
+        let master_key = kdf.derive(&SecretValue::new("pw".to_string())).unwrap();
+        let header = VaultHeader::master_password(kdf, &master_key).unwrap();
+
+        let json = serde_json::to_string(&header).unwrap();
+        assert!(json.contains("\"mode\":\"master_password\""));
+        let parsed: VaultHeader = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.mode, KeyMode::MasterPassword);
+        assert_eq!(parsed.kdf.unwrap().memory_kib, 1024);
+    }
+}
@@ -1,8 +1,36 @@
 use crate::secret::SecretValue;
 use crate::vault::CredentialStore;
+use keyring::credential::CredentialPersistence;
 use keyring::Entry;
 use nakama_core::error::{NakamaError, NakamaResult};
-use tracing::debug;
+use nakama_core::{paths, permissions};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use tracing::{debug, warn};
+
+/// Service name -> key names of the credentials stored in the keychain.
+type KeyIndex = BTreeMap<String, BTreeSet<String>>;
+
+/// Credentials the tools read under fixed names, as `(service, key)`.
+/// [`KeychainBackend::index_well_known`] looks these up directly, since
+/// entries stored before the name index existed are missing from it.
+pub const WELL_KNOWN_CREDENTIALS: &[(&str, &str)] = &[
+    ("anthropic", "api_key"),
+    ("openai", "api_key"),
+    ("google", "api_key"),
+    ("nakama", "anthropic_api_key"),
+    ("nakama", "openai_api_key"),
+    ("nakama", "google_api_key"),
+    ("nakama", "github_token"),
+    ("nakama", "gitlab_token"),
+    ("nakama", "bitbucket_token"),
+    ("nakama", "bitbucket_app_password_token"),
+    ("atlassian", "base_url"),
+    ("atlassian", "email"),
+    ("atlassian", "api_token"),
+    (crate::audit_anchor::SERVICE, crate::audit_anchor::KEY),
+];
 
 /// OS keychain credential backend using the `keyring` crate.
 ///
@@ -11,31 +39,34 @@ use tracing::debug;
 ///
 /// Service names are formatted as `nakama-<service>` to namespace all
 /// credentials under the Nakama CLI Suite.
+///
+/// The OS keychain cannot enumerate entries, so the names (never the values)
+/// of stored credentials are tracked in `~/.nakama/vault/keychain-index.json`.
 pub struct KeychainBackend {
-    _private: (),
+    index_path: PathBuf,
 }
 
 impl KeychainBackend {
     /// Create a new keychain backend.
     ///
-    /// This performs a lightweight probe to confirm the OS keychain is accessible.
+    /// The keychain is probed once per process, by reading an entry that
+    /// should not exist: `NoEntry` means the store answered.  A build whose
+    /// `keyring` store only lives in memory (the mock fallback) is reported
+    /// as unavailable without probing.
     pub fn new() -> NakamaResult<Self> {
-        // Probe the keychain by attempting to create an entry reference.
-        // The keyring crate will error at retrieval time if the daemon is
-        // unavailable, but entry creation itself is infallible. We do a
-        // quick test store+delete of a sentinel value.
-        let probe = Entry::new("nakama-probe", "connectivity-test").map_err(|e| {
-            NakamaError::Vault {
+        static PROBE: OnceLock<Result<(), String>> = OnceLock::new();
+        PROBE
+            .get_or_init(probe)
+            .clone()
+            .map_err(|e| NakamaError::Vault {
                 message: format!("Keychain not available: {}", e),
-                source: Some(Box::new(e)),
-            }
-        })?;
-
-        // Try to delete any leftover probe value (ignore errors)
-        let _ = probe.delete_credential();
+                source: None,
+            })?;
 
         debug!("OS keychain is accessible");
-        Ok(Self { _private: () })
+        Ok(Self {
+            index_path: paths::vault_dir()?.join("keychain-index.json"),
+        })
     }
 
     /// Format a keyring service name: `nakama-<service>`.
@@ -51,6 +82,87 @@ impl KeychainBackend {
             source: Some(Box::new(e)),
         })
     }
+
+    /// Add [`WELL_KNOWN_CREDENTIALS`] that are in the keychain but not in
+    /// the name index, so they can be listed and exported.  Returns their
+    /// `service/key` names.
+    pub fn index_well_known(&self) -> Vec<String> {
+        let index = load_index(&self.index_path);
+        let mut found = Vec::new();
+        for (service, key) in WELL_KNOWN_CREDENTIALS {
+            if index.get(*service).is_some_and(|keys| keys.contains(*key)) {
+                continue;
+            }
+            let present = Self::entry(service, key).is_ok_and(|entry| entry.get_password().is_ok());
+            if present {
+                self.update_index(service, key, true);
+                found.push(format!("{}/{}", service, key));
+            }
+        }
+        found
+    }
+
+    /// Add or remove a key in the name index.  Index failures never fail the
+    /// keychain operation itself; they only make `list_keys` incomplete.
+    fn update_index(&self, service: &str, key: &str, present: bool) {
+        let mut index = load_index(&self.index_path);
+        if present {
+            index
+                .entry(service.to_string())
+                .or_default()
+                .insert(key.to_string());
+        } else if let Some(keys) = index.get_mut(service) {
+            keys.remove(key);
+            if keys.is_empty() {
+                index.remove(service);
+            }
+        }
+
+        if let Err(e) = save_index(&self.index_path, &index) {
+            warn!("Failed to update keychain index: {}", e);
+        }
+    }
+}
+
+/// Load the keychain name index; a missing or unreadable index is empty.
+fn load_index(path: &Path) -> KeyIndex {
+    std::fs::read_to_string(path)
+        .ok()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+/// Write the keychain name index with owner-only permissions.
+fn save_index(path: &Path, index: &KeyIndex) -> NakamaResult<()> {
+    if let Some(parent) = path.parent() {
+        paths::ensure_dir(&parent.to_path_buf())?;
+    }
+    let json = serde_json::to_string_pretty(index).map_err(|e| NakamaError::Vault {
+        message: format!("Failed to serialize keychain index: {}", e),
+        source: Some(Box::new(e)),
+    })?;
+    std::fs::write(path, json)?;
+    permissions::set_file_permissions(path)?;
+    Ok(())
+}
+
+/// Check that the OS keychain answers, without writing to it.
+fn probe() -> Result<(), String> {
+    // Unit tests never touch the real keychain.
+    if cfg!(test) {
+        return Err("disabled in tests".to_string());
+    }
+    let persistence = keyring::default::default_credential_builder().persistence();
+    if matches!(
+        persistence,
+        CredentialPersistence::EntryOnly | CredentialPersistence::ProcessOnly
+    ) {
+        return Err("no persistent keychain store in this build".to_string());
+    }
+    match Entry::new("nakama-probe", "connectivity-test").and_then(|probe| probe.get_password()) {
+        Ok(_) | Err(keyring::Error::NoEntry) => Ok(()),
+        Err(e) => Err(e.to_string()),
+    }
 }
 
 impl CredentialStore for KeychainBackend {
@@ -65,6 +177,7 @@ impl CredentialStore for KeychainBackend {
                 ),
                 source: Some(Box::new(e)),
             })?;
+        self.update_index(service, key, true);
         debug!("Stored {}/{} in OS keychain", service, key);
         Ok(())
     }
@@ -93,15 +206,22 @@ impl CredentialStore for KeychainBackend {
                 ),
                 source: Some(Box::new(e)),
             })?;
+        self.update_index(service, key, false);
         debug!("Deleted {}/{} from OS keychain", service, key);
         Ok(())
     }
 
-    fn list_keys(&self, _service: &str) -> NakamaResult<Vec<String>> {
+    fn list_keys(&self, service: &str) -> NakamaResult<Vec<String>> {
         // The keyring crate does not provide a way to enumerate keys for a
-        // given service. Return an empty list; callers should combine results
-        // from all backends.
-        Ok(Vec::new())
+        // given service, so answer from the name index.
+        Ok(load_index(&self.index_path)
+            .remove(service)
+            .map(|keys| keys.into_iter().collect())
+            .unwrap_or_default())
+    }
+
+    fn list_services(&self) -> NakamaResult<Vec<String>> {
+        Ok(load_index(&self.index_path).into_keys().collect())
     }
 }
 
@@ -114,4 +234,29 @@ mod tests {
         assert_eq!(KeychainBackend::service_name("zangetsu"), "nakama-zangetsu");
         assert_eq!(KeychainBackend::service_name("gate"), "nakama-gate");
     }
+
+    #[test]
+    fn test_tests_stay_off_the_keychain() {
+        let err = KeychainBackend::new().err().expect("keychain disabled");
+        assert!(err.to_string().contains("disabled in tests"));
+    }
+
+    #[test]
+    fn test_index_roundtrip() {
+        let dir = std::env::temp_dir().join(format!("nakama-keychain-{}", std::process::id()));
+        let backend = KeychainBackend {
+            index_path: dir.join("keychain-index.json"),
+        };
+
+        backend.update_index("anthropic", "api_key", true);
+        backend.update_index("github", "api_key", true);
+        assert_eq!(backend.list_services().unwrap(), vec!["anthropic", "github"]);
+        assert_eq!(backend.list_keys("anthropic").unwrap(), vec!["api_key"]);
+
+        backend.update_index("anthropic", "api_key", false);
+        assert_eq!(backend.list_services().unwrap(), vec!["github"]);
+        assert!(backend.list_keys("anthropic").unwrap().is_empty());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
 }
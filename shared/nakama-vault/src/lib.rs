@@ -3,6 +3,30 @@ pub mod secret;
 pub mod keychain;
 pub mod encrypted_file;
 pub mod env_fallback;
+pub mod header;
+pub mod unlock;
+pub mod bundle;
+pub mod rotation;
+pub mod audit_anchor;
 
-pub use vault::{CredentialStore, Vault};
+pub use vault::{BackendStatus, CredentialStore, Vault};
+pub use header::KeyMode;
+pub use rotation::RotationLog;
 pub use secret::SecretValue;
+
+use nakama_core::config::Config;
+use nakama_ui::NakamaUI;
+
+/// Vault-backed startup chores shared by the tools: warn about stale
+/// credentials, then prune and anchor the audit log on a blocking thread.
+///
+/// Call this after parsing the command line, so `--help` and usage errors
+/// never touch the vault.  Must be called from within a Tokio runtime.
+pub fn startup(config: &Config, ui: &NakamaUI) {
+    rotation::warn_stale(&config.vault, ui);
+    let audit = config.audit.clone();
+    tokio::task::spawn_blocking(move || {
+        audit_anchor::maybe_prune(&audit);
+        audit_anchor::maybe_anchor(&audit);
+    });
+}
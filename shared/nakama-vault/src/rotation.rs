@@ -0,0 +1,207 @@
+//! Credential rotation tracking.
+//!
+//! The vault records when each credential was last stored in
+//! `~/.nakama/vault/rotation.json` (names and timestamps only, never values).
+//! [`crate::startup`] calls [`warn_stale`] to nag about credentials older
+//! than `[vault] rotation_warn_days`.
+
+use chrono::{DateTime, Duration, Utc};
+use nakama_core::config::VaultConfig;
+use nakama_core::error::{NakamaError, NakamaResult};
+use nakama_core::{paths, permissions};
+use nakama_ui::NakamaUI;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Service name -> key name -> last rotation time.
+type RotationMap = BTreeMap<String, BTreeMap<String, DateTime<Utc>>>;
+
+/// How many stale credentials the startup warning names before summarizing.
+const WARN_LIST_LIMIT: usize = 3;
+
+/// When a single credential was last rotated.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RotationRecord {
+    pub service: String,
+    pub key: String,
+    pub rotated_at: DateTime<Utc>,
+}
+
+impl RotationRecord {
+    /// Whole days since the last rotation.
+    pub fn age_days(&self, now: DateTime<Utc>) -> i64 {
+        (now - self.rotated_at).num_days()
+    }
+}
+
+/// The on-disk rotation log.
+pub struct RotationLog {
+    path: PathBuf,
+}
+
+impl RotationLog {
+    /// Open the rotation log at `~/.nakama/vault/rotation.json`.
+    pub fn open() -> NakamaResult<Self> {
+        Ok(Self::open_at(paths::vault_dir()?.join("rotation.json")))
+    }
+
+    /// Open a rotation log at a custom path (useful for testing).
+    pub fn open_at(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// Record that `service/key` was rotated at `at`.
+    pub fn record(&self, service: &str, key: &str, at: DateTime<Utc>) -> NakamaResult<()> {
+        let mut map = self.load();
+        map.entry(service.to_string())
+            .or_default()
+            .insert(key.to_string(), at);
+        self.save(&map)
+    }
+
+    /// Forget `service/key`, e.g. after it was deleted.
+    pub fn remove(&self, service: &str, key: &str) -> NakamaResult<()> {
+        let mut map = self.load();
+        if let Some(keys) = map.get_mut(service) {
+            keys.remove(key);
+            if keys.is_empty() {
+                map.remove(service);
+            }
+        }
+        self.save(&map)
+    }
+
+    /// When `service/key` was last rotated, if known.
+    pub fn last_rotated(&self, service: &str, key: &str) -> Option<DateTime<Utc>> {
+        self.load().get(service)?.get(key).copied()
+    }
+
+    /// Every tracked credential, sorted by service and key.
+    pub fn records(&self) -> Vec<RotationRecord> {
+        self.load()
+            .into_iter()
+            .flat_map(|(service, keys)| {
+                keys.into_iter()
+                    .map(move |(key, rotated_at)| RotationRecord {
+                        service: service.clone(),
+                        key,
+                        rotated_at,
+                    })
+            })
+            .collect()
+    }
+
+    /// Credentials last rotated more than `max_age_days` before `now`,
+    /// oldest first.
+    pub fn stale(&self, max_age_days: u32, now: DateTime<Utc>) -> Vec<RotationRecord> {
+        let cutoff = now - Duration::days(i64::from(max_age_days));
+        let mut stale: Vec<RotationRecord> = self
+            .records()
+            .into_iter()
+            .filter(|record| record.rotated_at < cutoff)
+            .collect();
+        stale.sort_by_key(|record| record.rotated_at);
+        stale
+    }
+
+    fn load(&self) -> RotationMap {
+        std::fs::read_to_string(&self.path)
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    fn save(&self, map: &RotationMap) -> NakamaResult<()> {
+        if let Some(parent) = self.path.parent() {
+            paths::ensure_dir(&parent.to_path_buf())?;
+        }
+        let json = serde_json::to_string_pretty(map).map_err(|e| NakamaError::Vault {
+            message: format!("Failed to serialize rotation log: {}", e),
+            source: Some(Box::new(e)),
+        })?;
+        std::fs::write(&self.path, json)?;
+        permissions::set_file_permissions(&self.path)?;
+        Ok(())
+    }
+
+    /// Path of the log file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+/// Warn about credentials that have not been rotated within
+/// `config.rotation_warn_days`.  A value of `0` disables the check.
+pub fn warn_stale(config: &VaultConfig, ui: &NakamaUI) {
+    if config.rotation_warn_days == 0 {
+        return;
+    }
+    let Ok(log) = RotationLog::open() else {
+        return;
+    };
+
+    let now = Utc::now();
+    let stale = log.stale(config.rotation_warn_days, now);
+    if stale.is_empty() {
+        return;
+    }
+
+    let mut names: Vec<String> = stale
+        .iter()
+        .take(WARN_LIST_LIMIT)
+        .map(|r| format!("{}/{} ({} days)", r.service, r.key, r.age_days(now)))
+        .collect();
+    if stale.len() > WARN_LIST_LIMIT {
+        names.push(format!("{} more", stale.len() - WARN_LIST_LIMIT));
+    }
+    ui.warn(&format!(
+        "Credentials not rotated in over {} days: {}. Rotate with `nakama-vault rotate <service> <key>`",
+        config.rotation_warn_days,
+        names.join(", ")
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_log(name: &str) -> RotationLog {
+        let dir =
+            std::env::temp_dir().join(format!("nakama-rotation-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        RotationLog::open_at(dir.join("rotation.json"))
+    }
+
+    #[test]
+    fn test_record_and_remove() {
+        let log = temp_log("record");
+        let at = Utc::now();
+        log.record("anthropic", "api_key", at).unwrap();
+        assert_eq!(log.last_rotated("anthropic", "api_key"), Some(at));
+
+        log.remove("anthropic", "api_key").unwrap();
+        assert_eq!(log.last_rotated("anthropic", "api_key"), None);
+        assert!(log.records().is_empty());
+
+        let _ = std::fs::remove_dir_all(log.path().parent().unwrap());
+    }
+
+    #[test]
+    fn test_stale_oldest_first() {
+        let log = temp_log("stale");
+        let now = Utc::now();
+        log.record("openai", "api_key", now - Duration::days(100))
+            .unwrap();
+        log.record("github", "api_key", now - Duration::days(200))
+            .unwrap();
+        log.record("anthropic", "api_key", now - Duration::days(10))
+            .unwrap();
+
+        let stale = log.stale(90, now);
+        let names: Vec<&str> = stale.iter().map(|r| r.service.as_str()).collect();
+        assert_eq!(names, vec!["github", "openai"]);
+        assert_eq!(stale[0].age_days(now), 200);
+
+        let _ = std::fs::remove_dir_all(log.path().parent().unwrap());
+    }
+}
@@ -0,0 +1,309 @@
+//! Unlock sessions for master-password vaults.
+//!
+//! `nakama-vault unlock` caches the master key until a timeout so the tools
+//! can read credentials without prompting for the passphrase each time.  The
+//! master key and its expiry are sealed together with a random per-session
+//! key, so neither can be recovered or extended from the session file alone.
+//! The session key is kept in the OS keychain when one is available,
+//! otherwise in a separate owner-only file.
+//!
+//! Session files live under `$XDG_RUNTIME_DIR/nakama/` (a per-user tmpfs
+//! cleared at logout) when that is available, otherwise in the user's cache
+//! directory, and never in the vault directory: copying or syncing the vault
+//! never carries an open session along.
+
+use crate::encrypted_file::{
+    ensure_secure, hex_decode, hex_encode, open_sealed, seal, EncryptedEntry,
+};
+use crate::keychain::KeychainBackend;
+use nakama_core::error::{NakamaError, NakamaResult};
+use nakama_core::{paths, permissions};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::debug;
+
+/// Session file name.
+const SESSION_FILE: &str = "vault-unlock.json";
+
+/// Session key file name, used when there is no OS keychain.
+const SESSION_KEY_FILE: &str = "vault-unlock.key";
+
+/// Keychain entry holding the session key.  Written directly rather than
+/// through [`KeychainBackend`], so it never shows up as a credential.
+const KEYCHAIN_SERVICE: &str = "nakama-vault";
+const KEYCHAIN_ACCOUNT: &str = "unlock-session-key";
+
+/// On-disk unlock session.
+#[derive(Serialize, Deserialize)]
+struct UnlockSession {
+    /// Unix time (seconds) after which the session is invalid.  Only a
+    /// hint for skipping expired sessions; the sealed copy is checked.
+    expires_at: u64,
+    /// Expiry (8 bytes, big-endian) followed by the master key, sealed with
+    /// the session key and the machine-derived key.
+    key: EncryptedEntry,
+}
+
+/// Where the unlock session is kept.
+pub fn session_path() -> NakamaResult<PathBuf> {
+    let dir = match std::env::var_os("XDG_RUNTIME_DIR") {
+        Some(runtime) if !runtime.is_empty() => PathBuf::from(runtime),
+        _ => dirs::cache_dir().ok_or_else(|| NakamaError::Vault {
+            message: "Could not determine a directory for the unlock session".to_string(),
+            source: None,
+        })?,
+    };
+    Ok(dir.join("nakama").join(SESSION_FILE))
+}
+
+/// Cache `master_key` at `path` for `timeout`.
+pub(crate) fn save(
+    path: &Path,
+    master_key: &[u8],
+    machine_identity: &[u8],
+    timeout: Duration,
+) -> NakamaResult<()> {
+    if let Some(parent) = path.parent() {
+        paths::ensure_dir(&parent.to_path_buf())?;
+        ensure_secure(parent)?;
+    }
+
+    let mut session_key = [0u8; 32];
+    OsRng.fill_bytes(&mut session_key);
+    store_session_key(path, &session_key)?;
+
+    let expires_at = now_secs() + timeout.as_secs();
+    let mut sealed = expires_at.to_be_bytes().to_vec();
+    sealed.extend_from_slice(master_key);
+    let session = UnlockSession {
+        expires_at,
+        key: seal(&key_material(&session_key, machine_identity), &sealed)?,
+    };
+    let json = serde_json::to_string(&session).map_err(|e| NakamaError::Vault {
+        message: format!("Failed to serialize unlock session: {}", e),
+        source: Some(Box::new(e)),
+    })?;
+
+    std::fs::write(path, json)?;
+    permissions::set_file_permissions(path)?;
+    debug!("Vault unlocked until {}", session.expires_at);
+    Ok(())
+}
+
+/// Load the cached master key, if a session exists and has not expired.
+///
+/// Expired or unreadable sessions are removed.  A session file that other
+/// users can read is refused.
+pub(crate) fn load(path: &Path, machine_identity: &[u8]) -> NakamaResult<Option<Vec<u8>>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    ensure_secure(path)?;
+
+    let session: Option<UnlockSession> = std::fs::read_to_string(path)
+        .ok()
+        .and_then(|json| serde_json::from_str(&json).ok());
+    let key = session
+        .filter(|s| s.expires_at > now_secs())
+        .and_then(|s| {
+            let session_key = load_session_key(path)?;
+            open_sealed(&key_material(&session_key, machine_identity), &s.key).ok()
+        })
+        .filter(|sealed| sealed.len() > 8)
+        .and_then(|mut sealed| {
+            let master_key = sealed.split_off(8);
+            let expires_at = u64::from_be_bytes(sealed.try_into().ok()?);
+            (expires_at > now_secs()).then_some(master_key)
+        });
+
+    if key.is_none() {
+        debug!("Discarding expired or invalid unlock session");
+        clear(path)?;
+    }
+    Ok(key)
+}
+
+/// Remove the session at `path` if it has expired or can't be opened.
+/// Returns whether an active session remains.
+pub(crate) fn remove_expired(path: &Path, machine_identity: &[u8]) -> NakamaResult<bool> {
+    Ok(load(path, machine_identity)?.is_some())
+}
+
+/// Remove the session at `path` and its session key.  Returns whether a
+/// session file existed.
+pub(crate) fn clear(path: &Path) -> NakamaResult<bool> {
+    if let Some(entry) = keychain_entry() {
+        match entry.delete_credential() {
+            Ok(()) | Err(keyring::Error::NoEntry) => {}
+            Err(e) => debug!(
+                "Failed to delete unlock session key from the keychain: {}",
+                e
+            ),
+        }
+    }
+    let key_file = session_key_file(path);
+    if key_file.exists() {
+        std::fs::remove_file(&key_file)?;
+    }
+    if path.exists() {
+        std::fs::remove_file(path)?;
+        return Ok(true);
+    }
+    Ok(false)
+}
+
+/// Remove a session left in `vault_dir` by an older build, which sealed it
+/// with the machine-derived key alone.
+pub(crate) fn remove_legacy(vault_dir: &Path) -> NakamaResult<()> {
+    let legacy = vault_dir.join(SESSION_FILE);
+    if legacy.exists() {
+        std::fs::remove_file(&legacy)?;
+        debug!("Removed legacy unlock session from {}", vault_dir.display());
+    }
+    Ok(())
+}
+
+/// The key a session is sealed with.
+fn key_material(session_key: &[u8], machine_identity: &[u8]) -> Vec<u8> {
+    [session_key, machine_identity].concat()
+}
+
+/// The keychain entry for the session key, if the keychain is available.
+fn keychain_entry() -> Option<keyring::Entry> {
+    KeychainBackend::new().ok()?;
+    keyring::Entry::new(KEYCHAIN_SERVICE, KEYCHAIN_ACCOUNT).ok()
+}
+
+fn session_key_file(session_path: &Path) -> PathBuf {
+    session_path.with_file_name(SESSION_KEY_FILE)
+}
+
+/// Keep `session_key` in the keychain, or next to the session file.
+fn store_session_key(session_path: &Path, session_key: &[u8]) -> NakamaResult<()> {
+    let key_file = session_key_file(session_path);
+    if let Some(entry) = keychain_entry() {
+        match entry.set_password(&hex_encode(session_key)) {
+            Ok(()) => {
+                if key_file.exists() {
+                    std::fs::remove_file(&key_file)?;
+                }
+                return Ok(());
+            }
+            Err(e) => debug!(
+                "Keychain refused the unlock session key, using a file: {}",
+                e
+            ),
+        }
+    }
+
+    std::fs::write(&key_file, hex_encode(session_key))?;
+    permissions::set_file_permissions(&key_file)?;
+    Ok(())
+}
+
+fn load_session_key(session_path: &Path) -> Option<Vec<u8>> {
+    let hex = keychain_entry()
+        .and_then(|entry| entry.get_password().ok())
+        .or_else(|| {
+            let key_file = session_key_file(session_path);
+            ensure_secure(&key_file).ok()?;
+            std::fs::read_to_string(key_file).ok()
+        })?;
+    hex_decode(hex.trim()).ok()
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_session(name: &str) -> PathBuf {
+        std::env::temp_dir()
+            .join(format!("nakama-unlock-{}-{}", name, std::process::id()))
+            .join(SESSION_FILE)
+    }
+
+    #[test]
+    fn test_session_roundtrip() {
+        let path = temp_session("roundtrip");
+        let identity = b"test-machine-identity".to_vec();
+        save(&path, &[7u8; 32], &identity, Duration::from_secs(60)).unwrap();
+        assert!(session_key_file(&path).exists());
+
+        assert_eq!(load(&path, &identity).unwrap(), Some(vec![7u8; 32]));
+        assert!(clear(&path).unwrap());
+        assert!(!session_key_file(&path).exists());
+        assert_eq!(load(&path, &identity).unwrap(), None);
+
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn test_expired_session_is_discarded() {
+        let path = temp_session("expired");
+        let identity = b"test-machine-identity".to_vec();
+        save(&path, &[7u8; 32], &identity, Duration::ZERO).unwrap();
+
+        assert!(!remove_expired(&path, &identity).unwrap());
+        assert!(!path.exists());
+        assert!(!session_key_file(&path).exists());
+
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn test_extended_expiry_is_ignored() {
+        let path = temp_session("extended");
+        let identity = b"test-machine-identity".to_vec();
+        save(&path, &[7u8; 32], &identity, Duration::ZERO).unwrap();
+
+        // Pushing the plaintext expiry out does not revive the session.
+        let json = std::fs::read_to_string(&path).unwrap();
+        let mut session: UnlockSession = serde_json::from_str(&json).unwrap();
+        session.expires_at = now_secs() + 3600;
+        std::fs::write(&path, serde_json::to_string(&session).unwrap()).unwrap();
+
+        assert_eq!(load(&path, &identity).unwrap(), None);
+        assert!(!path.exists());
+
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn test_session_without_its_key_is_discarded() {
+        let path = temp_session("nokey");
+        let identity = b"test-machine-identity".to_vec();
+        save(&path, &[7u8; 32], &identity, Duration::from_secs(60)).unwrap();
+        std::fs::remove_file(session_key_file(&path)).unwrap();
+
+        assert_eq!(load(&path, &identity).unwrap(), None);
+        assert!(!path.exists());
+
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn test_session_from_other_machine_is_discarded() {
+        let path = temp_session("machine");
+        save(
+            &path,
+            &[7u8; 32],
+            b"machine-a-identity",
+            Duration::from_secs(60),
+        )
+        .unwrap();
+
+        assert_eq!(load(&path, b"machine-b-identity").unwrap(), None);
+
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+}
@@ -1,7 +1,9 @@
 use crate::encrypted_file::EncryptedFileBackend;
 use crate::env_fallback::EnvBackend;
 use crate::keychain::KeychainBackend;
+use crate::rotation::RotationLog;
 use crate::secret::SecretValue;
+use chrono::Utc;
 use nakama_core::error::{NakamaError, NakamaResult};
 use tracing::{debug, warn};
 
@@ -21,14 +23,37 @@ pub trait CredentialStore {
 
     /// List all keys stored under the given service.
     fn list_keys(&self, service: &str) -> NakamaResult<Vec<String>>;
+
+    /// List all services that have at least one stored key.
+    ///
+    /// Backends that cannot enumerate their contents return an empty list.
+    fn list_services(&self) -> NakamaResult<Vec<String>> {
+        Ok(Vec::new())
+    }
+}
+
+/// Availability of a single vault backend, as detected by [`Vault::new`].
+#[derive(Debug, Clone)]
+pub struct BackendStatus {
+    /// Backend name (`keychain`, `encrypted_file`, `env`).
+    pub name: &'static str,
+    /// Whether the backend was initialized successfully.
+    pub available: bool,
+    /// Why the backend is unavailable, if it is.
+    pub error: Option<String>,
 }
 
 /// The primary credential vault that tries backends in priority order:
 /// 1. OS keychain (most secure)
 /// 2. Encrypted file store (portable fallback)
 /// 3. Environment variables (read-only, last resort)
+///
+/// Every successful store is recorded in the rotation log so stale
+/// credentials can be flagged.
 pub struct Vault {
     backends: Vec<(&'static str, Box<dyn CredentialStore>)>,
+    unavailable: Vec<(&'static str, String)>,
+    rotation: Option<RotationLog>,
 }
 
 impl Vault {
@@ -39,6 +64,7 @@ impl Vault {
     /// to env vars even when the keychain is available.
     pub fn new() -> NakamaResult<Self> {
         let mut backends: Vec<(&'static str, Box<dyn CredentialStore>)> = Vec::new();
+        let mut unavailable = Vec::new();
 
         // Try keychain backend (may fail if no keychain daemon is running)
         match KeychainBackend::new() {
@@ -48,6 +74,7 @@ impl Vault {
             }
             Err(e) => {
                 debug!("Keychain backend not available: {}", e);
+                unavailable.push(("keychain", e.to_string()));
             }
         }
 
@@ -59,6 +86,7 @@ impl Vault {
             }
             Err(e) => {
                 warn!("Encrypted file backend not available: {}", e);
+                unavailable.push(("encrypted_file", e.to_string()));
             }
         }
 
@@ -66,7 +94,16 @@ impl Vault {
         backends.push(("env", Box::new(EnvBackend::new())));
         debug!("Environment variable fallback backend registered");
 
-        Ok(Self { backends })
+        Ok(Self {
+            backends,
+            unavailable,
+            rotation: RotationLog::open().ok(),
+        })
+    }
+
+    /// The log of when each credential was last stored, if it is available.
+    pub fn rotation_log(&self) -> Option<&RotationLog> {
+        self.rotation.as_ref()
     }
 
     /// Return the name of the highest-priority backend currently available.
@@ -76,16 +113,47 @@ impl Vault {
             .map(|(name, _)| *name)
             .unwrap_or("none")
     }
-}
 
-impl CredentialStore for Vault {
-    fn store(&self, service: &str, key: &str, value: &SecretValue) -> NakamaResult<()> {
+    /// Report every backend the vault knows about, in priority order, and
+    /// whether it could be initialized.
+    pub fn backend_statuses(&self) -> Vec<BackendStatus> {
+        let mut statuses: Vec<BackendStatus> = self
+            .backends
+            .iter()
+            .map(|(name, _)| BackendStatus {
+                name,
+                available: true,
+                error: None,
+            })
+            .chain(self.unavailable.iter().map(|(name, error)| BackendStatus {
+                name,
+                available: false,
+                error: Some(error.clone()),
+            }))
+            .collect();
+        statuses.sort_by_key(|status| backend_priority(status.name));
+        statuses
+    }
+
+    /// Store a secret and return the name of the backend that accepted it.
+    ///
+    /// The `env` backend accepts every write as a no-op, so a result of
+    /// `"env"` means no persistent backend could store the value.
+    pub fn store_with_backend(
+        &self,
+        service: &str,
+        key: &str,
+        value: &SecretValue,
+    ) -> NakamaResult<&'static str> {
         // Store in the highest-priority writable backend
         for (name, backend) in &self.backends {
             match backend.store(service, key, value) {
                 Ok(()) => {
                     debug!("Stored credential {}/{} in {} backend", service, key, name);
-                    return Ok(());
+                    if *name != "env" {
+                        self.record_rotation(service, key);
+                    }
+                    return Ok(name);
                 }
                 Err(e) => {
                     debug!(
@@ -105,7 +173,12 @@ impl CredentialStore for Vault {
         })
     }
 
-    fn retrieve(&self, service: &str, key: &str) -> NakamaResult<SecretValue> {
+    /// Retrieve a secret together with the name of the backend it came from.
+    pub fn retrieve_with_backend(
+        &self,
+        service: &str,
+        key: &str,
+    ) -> NakamaResult<(&'static str, SecretValue)> {
         // Try each backend in priority order
         for (name, backend) in &self.backends {
             match backend.retrieve(service, key) {
@@ -114,7 +187,7 @@ impl CredentialStore for Vault {
                         "Retrieved credential {}/{} from {} backend",
                         service, key, name
                     );
-                    return Ok(value);
+                    return Ok((name, value));
                 }
                 Err(e) => {
                     debug!(
@@ -127,13 +200,57 @@ impl CredentialStore for Vault {
 
         Err(NakamaError::Vault {
             message: format!(
-                "Credential {}/{} not found in any backend",
-                service, key
+                "Credential {}/{} not found in any backend{}",
+                service,
+                key,
+                self.unavailable_hint()
             ),
             source: None,
         })
     }
 
+    /// Note that `service/key` was just stored.  Failures only affect the
+    /// staleness warnings, so they are logged rather than returned.
+    fn record_rotation(&self, service: &str, key: &str) {
+        if let Some(log) = &self.rotation {
+            if let Err(e) = log.record(service, key, Utc::now()) {
+                warn!("Failed to record rotation of {}/{}: {}", service, key, e);
+            }
+        }
+    }
+
+    /// Suffix naming the backends that could not be searched, if any.
+    fn unavailable_hint(&self) -> String {
+        if self.unavailable.is_empty() {
+            return String::new();
+        }
+        let reasons: Vec<String> = self
+            .unavailable
+            .iter()
+            .map(|(name, error)| format!("{}: {}", name, error))
+            .collect();
+        format!(" (not searched: {})", reasons.join("; "))
+    }
+}
+
+/// Position of a backend in the vault's priority order.
+fn backend_priority(name: &str) -> usize {
+    match name {
+        "keychain" => 0,
+        "encrypted_file" => 1,
+        _ => 2,
+    }
+}
+
+impl CredentialStore for Vault {
+    fn store(&self, service: &str, key: &str, value: &SecretValue) -> NakamaResult<()> {
+        self.store_with_backend(service, key, value).map(|_| ())
+    }
+
+    fn retrieve(&self, service: &str, key: &str) -> NakamaResult<SecretValue> {
+        self.retrieve_with_backend(service, key).map(|(_, value)| value)
+    }
+
     fn delete(&self, service: &str, key: &str) -> NakamaResult<()> {
         let mut deleted = false;
 
@@ -157,6 +274,11 @@ impl CredentialStore for Vault {
         }
 
         if deleted {
+            if let Some(log) = &self.rotation {
+                if let Err(e) = log.remove(service, key) {
+                    warn!("Failed to update rotation log for {}/{}: {}", service, key, e);
+                }
+            }
             Ok(())
         } else {
             Err(NakamaError::Vault {
@@ -198,6 +320,31 @@ impl CredentialStore for Vault {
 
         Ok(all_keys)
     }
+
+    fn list_services(&self) -> NakamaResult<Vec<String>> {
+        let mut all_services = Vec::new();
+
+        for (name, backend) in &self.backends {
+            match backend.list_services() {
+                Ok(services) => {
+                    for service in services {
+                        if !all_services.contains(&service) {
+                            all_services.push(service);
+                        }
+                    }
+                }
+                Err(e) => {
+                    debug!(
+                        "Could not list services from {} backend: {}",
+                        name, e
+                    );
+                }
+            }
+        }
+
+        all_services.sort();
+        Ok(all_services)
+    }
 }
 
 #[cfg(test)]
@@ -217,4 +364,16 @@ mod tests {
         // At minimum, the env backend should always be present
         assert!(!vault.backends.is_empty());
     }
+
+    #[test]
+    fn test_backend_statuses_in_priority_order() {
+        let vault = Vault::new().unwrap();
+        let statuses = vault.backend_statuses();
+        let names: Vec<&str> = statuses.iter().map(|s| s.name).collect();
+        assert_eq!(names.last(), Some(&"env"));
+        assert!(statuses.iter().all(|s| s.available == s.error.is_none()));
+        let mut sorted = names.clone();
+        sorted.sort_by_key(|name| backend_priority(name));
+        assert_eq!(names, sorted);
+    }
 }
@@ -1,6 +1,6 @@
 //! Read entire log file, parse with parser, send patterns to AI for analysis.
 
-use crate::ai_helper::{ask_ai, make_provider};
+use nakama_ai::{load_prompt, AiClient};
 use crate::parser::{detect_format, parse_line, LogLevel};
 use nakama_audit::{AuditEntry, AuditLog, Category, Outcome};
 use nakama_core::config::Config;
@@ -89,19 +89,11 @@ pub async fn run(config: &Config, ui: &NakamaUI, logfile: &str) -> NakamaResult<
 
     // Send to AI
     let spinner = ui.step_start("Analyzing patterns with AI...");
-    let (provider, model) = make_provider(config, ModelTier::Balanced)?;
+    let ai = AiClient::new(config, crate::TOOL_NAME, ModelTier::Balanced)?;
 
-    let system_prompt = r#"You are Sharingan, an AI-powered log analyzer. Analyze the log file summary and error patterns. Provide:
+    let system_prompt = load_prompt(crate::TOOL_NAME, "analyze", &[]);
 
-1. **Overview**: What this log file represents and its overall health.
-2. **Key Findings**: The most important patterns and anomalies found.
-3. **Error Analysis**: Root causes of the errors seen.
-4. **Recommendations**: Concrete steps to address the issues.
-5. **Risk Assessment**: Rate the overall risk level (LOW, MEDIUM, HIGH, CRITICAL).
-
-Be concise and actionable."#;
-
-    let result = ask_ai(provider.as_ref(), system_prompt, &summary, &model, 2048, 0.3).await;
+    let result = ai.ask( &system_prompt, &summary, 2048, 0.3).await;
     let elapsed = start.elapsed().as_millis() as u64;
 
     match &result {
@@ -121,7 +113,7 @@ Be concise and actionable."#;
                         "format": format.to_string(),
                         "total_lines": total,
                         "error_count": error_count,
-                        "model": model,
+                        "model": ai.model(),
                     }),
                     Outcome::Success,
                     elapsed,
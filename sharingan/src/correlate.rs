@@ -1,6 +1,6 @@
 //! Read two log files, find overlapping timestamps, look for related events.
 
-use crate::ai_helper::{ask_ai, make_provider};
+use nakama_ai::{load_prompt, AiClient};
 use crate::parser::{detect_format, parse_line};
 use nakama_audit::{AuditEntry, AuditLog, Category, Outcome};
 use nakama_core::config::Config;
@@ -126,19 +126,11 @@ pub async fn run(config: &Config, ui: &NakamaUI, source1: &str, source2: &str) -
     );
 
     let spinner = ui.step_start("Correlating events with AI...");
-    let (provider, model) = make_provider(config, ModelTier::Balanced)?;
+    let ai = AiClient::new(config, crate::TOOL_NAME, ModelTier::Balanced)?;
 
-    let system_prompt = r#"You are Sharingan, an AI-powered log analyzer. You are given two log files to correlate. Analyze:
+    let system_prompt = load_prompt(crate::TOOL_NAME, "correlate", &[]);
 
-1. **Temporal Overlap**: Do the log files cover the same time period?
-2. **Correlated Events**: Are there errors or events in one file that appear to cause or relate to events in the other?
-3. **Causal Chain**: If there is a causal relationship, describe the chain of events.
-4. **Common Patterns**: Any shared error patterns, IPs, service names, or identifiers.
-5. **Recommendations**: What to investigate next.
-
-Be concise and focus on actionable correlations."#;
-
-    let result = ask_ai(provider.as_ref(), system_prompt, &user_message, &model, 2048, 0.3).await;
+    let result = ai.ask( &system_prompt, &user_message, 2048, 0.3).await;
     let elapsed = start.elapsed().as_millis() as u64;
 
     match &result {
@@ -158,7 +150,7 @@ Be concise and focus on actionable correlations."#;
                         "source2": source2,
                         "lines1": parsed1.len(),
                         "lines2": parsed2.len(),
-                        "model": model,
+                        "model": ai.model(),
                     }),
                     Outcome::Success,
                     elapsed,
@@ -1,6 +1,6 @@
 //! Regex search in log file with AI-powered context explanation.
 
-use crate::ai_helper::{ask_ai, make_provider};
+use nakama_ai::{load_prompt, AiClient};
 use nakama_audit::{AuditEntry, AuditLog, Category, Outcome};
 use nakama_core::config::Config;
 use nakama_core::error::NakamaResult;
@@ -66,7 +66,7 @@ pub async fn run(config: &Config, ui: &NakamaUI, query: &str, source: &str) -> N
 
     // Send matches to AI for context explanation
     let spinner = ui.step_start("Getting AI explanation of matches...");
-    let (provider, model) = make_provider(config, ModelTier::Balanced)?;
+    let ai = AiClient::new(config, crate::TOOL_NAME, ModelTier::Balanced)?;
 
     let sample_matches: Vec<String> = matches
         .iter()
@@ -82,15 +82,9 @@ pub async fn run(config: &Config, ui: &NakamaUI, query: &str, source: &str) -> N
         sample_matches.join("\n"),
     );
 
-    let system_prompt = r#"You are Sharingan, an AI-powered log analyzer. The user searched for a pattern in a log file. Explain:
+    let system_prompt = load_prompt(crate::TOOL_NAME, "search", &[]);
 
-1. **Pattern Context**: What these matching log lines represent.
-2. **Significance**: Whether these matches indicate a problem, normal behavior, or something noteworthy.
-3. **Recommendations**: Any actions that should be taken based on these matches.
-
-Be concise."#;
-
-    let result = ask_ai(provider.as_ref(), system_prompt, &user_message, &model, 1024, 0.3).await;
+    let result = ai.ask( &system_prompt, &user_message, 1024, 0.3).await;
     let elapsed = start.elapsed().as_millis() as u64;
 
     match &result {
@@ -109,7 +103,7 @@ Be concise."#;
                         "query": query,
                         "source": source,
                         "match_count": matches.len(),
-                        "model": model,
+                        "model": ai.model(),
                     }),
                     Outcome::Success,
                     elapsed,
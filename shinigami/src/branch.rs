@@ -1,7 +1,7 @@
 //! `shinigami branch` — AI-powered branch name suggestion from natural language.
 
-use crate::ai_helper;
 use anyhow::Result;
+use nakama_ai::{load_prompt, AiClient};
 use nakama_audit::{AuditEntry, AuditLog, Category, Outcome};
 use nakama_core::config::Config;
 use nakama_core::types::ModelTier;
@@ -10,24 +10,6 @@ use nakama_ui::NakamaUI;
 use std::io::{self, Write};
 use std::time::Instant;
 
-const SYSTEM_PROMPT: &str = r#"You are a git branch naming assistant. Given a natural-language description of a feature, bug fix, or task, suggest exactly 3 well-formed branch names.
-
-Branch naming conventions:
-- Use the format: <type>/<short-description>
-- Types: feature, fix, refactor, docs, chore, test, ci, perf
-- Use lowercase kebab-case for the description part
-- Keep it under 50 characters total
-- Be specific but concise
-- No special characters except hyphens and forward slashes
-
-Output exactly 3 suggestions, one per line, numbered:
-1. <branch-name>
-2. <branch-name>
-3. <branch-name>
-
-Output ONLY the numbered list, no other text.
-"#;
-
 /// Run the branch subcommand.
 pub async fn run(config: &Config, ui: &NakamaUI, description: &str) -> Result<()> {
     let start = Instant::now();
@@ -36,19 +18,17 @@ pub async fn run(config: &Config, ui: &NakamaUI, description: &str) -> Result<()
     ui.step_done(&format!("Description: {}", description));
 
     let spinner = ui.step_start("Suggesting branch names...");
-    let provider = ai_helper::build_provider(config, ModelTier::Fast)?;
-    let model = config.resolve_model(config.ai.default_provider, ModelTier::Fast);
+    let ai = AiClient::new(config, crate::TOOL_NAME, ModelTier::Fast)?;
+    let system_prompt = load_prompt(crate::TOOL_NAME, "branch", &[]);
 
     let user_message = format!(
         "Suggest branch names for: {}",
         description
     );
 
-    let suggestions = ai_helper::ask_ai(
-        provider.as_ref(),
-        SYSTEM_PROMPT,
+    let suggestions = ai.ask(
+        &system_prompt,
         &user_message,
-        &model,
         256,
         0.5,
     )
@@ -1,8 +1,8 @@
 //! `shinigami reap` — Generates a changelog from recent commits using AI.
 
-use crate::ai_helper;
 use crate::git;
 use anyhow::Result;
+use nakama_ai::{load_prompt, AiClient};
 use nakama_audit::{AuditEntry, AuditLog, Category, Outcome};
 use nakama_core::config::Config;
 use nakama_core::types::ModelTier;
@@ -10,35 +10,8 @@ use nakama_core::TraceContext;
 use nakama_ui::NakamaUI;
 use std::time::Instant;
 
-const SYSTEM_PROMPT: &str = r#"You are a changelog writer. Given a list of git commit messages (some may follow conventional commits format), produce a well-organized changelog.
-
-Format the changelog as markdown with these sections (omit empty sections):
-
-### Features
-- Description of new features
-
-### Bug Fixes
-- Description of bug fixes
-
-### Performance
-- Performance improvements
-
-### Refactoring
-- Code refactoring changes
-
-### Documentation
-- Documentation updates
-
-### Other
-- Anything that doesn't fit above
-
-Rules:
-- Group commits by type (feat -> Features, fix -> Bug Fixes, etc.)
-- Rewrite each entry to be user-friendly and concise
-- Include the short commit hash in parentheses at the end of each entry
-- Do not include merge commits or trivial changes (like version bumps) unless significant
-- Output ONLY the changelog markdown, no extra commentary
-"#;
+/// Upper bound on the commit list sent to the model, in tokens.
+const MAX_COMMIT_LIST_TOKENS: usize = 1_500;
 
 /// Run the reap (changelog) subcommand.
 pub async fn run(
@@ -95,12 +68,14 @@ pub async fn run(
         .collect::<Vec<_>>()
         .join("\n");
 
-    // Truncate if very long
-    let commit_list = nakama_core::diff::truncate_diff(&commit_list, 6000);
-
     let spinner = ui.step_start("Generating changelog...");
-    let provider = ai_helper::build_provider(config, ModelTier::Balanced)?;
-    let model = config.resolve_model(config.ai.default_provider, ModelTier::Balanced);
+    let ai = AiClient::new(config, crate::TOOL_NAME, ModelTier::Balanced)?;
+    let system_prompt = load_prompt(crate::TOOL_NAME, "changelog", &[]);
+
+    // Truncate if very long
+    let mut budget = ai.context_budget(1500);
+    budget.reserve(&system_prompt);
+    let commit_list = budget.at_most(MAX_COMMIT_LIST_TOKENS).fit_text(&commit_list);
 
     let user_message = format!(
         "Generate a changelog from these {} commits (range: {} .. {}):\n\n{}",
@@ -110,11 +85,9 @@ pub async fn run(
         commit_list
     );
 
-    let changelog = ai_helper::ask_ai(
-        provider.as_ref(),
-        SYSTEM_PROMPT,
+    let changelog = ai.ask(
+        &system_prompt,
         &user_message,
-        &model,
         1500,
         0.3,
     )
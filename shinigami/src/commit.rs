@@ -1,8 +1,8 @@
 //! `shinigami commit` — AI-generated conventional commit messages from staged changes.
 
-use crate::ai_helper;
 use crate::git;
 use anyhow::{bail, Context, Result};
+use nakama_ai::{load_prompt, AiClient};
 use nakama_audit::{AuditEntry, AuditLog, Category, Outcome};
 use nakama_core::config::Config;
 use nakama_core::types::ModelTier;
@@ -11,32 +11,8 @@ use nakama_ui::NakamaUI;
 use std::io::{self, Write};
 use std::time::Instant;
 
-const SYSTEM_PROMPT: &str = r#"You are an expert at writing git commit messages following the Conventional Commits specification.
-
-Given a diff of staged changes, produce a commit message in this exact format:
-
-<type>(<scope>): <description>
-
-<body>
-
-Rules:
-- type MUST be one of: feat, fix, refactor, docs, test, chore, ci, perf, style
-- scope is optional but recommended — it should be a short module/area name (lowercase, no spaces)
-- description MUST be lowercase, imperative mood, no period at the end, max 72 chars
-- body should explain WHAT changed and WHY (not how), wrapped at 72 chars
-- If there are breaking changes, add a footer: BREAKING CHANGE: <explanation>
-- Do NOT include any markdown formatting, code fences, or commentary
-- Output ONLY the commit message, nothing else
-
-Examples of good commit messages:
-  feat(auth): add OAuth2 PKCE flow for CLI login
-  fix(parser): handle empty input without panicking
-  refactor(db): extract connection pooling into separate module
-  docs(readme): add quickstart guide for new contributors
-  chore(deps): bump tokio to 1.35
-"#;
-
-const MAX_DIFF_CHARS: usize = 8000;
+/// Upper bound on the diff sent for a commit message, in tokens.
+const MAX_DIFF_TOKENS: usize = 2_000;
 
 /// Run the commit subcommand.
 pub async fn run(config: &Config, ui: &NakamaUI) -> Result<()> {
@@ -64,13 +40,16 @@ pub async fn run(config: &Config, ui: &NakamaUI) -> Result<()> {
         return Ok(());
     }
 
-    // 2. Truncate diff if too large
-    let diff_for_ai = nakama_core::diff::compress_diff(&diff, MAX_DIFF_CHARS);
-
-    // 3. Build AI provider and request commit message
+    // 2. Build AI provider and fit the diff to its context window
     let spinner = ui.step_start("Generating commit message...");
-    let provider = ai_helper::build_provider(config, ModelTier::Balanced)?;
-    let model = config.resolve_model(config.ai.default_provider, ModelTier::Balanced);
+    let ai = AiClient::new(config, crate::TOOL_NAME, ModelTier::Balanced)?;
+    let system_prompt = load_prompt(crate::TOOL_NAME, "commit", &[]);
+
+    let mut budget = ai.context_budget(512);
+    budget.reserve(&system_prompt);
+    let diff_for_ai = budget.at_most(MAX_DIFF_TOKENS).fit_diff(&diff);
+
+    // 3. Request commit message
 
     let user_message = format!(
         "Here are the staged changes:\n\n```diff\n{}\n```\n\nFiles changed: {}\n\nGenerate a conventional commit message for these changes.",
@@ -78,11 +57,9 @@ pub async fn run(config: &Config, ui: &NakamaUI) -> Result<()> {
         staged_files.join(", ")
     );
 
-    let commit_msg = ai_helper::ask_ai(
-        provider.as_ref(),
-        SYSTEM_PROMPT,
+    let commit_msg = ai.ask(
+        &system_prompt,
         &user_message,
-        &model,
         512,
         0.3,
     )
@@ -1,8 +1,8 @@
 //! `shinigami release` — Generate release notes for a version.
 
-use crate::ai_helper;
 use crate::git;
 use anyhow::Result;
+use nakama_ai::{load_prompt, AiClient};
 use nakama_audit::{AuditEntry, AuditLog, Category, Outcome};
 use nakama_core::config::Config;
 use nakama_core::types::ModelTier;
@@ -10,37 +10,8 @@ use nakama_core::TraceContext;
 use nakama_ui::NakamaUI;
 use std::time::Instant;
 
-const SYSTEM_PROMPT: &str = r#"You are a release notes writer for a software project. Given a version number and a list of commits since the last release, write professional release notes.
-
-Format:
-# Release v<version>
-
-## Highlights
-A brief paragraph (2-3 sentences) summarizing the most important changes in this release.
-
-## What's New
-### Features
-- Feature descriptions
-
-### Bug Fixes
-- Bug fix descriptions
-
-### Improvements
-- Performance, refactoring, and other improvements
-
-### Breaking Changes
-- Any breaking changes (if none, omit this section)
-
-## Contributors
-List of unique authors from the commits.
-
-Rules:
-- Be concise and user-friendly
-- Focus on what matters to users, not internal details
-- Include commit hashes in parentheses where relevant
-- Omit empty sections
-- Output ONLY the release notes, no extra commentary
-"#;
+/// Upper bound on the commit list sent to the model, in tokens.
+const MAX_COMMIT_LIST_TOKENS: usize = 1_500;
 
 /// Run the release subcommand.
 pub async fn run(config: &Config, ui: &NakamaUI, version: &str) -> Result<()> {
@@ -72,22 +43,22 @@ pub async fn run(config: &Config, ui: &NakamaUI, version: &str) -> Result<()> {
         .collect::<Vec<_>>()
         .join("\n");
 
-    let commit_list = nakama_core::diff::truncate_diff(&commit_list, 6000);
-
     let spinner = ui.step_start("Generating release notes...");
-    let provider = ai_helper::build_provider(config, ModelTier::Balanced)?;
-    let model = config.resolve_model(config.ai.default_provider, ModelTier::Balanced);
+    let ai = AiClient::new(config, crate::TOOL_NAME, ModelTier::Balanced)?;
+    let system_prompt = load_prompt(crate::TOOL_NAME, "release", &[]);
+
+    let mut budget = ai.context_budget(2000);
+    budget.reserve(&system_prompt);
+    let commit_list = budget.at_most(MAX_COMMIT_LIST_TOKENS).fit_text(&commit_list);
 
     let user_message = format!(
         "Write release notes for version {}.\n\nCommits since last release:\n{}",
         version, commit_list
     );
 
-    let notes = ai_helper::ask_ai(
-        provider.as_ref(),
-        SYSTEM_PROMPT,
+    let notes = ai.ask(
+        &system_prompt,
         &user_message,
-        &model,
         2000,
         0.3,
     )
@@ -1,8 +1,8 @@
 //! `shinigami review` — AI-powered code review of uncommitted changes.
 
-use crate::ai_helper;
 use crate::git;
 use anyhow::Result;
+use nakama_ai::{load_prompt, AiClient};
 use nakama_audit::{AuditEntry, AuditLog, Category, Outcome};
 use nakama_core::config::Config;
 use nakama_core::types::ModelTier;
@@ -10,35 +10,8 @@ use nakama_core::TraceContext;
 use nakama_ui::NakamaUI;
 use std::time::Instant;
 
-const SYSTEM_PROMPT: &str = r#"You are a senior software engineer performing a thorough code review.
-
-You will be given a diff of uncommitted changes in a repository. Provide a structured review with these sections:
-
-## Summary
-A 1-2 sentence summary of what the changes do.
-
-## Issues Found
-List any bugs, logic errors, security concerns, or correctness problems. For each:
-- Severity: [critical / warning / info]
-- File/area affected
-- What the issue is and how to fix it
-
-If no issues found, say "No issues found."
-
-## Suggestions
-List style, performance, maintainability, or readability improvements. These are optional, non-blocking suggestions.
-
-## Verdict
-One of:
-- APPROVE — changes look good, no blocking issues
-- REQUEST_CHANGES — there are issues that should be fixed before committing
-- COMMENT — informational review, up to the developer
-
-Keep the review concise and actionable. Do not repeat the diff back.
-Do not use code fences for the overall structure — just use markdown headings and bullet points.
-"#;
-
-const MAX_DIFF_CHARS: usize = 10000;
+/// Upper bound on the diff sent for review, in tokens.
+const MAX_DIFF_TOKENS: usize = 2_500;
 
 /// Run the review subcommand.
 pub async fn run(config: &Config, ui: &NakamaUI) -> Result<()> {
@@ -58,22 +31,22 @@ pub async fn run(config: &Config, ui: &NakamaUI) -> Result<()> {
     let branch = git::current_branch(&repo).unwrap_or_else(|_| "unknown".to_string());
     ui.step_done(&format!("Reviewing uncommitted changes on branch '{}'", branch));
 
-    let diff_for_ai = nakama_core::diff::compress_diff(&diff, MAX_DIFF_CHARS);
-
     let spinner = ui.step_start("AI is reviewing your changes...");
-    let provider = ai_helper::build_provider(config, ModelTier::Balanced)?;
-    let model = config.resolve_model(config.ai.default_provider, ModelTier::Balanced);
+    let ai = AiClient::new(config, crate::TOOL_NAME, ModelTier::Balanced)?;
+    let system_prompt = load_prompt(crate::TOOL_NAME, "review", &[]);
+
+    let mut budget = ai.context_budget(1500);
+    budget.reserve(&system_prompt);
+    let diff_for_ai = budget.at_most(MAX_DIFF_TOKENS).fit_diff(&diff);
 
     let user_message = format!(
         "Review the following uncommitted changes on branch '{}':\n\n```diff\n{}\n```",
         branch, diff_for_ai
     );
 
-    let review = ai_helper::ask_ai(
-        provider.as_ref(),
-        SYSTEM_PROMPT,
+    let review = ai.ask(
+        &system_prompt,
         &user_message,
-        &model,
         1500,
         0.2,
     )
@@ -5,9 +5,9 @@
 //! single new commit with an AI-generated message summarizing all the squashed
 //! commits.
 
-use crate::ai_helper;
 use crate::git;
 use anyhow::{bail, Context, Result};
+use nakama_ai::{load_prompt, AiClient};
 use nakama_audit::{AuditEntry, AuditLog, Category, Outcome};
 use nakama_core::config::Config;
 use nakama_core::types::ModelTier;
@@ -16,25 +16,6 @@ use nakama_ui::NakamaUI;
 use std::io::{self, Write};
 use std::time::Instant;
 
-const SYSTEM_PROMPT: &str = r#"You are an expert at writing git commit messages following the Conventional Commits specification.
-
-You will be given a list of commit messages that are about to be squashed into a single commit. Write a single, well-crafted conventional commit message that summarizes all the changes.
-
-Format:
-<type>(<scope>): <description>
-
-<body>
-
-Rules:
-- type MUST be one of: feat, fix, refactor, docs, test, chore, ci, perf, style
-- If commits span multiple types, pick the most significant one (feat > fix > refactor > chore)
-- scope is optional — use it if all commits share a common area
-- description: lowercase, imperative mood, max 72 chars, no period
-- body: summarize what was done across all commits, wrapped at 72 chars
-- If multiple features/fixes, use bullet points in the body
-- Output ONLY the commit message, no markdown fences or commentary
-"#;
-
 /// Run the squash subcommand.
 pub async fn run(config: &Config, ui: &NakamaUI) -> Result<()> {
     let start = Instant::now();
@@ -99,19 +80,17 @@ pub async fn run(config: &Config, ui: &NakamaUI) -> Result<()> {
 
     // Ask AI for a squash message
     let spinner = ui.step_start("Generating squash commit message...");
-    let provider = ai_helper::build_provider(config, ModelTier::Balanced)?;
-    let model = config.resolve_model(config.ai.default_provider, ModelTier::Balanced);
+    let ai = AiClient::new(config, crate::TOOL_NAME, ModelTier::Balanced)?;
+    let system_prompt = load_prompt(crate::TOOL_NAME, "squash", &[]);
 
     let user_message = format!(
         "Squash these {} commits into one:\n\n{}\n\nWrite a single conventional commit message.",
         count, commit_list
     );
 
-    let squash_msg = ai_helper::ask_ai(
-        provider.as_ref(),
-        SYSTEM_PROMPT,
+    let squash_msg = ai.ask(
+        &system_prompt,
         &user_message,
-        &model,
         512,
         0.3,
     )
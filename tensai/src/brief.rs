@@ -1,4 +1,4 @@
-use crate::ai_helper::{ask_ai, make_provider};
+use nakama_ai::{load_prompt, AiClient};
 use crate::git_info::GitInfo;
 use nakama_audit::{AuditEntry, AuditLog, Category, Outcome};
 use nakama_core::config::Config;
@@ -28,33 +28,16 @@ pub async fn run(config: &Config, ui: &NakamaUI) -> NakamaResult<()> {
     spinner.finish_with_success("Context gathered");
 
     let spinner = ui.step_start("Generating briefing...");
-    let (provider, model) = make_provider(config, ModelTier::Fast)?;
+    let ai = AiClient::new(config, crate::TOOL_NAME, ModelTier::Fast)?;
 
-    let system_prompt = r#"You are Tensai, a dev productivity assistant. Generate a concise morning briefing.
-
-Format:
-## Good Morning! Here's your dev briefing:
-
-### Git Status
-Summary of branch, recent work, and pending changes.
-
-### Open PRs
-PRs that need attention (if any).
-
-### Priorities
-Based on recent activity, suggest 3-5 priorities for today.
-
-### Heads Up
-Any potential issues or blockers to watch for.
-
-Keep it brief and actionable. No fluff."#;
+    let system_prompt = load_prompt(crate::TOOL_NAME, "brief", &[]);
 
     let user_msg = format!(
         "Generate my morning briefing.\n\nGit info:\n{}\n\nOpen PRs:\n{}",
         git_summary, pr_info,
     );
 
-    let result = ask_ai(provider.as_ref(), system_prompt, &user_msg, &model, 1536, 0.4).await;
+    let result = ai.ask( &system_prompt, &user_msg, 1536, 0.4).await;
     let elapsed = start.elapsed().as_millis() as u64;
 
     match &result {
@@ -66,7 +49,7 @@ Keep it brief and actionable. No fluff."#;
                 let entry = AuditEntry::new(
                     &trace.trace_id, "tensai", "brief", Category::AiInteraction,
                     "Generated morning briefing",
-                    serde_json::json!({ "model": model }),
+                    serde_json::json!({ "model": ai.model() }),
                     Outcome::Success, elapsed,
                 );
                 let _ = audit.log(entry);
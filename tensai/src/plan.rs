@@ -1,4 +1,4 @@
-use crate::ai_helper::{ask_ai, make_provider};
+use nakama_ai::{load_prompt, AiClient};
 use crate::git_info::{find_todos, GitInfo};
 use nakama_core::config::Config;
 use nakama_core::error::NakamaResult;
@@ -24,36 +24,16 @@ pub async fn run(config: &Config, ui: &NakamaUI) -> NakamaResult<()> {
     spinner.finish_with_success("Context gathered");
 
     let spinner = ui.step_start("Planning your day...");
-    let (provider, model) = make_provider(config, ModelTier::Fast)?;
+    let ai = AiClient::new(config, crate::TOOL_NAME, ModelTier::Fast)?;
 
-    let system_prompt = r#"You are Tensai, a productivity planner. Create a focused day plan.
-
-Format:
-## Today's Plan
-
-### High Priority (do first)
-1. Task — reasoning
-
-### Medium Priority
-1. Task — reasoning
-
-### If Time Permits
-1. Task — reasoning
-
-### Time Blocks (suggested)
-- 9:00-10:30: Deep work on [X]
-- 10:30-11:00: Code review
-- etc.
-
-Base priorities on: current branch work, uncommitted changes, TODOs, and pending PRs.
-Be specific and actionable."#;
+    let system_prompt = load_prompt(crate::TOOL_NAME, "plan", &[]);
 
     let user_msg = format!(
         "Plan my day.\n\nGit state:\n{}\n\nTODOs in codebase:\n{}",
         git_summary, todo_summary,
     );
 
-    let result = ask_ai(provider.as_ref(), system_prompt, &user_msg, &model, 1536, 0.4).await;
+    let result = ai.ask( &system_prompt, &user_msg, 1536, 0.4).await;
 
     match &result {
         Ok(content) => {
@@ -1,4 +1,4 @@
-use crate::ai_helper::{ask_ai, make_provider};
+use nakama_ai::{load_prompt, AiClient};
 use nakama_core::config::Config;
 use nakama_core::error::NakamaResult;
 use nakama_core::types::ModelTier;
@@ -23,26 +23,9 @@ pub async fn run(config: &Config, ui: &NakamaUI) -> NakamaResult<()> {
     spinner.finish_with_success("Activity collected");
 
     let spinner = ui.step_start("Generating review...");
-    let (provider, model) = make_provider(config, ModelTier::Fast)?;
+    let ai = AiClient::new(config, crate::TOOL_NAME, ModelTier::Fast)?;
 
-    let system_prompt = r#"You are Tensai, an end-of-day review assistant. Summarize the day's work.
-
-Format:
-## End of Day Review
-
-### Accomplished
-- What was done today (from commits)
-
-### Stats
-- Files changed, lines added/removed
-
-### Carry Forward
-- What should be continued tomorrow
-
-### Reflection
-One sentence on productivity and focus.
-
-Be encouraging and constructive."#;
+    let system_prompt = load_prompt(crate::TOOL_NAME, "review", &[]);
 
     let user_msg = format!(
         "Review my day.\n\nToday's commits:\n{}\n\nDiff stats:\n{}",
@@ -50,7 +33,7 @@ Be encouraging and constructive."#;
         if diff_stat.is_empty() { "No recent changes." } else { &diff_stat },
     );
 
-    let result = ask_ai(provider.as_ref(), system_prompt, &user_msg, &model, 1024, 0.4).await;
+    let result = ai.ask( &system_prompt, &user_msg, 1024, 0.4).await;
 
     match &result {
         Ok(content) => {
@@ -1,4 +1,4 @@
-use crate::ai_helper::{ask_ai, make_provider};
+use nakama_ai::{load_prompt, AiClient};
 use crate::git_info::GitInfo;
 use nakama_audit::{AuditEntry, AuditLog, Category, Outcome};
 use nakama_core::config::Config;
@@ -28,21 +28,9 @@ pub async fn run(config: &Config, ui: &NakamaUI) -> NakamaResult<()> {
     spinner.finish_with_success("Activity collected");
 
     let spinner = ui.step_start("Generating standup...");
-    let (provider, model) = make_provider(config, ModelTier::Fast)?;
+    let ai = AiClient::new(config, crate::TOOL_NAME, ModelTier::Fast)?;
 
-    let system_prompt = r#"You are Tensai, a standup summary generator. Create a concise standup report.
-
-Format exactly as:
-## Yesterday
-- What was accomplished (based on commits)
-
-## Today
-- Planned work (inferred from current branch and pending changes)
-
-## Blockers
-- Any potential blockers (inferred from context)
-
-Be concise — each bullet should be one short sentence. Max 3-5 bullets per section."#;
+    let system_prompt = load_prompt(crate::TOOL_NAME, "standup", &[]);
 
     let user_msg = format!(
         "Generate standup from this activity.\n\nCommits since yesterday:\n{}\n\nCurrent state:\n{}",
@@ -50,7 +38,7 @@ Be concise — each bullet should be one short sentence. Max 3-5 bullets per sec
         git_summary,
     );
 
-    let result = ask_ai(provider.as_ref(), system_prompt, &user_msg, &model, 1024, 0.3).await;
+    let result = ai.ask( &system_prompt, &user_msg, 1024, 0.3).await;
     let elapsed = start.elapsed().as_millis() as u64;
 
     match &result {
@@ -62,7 +50,7 @@ Be concise — each bullet should be one short sentence. Max 3-5 bullets per sec
                 let entry = AuditEntry::new(
                     &trace.trace_id, "tensai", "standup", Category::AiInteraction,
                     "Generated standup report",
-                    serde_json::json!({ "model": model }),
+                    serde_json::json!({ "model": ai.model() }),
                     Outcome::Success, elapsed,
                 );
                 let _ = audit.log(entry);
@@ -1,7 +1,7 @@
 //! The `ask` subcommand — translate a natural-language question into a shell command.
 
 use anyhow::Result;
-use nakama_ai::{CompletionRequest, Message};
+use nakama_ai::{load_prompt, AiClient, AttachArgs, Message, SessionArgs};
 use nakama_audit::{AuditEntry, AuditLog, Category, Outcome};
 use nakama_core::config::Config;
 use nakama_core::TraceContext;
@@ -9,11 +9,18 @@ use nakama_ui::NakamaUI;
 use std::time::Instant;
 
 use crate::context::{build_context_prompt, ShellContext};
-use crate::provider::create_ai_provider;
 use crate::risk::{assess_risk, format_risk_display};
 
 /// Execute the `ask` subcommand.
-pub async fn run(config: &Config, ui: &NakamaUI, query: &str) -> Result<()> {
+pub async fn run(
+    config: &Config,
+    ui: &NakamaUI,
+    query: &str,
+    attach: &AttachArgs,
+    session: &SessionArgs,
+) -> Result<()> {
+    let attachments = attach.load()?;
+    let mut conversation = session.open(crate::TOOL_NAME)?;
     let trace = TraceContext::new("zangetsu", "ask");
     let ctx = ShellContext::collect();
     let start = Instant::now();
@@ -30,21 +37,14 @@ pub async fn run(config: &Config, ui: &NakamaUI, query: &str) -> Result<()> {
         query
     );
 
-    let provider = create_ai_provider(config)?;
-    let model = config.resolve_model(
-        config.ai.default_provider,
-        nakama_core::types::ModelTier::Balanced,
-    );
-
-    let request = CompletionRequest {
-        system_prompt: system_prompt.clone(),
-        messages: vec![Message::user(&user_prompt)],
-        model,
-        max_tokens: 2048,
-        temperature: 0.3,
+    let ai = AiClient::new(config, crate::TOOL_NAME, nakama_core::types::ModelTier::Balanced)?;
+    let message = Message::user(&user_prompt).with_attachments(attachments);
+    let result = match conversation.prepare(&ai, &system_prompt, message, 2048, 0.3).await {
+        Ok(request) => ai.provider().complete(request).await,
+        Err(e) => Err(e),
     };
 
-    let response = match provider.complete(request).await {
+    let response = match result {
         Ok(resp) => {
             spinner.finish_with_success("Got it!");
             resp
@@ -56,6 +56,10 @@ pub async fn run(config: &Config, ui: &NakamaUI, query: &str) -> Result<()> {
         }
     };
 
+    if let Err(e) = conversation.record(&response.content) {
+        ui.warn(&format!("Failed to save session: {}", e));
+    }
+
     // Parse the response to extract commands and explanations
     let parsed = parse_ask_response(&response.content);
 
@@ -87,20 +91,11 @@ pub async fn run(config: &Config, ui: &NakamaUI, query: &str) -> Result<()> {
 
 /// Build the system prompt for the `ask` command.
 fn build_ask_system_prompt(ctx: &ShellContext) -> String {
-    let mut prompt = String::new();
-    prompt.push_str(
-        "You are Zangetsu, an expert shell command assistant. \
-         Your job is to translate natural-language requests into precise shell commands.\n\n",
-    );
-    prompt.push_str("Rules:\n");
-    prompt.push_str("1. Output commands appropriate for the user's OS and shell.\n");
-    prompt.push_str("2. Prefer standard, widely-available tools.\n");
-    prompt.push_str("3. Use safe defaults (e.g., prefer interactive/confirmation flags when available).\n");
-    prompt.push_str("4. If a task requires multiple steps, provide each as a separate COMMAND: line.\n");
-    prompt.push_str("5. NEVER fabricate flags or options that do not exist.\n");
-    prompt.push_str("6. Format response strictly as COMMAND: and EXPLANATION: lines.\n\n");
-    prompt.push_str(&build_context_prompt(ctx));
-    prompt
+    load_prompt(
+        crate::TOOL_NAME,
+        "ask",
+        &[("shell_context", &build_context_prompt(ctx))],
+    )
 }
 
 /// A parsed command entry from the AI response.
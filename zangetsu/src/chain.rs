@@ -1,7 +1,7 @@
 //! The `chain` subcommand — generate multi-step command pipelines from a description.
 
 use anyhow::Result;
-use nakama_ai::{CompletionRequest, Message};
+use nakama_ai::{load_prompt, AiClient, CompletionRequest, Message};
 use nakama_audit::{AuditEntry, AuditLog, Category, Outcome};
 use nakama_core::config::Config;
 use nakama_core::TraceContext;
@@ -9,7 +9,6 @@ use nakama_ui::NakamaUI;
 use std::time::Instant;
 
 use crate::context::{build_context_prompt, ShellContext};
-use crate::provider::create_ai_provider;
 use crate::risk::{assess_risk, format_risk_display, RiskLevel};
 
 /// Execute the `chain` subcommand.
@@ -32,11 +31,8 @@ pub async fn run(config: &Config, ui: &NakamaUI, query: &str) -> Result<()> {
         query
     );
 
-    let provider = create_ai_provider(config)?;
-    let model = config.resolve_model(
-        config.ai.default_provider,
-        nakama_core::types::ModelTier::Balanced,
-    );
+    let (provider, model) =
+        AiClient::new(config, crate::TOOL_NAME, nakama_core::types::ModelTier::Balanced)?.into_parts();
 
     let request = CompletionRequest {
         system_prompt: system_prompt.clone(),
@@ -44,6 +40,7 @@ pub async fn run(config: &Config, ui: &NakamaUI, query: &str) -> Result<()> {
         model,
         max_tokens: 4096,
         temperature: 0.3,
+        ..Default::default()
     };
 
     let response = match provider.complete(request).await {
@@ -119,22 +116,11 @@ pub async fn run(config: &Config, ui: &NakamaUI, query: &str) -> Result<()> {
 
 /// Build the system prompt for the `chain` command.
 fn build_chain_system_prompt(ctx: &ShellContext) -> String {
-    let mut prompt = String::new();
-    prompt.push_str(
-        "You are Zangetsu, an expert shell pipeline builder. \
-         Your job is to create multi-step command pipelines from natural-language descriptions.\n\n",
-    );
-    prompt.push_str("Rules:\n");
-    prompt.push_str("1. Break complex tasks into clear, sequential steps.\n");
-    prompt.push_str("2. Each step should be a single, runnable command.\n");
-    prompt.push_str("3. Use pipes (|), logical AND (&&), or sequential execution (;) appropriately.\n");
-    prompt.push_str("4. Prefer standard POSIX tools when possible.\n");
-    prompt.push_str("5. Consider the user's OS and shell when choosing tools.\n");
-    prompt.push_str("6. If possible, also provide a single combined pipeline.\n");
-    prompt.push_str("7. Handle edge cases (empty output, missing files, etc.) gracefully.\n");
-    prompt.push_str("8. Format response strictly with STEP, COMMAND, EXPLANATION, and PIPELINE markers.\n\n");
-    prompt.push_str(&build_context_prompt(ctx));
-    prompt
+    load_prompt(
+        crate::TOOL_NAME,
+        "chain",
+        &[("shell_context", &build_context_prompt(ctx))],
+    )
 }
 
 /// A parsed step from the AI response.
@@ -1,7 +1,7 @@
 //! The `explain` subcommand — explain what a shell command does in plain English.
 
 use anyhow::Result;
-use nakama_ai::{CompletionRequest, Message};
+use nakama_ai::{load_prompt, AiClient, CompletionRequest, Message};
 use nakama_audit::{AuditEntry, AuditLog, Category, Outcome};
 use nakama_core::config::Config;
 use nakama_core::TraceContext;
@@ -9,7 +9,6 @@ use nakama_ui::NakamaUI;
 use std::time::Instant;
 
 use crate::context::{build_context_prompt, ShellContext};
-use crate::provider::create_ai_provider;
 use crate::risk::{assess_risk, format_risk_display};
 
 /// Execute the `explain` subcommand.
@@ -28,11 +27,8 @@ pub async fn run(config: &Config, ui: &NakamaUI, command: &str) -> Result<()> {
         command
     );
 
-    let provider = create_ai_provider(config)?;
-    let model = config.resolve_model(
-        config.ai.default_provider,
-        nakama_core::types::ModelTier::Balanced,
-    );
+    let (provider, model) =
+        AiClient::new(config, crate::TOOL_NAME, nakama_core::types::ModelTier::Balanced)?.into_parts();
 
     let request = CompletionRequest {
         system_prompt: system_prompt.clone(),
@@ -40,6 +36,7 @@ pub async fn run(config: &Config, ui: &NakamaUI, command: &str) -> Result<()> {
         model,
         max_tokens: 2048,
         temperature: 0.3,
+        ..Default::default()
     };
 
     let response = match provider.complete(request).await {
@@ -76,20 +73,11 @@ pub async fn run(config: &Config, ui: &NakamaUI, command: &str) -> Result<()> {
 
 /// Build the system prompt for the `explain` command.
 fn build_explain_system_prompt(ctx: &ShellContext) -> String {
-    let mut prompt = String::new();
-    prompt.push_str(
-        "You are Zangetsu, an expert shell command explainer. \
-         Your job is to break down shell commands and explain them in clear, plain English.\n\n",
-    );
-    prompt.push_str("Rules:\n");
-    prompt.push_str("1. Break down each component: the base command, flags/options, arguments, pipes, and redirections.\n");
-    prompt.push_str("2. Explain what each part does individually.\n");
-    prompt.push_str("3. Use simple language accessible to beginners.\n");
-    prompt.push_str("4. Mention any important side effects or caveats.\n");
-    prompt.push_str("5. End with a one-sentence overall summary.\n");
-    prompt.push_str("6. If the command is dangerous, clearly warn about the risks.\n\n");
-    prompt.push_str(&build_context_prompt(ctx));
-    prompt
+    load_prompt(
+        crate::TOOL_NAME,
+        "explain",
+        &[("shell_context", &build_context_prompt(ctx))],
+    )
 }
 
 /// Log the interaction to the audit database.
@@ -1,7 +1,7 @@
 //! The `fix` subcommand — reads the last failed command from shell history and suggests a fix.
 
 use anyhow::Result;
-use nakama_ai::{CompletionRequest, Message};
+use nakama_ai::{load_prompt, AiClient, CompletionRequest, Message};
 use nakama_audit::{AuditEntry, AuditLog, Category, Outcome};
 use nakama_core::config::Config;
 use nakama_core::TraceContext;
@@ -9,7 +9,6 @@ use nakama_ui::NakamaUI;
 use std::time::Instant;
 
 use crate::context::{build_context_prompt, ShellContext};
-use crate::provider::create_ai_provider;
 use crate::risk::{assess_risk, format_risk_display};
 
 /// Execute the `fix` subcommand.
@@ -52,11 +51,8 @@ pub async fn run(config: &Config, ui: &NakamaUI) -> Result<()> {
             .join("\n"),
     );
 
-    let provider = create_ai_provider(config)?;
-    let model = config.resolve_model(
-        config.ai.default_provider,
-        nakama_core::types::ModelTier::Balanced,
-    );
+    let (provider, model) =
+        AiClient::new(config, crate::TOOL_NAME, nakama_core::types::ModelTier::Balanced)?.into_parts();
 
     let request = CompletionRequest {
         system_prompt: system_prompt.clone(),
@@ -64,6 +60,7 @@ pub async fn run(config: &Config, ui: &NakamaUI) -> Result<()> {
         model,
         max_tokens: 2048,
         temperature: 0.3,
+        ..Default::default()
     };
 
     let response = match provider.complete(request).await {
@@ -124,20 +121,11 @@ pub async fn run(config: &Config, ui: &NakamaUI) -> Result<()> {
 
 /// Build the system prompt for the `fix` command.
 fn build_fix_system_prompt(ctx: &ShellContext) -> String {
-    let mut prompt = String::new();
-    prompt.push_str(
-        "You are Zangetsu, an expert shell command debugger. \
-         Your job is to diagnose failed shell commands and suggest corrected versions.\n\n",
-    );
-    prompt.push_str("Rules:\n");
-    prompt.push_str("1. Analyze the command to identify common failure modes (typos, wrong flags, missing arguments, permission issues, etc.).\n");
-    prompt.push_str("2. Consider the user's OS, shell, and working directory.\n");
-    prompt.push_str("3. Suggest the SIMPLEST fix that addresses the most likely cause.\n");
-    prompt.push_str("4. If the command seems correct but might fail for environmental reasons, suggest diagnostic steps.\n");
-    prompt.push_str("5. Format response strictly as DIAGNOSIS:, FIX:, and EXPLANATION: lines.\n");
-    prompt.push_str("6. Provide only ONE corrected command in the FIX: line.\n\n");
-    prompt.push_str(&build_context_prompt(ctx));
-    prompt
+    load_prompt(
+        crate::TOOL_NAME,
+        "fix",
+        &[("shell_context", &build_context_prompt(ctx))],
+    )
 }
 
 /// Parsed fix response from the AI.
@@ -5,6 +5,7 @@
 
 use anyhow::Result;
 use clap::{Parser, Subcommand};
+use nakama_ai::{AiArgs, AttachArgs, SessionArgs};
 use nakama_core::Config;
 use nakama_log::init_logging;
 use nakama_ui::NakamaUI;
@@ -15,7 +16,6 @@ mod context;
 mod explain;
 mod fix;
 mod history;
-mod provider;
 mod risk;
 
 const TOOL_NAME: &str = "zangetsu";
@@ -24,6 +24,9 @@ const TOOL_NAME: &str = "zangetsu";
 #[derive(Parser, Debug)]
 #[command(name = TOOL_NAME, version, about, long_about = None)]
 struct Cli {
+    #[command(flatten)]
+    ai: AiArgs,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -35,6 +38,12 @@ enum Commands {
         /// The natural-language query describing what you want to do
         #[arg()]
         query: String,
+
+        #[command(flatten)]
+        attach: AttachArgs,
+
+        #[command(flatten)]
+        session: SessionArgs,
     },
 
     /// Explain what a shell command does in plain English
@@ -60,16 +69,23 @@ enum Commands {
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let config = Config::load(TOOL_NAME).unwrap_or_default();
+    let (mut config, diagnostics) = Config::load_or_default(TOOL_NAME);
     let _log_guard = init_logging(TOOL_NAME, &config.logging)?;
     let ui = NakamaUI::from_config(&config);
+    ui.config_diagnostics(&diagnostics);
 
     let update_rx = nakama_update::spawn_check(&config.updates, env!("CARGO_PKG_VERSION"));
 
     let cli = Cli::parse();
+    cli.ai.apply(&mut config);
+    nakama_vault::startup(&config, &ui);
 
     let result = match cli.command {
-        Commands::Ask { query } => ask::run(&config, &ui, &query).await,
+        Commands::Ask {
+            query,
+            attach,
+            session,
+        } => ask::run(&config, &ui, &query, &attach, &session).await,
         Commands::Explain { command } => explain::run(&config, &ui, &command).await,
         Commands::Fix => fix::run(&config, &ui).await,
         Commands::Chain { query } => chain::run(&config, &ui, &query).await,